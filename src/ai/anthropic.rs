@@ -0,0 +1,147 @@
+use anyhow::{Context, Result};
+use reqwest::{Client, RequestBuilder};
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use super::{
+    AiResult, AiService, DEFAULT_CONNECT_TIMEOUT, DEFAULT_MAX_TOKENS, DEFAULT_TIMEOUT, IMAGE_PLACEHOLDER,
+    apply_headers, http_client, parse_ai_response, read_response, request_error,
+};
+
+/// API version sent in the `anthropic-version` header.
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+
+pub struct ClaudeService {
+    api_key: String,
+    model: String,
+    headers: BTreeMap<String, String>,
+    max_tokens: u32,
+    client: Client,
+}
+
+impl ClaudeService {
+    pub fn new(api_key: String, model: String) -> Self {
+        Self {
+            api_key,
+            model,
+            headers: BTreeMap::new(),
+            max_tokens: DEFAULT_MAX_TOKENS,
+            client: http_client(DEFAULT_TIMEOUT, DEFAULT_CONNECT_TIMEOUT),
+        }
+    }
+
+    /// Send these extra HTTP headers with every request (e.g. for a gateway).
+    pub fn with_headers(mut self, headers: BTreeMap<String, String>) -> Self {
+        self.headers = headers;
+        self
+    }
+
+    /// Limit the length of the model's response (default [`DEFAULT_MAX_TOKENS`]).
+    pub fn with_max_tokens(mut self, max_tokens: u32) -> Self {
+        self.max_tokens = max_tokens;
+        self
+    }
+
+    /// Give up on a request after `timeout` in total, or `connect_timeout`
+    /// to establish the connection (default 60s and 10s).
+    pub fn with_timeouts(mut self, timeout: Duration, connect_timeout: Duration) -> Self {
+        self.client = http_client(timeout, connect_timeout);
+        self
+    }
+
+    /// Build the Messages API request for `body`.
+    pub(crate) fn request(&self, body: &serde_json::Value) -> RequestBuilder {
+        let builder = self
+            .client
+            .post("https://api.anthropic.com/v1/messages")
+            .header("x-api-key", &self.api_key)
+            .header("anthropic-version", ANTHROPIC_VERSION)
+            .json(body);
+        apply_headers(builder, &self.headers)
+    }
+
+    /// Build the JSON request body for one image.
+    pub(crate) fn request_body(
+        &self,
+        image_base64: &str,
+        prompt: &str,
+        mime_type: &str,
+        max_tokens: u32,
+    ) -> serde_json::Value {
+        json!({
+            "model": self.model,
+            "max_tokens": max_tokens,
+            "system": "You are an image analysis assistant. You MUST respond with valid JSON only. No markdown, no code blocks, no extra text. All string values MUST be enclosed in double quotes.",
+            "messages": [
+                {
+                    "role": "user",
+                    "content": [
+                        {
+                            "type": "image",
+                            "source": {
+                                "type": "base64",
+                                "media_type": mime_type,
+                                "data": image_base64
+                            }
+                        },
+                        {
+                            "type": "text",
+                            "text": prompt
+                        }
+                    ]
+                }
+            ]
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl AiService for ClaudeService {
+    fn name(&self) -> &str {
+        "Claude"
+    }
+
+    fn model(&self) -> Option<&str> {
+        Some(&self.model)
+    }
+
+    fn max_tokens(&self) -> Option<u32> {
+        Some(self.max_tokens)
+    }
+
+    fn request_preview(&self, prompt: &str, mime_type: &str) -> Option<serde_json::Value> {
+        Some(self.request_body(IMAGE_PLACEHOLDER, prompt, mime_type, self.max_tokens))
+    }
+
+    async fn analyze(&self, image_base64: &str, prompt: &str, mime_type: &str) -> Result<AiResult> {
+        self.analyze_with_max_tokens(image_base64, prompt, mime_type, self.max_tokens).await
+    }
+
+    async fn analyze_with_max_tokens(
+        &self,
+        image_base64: &str,
+        prompt: &str,
+        mime_type: &str,
+        max_tokens: u32,
+    ) -> Result<AiResult> {
+        let body = self.request_body(image_base64, prompt, mime_type, max_tokens);
+
+        let resp = self
+            .request(&body)
+            .send()
+            .await
+            .map_err(|e| request_error(self.name(), e))?;
+
+        let text = read_response(self.name(), resp).await?;
+
+        let json: serde_json::Value =
+            serde_json::from_str(&text).context("Failed to parse Claude response JSON")?;
+
+        let content = json["content"][0]["text"]
+            .as_str()
+            .context("No content in Claude response")?;
+
+        parse_ai_response(content)
+    }
+}
@@ -1,13 +1,20 @@
 use anyhow::{Context, Result};
-use reqwest::Client;
+use reqwest::{Client, RequestBuilder};
 use serde_json::json;
+use std::collections::BTreeMap;
+use std::time::Duration;
 
-use super::{AiResult, AiService, parse_ai_response};
+use super::{
+    AiResult, AiService, DEFAULT_CONNECT_TIMEOUT, DEFAULT_MAX_TOKENS, DEFAULT_TIMEOUT, IMAGE_PLACEHOLDER,
+    apply_headers, http_client, parse_ai_response, read_response, request_error,
+};
 
 pub struct CloudflareService {
     account_id: String,
     api_token: String,
     model: String,
+    headers: BTreeMap<String, String>,
+    max_tokens: u32,
     client: Client,
 }
 
@@ -17,51 +24,103 @@ impl CloudflareService {
             account_id,
             api_token,
             model,
-            client: Client::new(),
+            headers: BTreeMap::new(),
+            max_tokens: DEFAULT_MAX_TOKENS,
+            client: http_client(DEFAULT_TIMEOUT, DEFAULT_CONNECT_TIMEOUT),
         }
     }
-}
 
-#[async_trait::async_trait]
-impl AiService for CloudflareService {
-    fn name(&self) -> &str {
-        "Cloudflare"
+    /// Send these extra HTTP headers with every request (e.g. for a gateway).
+    pub fn with_headers(mut self, headers: BTreeMap<String, String>) -> Self {
+        self.headers = headers;
+        self
+    }
+
+    /// Limit the length of the model's response (default [`DEFAULT_MAX_TOKENS`]).
+    pub fn with_max_tokens(mut self, max_tokens: u32) -> Self {
+        self.max_tokens = max_tokens;
+        self
+    }
+
+    /// Give up on a request after `timeout` in total, or `connect_timeout`
+    /// to establish the connection (default 60s and 10s).
+    pub fn with_timeouts(mut self, timeout: Duration, connect_timeout: Duration) -> Self {
+        self.client = http_client(timeout, connect_timeout);
+        self
     }
 
-    async fn analyze(&self, image_base64: &str, prompt: &str, _mime_type: &str) -> Result<AiResult> {
+    /// Build the Workers AI run request for `body`.
+    pub(crate) fn request(&self, body: &serde_json::Value) -> RequestBuilder {
         let url = format!(
             "https://api.cloudflare.com/client/v4/accounts/{}/ai/run/{}",
             self.account_id, self.model
         );
+        let builder = self
+            .client
+            .post(&url)
+            .header("Authorization", format!("Bearer {}", self.api_token))
+            .json(body);
+        apply_headers(builder, &self.headers)
+    }
 
-        let body = json!({
+    /// Build the JSON request body for one image.
+    pub(crate) fn request_body(
+        &self,
+        image_base64: &str,
+        prompt: &str,
+        max_tokens: u32,
+    ) -> serde_json::Value {
+        json!({
             "messages": [
                 {
                     "role": "user",
                     "content": prompt
                 }
             ],
-            "image": image_base64
-        });
+            "image": image_base64,
+            "max_tokens": max_tokens
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl AiService for CloudflareService {
+    fn name(&self) -> &str {
+        "Cloudflare"
+    }
+
+    fn model(&self) -> Option<&str> {
+        Some(&self.model)
+    }
+
+    fn max_tokens(&self) -> Option<u32> {
+        Some(self.max_tokens)
+    }
+
+    fn request_preview(&self, prompt: &str, _mime_type: &str) -> Option<serde_json::Value> {
+        Some(self.request_body(IMAGE_PLACEHOLDER, prompt, self.max_tokens))
+    }
+
+    async fn analyze(&self, image_base64: &str, prompt: &str, mime_type: &str) -> Result<AiResult> {
+        self.analyze_with_max_tokens(image_base64, prompt, mime_type, self.max_tokens).await
+    }
+
+    async fn analyze_with_max_tokens(
+        &self,
+        image_base64: &str,
+        prompt: &str,
+        _mime_type: &str,
+        max_tokens: u32,
+    ) -> Result<AiResult> {
+        let body = self.request_body(image_base64, prompt, max_tokens);
 
         let resp = self
-            .client
-            .post(&url)
-            .header("Authorization", format!("Bearer {}", self.api_token))
-            .json(&body)
+            .request(&body)
             .send()
             .await
-            .context("Cloudflare request failed")?;
-
-        let status = resp.status();
-        let text = resp
-            .text()
-            .await
-            .context("Failed to read Cloudflare response")?;
+            .map_err(|e| request_error(self.name(), e))?;
 
-        if !status.is_success() {
-            anyhow::bail!("Cloudflare API error ({}): {}", status, text);
-        }
+        let text = read_response(self.name(), resp).await?;
 
         let json: serde_json::Value =
             serde_json::from_str(&text).context("Failed to parse Cloudflare response JSON")?;
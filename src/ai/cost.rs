@@ -0,0 +1,186 @@
+//! Offline cost estimates for the remote AI services.
+//!
+//! Estimates are computed from an image's dimensions and the model's published
+//! per-token prices, without calling any API. Image token counts follow each
+//! provider's documented formula for the request this crate sends (OpenAI
+//! `detail: "low"`, Gemini 768px tiles, Claude pixels / 750). Prompt and output tokens are rough
+//! averages, so treat the result as a ballpark figure.
+//!
+//! ```rust
+//! use exif_ai::ai::cost;
+//!
+//! let price = cost::model_price("gpt-4o-mini").unwrap();
+//! let estimate = cost::estimate("gpt-4o-mini", 4000, 3000, &price);
+//! println!("~{} tokens, ${:.4}", estimate.total_tokens(), estimate.usd);
+//! ```
+
+/// Price of a model in USD per million tokens.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ModelPrice {
+    pub input_per_million: f64,
+    pub output_per_million: f64,
+}
+
+/// Built-in price table (USD per million tokens). Model names are matched by
+/// longest prefix, so dated variants like `gpt-4o-mini-2024-07-18` resolve too.
+pub const PRICES: &[(&str, ModelPrice)] = &[
+    ("gpt-4o-mini", ModelPrice { input_per_million: 0.15, output_per_million: 0.60 }),
+    ("gpt-4o", ModelPrice { input_per_million: 2.50, output_per_million: 10.00 }),
+    ("gpt-4.1-mini", ModelPrice { input_per_million: 0.40, output_per_million: 1.60 }),
+    ("gpt-4.1", ModelPrice { input_per_million: 2.00, output_per_million: 8.00 }),
+    ("gemini-2.0-flash-lite", ModelPrice { input_per_million: 0.075, output_per_million: 0.30 }),
+    ("gemini-2.0-flash", ModelPrice { input_per_million: 0.10, output_per_million: 0.40 }),
+    ("gemini-1.5-flash", ModelPrice { input_per_million: 0.075, output_per_million: 0.30 }),
+    ("gemini-1.5-pro", ModelPrice { input_per_million: 1.25, output_per_million: 5.00 }),
+    ("claude-3-5-sonnet", ModelPrice { input_per_million: 3.00, output_per_million: 15.00 }),
+    ("claude-3-5-haiku", ModelPrice { input_per_million: 0.80, output_per_million: 4.00 }),
+    ("claude-3-haiku", ModelPrice { input_per_million: 0.25, output_per_million: 1.25 }),
+];
+
+/// Typical completion size for the JSON this crate asks for.
+pub const OUTPUT_TOKENS_ESTIMATE: u64 = 300;
+
+/// Estimated tokens and cost for analyzing one image.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CostEstimate {
+    /// Prompt plus image tokens.
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub usd: f64,
+}
+
+impl CostEstimate {
+    pub fn total_tokens(&self) -> u64 {
+        self.input_tokens + self.output_tokens
+    }
+}
+
+/// Look up `model` in [`PRICES`]. Returns `None` for unknown or free models.
+pub fn model_price(model: &str) -> Option<ModelPrice> {
+    PRICES
+        .iter()
+        .filter(|(name, _)| model.starts_with(name))
+        .max_by_key(|(name, _)| name.len())
+        .map(|(_, price)| *price)
+}
+
+/// Tokens an image of `width`×`height` costs as input to `model`.
+pub fn image_tokens(model: &str, width: u32, height: u32) -> u64 {
+    if model.starts_with("gemini") {
+        // Small images are one tile; larger ones are cut into square crops
+        // sized from the shorter side, each costing 258 tokens.
+        if width <= 384 && height <= 384 {
+            return 258;
+        }
+        let unit = (width.min(height) as f64 / 1.5).clamp(256.0, 768.0);
+        let tiles = (width as f64 / unit).ceil() * (height as f64 / unit).ceil();
+        return 258 * tiles as u64;
+    }
+
+    if model.starts_with("claude") {
+        // Images are scaled to fit 1568px on the long edge and ~1.15
+        // megapixels, then billed at one token per 750 pixels.
+        let scale = (1568.0 / width.max(height) as f64)
+            .min((1_150_000.0 / (width as f64 * height as f64)).sqrt())
+            .min(1.0);
+        let pixels = (width as f64 * scale).round() * (height as f64 * scale).round();
+        return (pixels / 750.0).ceil() as u64;
+    }
+
+    // OpenAI low detail is a flat rate; the mini models bill ~33x the tokens.
+    if model.starts_with("gpt-4o-mini") { 2833 } else { 85 }
+}
+
+/// Rough token count of the default prompt.
+pub fn prompt_tokens() -> u64 {
+    (super::build_prompt().len() as u64).div_ceil(4)
+}
+
+/// Estimate the cost of analyzing one `width`×`height` image with `model`.
+pub fn estimate(model: &str, width: u32, height: u32, price: &ModelPrice) -> CostEstimate {
+    let input_tokens = prompt_tokens() + image_tokens(model, width, height);
+    let output_tokens = OUTPUT_TOKENS_ESTIMATE;
+    let usd = (input_tokens as f64 * price.input_per_million
+        + output_tokens as f64 * price.output_per_million)
+        / 1_000_000.0;
+    CostEstimate { input_tokens, output_tokens, usd }
+}
+
+/// Pixel dimensions of the image at `path`, from its header when the format is
+/// decodable, otherwise from the EXIF width/height tags.
+pub fn image_dimensions(path: &std::path::Path, exif: &crate::exif::ExifData) -> Option<(u32, u32)> {
+    if let Ok(dims) = image::image_dimensions(path) {
+        return Some(dims);
+    }
+    let width = exif.image_width.as_deref()?.trim().parse().ok()?;
+    let height = exif.image_height.as_deref()?.trim().parse().ok()?;
+    Some((width, height))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn model_price_uses_longest_prefix() {
+        assert_eq!(model_price("gpt-4o-mini-2024-07-18"), Some(PRICES[0].1));
+        assert_eq!(model_price("gpt-4o"), Some(PRICES[1].1));
+        assert_eq!(model_price("gemini-2.0-flash-lite-001").unwrap().input_per_million, 0.075);
+        assert_eq!(model_price("@cf/llava-hf/llava-1.5-7b-hf"), None);
+    }
+
+    #[test]
+    fn image_tokens_gemini_tiles() {
+        assert_eq!(image_tokens("gemini-2.0-flash", 300, 200), 258);
+        // 1024×768: unit = 768 / 1.5 = 512 → 2×2 tiles
+        assert_eq!(image_tokens("gemini-2.0-flash", 1024, 768), 258 * 4);
+        // 4000×3000: unit clamps to 768 → 6×4 tiles
+        assert_eq!(image_tokens("gemini-2.0-flash", 4000, 3000), 258 * 24);
+    }
+
+    #[test]
+    fn image_tokens_claude_pixels() {
+        assert_eq!(image_tokens("claude-3-5-sonnet-latest", 750, 750), 750);
+        // 4000×3000 is scaled to ~1.15 megapixels (1238×929) first
+        assert_eq!(image_tokens("claude-3-5-sonnet-latest", 4000, 3000), 1534);
+    }
+
+    #[test]
+    fn image_tokens_openai_low_detail_is_flat() {
+        assert_eq!(image_tokens("gpt-4o", 4000, 3000), 85);
+        assert_eq!(image_tokens("gpt-4o-mini", 100, 100), 2833);
+    }
+
+    #[test]
+    fn estimate_known_size_and_price() {
+        let price = ModelPrice { input_per_million: 1.0, output_per_million: 4.0 };
+        let est = estimate("gemini-2.0-flash", 1024, 768, &price);
+
+        assert_eq!(est.input_tokens, prompt_tokens() + 1032);
+        assert_eq!(est.output_tokens, 300);
+        let expected = (est.input_tokens as f64 + 300.0 * 4.0) / 1_000_000.0;
+        assert!((est.usd - expected).abs() < 1e-12);
+        assert_eq!(est.total_tokens(), est.input_tokens + 300);
+    }
+
+    #[test]
+    fn image_dimensions_from_header() {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("data/test_canon_powershot.jpg");
+        let exif = crate::exif::read_exif(&path).unwrap();
+        // The EXIF tags still claim the camera's 2272×1704; the pixels were downscaled
+        assert_eq!(exif.image_width.as_deref(), Some("2272"));
+        assert_eq!(image_dimensions(&path, &exif), Some((480, 360)));
+    }
+
+    #[test]
+    fn image_dimensions_falls_back_to_exif() {
+        let exif = crate::exif::ExifData {
+            image_width: Some("640".into()),
+            image_height: Some("480".into()),
+            ..Default::default()
+        };
+        assert_eq!(image_dimensions(std::path::Path::new("/nonexistent.cr3"), &exif), Some((640, 480)));
+        assert_eq!(image_dimensions(std::path::Path::new("/nonexistent.cr3"), &Default::default()), None);
+    }
+}
@@ -0,0 +1,227 @@
+use std::fmt;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use reqwest::StatusCode;
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+
+/// Typed AI service failures that callers may want to react to.
+///
+/// Services return these wrapped in [`anyhow::Error`]; recover them with
+/// `err.downcast_ref::<AiError>()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AiError {
+    /// The service answered `429 Too Many Requests`. `retry_after` is taken
+    /// from the `Retry-After` header when present.
+    RateLimited {
+        service: String,
+        retry_after: Option<Duration>,
+    },
+    /// The service answered with a server error that is usually temporary
+    /// (`500`, `502`, `503` or `504`). `body` is the response text.
+    ServerError {
+        service: String,
+        status: u16,
+        body: String,
+        retry_after: Option<Duration>,
+    },
+    /// The response could not be parsed as the expected JSON object.
+    /// `raw` is the text the model returned.
+    InvalidJson { raw: String },
+}
+
+impl AiError {
+    /// Build a [`AiError::RateLimited`] from a 429 response's headers.
+    pub(crate) fn rate_limited(service: &str, headers: &HeaderMap) -> Self {
+        AiError::RateLimited { service: service.to_string(), retry_after: retry_after(headers) }
+    }
+
+    /// Whether retrying the same request later may succeed.
+    pub fn is_transient(&self) -> bool {
+        matches!(self, AiError::RateLimited { .. } | AiError::ServerError { .. })
+    }
+
+    /// How long the service asked callers to wait before retrying.
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            AiError::RateLimited { retry_after, .. } | AiError::ServerError { retry_after, .. } => *retry_after,
+            AiError::InvalidJson { .. } => None,
+        }
+    }
+}
+
+/// The `Retry-After` of a response, if present and valid.
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    headers
+        .get(RETRY_AFTER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| parse_retry_after(v, SystemTime::now()))
+}
+
+/// Turn a non-success response into an error: [`AiError::RateLimited`] for
+/// 429, [`AiError::ServerError`] for transient server errors, and a plain
+/// error for everything else (bad request, invalid key, …).
+pub(crate) fn status_error(service: &str, status: StatusCode, headers: &HeaderMap, body: String) -> anyhow::Error {
+    match status.as_u16() {
+        429 => AiError::rate_limited(service, headers).into(),
+        500 | 502 | 503 | 504 => AiError::ServerError {
+            service: service.to_string(),
+            status: status.as_u16(),
+            body,
+            retry_after: retry_after(headers),
+        }
+        .into(),
+        _ => anyhow::anyhow!("{service} API error ({status}): {body}"),
+    }
+}
+
+impl fmt::Display for AiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AiError::RateLimited { service, retry_after: Some(d) } => {
+                write!(f, "{service} rate limited (retry after {}s)", d.as_secs())
+            }
+            AiError::RateLimited { service, retry_after: None } => {
+                write!(f, "{service} rate limited")
+            }
+            AiError::ServerError { service, status, body, .. } => {
+                let status = StatusCode::from_u16(*status).map_or_else(|_| status.to_string(), |s| s.to_string());
+                write!(f, "{service} API error ({status}): {body}")
+            }
+            AiError::InvalidJson { .. } => write!(f, "Could not parse AI response as JSON"),
+        }
+    }
+}
+
+impl std::error::Error for AiError {}
+
+/// Parse a `Retry-After` header value into a wait duration.
+///
+/// Accepts delay-seconds (`"120"`) and an HTTP-date
+/// (`"Wed, 21 Oct 2015 07:28:00 GMT"`), measured from `now`. Dates in the past
+/// yield [`Duration::ZERO`].
+///
+/// # Example
+///
+/// ```rust
+/// use exif_ai::ai::parse_retry_after;
+/// use std::time::{Duration, SystemTime};
+///
+/// assert_eq!(parse_retry_after("30", SystemTime::now()), Some(Duration::from_secs(30)));
+/// ```
+pub fn parse_retry_after(value: &str, now: SystemTime) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let at = parse_http_date(value)?;
+    Some(at.duration_since(now).unwrap_or(Duration::ZERO))
+}
+
+/// Parse an IMF-fixdate such as `Sun, 06 Nov 1994 08:49:37 GMT`.
+fn parse_http_date(value: &str) -> Option<SystemTime> {
+    const MONTHS: [&str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+
+    let parts: Vec<&str> = value.split_whitespace().collect();
+    let [_weekday, day, month, year, time, "GMT"] = parts.as_slice() else {
+        return None;
+    };
+    let day: u32 = day.parse().ok()?;
+    let month = MONTHS.iter().position(|m| m == month)? as u32 + 1;
+    let year: i64 = year.parse().ok()?;
+    let mut hms = time.split(':').map(|p| p.parse::<u64>().ok());
+    let (h, m, s) = (hms.next()??, hms.next()??, hms.next()??);
+    // IMF-fixdate years have four digits
+    if hms.next().is_some() || !(1..=31).contains(&day) || !(0..=9999).contains(&year) || h > 23 || m > 59 || s > 60 {
+        return None;
+    }
+
+    let days = u64::try_from(days_from_civil(year, month, day)).ok()?;
+    let secs = days.checked_mul(86_400)?.checked_add(h * 3600 + m * 60 + s)?;
+    UNIX_EPOCH.checked_add(Duration::from_secs(secs))
+}
+
+/// Days since 1970-01-01 for a proleptic Gregorian date.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let mp = (month as i64 + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retry_after_seconds() {
+        let now = SystemTime::now();
+        assert_eq!(parse_retry_after("120", now), Some(Duration::from_secs(120)));
+        assert_eq!(parse_retry_after(" 0 ", now), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn retry_after_http_date() {
+        // 2015-10-21T07:28:00Z
+        let at = UNIX_EPOCH + Duration::from_secs(1_445_412_480);
+        let now = at - Duration::from_secs(90);
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT", now),
+            Some(Duration::from_secs(90))
+        );
+    }
+
+    #[test]
+    fn retry_after_past_date_is_zero() {
+        assert_eq!(
+            parse_retry_after("Sun, 06 Nov 1994 08:49:37 GMT", SystemTime::now()),
+            Some(Duration::ZERO)
+        );
+    }
+
+    #[test]
+    fn retry_after_invalid() {
+        let now = SystemTime::now();
+        assert_eq!(parse_retry_after("soon", now), None);
+        assert_eq!(parse_retry_after("-5", now), None);
+        assert_eq!(parse_retry_after("Wed, 21 Foo 2015 07:28:00 GMT", now), None);
+        assert_eq!(parse_retry_after("Wed, 21 Oct 2015 07:28:00 PST", now), None);
+        assert_eq!(parse_retry_after("Wed, 21 Oct 300000000000 07:28:00 GMT", now), None);
+        assert_eq!(parse_retry_after("Wed, 21 Oct 9223372036854775807 07:28:00 GMT", now), None);
+    }
+
+    #[test]
+    fn rate_limited_from_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, "7".parse().unwrap());
+        let err = AiError::rate_limited("OpenAI", &headers);
+        assert_eq!(err, AiError::RateLimited { service: "OpenAI".into(), retry_after: Some(Duration::from_secs(7)) });
+        assert_eq!(err.to_string(), "OpenAI rate limited (retry after 7s)");
+
+        let err = AiError::rate_limited("Gemini", &HeaderMap::new());
+        assert_eq!(err.to_string(), "Gemini rate limited");
+    }
+
+    #[test]
+    fn status_errors_classified() {
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, "3".parse().unwrap());
+
+        let err = status_error("OpenAI", StatusCode::SERVICE_UNAVAILABLE, &headers, "overloaded".into());
+        let ai = err.downcast_ref::<AiError>().unwrap();
+        assert!(ai.is_transient());
+        assert_eq!(ai.retry_after(), Some(Duration::from_secs(3)));
+        assert_eq!(err.to_string(), "OpenAI API error (503 Service Unavailable): overloaded");
+
+        let err = status_error("OpenAI", StatusCode::TOO_MANY_REQUESTS, &HeaderMap::new(), String::new());
+        assert!(err.downcast_ref::<AiError>().unwrap().is_transient());
+
+        for status in [StatusCode::BAD_REQUEST, StatusCode::UNAUTHORIZED] {
+            let err = status_error("OpenAI", status, &headers, "nope".into());
+            assert!(err.downcast_ref::<AiError>().is_none(), "{status} must not be retried");
+            assert_eq!(err.to_string(), format!("OpenAI API error ({status}): nope"));
+        }
+    }
+}
@@ -10,6 +10,8 @@ use tokenizers::Tokenizer;
 use std::path::{Path, PathBuf};
 
 use super::{AiResult, AiService};
+use crate::config::LocalConfig;
+use crate::decode::Decoders;
 
 /// Default model directory name inside the user's cache.
 const MODEL_DIR_NAME: &str = "exif-ai";
@@ -59,6 +61,8 @@ fn best_device() -> Result<Device> {
 pub struct LocalService {
     model_path: PathBuf,
     tokenizer_path: PathBuf,
+    cpu_threads: Option<usize>,
+    decoders: Decoders,
 }
 
 impl LocalService {
@@ -67,6 +71,8 @@ impl LocalService {
         Self {
             model_path,
             tokenizer_path,
+            cpu_threads: None,
+            decoders: Decoders::new(),
         }
     }
 
@@ -78,9 +84,41 @@ impl LocalService {
         Ok(Self {
             model_path,
             tokenizer_path,
+            cpu_threads: None,
+            decoders: Decoders::new(),
         })
     }
 
+    /// Create a LocalService from the `local` config section: the model
+    /// directory (default cache dir if empty) and CPU thread count.
+    pub fn from_config(config: &LocalConfig) -> Result<Self> {
+        let service = if config.model_path.is_empty() {
+            Self::from_default_dir()?
+        } else {
+            let dir = PathBuf::from(&config.model_path);
+            Self::new(dir.join(MODEL_FILENAME), dir.join(TOKENIZER_FILENAME))
+        };
+        Ok(service.with_cpu_threads(config.cpu_threads))
+    }
+
+    /// Limit CPU inference to `threads` threads. `None` uses candle's default
+    /// (all logical cores, or `RAYON_NUM_THREADS`).
+    pub fn with_cpu_threads(mut self, threads: Option<usize>) -> Self {
+        self.cpu_threads = threads;
+        self
+    }
+
+    /// The configured CPU thread count, if any.
+    pub fn cpu_threads(&self) -> Option<usize> {
+        self.cpu_threads
+    }
+
+    /// Decode input images with these hooks before the default decoder.
+    pub fn with_decoders(mut self, decoders: Decoders) -> Self {
+        self.decoders = decoders;
+        self
+    }
+
     /// Check if the model files exist.
     pub fn model_exists(&self) -> bool {
         self.model_path.exists() && self.tokenizer_path.exists()
@@ -93,20 +131,46 @@ impl LocalService {
     fn caption_image(&self, image_path: &Path) -> Result<String> {
         let device = best_device()?;
 
+        if matches!(device, Device::Cpu) {
+            return self.run_on_cpu(image_path);
+        }
+
         match self.run_inference(image_path, &device) {
             Ok(caption) => Ok(caption),
-            Err(e) if !matches!(device, Device::Cpu) => {
+            Err(e) => {
                 log::warn!("Inference failed on GPU ({e}), retrying on CPU...");
-                self.run_inference(image_path, &Device::Cpu)
+                self.run_on_cpu(image_path)
             }
-            Err(e) => Err(e),
         }
     }
 
+    /// Run inference on the CPU, inside a dedicated thread pool when
+    /// `cpu_threads` is set. candle's CPU kernels parallelize over the
+    /// current rayon pool, so this caps their intra-op threads.
+    fn run_on_cpu(&self, image_path: &Path) -> Result<String> {
+        match self.cpu_pool()? {
+            Some(pool) => pool.install(|| self.run_inference(image_path, &Device::Cpu)),
+            None => self.run_inference(image_path, &Device::Cpu),
+        }
+    }
+
+    /// Build the thread pool for CPU inference, or `None` to use the global one.
+    fn cpu_pool(&self) -> Result<Option<rayon::ThreadPool>> {
+        let Some(threads) = self.cpu_threads else {
+            return Ok(None);
+        };
+        log::debug!("Using {threads} thread(s) for CPU inference");
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .context("Failed to create CPU inference thread pool")?;
+        Ok(Some(pool))
+    }
+
     /// Run BLIP inference on the given device.
     fn run_inference(&self, image_path: &Path, device: &Device) -> Result<String> {
         // Load and preprocess image
-        let image = load_image(image_path, device)?;
+        let image = load_image(image_path, &self.decoders, device)?;
 
         // Load tokenizer
         let tokenizer = Tokenizer::from_file(&self.tokenizer_path)
@@ -159,6 +223,10 @@ impl AiService for LocalService {
         "Local (BLIP)"
     }
 
+    fn model(&self) -> Option<&str> {
+        Some(MODEL_REPO)
+    }
+
     async fn analyze(
         &self,
         _image_base64: &str,
@@ -170,6 +238,11 @@ impl AiService for LocalService {
         )
     }
 
+    fn supported_mime_types(&self) -> &[&str] {
+        // Formats enabled for the `image` crate decoder
+        &["image/jpeg", "image/png", "image/webp"]
+    }
+
     fn supports_file_analysis(&self) -> bool {
         true
     }
@@ -196,15 +269,15 @@ impl AiService for LocalService {
             tags: if tags.is_empty() { None } else { Some(tags) },
             gps: None,
             subject: None,
+            ..Default::default()
         })
     }
 }
 
 /// Load and preprocess an image for BLIP (resize to 384×384, normalize).
-fn load_image(path: &Path, device: &Device) -> Result<Tensor> {
-    let img = image::ImageReader::open(path)
-        .context("Failed to open image")?
-        .decode()
+fn load_image(path: &Path, decoders: &Decoders, device: &Device) -> Result<Tensor> {
+    let img = decoders
+        .decode(path)
         .context("Failed to decode image")?
         .resize_to_fill(384, 384, image::imageops::FilterType::Triangle);
     let img = img.to_rgb8();
@@ -279,10 +352,10 @@ fn build_title(caption: &str) -> String {
             title.extend(first.to_uppercase());
             title.extend(chars);
         }
-        if title.len() > 50 {
-            if let Some(pos) = title[..50].rfind(' ') {
-                title.truncate(pos);
-            }
+        if title.len() > 50
+            && let Some(pos) = title[..50].rfind(' ')
+        {
+            title.truncate(pos);
         }
         return title;
     }
@@ -485,6 +558,30 @@ mod tests {
         assert_eq!(svc.tokenizer_path.parent().unwrap(), custom_dir.as_path());
     }
 
+    #[test]
+    fn from_config_applies_model_path_and_threads() {
+        let config = LocalConfig {
+            model_path: "/custom/models".into(),
+            enabled: true,
+            cpu_threads: Some(3),
+        };
+        let svc = LocalService::from_config(&config).unwrap();
+        assert_eq!(svc.model_path, PathBuf::from("/custom/models").join(MODEL_FILENAME));
+        assert_eq!(svc.tokenizer_path, PathBuf::from("/custom/models").join(TOKENIZER_FILENAME));
+        assert_eq!(svc.cpu_threads(), Some(3));
+    }
+
+    #[test]
+    fn cpu_pool_honors_thread_count() {
+        let svc = LocalService::new(PathBuf::new(), PathBuf::new()).with_cpu_threads(Some(2));
+        let pool = svc.cpu_pool().unwrap().unwrap();
+        assert_eq!(pool.current_num_threads(), 2);
+        assert_eq!(pool.install(rayon::current_num_threads), 2);
+
+        let default = LocalService::new(PathBuf::new(), PathBuf::new());
+        assert!(default.cpu_pool().unwrap().is_none());
+    }
+
     #[test]
     fn from_default_dir_uses_cache() {
         let svc = LocalService::from_default_dir().unwrap();
@@ -0,0 +1,65 @@
+use anyhow::{Result, anyhow};
+
+use super::{AiResult, AiService};
+
+/// Canned AI service for tests: returns a fixed [`AiResult`] or a fixed error
+/// without any network access. Accepts every image format.
+///
+/// Available in this crate's own tests and, for downstream crates, behind the
+/// `test-util` feature.
+///
+/// # Example
+///
+/// ```rust
+/// use exif_ai::ai::{AiResult, MockAiService};
+/// use exif_ai::pipeline::Pipeline;
+///
+/// let pipeline = Pipeline::builder()
+///     .add_service(Box::new(MockAiService::failing("quota exceeded")))
+///     .add_service(Box::new(MockAiService::returning(AiResult {
+///         title: Some("Red barn".into()),
+///         ..Default::default()
+///     })))
+///     .dry_run(true)
+///     .build()
+///     .unwrap();
+/// assert_eq!(pipeline.service_names(), ["Mock", "Mock"]);
+/// ```
+#[derive(Debug)]
+pub struct MockAiService {
+    name: String,
+    outcome: std::result::Result<AiResult, String>,
+}
+
+impl MockAiService {
+    /// A service that answers every request with `result`.
+    pub fn returning(result: AiResult) -> Self {
+        Self { name: "Mock".into(), outcome: Ok(result) }
+    }
+
+    /// A service that fails every request with `message`, e.g. to exercise failover.
+    pub fn failing(message: &str) -> Self {
+        Self { name: "Mock".into(), outcome: Err(message.to_string()) }
+    }
+
+    /// Report `name` instead of `"Mock"`, to tell services in a chain apart.
+    pub fn named(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl AiService for MockAiService {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn supported_mime_types(&self) -> &[&str] {
+        &["image/*"]
+    }
+
+    async fn analyze(&self, _image_base64: &str, _prompt: &str, _mime_type: &str) -> Result<AiResult> {
+        self.outcome.clone().map_err(|message| anyhow!("{}: {message}", self.name))
+    }
+}
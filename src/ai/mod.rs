@@ -1,15 +1,35 @@
 mod openai;
+mod openai_batch;
+mod anthropic;
+mod ollama;
 mod gemini;
 mod cloudflare;
+mod error;
+#[cfg(any(test, feature = "test-util"))]
+mod mock;
 pub mod local;
+pub mod cost;
 
-pub use openai::OpenAiService;
+pub use openai::{DEFAULT_OPENAI_URL, OpenAiService};
+pub use openai_batch::{BatchRequest, OpenAiBatch};
+pub use anthropic::ClaudeService;
+pub use ollama::{DEFAULT_OLLAMA_TIMEOUT, DEFAULT_OLLAMA_URL, OllamaService};
 pub use gemini::GeminiService;
 pub use cloudflare::CloudflareService;
 pub use local::LocalService;
+pub use error::{AiError, parse_retry_after};
+#[cfg(any(test, feature = "test-util"))]
+pub use mock::MockAiService;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use crate::exif::Field;
+
+/// MIME types accepted by [`AiService::supported_mime_types`] unless a service overrides it.
+pub const WEB_MIME_TYPES: &[&str] = &["image/jpeg", "image/png", "image/webp", "image/gif"];
 
 /// Structured metadata returned by AI vision analysis.
 ///
@@ -24,6 +44,17 @@ use serde::{Deserialize, Serialize};
 /// - `tags` — 5–10 SEO keywords
 /// - `gps` — GPS coordinates if a known location is identified
 /// - `subject` — Identified people, species, landmarks
+/// - `date_taken` — Capture date (ISO 8601) if one is evident in the image,
+///   only present when requested with [`append_date_request`]
+/// - `hierarchical_tags` — Keyword paths from general to specific, separated
+///   by `|` (e.g. `"Nature|Birds|Owl"`), only present when requested with
+///   [`append_hierarchy_request`]
+/// - `titles` / `descriptions` — Translations keyed by language tag (e.g. `"fr"`),
+///   only present when requested with [`append_language_request`]; also
+///   accepted as `title_translations` / `description_translations`
+/// - `language` — Language tag the title and description are written in, set
+///   by the pipeline from [`Config::language`](crate::config::Config::language);
+///   XMP tags them with it next to `x-default`
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct AiResult {
     pub title: Option<String>,
@@ -31,6 +62,133 @@ pub struct AiResult {
     pub tags: Option<Vec<String>>,
     pub gps: Option<GpsCoords>,
     pub subject: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub date_taken: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hierarchical_tags: Option<Vec<String>>,
+    #[serde(default, alias = "title_translations", skip_serializing_if = "Option::is_none")]
+    pub titles: Option<BTreeMap<String, String>>,
+    #[serde(default, alias = "description_translations", skip_serializing_if = "Option::is_none")]
+    pub descriptions: Option<BTreeMap<String, String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+}
+
+impl AiResult {
+    /// Combine two results, e.g. from different services or images of one set.
+    ///
+    /// - `title` / `description`: the non-empty value wins; if both are
+    ///   set, the longer one does (ties keep `self`).
+    /// - `tags` / `subject` / `hierarchical_tags`: union of both, deduplicated case-insensitively,
+    ///   in `self`-then-`other` order.
+    /// - `gps` / `date_taken` / `language`: `self`'s value if present, otherwise `other`'s.
+    /// - `titles` / `descriptions`: union, `self` winning per language.
+    ///
+    /// ```
+    /// use exif_ai::ai::AiResult;
+    ///
+    /// let a = AiResult { title: Some("Barn".into()), tags: Some(vec!["barn".into()]), ..Default::default() };
+    /// let b = AiResult { title: Some("Red barn at dusk".into()), tags: Some(vec!["Barn".into(), "dusk".into()]), ..Default::default() };
+    /// let merged = a.merge(b);
+    /// assert_eq!(merged.title.as_deref(), Some("Red barn at dusk"));
+    /// assert_eq!(merged.tags, Some(vec!["barn".to_string(), "dusk".to_string()]));
+    /// ```
+    pub fn merge(self, other: AiResult) -> AiResult {
+        AiResult {
+            title: merge_text(self.title, other.title),
+            description: merge_text(self.description, other.description),
+            tags: merge_list(self.tags, other.tags),
+            gps: self.gps.or(other.gps),
+            subject: merge_list(self.subject, other.subject),
+            date_taken: self.date_taken.or(other.date_taken),
+            hierarchical_tags: merge_list(self.hierarchical_tags, other.hierarchical_tags),
+            titles: merge_map(self.titles, other.titles),
+            descriptions: merge_map(self.descriptions, other.descriptions),
+            language: self.language.or(other.language),
+        }
+    }
+
+    /// Cut titles to `max_title_chars` and descriptions to
+    /// `max_description_chars` characters, at a word boundary where there
+    /// is one. Translations are cut to the same limits. Returns whether
+    /// anything was cut.
+    ///
+    /// ```
+    /// use exif_ai::ai::AiResult;
+    ///
+    /// let mut ai = AiResult { title: Some("Red barn in a snowy field".into()), ..Default::default() };
+    /// assert!(ai.clamp(12, 254));
+    /// assert_eq!(ai.title.as_deref(), Some("Red barn in"));
+    /// ```
+    pub fn clamp(&mut self, max_title_chars: usize, max_description_chars: usize) -> bool {
+        let mut cut = false;
+        let titles = self.title.iter_mut().chain(self.titles.iter_mut().flat_map(|t| t.values_mut()));
+        for title in titles {
+            cut |= truncate_at_word(title, max_title_chars);
+        }
+        let descriptions =
+            self.description.iter_mut().chain(self.descriptions.iter_mut().flat_map(|d| d.values_mut()));
+        for description in descriptions {
+            cut |= truncate_at_word(description, max_description_chars);
+        }
+        cut
+    }
+}
+
+/// Shorten `text` to at most `max_chars` characters, cutting before the last
+/// word that doesn't fit (or mid-word if the first word is too long).
+/// Returns whether `text` was shortened.
+fn truncate_at_word(text: &mut String, max_chars: usize) -> bool {
+    let Some((end, _)) = text.char_indices().nth(max_chars) else {
+        return false;
+    };
+    let mid_word = !text[end..].starts_with(char::is_whitespace);
+    let cut = match text[..end].rfind(char::is_whitespace) {
+        Some(space) if mid_word => space,
+        _ => end,
+    };
+    let kept = text[..cut].trim_end().trim_end_matches([',', ';', ':', '-', '–', '—']).trim_end().len();
+    text.truncate(if kept == 0 { end } else { kept });
+    true
+}
+
+/// Keep the longer of two optional texts, ignoring blank ones.
+fn merge_text(a: Option<String>, b: Option<String>) -> Option<String> {
+    let a = a.filter(|s| !s.trim().is_empty());
+    let b = b.filter(|s| !s.trim().is_empty());
+    match (a, b) {
+        (Some(a), Some(b)) if b.trim().chars().count() > a.trim().chars().count() => Some(b),
+        (a, b) => a.or(b),
+    }
+}
+
+/// Union of two optional lists, deduplicated case-insensitively.
+fn merge_list(a: Option<Vec<String>>, b: Option<Vec<String>>) -> Option<Vec<String>> {
+    if a.is_none() && b.is_none() {
+        return None;
+    }
+    let mut seen = std::collections::HashSet::new();
+    let merged: Vec<String> = a
+        .into_iter()
+        .chain(b)
+        .flatten()
+        .filter(|item| !item.trim().is_empty() && seen.insert(item.trim().to_lowercase()))
+        .collect();
+    Some(merged).filter(|m| !m.is_empty())
+}
+
+/// Union of two optional translation maps, `a` winning per key.
+fn merge_map(
+    a: Option<BTreeMap<String, String>>,
+    b: Option<BTreeMap<String, String>>,
+) -> Option<BTreeMap<String, String>> {
+    match (a, b) {
+        (Some(a), Some(mut b)) => {
+            b.extend(a);
+            Some(b)
+        }
+        (a, b) => a.or(b),
+    }
 }
 
 /// GPS coordinates identified by the AI for a known location.
@@ -41,13 +199,39 @@ pub struct AiResult {
 pub struct GpsCoords {
     pub latitude: f64,
     pub longitude: f64,
+    /// Meters above sea level (negative below), e.g. for a mountain peak.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub altitude: Option<f64>,
+}
+
+impl GpsCoords {
+    /// Whether the coordinates are on Earth: latitude within ±90°, longitude
+    /// within ±180°, and any altitude finite.
+    ///
+    /// ```
+    /// use exif_ai::ai::GpsCoords;
+    ///
+    /// assert!(GpsCoords { latitude: 48.8584, longitude: 2.2945, altitude: None }.validate());
+    /// assert!(!GpsCoords { latitude: 200.0, longitude: 2.2945, altitude: None }.validate());
+    /// ```
+    pub fn validate(&self) -> bool {
+        (-90.0..=90.0).contains(&self.latitude)
+            && (-180.0..=180.0).contains(&self.longitude)
+            && self.altitude.is_none_or(f64::is_finite)
+    }
+
+    /// Whether this is exactly (0, 0), which is treated as "no location".
+    pub fn is_null_island(&self) -> bool {
+        self.latitude == 0.0 && self.longitude == 0.0
+    }
 }
 
 /// Trait for AI vision services.
 ///
 /// Implement this trait to add a custom AI backend. The library ships with
-/// three implementations: [`OpenAiService`], [`GeminiService`], and
-/// [`CloudflareService`].
+/// four remote implementations: [`OpenAiService`], [`GeminiService`],
+/// [`ClaudeService`], and [`CloudflareService`], plus [`OllamaService`] for
+/// self-hosted models.
 ///
 /// # Example
 ///
@@ -72,6 +256,72 @@ pub trait AiService: Send + Sync {
     /// * `mime_type` — The MIME type of the image (e.g., `"image/jpeg"`, `"image/heic"`)
     async fn analyze(&self, image_base64: &str, prompt: &str, mime_type: &str) -> Result<AiResult>;
 
+    /// Analyze raw image bytes, e.g. an upload already held in memory, so
+    /// nothing has to be written to disk first.
+    ///
+    /// Defaults to base64-encoding `bytes` and calling [`analyze`](Self::analyze).
+    ///
+    /// ```rust,no_run
+    /// use exif_ai::ai::{build_prompt, AiService, OpenAiService};
+    ///
+    /// # async fn example(upload: &[u8]) -> anyhow::Result<()> {
+    /// let service = OpenAiService::new("sk-...".into(), "gpt-4o-mini".into());
+    /// let result = service.analyze_bytes(upload, &build_prompt(), "image/jpeg").await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    async fn analyze_bytes(&self, bytes: &[u8], prompt: &str, mime_type: &str) -> Result<AiResult> {
+        let image_base64 = base64::Engine::encode(&base64::engine::general_purpose::STANDARD, bytes);
+        self.analyze(&image_base64, prompt, mime_type).await
+    }
+
+    /// MIME types this service can analyze. The pipeline skips the service
+    /// for other images. `"image/*"` accepts everything.
+    ///
+    /// Defaults to [`WEB_MIME_TYPES`].
+    fn supported_mime_types(&self) -> &[&str] {
+        WEB_MIME_TYPES
+    }
+
+    /// Whether `mime_type` is listed in [`supported_mime_types`](Self::supported_mime_types).
+    fn supports_mime_type(&self, mime_type: &str) -> bool {
+        self.supported_mime_types()
+            .iter()
+            .any(|m| *m == "image/*" || m.eq_ignore_ascii_case(mime_type))
+    }
+
+    /// The model this service runs, for provenance records. `None` if unknown.
+    fn model(&self) -> Option<&str> {
+        None
+    }
+
+    /// The request body this service would send for `prompt`, with the image
+    /// replaced by [`IMAGE_PLACEHOLDER`]. `None` if the service doesn't send
+    /// the prompt (e.g. the local model).
+    fn request_preview(&self, _prompt: &str, _mime_type: &str) -> Option<serde_json::Value> {
+        None
+    }
+
+    /// The output token limit sent with each request. `None` if the service
+    /// has no such limit, in which case truncated responses aren't retried.
+    fn max_tokens(&self) -> Option<u32> {
+        None
+    }
+
+    /// Like [`analyze`](Self::analyze), but with an explicit output token limit.
+    ///
+    /// The pipeline uses this to retry a truncated response at a higher limit.
+    /// Defaults to `analyze`, ignoring the limit.
+    async fn analyze_with_max_tokens(
+        &self,
+        image_base64: &str,
+        prompt: &str,
+        mime_type: &str,
+        _max_tokens: u32,
+    ) -> Result<AiResult> {
+        self.analyze(image_base64, prompt, mime_type).await
+    }
+
     /// Whether this service supports direct file-based analysis.
     fn supports_file_analysis(&self) -> bool {
         false
@@ -85,33 +335,584 @@ pub trait AiService: Send + Sync {
     }
 }
 
+/// Default total time a remote service may take to answer one request.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Default time allowed to connect to a remote service.
+pub const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Build the HTTP client for a remote service.
+fn http_client(timeout: Duration, connect_timeout: Duration) -> reqwest::Client {
+    reqwest::Client::builder()
+        .timeout(timeout)
+        .connect_timeout(connect_timeout)
+        .build()
+        .expect("failed to build HTTP client")
+}
+
+/// Describe a request that got no response, naming timeouts explicitly so the
+/// failover chain reports them clearly.
+fn request_error(service: &str, error: reqwest::Error) -> anyhow::Error {
+    if error.is_timeout() {
+        anyhow::anyhow!("{service} request timed out")
+    } else {
+        anyhow::Error::new(error).context(format!("{service} request failed"))
+    }
+}
+
+/// Read a service's response text, turning error statuses into errors
+/// (transient ones as [`AiError`], so they can be retried).
+async fn read_response(service: &str, resp: reqwest::Response) -> Result<String> {
+    let status = resp.status();
+    let headers = resp.headers().clone();
+    let text = resp.text().await.with_context(|| format!("Failed to read {service} response"))?;
+    if !status.is_success() {
+        return Err(error::status_error(service, status, &headers, text));
+    }
+    Ok(text)
+}
+
+/// Longest single wait [`retry_analyze`] accepts; a longer `Retry-After`
+/// fails the call instead, so the chain can move on to the next service.
+pub const MAX_RETRY_WAIT: Duration = Duration::from_secs(60);
+
+/// How often and how patiently to retry transient service errors
+/// (rate limits and 5xx responses).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt; `0` disables retrying.
+    pub max_retries: u32,
+    /// Wait before the first retry. Each further retry waits twice as long.
+    pub base_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self { max_retries: 2, base_backoff: Duration::from_millis(500) }
+    }
+}
+
+impl RetryPolicy {
+    /// The wait before retry number `attempt` (from 0): `base_backoff * 2^attempt`
+    /// plus up to one `base_backoff` of random jitter, at most [`MAX_RETRY_WAIT`].
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let exponential = self.base_backoff.saturating_mul(2u32.saturating_pow(attempt));
+        let jitter = self.base_backoff.mul_f64(fastrand::f64());
+        exponential.saturating_add(jitter).min(MAX_RETRY_WAIT)
+    }
+}
+
+/// Run `call` and retry it on transient errors ([`AiError::is_transient`]).
+///
+/// Waits for the service's `Retry-After` when it sends one, otherwise for
+/// [`RetryPolicy::backoff`]. Other errors (bad request, invalid API key, …)
+/// and a `Retry-After` over [`MAX_RETRY_WAIT`] are returned at once.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use exif_ai::ai::{retry_analyze, AiService, OpenAiService, RetryPolicy};
+///
+/// # async fn example() -> anyhow::Result<()> {
+/// let service = OpenAiService::new("sk-...".into(), "gpt-4o-mini".into());
+/// let result = retry_analyze(RetryPolicy::default(), || service.analyze("base64data", "prompt", "image/jpeg")).await?;
+/// # Ok(())
+/// # }
+/// ```
+pub async fn retry_analyze<F, Fut>(policy: RetryPolicy, mut call: F) -> Result<AiResult>
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = Result<AiResult>>,
+{
+    let mut attempt = 0;
+    loop {
+        let result = call().await;
+        let Err(e) = &result else {
+            return result;
+        };
+        let Some(err) = e.downcast_ref::<AiError>().filter(|err| err.is_transient()) else {
+            return result;
+        };
+        if attempt >= policy.max_retries {
+            return result;
+        }
+        let wait = err.retry_after().unwrap_or_else(|| policy.backoff(attempt));
+        if wait > MAX_RETRY_WAIT {
+            return result;
+        }
+        attempt += 1;
+        log::warn!("  {err}; retrying in {:.1}s ({attempt}/{})", wait.as_secs_f32(), policy.max_retries);
+        tokio::time::sleep(wait).await;
+    }
+}
+
+/// Add a service's configured extra headers to `builder`.
+///
+/// They are sent in addition to the service's own headers (e.g. `Authorization`).
+fn apply_headers(mut builder: reqwest::RequestBuilder, headers: &BTreeMap<String, String>) -> reqwest::RequestBuilder {
+    for (name, value) in headers {
+        builder = builder.header(name, value);
+    }
+    builder
+}
+
+/// Boxed services are services too, so `Box<dyn AiService>` can be used
+/// wherever a concrete `S: AiService` is expected.
+#[async_trait::async_trait]
+impl<T: AiService + ?Sized> AiService for Box<T> {
+    fn name(&self) -> &str {
+        (**self).name()
+    }
+
+    async fn analyze(&self, image_base64: &str, prompt: &str, mime_type: &str) -> Result<AiResult> {
+        (**self).analyze(image_base64, prompt, mime_type).await
+    }
+
+    async fn analyze_bytes(&self, bytes: &[u8], prompt: &str, mime_type: &str) -> Result<AiResult> {
+        (**self).analyze_bytes(bytes, prompt, mime_type).await
+    }
+
+    fn supported_mime_types(&self) -> &[&str] {
+        (**self).supported_mime_types()
+    }
+
+    fn model(&self) -> Option<&str> {
+        (**self).model()
+    }
+
+    fn request_preview(&self, prompt: &str, mime_type: &str) -> Option<serde_json::Value> {
+        (**self).request_preview(prompt, mime_type)
+    }
+
+    fn max_tokens(&self) -> Option<u32> {
+        (**self).max_tokens()
+    }
+
+    async fn analyze_with_max_tokens(
+        &self,
+        image_base64: &str,
+        prompt: &str,
+        mime_type: &str,
+        max_tokens: u32,
+    ) -> Result<AiResult> {
+        (**self).analyze_with_max_tokens(image_base64, prompt, mime_type, max_tokens).await
+    }
+
+    fn supports_file_analysis(&self) -> bool {
+        (**self).supports_file_analysis()
+    }
+
+    fn analyze_file(&self, path: &std::path::Path) -> Result<AiResult> {
+        (**self).analyze_file(path)
+    }
+}
+
 /// Build the default AI prompt that asks for structured JSON output.
 ///
 /// Returns the prompt string used to instruct the AI model to return
 /// a JSON object with `title`, `description`, `tags`, `gps`, and `subject` fields.
 ///
 /// You can use this directly or provide your own custom prompt to
-/// [`AiService::analyze`].
+/// [`AiService::analyze`]. Use [`build_prompt_for`] to ask only for the
+/// fields that will be written.
 pub fn build_prompt() -> String {
-    r#"Analyze this image and return a JSON object with the following fields:
+    build_prompt_requesting(|_| true)
+}
 
-{
-  "title": "A concise, SEO-optimized title for this image (max 60 characters)",
-  "description": "An engaging SEO meta description of this image (max 254 characters)",
-  "tags": ["keyword1", "keyword2", "keyword3", "keyword4", "keyword5"],
-  "gps": { "latitude": 0.0, "longitude": 0.0 },
-  "subject": ["identified subject 1", "identified subject 2"]
+/// Build the default prompt, asking only for the fields `fields` writes.
+///
+/// Fields turned off (e.g. `write_gps: false`) are left out of both the JSON
+/// template and the rules, so the model doesn't spend tokens on them. With
+/// every field on, this is exactly [`build_prompt`].
+///
+/// # Example
+///
+/// ```rust
+/// use exif_ai::ai::build_prompt_for;
+/// use exif_ai::config::ExifFields;
+///
+/// let fields = ExifFields { write_gps: false, write_subject: false, ..Default::default() };
+/// let prompt = build_prompt_for(&fields);
+/// assert!(prompt.contains("\"title\""));
+/// assert!(!prompt.contains("\"gps\""));
+/// ```
+pub fn build_prompt_for(fields: &crate::config::ExifFields) -> String {
+    build_prompt_requesting(|field| fields.writes(field))
+}
+
+/// The default prompt, asking for the JSON fields `requested` accepts.
+pub(crate) fn build_prompt_requesting(requested: impl Fn(Field) -> bool) -> String {
+    const FIELDS: [(Field, &str, &str); 5] = [
+        (
+            Field::Title,
+            r#""title": "A concise, SEO-optimized title for this image (max 60 characters)""#,
+            r#""title": A short, catchy SEO title. Max 60 characters. Think of it as a headline."#,
+        ),
+        (
+            Field::Description,
+            r#""description": "An engaging SEO meta description of this image (max 254 characters)""#,
+            r#""description": A detailed, descriptive paragraph about the image content, scene, mood, colors, and context. Write it as a full sentence or two, like an image caption in a magazine. Max 254 characters."#,
+        ),
+        (
+            Field::Tags,
+            r#""tags": ["keyword1", "keyword2", "keyword3", "keyword4", "keyword5"]"#,
+            r#""tags": 5-10 relevant SEO keywords/tags for the image."#,
+        ),
+        (
+            Field::Gps,
+            r#""gps": { "latitude": 0.0, "longitude": 0.0, "altitude": null }"#,
+            r#""gps": If you can identify a specific, well-known location in the image, provide GPS coordinates. Include "altitude" in meters above sea level only if the location has a well-known elevation (e.g. a mountain summit), otherwise null. If unsure or the location is not identifiable, set "gps" to null."#,
+        ),
+        (
+            Field::Subject,
+            r#""subject": ["identified subject 1", "identified subject 2"]"#,
+            r#""subject": If you can identify specific known people, bird species, animal species, landmarks, or other notable subjects, list them. If none are identifiable, set to null."#,
+        ),
+    ];
+    let fields: Vec<_> = FIELDS.iter().filter(|(field, ..)| requested(*field)).collect();
+    let template: Vec<String> = fields.iter().map(|(_, example, _)| format!("  {example}")).collect();
+    let rules: Vec<String> = fields.iter().map(|(_, _, rule)| format!("- {rule}")).collect();
+
+    format!(
+        "Analyze this image and return a JSON object with the following fields:\n\n{{\n{}\n}}\n\nRules:\n{}\n\n\
+Return ONLY the JSON object, no markdown formatting, no code blocks, no extra text.",
+        template.join(",\n"),
+        rules.join("\n"),
+    )
+}
+
+/// Build a prompt that asks the AI to refine an image's existing title and
+/// description instead of writing new ones from scratch.
+///
+/// Falls back to [`build_prompt`] when neither value is present.
+///
+/// # Example
+///
+/// ```rust
+/// use exif_ai::ai::build_prompt_with_existing;
+///
+/// let prompt = build_prompt_with_existing(Some("Sunset"), None);
+/// assert!(prompt.contains("Existing title: \"Sunset\""));
+/// ```
+pub fn build_prompt_with_existing(title: Option<&str>, description: Option<&str>) -> String {
+    let mut prompt = build_prompt();
+    append_existing_metadata(&mut prompt, title, description);
+    prompt
 }
 
-Rules:
-- "title": A short, catchy SEO title. Max 60 characters. Think of it as a headline.
-- "description": A detailed, descriptive paragraph about the image content, scene, mood, colors, and context. Write it as a full sentence or two, like an image caption in a magazine. Max 254 characters.
-- "tags": 5-10 relevant SEO keywords/tags for the image.
-- "gps": If you can identify a specific, well-known location in the image, provide GPS coordinates. If unsure or the location is not identifiable, set to null.
-- "subject": If you can identify specific known people, bird species, animal species, landmarks, or other notable subjects, list them. If none are identifiable, set to null.
+/// Extend a prompt with an image's existing title and description, asking
+/// the AI to refine them (see [`build_prompt_with_existing`]).
+///
+/// Does nothing when neither value is present.
+///
+/// # Example
+///
+/// ```rust
+/// use exif_ai::ai::{append_existing_metadata, build_prompt};
+///
+/// let mut prompt = build_prompt();
+/// append_existing_metadata(&mut prompt, None, Some("A mill by the river."));
+/// assert!(prompt.contains("Existing description: \"A mill by the river.\""));
+/// ```
+pub fn append_existing_metadata(prompt: &mut String, title: Option<&str>, description: Option<&str>) {
+    let title = title.map(str::trim).filter(|t| !t.is_empty());
+    let description = description.map(str::trim).filter(|d| !d.is_empty());
+    if title.is_none() && description.is_none() {
+        return;
+    }
 
-Return ONLY the JSON object, no markdown formatting, no code blocks, no extra text."#
-        .to_string()
+    prompt.push_str("\n\nThis image already has human-written metadata:\n");
+    if let Some(t) = title {
+        prompt.push_str(&format!("Existing title: {t:?}\n"));
+    }
+    if let Some(d) = description {
+        prompt.push_str(&format!("Existing description: {d:?}\n"));
+    }
+    prompt.push_str(
+        "Refine and extend this metadata rather than replacing it: keep the facts, names, and \
+intent it states, correct obvious errors, and add what is visible in the image but missing. \
+The \"title\" and \"description\" you return must read as improved versions of the existing ones \
+and still follow the length limits above.",
+    );
+}
+
+/// Extend a prompt to also ask for the title and description in `languages`.
+///
+/// The AI is asked for `titles` and `descriptions` objects keyed by language
+/// tag, which are written as `xml:lang` alternatives in XMP `dc:title` and
+/// `dc:description`. Does nothing when `languages` is empty.
+///
+/// # Example
+///
+/// ```rust
+/// use exif_ai::ai::{append_language_request, build_prompt};
+///
+/// let mut prompt = build_prompt();
+/// append_language_request(&mut prompt, &["fr".into(), "de".into()]);
+/// assert!(prompt.contains("\"titles\""));
+/// ```
+pub fn append_language_request(prompt: &mut String, languages: &[String]) {
+    if languages.is_empty() {
+        return;
+    }
+    let list = languages.iter().map(|l| format!("\"{l}\"")).collect::<Vec<_>>().join(", ");
+    let example = languages.iter().map(|l| format!("\"{l}\": \"...\"")).collect::<Vec<_>>().join(", ");
+    prompt.push_str(&format!(
+        "\n\nAlso translate the title and description into these languages: {list}. \
+Add them to the JSON object as \"titles\": {{ {example} }} and \"descriptions\": {{ {example} }}, \
+keyed by language tag and following the same length limits. Keep \"title\" and \"description\" as they are."
+    ));
+}
+
+/// Extend a prompt with the language (a BCP-47 tag such as `"de"`) the text
+/// values should be written in. Common languages are named in English;
+/// other tags are passed through as-is.
+///
+/// # Example
+///
+/// ```rust
+/// use exif_ai::ai::{append_output_language, build_prompt};
+///
+/// let mut prompt = build_prompt();
+/// append_output_language(&mut prompt, "de");
+/// assert!(prompt.ends_with("Respond in German (de): write the title, description, tags and subject in German."));
+/// ```
+pub fn append_output_language(prompt: &mut String, lang: &str) {
+    const NAMES: [(&str, &str); 20] = [
+        ("ar", "Arabic"), ("cs", "Czech"), ("da", "Danish"), ("de", "German"), ("el", "Greek"),
+        ("en", "English"), ("es", "Spanish"), ("fi", "Finnish"), ("fr", "French"), ("hi", "Hindi"),
+        ("it", "Italian"), ("ja", "Japanese"), ("ko", "Korean"), ("nl", "Dutch"), ("no", "Norwegian"),
+        ("pl", "Polish"), ("pt", "Portuguese"), ("ru", "Russian"), ("sv", "Swedish"), ("zh", "Chinese"),
+    ];
+    let lang = lang.trim();
+    if lang.is_empty() {
+        return;
+    }
+    let primary = lang.split('-').next().unwrap_or(lang);
+    let language = NAMES.iter()
+        .find(|(tag, _)| tag.eq_ignore_ascii_case(primary))
+        .map_or_else(|| format!("the language \"{lang}\""), |(_, name)| format!("{name} ({lang})"));
+    let short = language.split(" (").next().unwrap_or(&language).to_string();
+    prompt.push_str(&format!(
+        "\n\nRespond in {language}: write the title, description, tags and subject in {short}."
+    ));
+}
+
+/// Extend a prompt with camera metadata the image already carries, as
+/// `(label, value)` pairs (see [`ExifData::prompt_context`](crate::exif::ExifData::prompt_context)).
+/// Does nothing when `context` is empty.
+///
+/// # Example
+///
+/// ```rust
+/// use exif_ai::ai::{append_exif_context, build_prompt};
+///
+/// let mut prompt = build_prompt();
+/// append_exif_context(&mut prompt, &[("Camera model", "EOS R5".into())]);
+/// assert!(prompt.contains("- Camera model: EOS R5"));
+/// ```
+pub fn append_exif_context(prompt: &mut String, context: &[(&str, String)]) {
+    if context.is_empty() {
+        return;
+    }
+    prompt.push_str("\n\nThe image file records this capture information, which may help with context:\n");
+    for (label, value) in context {
+        prompt.push_str(&format!("- {label}: {value}\n"));
+    }
+    prompt.push_str("Describe what is visible in the image; don't mention this information unless it is evident from the image.");
+}
+
+/// How AI tags should be shaped: single keywords or multi-word phrases.
+///
+/// Serializes as `"keywords"` or `"phrases"`. See [`append_tag_style`] and
+/// [`normalize_tags`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TagStyle {
+    /// One word per tag (`"golden gate bridge"` → `golden`, `gate`, `bridge`).
+    Keywords,
+    /// Descriptive multi-word phrases, kept as returned.
+    Phrases,
+}
+
+/// Connecting words dropped when phrases are split into keywords.
+const TAG_FILLER_WORDS: &[&str] = &["a", "an", "and", "at", "by", "for", "in", "of", "on", "the", "to", "with"];
+
+/// Extend a prompt with an instruction for the shape of `tags`.
+///
+/// # Example
+///
+/// ```rust
+/// use exif_ai::ai::{append_tag_style, build_prompt, TagStyle};
+///
+/// let mut prompt = build_prompt();
+/// append_tag_style(&mut prompt, TagStyle::Keywords);
+/// assert!(prompt.contains("single word"));
+/// ```
+pub fn append_tag_style(prompt: &mut String, style: TagStyle) {
+    prompt.push_str(match style {
+        TagStyle::Keywords => "\n\nEach entry in \"tags\" must be a single word (e.g. \"sunset\", \"beach\"), not a phrase.",
+        TagStyle::Phrases => "\n\nEach entry in \"tags\" should be a short descriptive phrase of two to four words \
+(e.g. \"sunset over the ocean\", \"sandy beach\") rather than a single word.",
+    });
+}
+
+/// Extend a prompt to also ask for the capture date, when the image shows it.
+///
+/// The AI is told to return `null` unless the date is evident from the image
+/// itself (a dated newspaper, event signage, a date imprint), never a guess
+/// from the style of the photo.
+///
+/// # Example
+///
+/// ```rust
+/// use exif_ai::ai::{append_date_request, build_prompt};
+///
+/// let mut prompt = build_prompt();
+/// append_date_request(&mut prompt);
+/// assert!(prompt.contains("\"date_taken\""));
+/// ```
+pub fn append_date_request(prompt: &mut String) {
+    prompt.push_str(
+        "\n\nAlso include a \"date_taken\" field with the date the photo was taken, in ISO 8601 \
+(\"YYYY-MM-DD\", or \"YYYY-MM-DDTHH:MM:SS\" if the time is also known), but only if the image \
+itself shows it: a dated newspaper, event signage, a calendar, a camera date imprint. Do not guess \
+from the style, clothing or image quality. If the full date is not evident, set it to null.",
+    );
+}
+
+/// Extend a prompt to also ask for each tag's place in a keyword hierarchy,
+/// as written to Lightroom's `lr:hierarchicalSubject`.
+///
+/// # Example
+///
+/// ```rust
+/// use exif_ai::ai::{append_hierarchy_request, build_prompt};
+///
+/// let mut prompt = build_prompt();
+/// append_hierarchy_request(&mut prompt);
+/// assert!(prompt.contains("\"hierarchical_tags\""));
+/// ```
+pub fn append_hierarchy_request(prompt: &mut String) {
+    prompt.push_str(
+        "\n\nAlso include a \"hierarchical_tags\" array placing the tags in a keyword hierarchy: one \
+path per tag, from the most general category to the tag itself, separated by \"|\" \
+(e.g. \"Nature|Birds|Owl\", \"Places|Europe|France|Paris\"). Use 2 to 4 levels.",
+    );
+}
+
+/// Clean up a `|`-separated keyword path: trim each level and drop empty
+/// ones, so `" Nature | | Birds|"` becomes `"Nature|Birds"`.
+fn normalize_hierarchy_path(path: &str) -> Option<String> {
+    let levels: Vec<&str> = path.split('|').map(str::trim).filter(|l| !l.is_empty()).collect();
+    (!levels.is_empty()).then(|| levels.join("|"))
+}
+
+/// Normalize AI tags to `style`, in case the model ignored the instruction.
+///
+/// [`TagStyle::Keywords`] splits phrases into words and drops connecting words
+/// like "the" or "of"; [`TagStyle::Phrases`] keeps each tag whole. Both trim
+/// whitespace, drop empty tags and remove case-insensitive duplicates,
+/// keeping the first occurrence.
+///
+/// # Example
+///
+/// ```rust
+/// use exif_ai::ai::{normalize_tags, TagStyle};
+///
+/// let tags = vec!["sunset over the bay".to_string(), "Sunset".to_string()];
+/// assert_eq!(normalize_tags(&tags, TagStyle::Keywords), ["sunset", "over", "bay"]);
+/// ```
+pub fn normalize_tags(tags: &[String], style: TagStyle) -> Vec<String> {
+    let candidates: Vec<&str> = match style {
+        TagStyle::Keywords => tags
+            .iter()
+            .flat_map(|tag| tag.split_whitespace())
+            .map(|word| word.trim_matches(|c: char| !c.is_alphanumeric()))
+            .filter(|word| !TAG_FILLER_WORDS.contains(&word.to_lowercase().as_str()))
+            .collect(),
+        TagStyle::Phrases => tags.iter().map(|tag| tag.trim()).collect(),
+    };
+
+    let mut seen = std::collections::HashSet::new();
+    candidates
+        .into_iter()
+        .filter(|tag| !tag.is_empty() && seen.insert(tag.to_lowercase()))
+        .map(String::from)
+        .collect()
+}
+
+/// Post-processing applied to AI descriptions to meet a platform's style rules.
+///
+/// Serializes as `"as-is"`, `"strip-trailing-period"`, `"first-sentence-only"`
+/// or `"sentence-case"`. See [`format_description`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DescriptionFormat {
+    /// Leave the description unchanged.
+    #[default]
+    AsIs,
+    /// Remove periods at the end (`"A red barn."` → `"A red barn"`).
+    StripTrailingPeriod,
+    /// Keep only the first sentence.
+    FirstSentenceOnly,
+    /// Start every sentence with a capital letter; all-caps text is lowercased first.
+    SentenceCase,
+}
+
+/// Apply `format` to a description. Surrounding whitespace is always trimmed.
+///
+/// # Example
+///
+/// ```rust
+/// use exif_ai::ai::{format_description, DescriptionFormat};
+///
+/// let desc = "A red barn at dusk. Snow covers the field.";
+/// assert_eq!(format_description(desc, DescriptionFormat::FirstSentenceOnly), "A red barn at dusk.");
+/// assert_eq!(format_description("A red barn.", DescriptionFormat::StripTrailingPeriod), "A red barn");
+/// ```
+pub fn format_description(description: &str, format: DescriptionFormat) -> String {
+    let description = description.trim();
+    match format {
+        DescriptionFormat::AsIs => description.to_string(),
+        DescriptionFormat::StripTrailingPeriod => description.trim_end_matches('.').trim_end().to_string(),
+        DescriptionFormat::FirstSentenceOnly => first_sentence(description).to_string(),
+        DescriptionFormat::SentenceCase => sentence_case(description),
+    }
+}
+
+/// The text up to and including the first `.`, `!` or `?` that ends a
+/// sentence (followed by whitespace or the end), so `3.5` and `.com` don't split.
+fn first_sentence(text: &str) -> &str {
+    let mut chars = text.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        if matches!(c, '.' | '!' | '?') && chars.peek().is_none_or(|(_, next)| next.is_whitespace()) {
+            return &text[..i + c.len_utf8()];
+        }
+    }
+    text
+}
+
+/// Capitalize the first letter of each sentence.
+fn sentence_case(text: &str) -> String {
+    let shouting = text.chars().any(char::is_alphabetic) && !text.chars().any(char::is_lowercase);
+    let text = if shouting { text.to_lowercase() } else { text.to_string() };
+
+    let mut out = String::with_capacity(text.len());
+    let mut capitalize = true;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if capitalize && c.is_alphabetic() {
+            out.extend(c.to_uppercase());
+            capitalize = false;
+            continue;
+        }
+        out.push(c);
+        if matches!(c, '.' | '!' | '?') && chars.peek().is_some_and(|next| next.is_whitespace()) {
+            capitalize = true;
+        }
+    }
+    out
 }
 
 /// Parse raw AI response text into an [`AiResult`].
@@ -130,13 +931,13 @@ pub fn parse_ai_response(text: &str) -> Result<AiResult> {
     for candidate in &candidates {
         // Try parsing directly
         if let Ok(result) = serde_json::from_str::<AiResult>(candidate) {
-            return Ok(result);
+            return Ok(normalize_hierarchy(drop_null_island(result)));
         }
 
         // Try after fixing trailing commas (common AI quirk)
         let fixed = fix_trailing_commas(candidate);
         if let Ok(result) = serde_json::from_str::<AiResult>(&fixed) {
-            return Ok(result);
+            return Ok(normalize_hierarchy(drop_null_island(result)));
         }
     }
 
@@ -157,7 +958,106 @@ pub fn parse_ai_response(text: &str) -> Result<AiResult> {
         }
     }
 
-    anyhow::bail!("Could not parse AI response as JSON")
+    Err(AiError::InvalidJson { raw: text.to_string() }.into())
+}
+
+/// Output token limit the remote services send unless configured otherwise.
+pub const DEFAULT_MAX_TOKENS: u32 = 1000;
+
+/// Stands in for the base64 image in [`AiService::request_preview`].
+pub const IMAGE_PLACEHOLDER: &str = "<base64 image>";
+
+/// Whether `text` looks like a JSON object cut off mid-way, e.g. because the
+/// model hit its output token limit.
+///
+/// True when an object was opened but its braces never balance (strings and
+/// escapes are skipped).
+///
+/// ```
+/// use exif_ai::ai::is_truncated_json;
+///
+/// assert!(is_truncated_json(r#"{"title": "Red barn", "tags": ["barn", "sn"#));
+/// assert!(!is_truncated_json(r#"{"title": "Red barn"}"#));
+/// assert!(!is_truncated_json("I can't describe this image."));
+/// ```
+pub fn is_truncated_json(text: &str) -> bool {
+    let Some(start) = text.find('{') else {
+        return false;
+    };
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+    for c in text[start..].chars() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match c {
+            '"' => in_string = true,
+            '{' | '[' => depth += 1,
+            '}' | ']' => {
+                depth = depth.saturating_sub(1);
+                if depth == 0 {
+                    return false;
+                }
+            }
+            _ => {}
+        }
+    }
+    depth > 0
+}
+
+/// Longest slice of a failed response echoed back by [`build_reprompt`].
+const REPROMPT_ECHO_MAX: usize = 4000;
+
+/// Build a corrective follow-up to `prompt` after the model returned `invalid`
+/// instead of JSON.
+///
+/// The failed output is echoed (truncated to a few thousand characters) with an
+/// instruction to answer with only the JSON object.
+///
+/// # Example
+///
+/// ```rust
+/// use exif_ai::ai::{build_prompt, build_reprompt};
+///
+/// let prompt = build_reprompt(&build_prompt(), "Sure! Here is a title: Sunset");
+/// assert!(prompt.contains("Sure! Here is a title: Sunset"));
+/// ```
+pub fn build_reprompt(prompt: &str, invalid: &str) -> String {
+    let echo: String = invalid.chars().take(REPROMPT_ECHO_MAX).collect();
+    format!(
+        "{prompt}\n\nYour previous output was not valid JSON:\n\n{echo}\n\n\
+Return only the JSON object described above, with no other text."
+    )
+}
+
+/// Treat GPS (0, 0) as "no location identified".
+///
+/// Models often fill the prompt's placeholder coordinates instead of returning
+/// `null`; writing them would tag the image with a point in the Gulf of Guinea.
+/// [`value_to_ai_result`] applies the same rule on its fallback path.
+fn drop_null_island(mut result: AiResult) -> AiResult {
+    if result.gps.as_ref().is_some_and(GpsCoords::is_null_island) {
+        log::debug!("Ignoring GPS (0, 0) from AI response");
+        result.gps = None;
+    }
+    result
+}
+
+/// Normalize `hierarchical_tags` parsed straight into [`AiResult`], dropping
+/// empty paths and the list itself when nothing usable remains.
+fn normalize_hierarchy(mut result: AiResult) -> AiResult {
+    result.hierarchical_tags = result
+        .hierarchical_tags
+        .map(|paths| paths.iter().filter_map(|p| normalize_hierarchy_path(p)).collect::<Vec<_>>())
+        .filter(|paths| !paths.is_empty());
+    result
 }
 
 /// Extract possible JSON object strings from AI response text.
@@ -179,18 +1079,17 @@ fn extract_json_candidates(text: &str) -> Vec<String> {
     }
 
     // Strategy 2: Find outermost { ... }
-    if let Some(start) = text.find('{') {
-        if let Some(end) = text.rfind('}') {
-            if end > start {
-                let extracted = text[start..=end].to_string();
-                candidates.push(extracted.clone());
-
-                // Strategy 3: Fix unquoted string values (common AI quirk)
-                let fixed = fix_unquoted_values(&extracted);
-                if fixed != extracted {
-                    candidates.push(fixed);
-                }
-            }
+    if let Some(start) = text.find('{')
+        && let Some(end) = text.rfind('}')
+        && end > start
+    {
+        let extracted = text[start..=end].to_string();
+        candidates.push(extracted.clone());
+
+        // Strategy 3: Fix unquoted string values (common AI quirk)
+        let fixed = fix_unquoted_values(&extracted);
+        if fixed != extracted {
+            candidates.push(fixed);
         }
     }
 
@@ -243,28 +1142,27 @@ fn fix_unquoted_values(text: &str) -> String {
             result.push_str(&ws);
 
             // Check if next char starts an unquoted string value
-            if let Some(&next) = chars.peek() {
-                if next != '"' && next != '{' && next != '[' && next != 'n'
-                    && next != 't' && next != 'f' && !next.is_ascii_digit() && next != '-'
-                {
-                    // Likely an unquoted string — collect until , or } or newline
-                    let mut value = String::new();
-                    while let Some(&vc) = chars.peek() {
-                        if vc == ',' || vc == '}' || vc == '\n' {
-                            break;
-                        }
-                        value.push(vc);
-                        chars.next();
+            if let Some(&next) = chars.peek()
+                && next != '"' && next != '{' && next != '[' && next != 'n'
+                && next != 't' && next != 'f' && !next.is_ascii_digit() && next != '-'
+            {
+                // Likely an unquoted string — collect until , or } or newline
+                let mut value = String::new();
+                while let Some(&vc) = chars.peek() {
+                    if vc == ',' || vc == '}' || vc == '\n' {
+                        break;
                     }
-                    let value = value.trim_end();
-                    // Escape any quotes inside the value
-                    let escaped = value.replace('"', "\\\"");
-                    let _ = write!(result, "\"{escaped}\"");
-                    continue;
+                    value.push(vc);
+                    chars.next();
                 }
-                // Check for null/true/false that start with n/t/f
-                // These are valid JSON literals, leave them alone
+                let value = value.trim_end();
+                // Escape any quotes inside the value
+                let escaped = value.replace('"', "\\\"");
+                let _ = write!(result, "\"{escaped}\"");
+                continue;
             }
+            // Check for null/true/false that start with n/t/f
+            // These are valid JSON literals, leave them alone
             continue;
         }
 
@@ -332,15 +1230,17 @@ fn value_to_ai_result(val: &serde_json::Value) -> Option<AiResult> {
             found_any = true;
         }
     }
-    if let Some(gps_obj) = obj.get("gps").and_then(|v| v.as_object()) {
-        if let (Some(lat), Some(lon)) = (
+    if let Some(gps_obj) = obj.get("gps").and_then(|v| v.as_object())
+        && let (Some(lat), Some(lon)) = (
             gps_obj.get("latitude").and_then(|v| v.as_f64()),
             gps_obj.get("longitude").and_then(|v| v.as_f64()),
-        ) {
-            if lat != 0.0 || lon != 0.0 {
-                result.gps = Some(GpsCoords { latitude: lat, longitude: lon });
-                found_any = true;
-            }
+        )
+    {
+        let altitude = gps_obj.get("altitude").and_then(|v| v.as_f64());
+        let gps = GpsCoords { latitude: lat, longitude: lon, altitude };
+        if !gps.is_null_island() {
+            result.gps = Some(gps);
+            found_any = true;
         }
     }
     if let Some(arr) = obj.get("subject").and_then(|v| v.as_array()) {
@@ -350,16 +1250,92 @@ fn value_to_ai_result(val: &serde_json::Value) -> Option<AiResult> {
             found_any = true;
         }
     }
+    if let Some(v) = obj.get("date_taken").and_then(|v| v.as_str()) {
+        result.date_taken = Some(v.to_string());
+        found_any = true;
+    }
+    if let Some(arr) = obj.get("hierarchical_tags").and_then(|v| v.as_array()) {
+        let paths: Vec<String> = arr.iter().filter_map(|v| v.as_str()).filter_map(normalize_hierarchy_path).collect();
+        if !paths.is_empty() {
+            result.hierarchical_tags = Some(paths);
+            found_any = true;
+        }
+    }
+    let lang_map = |keys: [&str; 2]| keys.iter().find_map(|key| obj.get(*key).and_then(value_to_lang_map));
+    result.titles = lang_map(["titles", "title_translations"]);
+    result.descriptions = lang_map(["descriptions", "description_translations"]);
 
     if found_any { Some(result) } else { None }
 }
 
+/// Collect the string entries of a `{ "fr": "...", ... }` object.
+fn value_to_lang_map(val: &serde_json::Value) -> Option<BTreeMap<String, String>> {
+    let map: BTreeMap<String, String> = val.as_object()?
+        .iter()
+        .filter_map(|(lang, v)| v.as_str().map(|s| (lang.clone(), s.to_string())))
+        .collect();
+    if map.is_empty() { None } else { Some(map) }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    // ── supported_mime_types ─────────────────────────────────────────
+
+    #[test]
+    fn default_mime_support_is_web_formats() {
+        let service = OpenAiService::new(String::new(), String::new());
+        assert!(service.supports_mime_type("image/jpeg"));
+        assert!(service.supports_mime_type("IMAGE/PNG"));
+        assert!(!service.supports_mime_type("image/heic"));
+        assert!(!service.supports_mime_type("image/tiff"));
+
+        let gemini = GeminiService::new(String::new(), String::new());
+        assert!(gemini.supports_mime_type("image/heic"));
+        assert!(!gemini.supports_mime_type("image/x-canon-cr3"));
+
+        let boxed: Box<dyn AiService> = Box::new(gemini);
+        assert!(boxed.supports_mime_type("image/heif"));
+    }
+
     // ── build_prompt ─────────────────────────────────────────────────
 
+    #[test]
+    fn build_prompt_with_existing_includes_both() {
+        let prompt = build_prompt_with_existing(Some("Old Mill"), Some("A mill by the \"river\"."));
+        assert!(prompt.starts_with(&build_prompt()));
+        assert!(prompt.contains("Existing title: \"Old Mill\""));
+        assert!(prompt.contains(r#"Existing description: "A mill by the \"river\".""#));
+        assert!(prompt.contains("Refine and extend"));
+    }
+
+    #[test]
+    fn build_prompt_with_existing_title_only() {
+        let prompt = build_prompt_with_existing(Some("Old Mill"), Some("  "));
+        assert!(prompt.contains("Existing title"));
+        assert!(!prompt.contains("Existing description"));
+    }
+
+    #[test]
+    fn build_prompt_with_existing_none_is_default() {
+        assert_eq!(build_prompt_with_existing(None, None), build_prompt());
+        assert_eq!(build_prompt_with_existing(Some(""), None), build_prompt());
+    }
+
+    #[test]
+    fn build_prompt_for_leaves_out_unwritten_fields() {
+        let fields = crate::config::ExifFields { write_gps: false, write_subject: false, ..Default::default() };
+        let prompt = build_prompt_for(&fields);
+        for kept in ["\"title\"", "\"description\"", "\"tags\""] {
+            assert_eq!(prompt.matches(kept).count(), 2, "{kept} in template and rules");
+        }
+        assert!(!prompt.contains("\"gps\"") && !prompt.contains("\"subject\""), "{prompt}");
+        assert!(prompt.contains("\"tags\": [\"keyword1\", \"keyword2\", \"keyword3\", \"keyword4\", \"keyword5\"]\n}"));
+
+        assert_eq!(build_prompt_for(&crate::config::ExifFields::default()), build_prompt());
+    }
+
     #[test]
     fn build_prompt_non_empty() {
         let prompt = build_prompt();
@@ -484,12 +1460,25 @@ Hope this helps!"#;
             "gps": { "latitude": 0.0, "longitude": 0.0 }
         }"#;
         // GPS 0,0 is in the ocean — AI should return null, but if it returns 0,0
-        // we treat it as "no GPS identified" via value_to_ai_result
+        // we treat it as "no GPS identified"
         let result = parse_ai_response(json).unwrap();
-        // Direct serde parse will include gps with 0,0
-        // but value_to_ai_result filters it out
-        // The direct parse path keeps it, which is fine — the writer checks has_gps
         assert!(result.title.is_some());
+        assert!(result.gps.is_none());
+    }
+
+    #[test]
+    fn parse_gps_zero_treated_as_none_after_comma_fix() {
+        let json = r#"{"title": "Sea", "gps": {"latitude": 0, "longitude": 0},}"#;
+        let result = parse_ai_response(json).unwrap();
+        assert!(result.gps.is_none());
+    }
+
+    #[test]
+    fn parse_gps_single_zero_axis_kept() {
+        let json = r#"{"title": "Equator", "gps": {"latitude": 0.0, "longitude": 32.5}}"#;
+        let gps = parse_ai_response(json).unwrap().gps.unwrap();
+        assert_eq!(gps.latitude, 0.0);
+        assert_eq!(gps.longitude, 32.5);
     }
 
     // ── parse_ai_response: errors ────────────────────────────────────
@@ -500,6 +1489,27 @@ Hope this helps!"#;
         assert!(result.is_err());
     }
 
+    #[test]
+    fn parse_failure_keeps_raw_text() {
+        let err = parse_ai_response("Sure! The title is Sunset.").unwrap_err();
+        assert_eq!(err.to_string(), "Could not parse AI response as JSON");
+        assert_eq!(
+            err.downcast_ref::<AiError>(),
+            Some(&AiError::InvalidJson { raw: "Sure! The title is Sunset.".into() })
+        );
+    }
+
+    #[test]
+    fn build_reprompt_echoes_and_truncates() {
+        let prompt = build_reprompt("PROMPT", "oops");
+        assert!(prompt.starts_with("PROMPT\n\n"));
+        assert!(prompt.contains("not valid JSON:\n\noops\n\n"));
+        assert!(prompt.ends_with("with no other text."));
+
+        let long = "é".repeat(REPROMPT_ECHO_MAX + 10);
+        assert_eq!(build_reprompt("", &long).matches('é').count(), REPROMPT_ECHO_MAX);
+    }
+
     #[test]
     fn parse_empty_fails() {
         let result = parse_ai_response("");
@@ -584,6 +1594,20 @@ Hope this helps!"#;
         assert!(value_to_ai_result(&val).is_none());
     }
 
+    #[test]
+    fn gps_validate_checks_ranges() {
+        let at = |latitude, longitude| GpsCoords { latitude, longitude, altitude: None };
+        assert!(at(90.0, -180.0).validate());
+        assert!(at(-33.86, 151.21).validate());
+        assert!(!at(90.5, 0.0).validate());
+        assert!(!at(-91.0, 10.0).validate());
+        assert!(!at(10.0, 180.01).validate());
+        assert!(!at(f64::NAN, 10.0).validate());
+        assert!(!GpsCoords { altitude: Some(f64::INFINITY), ..at(10.0, 10.0) }.validate());
+        assert!(at(0.0, 0.0).is_null_island());
+        assert!(!at(0.0, 10.0).is_null_island());
+    }
+
     #[test]
     fn value_to_ai_result_gps_zero_skipped() {
         let val: serde_json::Value = serde_json::from_str(r#"{
@@ -594,6 +1618,533 @@ Hope this helps!"#;
         assert!(result.gps.is_none()); // 0,0 is filtered out
     }
 
+    #[test]
+    fn parse_multilingual_titles() {
+        let json = r#"{
+            "title": "Eiffel Tower",
+            "description": "The tower at dusk",
+            "titles": {"fr": "Tour Eiffel", "de": "Eiffelturm"},
+            "descriptions": {"fr": "La tour au crépuscule"}
+        }"#;
+        let result = parse_ai_response(json).unwrap();
+        let titles = result.titles.unwrap();
+        assert_eq!(titles["fr"], "Tour Eiffel");
+        assert_eq!(titles["de"], "Eiffelturm");
+        assert_eq!(result.descriptions.unwrap()["fr"], "La tour au crépuscule");
+    }
+
+    #[test]
+    fn value_to_ai_result_skips_null_translations() {
+        let val: serde_json::Value = serde_json::from_str(r#"{
+            "title": "Test",
+            "titles": {"fr": "Essai", "de": null}
+        }"#).unwrap();
+        let result = value_to_ai_result(&val).unwrap();
+        let titles = result.titles.unwrap();
+        assert_eq!(titles.len(), 1);
+        assert_eq!(titles["fr"], "Essai");
+    }
+
+    #[test]
+    fn translation_aliases_are_accepted() {
+        let json = r#"{
+            "title": "Eiffel Tower",
+            "title_translations": {"fr": "Tour Eiffel"},
+            "description_translations": {"de": "Der Turm"}
+        }"#;
+        let result = parse_ai_response(json).unwrap();
+        assert_eq!(result.titles.unwrap()["fr"], "Tour Eiffel");
+        assert_eq!(result.descriptions.unwrap()["de"], "Der Turm");
+
+        let result: AiResult = serde_json::from_str(r#"{"title_translations": {"es": "Torre Eiffel"}}"#).unwrap();
+        assert_eq!(result.titles.unwrap()["es"], "Torre Eiffel");
+    }
+
+    #[test]
+    fn append_language_request_lists_languages() {
+        let mut prompt = build_prompt();
+        append_language_request(&mut prompt, &[]);
+        assert_eq!(prompt, build_prompt());
+
+        append_language_request(&mut prompt, &["fr".into(), "de".into()]);
+        assert!(prompt.contains(r#""fr", "de""#));
+        assert!(prompt.contains(r#""titles": { "fr": "...", "de": "..." }"#));
+    }
+
+    #[test]
+    fn append_output_language_names_the_language() {
+        let mut prompt = build_prompt();
+        append_output_language(&mut prompt, "de-CH");
+        assert!(prompt.ends_with("Respond in German (de-CH): write the title, description, tags and subject in German."));
+
+        let mut prompt = build_prompt();
+        append_output_language(&mut prompt, "tlh");
+        assert!(prompt.ends_with(r#"Respond in the language "tlh": write the title, description, tags and subject in the language "tlh"."#));
+    }
+
+    #[test]
+    fn gps_altitude_is_optional() {
+        let ai = parse_ai_response(r#"{"gps": {"latitude": 27.988, "longitude": 86.925, "altitude": 8848.86}}"#).unwrap();
+        assert_eq!(ai.gps.unwrap().altitude, Some(8848.86));
+        let ai = parse_ai_response(r#"{"gps": {"latitude": 48.85, "longitude": 2.29}}"#).unwrap();
+        assert_eq!(ai.gps.as_ref().unwrap().altitude, None);
+        assert!(!serde_json::to_string(&ai).unwrap().contains("altitude"));
+
+        let val: serde_json::Value =
+            serde_json::from_str(r#"{"gps": {"latitude": 27.988, "longitude": 86.925, "altitude": 8848.86}, "tags": "x"}"#).unwrap();
+        assert_eq!(value_to_ai_result(&val).unwrap().gps.unwrap().altitude, Some(8848.86));
+    }
+
+    #[test]
+    fn date_taken_is_parsed_and_merged() {
+        let ai = parse_ai_response(r#"{"title": "Front page", "date_taken": "1969-07-21"}"#).unwrap();
+        assert_eq!(ai.date_taken.as_deref(), Some("1969-07-21"));
+
+        let val: serde_json::Value = serde_json::from_str(r#"{"date_taken": "1969-07-21", "tags": "x"}"#).unwrap();
+        assert_eq!(value_to_ai_result(&val).unwrap().date_taken.as_deref(), Some("1969-07-21"));
+
+        let merged = AiResult::default().merge(ai);
+        assert_eq!(merged.date_taken.as_deref(), Some("1969-07-21"));
+        assert!(!serde_json::to_string(&AiResult::default()).unwrap().contains("date_taken"));
+    }
+
+    #[test]
+    fn hierarchical_tags_are_parsed_and_normalized() {
+        let ai = parse_ai_response(
+            r#"{"tags": ["owl"], "hierarchical_tags": [" Nature | Birds |Owl", "||", "Places|"]}"#,
+        ).unwrap();
+        assert_eq!(ai.hierarchical_tags, Some(vec!["Nature|Birds|Owl".to_string(), "Places".to_string()]));
+        assert!(!serde_json::to_string(&AiResult::default()).unwrap().contains("hierarchical_tags"));
+    }
+
+    // ── Tag style ────────────────────────────────────────────────────
+
+    fn strings(items: &[&str]) -> Vec<String> {
+        items.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn append_tag_style_adds_instruction() {
+        let mut prompt = build_prompt();
+        append_tag_style(&mut prompt, TagStyle::Keywords);
+        assert!(prompt.contains("single word"));
+
+        let mut prompt = build_prompt();
+        append_tag_style(&mut prompt, TagStyle::Phrases);
+        assert!(prompt.contains("descriptive phrase"));
+    }
+
+    #[test]
+    fn normalize_tags_keywords_splits_phrases() {
+        let tags = strings(&["Golden Gate Bridge", "sunset over the bay", "bridge", "  fog, "]);
+        assert_eq!(
+            normalize_tags(&tags, TagStyle::Keywords),
+            strings(&["Golden", "Gate", "Bridge", "sunset", "over", "bay", "fog"])
+        );
+    }
+
+    #[test]
+    fn normalize_tags_phrases_keeps_phrases() {
+        let tags = strings(&[" Golden Gate Bridge ", "golden gate bridge", "", "fog"]);
+        assert_eq!(normalize_tags(&tags, TagStyle::Phrases), strings(&["Golden Gate Bridge", "fog"]));
+    }
+
+    #[test]
+    fn tag_style_serializes_lowercase() {
+        assert_eq!(serde_json::to_string(&TagStyle::Keywords).unwrap(), r#""keywords""#);
+        assert_eq!(serde_json::from_str::<TagStyle>(r#""phrases""#).unwrap(), TagStyle::Phrases);
+    }
+
+    /// Returns the base64 data and MIME type it was given.
+    struct Echo;
+
+    #[async_trait::async_trait]
+    impl AiService for Echo {
+        fn name(&self) -> &str {
+            "Echo"
+        }
+
+        async fn analyze(&self, image_base64: &str, _prompt: &str, mime_type: &str) -> Result<AiResult> {
+            Ok(AiResult { title: Some(image_base64.into()), description: Some(mime_type.into()), ..Default::default() })
+        }
+    }
+
+    #[tokio::test]
+    async fn analyze_bytes_encodes_and_delegates() {
+        let result = Echo.analyze_bytes(b"hello", "prompt", "image/png").await.unwrap();
+        assert_eq!(result.title.as_deref(), Some("aGVsbG8="));
+        assert_eq!(result.description.as_deref(), Some("image/png"));
+
+        let boxed: Box<dyn AiService> = Box::new(Echo);
+        let result = boxed.analyze_bytes(b"hello", "prompt", "image/png").await.unwrap();
+        assert_eq!(result.title.as_deref(), Some("aGVsbG8="));
+    }
+
+    // ── Extra headers ────────────────────────────────────────────────
+
+    fn gateway_headers() -> BTreeMap<String, String> {
+        BTreeMap::from([
+            ("X-Request-ID".to_string(), "req-42".to_string()),
+            ("X-Gateway-Auth".to_string(), "gw-token".to_string()),
+        ])
+    }
+
+    fn assert_gateway_headers(request: reqwest::Request) {
+        let headers = request.headers();
+        assert_eq!(headers["x-request-id"], "req-42");
+        assert_eq!(headers["x-gateway-auth"], "gw-token");
+    }
+
+    #[test]
+    fn services_send_extra_headers() {
+        let body = serde_json::json!({});
+
+        let openai = OpenAiService::new("sk-test".into(), "gpt-4o-mini".into()).with_headers(gateway_headers());
+        let request = openai.request(&body).build().unwrap();
+        assert_eq!(request.headers()["authorization"], "Bearer sk-test");
+        assert_gateway_headers(request);
+
+        let gemini = GeminiService::new("key".into(), "gemini-2.0-flash".into()).with_headers(gateway_headers());
+        assert_gateway_headers(gemini.request(&body).build().unwrap());
+
+        let claude = ClaudeService::new("sk-ant".into(), "claude-3-5-sonnet-latest".into()).with_headers(gateway_headers());
+        let request = claude.request(&body).build().unwrap();
+        assert_eq!(request.url().as_str(), "https://api.anthropic.com/v1/messages");
+        assert_eq!(request.headers()["x-api-key"], "sk-ant");
+        assert_eq!(request.headers()["anthropic-version"], "2023-06-01");
+        assert_gateway_headers(request);
+
+        let cloudflare = CloudflareService::new("acct".into(), "token".into(), "@cf/model".into())
+            .with_headers(gateway_headers());
+        let request = cloudflare.request(&body).build().unwrap();
+        assert_eq!(request.headers()["authorization"], "Bearer token");
+        assert_gateway_headers(request);
+
+        let ollama = OllamaService::new(DEFAULT_OLLAMA_URL.into(), "llava".into()).with_headers(gateway_headers());
+        assert_gateway_headers(ollama.request(&body).build().unwrap());
+    }
+
+    #[test]
+    fn openai_posts_to_chat_completions_under_base_url() {
+        let body = serde_json::json!({});
+        let openai = OpenAiService::new("sk-test".into(), "gpt-4o-mini".into());
+        let request = openai.request(&body).build().unwrap();
+        assert_eq!(request.url().as_str(), "https://api.openai.com/v1/chat/completions");
+
+        let router = OpenAiService::new("sk-or".into(), "openai/gpt-4o".into())
+            .with_base_url("https://openrouter.ai/api/v1/".into());
+        let request = router.request(&body).build().unwrap();
+        assert_eq!(request.url().as_str(), "https://openrouter.ai/api/v1/chat/completions");
+        assert_eq!(request.headers()["authorization"], "Bearer sk-or");
+
+        let local = OpenAiService::new(String::new(), "llava".into()).with_base_url("http://localhost:8080/v1".into());
+        assert!(local.request(&body).build().unwrap().headers().get("authorization").is_none());
+
+        let azure = OpenAiService::new("az-key".into(), "gpt-4o".into())
+            .with_base_url("https://my-resource.openai.azure.com/openai/deployments/gpt-4o/chat/completions".into())
+            .with_api_version("2024-06-01".into());
+        let request = azure.request(&body).build().unwrap();
+        assert_eq!(
+            request.url().as_str(),
+            "https://my-resource.openai.azure.com/openai/deployments/gpt-4o/chat/completions?api-version=2024-06-01"
+        );
+        assert_eq!(request.headers()["api-key"], "az-key");
+        assert!(request.headers().get("authorization").is_none());
+    }
+
+    #[test]
+    fn ollama_posts_to_generate_under_base_url() {
+        let body = serde_json::json!({});
+        let local = OllamaService::new(DEFAULT_OLLAMA_URL.into(), "llava".into());
+        assert_eq!(local.request(&body).build().unwrap().url().as_str(), "http://localhost:11434/api/generate");
+        let remote = OllamaService::new("https://gpu-box.lan:8443/ollama/".into(), "llava".into());
+        assert_eq!(remote.request(&body).build().unwrap().url().as_str(), "https://gpu-box.lan:8443/ollama/api/generate");
+
+        let body = local.request_body("aGVsbG8=", "Describe", 1000);
+        assert_eq!(body["model"], "llava");
+        assert_eq!(body["prompt"], "Describe");
+        assert_eq!(body["images"], serde_json::json!(["aGVsbG8="]));
+        assert_eq!(body["stream"], false);
+        assert_eq!(body["options"]["num_predict"], 1000);
+    }
+
+    // ── Retries ──────────────────────────────────────────────────────
+
+    fn server_error() -> anyhow::Error {
+        AiError::ServerError { service: "Flaky".into(), status: 503, body: String::new(), retry_after: None }.into()
+    }
+
+    /// Call `retry_analyze` on a service that fails `failures` times with `error`.
+    async fn retried(max_retries: u32, failures: usize, error: fn() -> anyhow::Error) -> (Result<AiResult>, usize) {
+        let calls = std::sync::atomic::AtomicUsize::new(0);
+        let policy = RetryPolicy { max_retries, base_backoff: Duration::ZERO };
+        let result = retry_analyze(policy, || async {
+            if calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst) < failures {
+                Err(error())
+            } else {
+                Ok(AiResult { title: Some("ok".into()), ..Default::default() })
+            }
+        })
+        .await;
+        (result, calls.into_inner())
+    }
+
+    #[tokio::test]
+    async fn retry_recovers_from_transient_errors() {
+        let (result, calls) = retried(2, 2, server_error).await;
+        assert_eq!(result.unwrap().title.as_deref(), Some("ok"));
+        assert_eq!(calls, 3);
+    }
+
+    #[tokio::test]
+    async fn retry_gives_up_after_max_retries() {
+        let (result, calls) = retried(1, 5, server_error).await;
+        assert!(result.unwrap_err().to_string().contains("503"));
+        assert_eq!(calls, 2);
+
+        let (_, calls) = retried(0, 5, server_error).await;
+        assert_eq!(calls, 1);
+    }
+
+    #[tokio::test]
+    async fn retry_fails_fast_on_client_errors() {
+        let (result, calls) = retried(3, 5, || anyhow::anyhow!("Flaky API error (401 Unauthorized): bad key")).await;
+        assert!(result.unwrap_err().to_string().contains("401"));
+        assert_eq!(calls, 1);
+    }
+
+    #[test]
+    fn backoff_doubles_with_jitter() {
+        let policy = RetryPolicy { max_retries: 5, base_backoff: Duration::from_millis(100) };
+        for attempt in 0..4 {
+            let wait = policy.backoff(attempt);
+            let floor = Duration::from_millis(100 << attempt);
+            assert!(wait >= floor && wait <= floor + Duration::from_millis(100), "{attempt}: {wait:?}");
+        }
+        assert_eq!(policy.backoff(20), MAX_RETRY_WAIT);
+    }
+
+    #[test]
+    fn services_send_no_extra_headers_by_default() {
+        let openai = OpenAiService::new("sk-test".into(), "gpt-4o-mini".into());
+        let request = openai.request(&serde_json::json!({})).build().unwrap();
+        assert!(request.headers().get("x-request-id").is_none());
+    }
+
+    // ── Description format ───────────────────────────────────────────
+
+    #[test]
+    fn description_as_is_only_trims() {
+        assert_eq!(format_description("  A red barn.  ", DescriptionFormat::AsIs), "A red barn.");
+    }
+
+    #[test]
+    fn description_strip_trailing_period() {
+        let format = DescriptionFormat::StripTrailingPeriod;
+        assert_eq!(format_description("A red barn at dusk.", format), "A red barn at dusk");
+        assert_eq!(format_description("Wait for it... ", format), "Wait for it");
+        assert_eq!(format_description("Is it a barn?", format), "Is it a barn?");
+        assert_eq!(format_description("No period", format), "No period");
+    }
+
+    #[test]
+    fn description_first_sentence_only() {
+        let format = DescriptionFormat::FirstSentenceOnly;
+        assert_eq!(format_description("A red barn. Snow covers the field.", format), "A red barn.");
+        assert_eq!(format_description("Is it 3.5 km away? Maybe.", format), "Is it 3.5 km away?");
+        assert_eq!(format_description("Visit example.com today", format), "Visit example.com today");
+        assert_eq!(format_description("Wow! A barn.", format), "Wow!");
+    }
+
+    #[test]
+    fn description_sentence_case() {
+        let format = DescriptionFormat::SentenceCase;
+        assert_eq!(format_description("a red barn. snow covers the Field.", format), "A red barn. Snow covers the Field.");
+        assert_eq!(format_description("A RED BARN AT DUSK", format), "A red barn at dusk");
+        assert_eq!(format_description("\"quiet\" morning, 3.5 km out", format), "\"Quiet\" morning, 3.5 km out");
+    }
+
+    #[test]
+    fn description_format_serializes_kebab_case() {
+        assert_eq!(serde_json::to_string(&DescriptionFormat::FirstSentenceOnly).unwrap(), r#""first-sentence-only""#);
+        assert_eq!(serde_json::from_str::<DescriptionFormat>(r#""as-is""#).unwrap(), DescriptionFormat::AsIs);
+        assert_eq!(DescriptionFormat::default(), DescriptionFormat::AsIs);
+    }
+
+    // ── Truncated JSON ───────────────────────────────────────────────
+
+    #[test]
+    fn truncated_json_detected() {
+        let truncated = r#"{"title": "Red barn", "description": "A barn {in} snow", "tags": ["barn", "snow"#;
+        assert!(parse_ai_response(truncated).is_err());
+        assert!(is_truncated_json(truncated));
+        assert!(is_truncated_json("```json\n{\"title\": \"Red ba"));
+        assert!(is_truncated_json(r#"{"title": "say \"hi\"}"#));
+    }
+
+    #[test]
+    fn complete_or_non_json_not_truncated() {
+        assert!(!is_truncated_json(r#"{"title": "Red barn", "tags": ["a}"]}"#));
+        assert!(!is_truncated_json(r#"{"title": "Red barn",}"#));
+        assert!(!is_truncated_json("Sure! It's a sunset."));
+        assert!(!is_truncated_json(""));
+    }
+
+    #[test]
+    fn remote_services_report_max_tokens() {
+        let openai = OpenAiService::new("k".into(), "m".into());
+        assert_eq!(openai.max_tokens(), Some(DEFAULT_MAX_TOKENS));
+        assert_eq!(openai.with_max_tokens(4000).max_tokens(), Some(4000));
+        let gemini = GeminiService::new("k".into(), "m".into()).with_max_tokens(2500);
+        assert_eq!(gemini.max_tokens(), Some(2500));
+        let cloudflare = CloudflareService::new("a".into(), "t".into(), "m".into());
+        assert_eq!(cloudflare.max_tokens(), Some(DEFAULT_MAX_TOKENS));
+    }
+
+    #[test]
+    fn request_bodies_carry_max_tokens() {
+        let openai = OpenAiService::new("k".into(), "m".into());
+        assert_eq!(openai.request_body("b64", "prompt", "image/jpeg", 1000)["max_tokens"], 1000);
+        assert_eq!(openai.request_body("b64", "prompt", "image/jpeg", 4096)["max_tokens"], 4096);
+
+        let gemini = GeminiService::new("k".into(), "m".into());
+        let body = gemini.request_body("b64", "prompt", "image/jpeg", 4096);
+        assert_eq!(body["generationConfig"]["maxOutputTokens"], 4096);
+
+        let cloudflare = CloudflareService::new("a".into(), "t".into(), "m".into());
+        assert_eq!(cloudflare.request_body("b64", "prompt", 4096)["max_tokens"], 4096);
+
+        let claude = ClaudeService::new("k".into(), "m".into());
+        assert_eq!(claude.request_body("b64", "prompt", "image/jpeg", 4096)["max_tokens"], 4096);
+    }
+
+    #[test]
+    fn request_bodies_use_image_mime_type() {
+        let openai = OpenAiService::new("k".into(), "m".into());
+        let body = openai.request_body("b64", "prompt", "image/png", 1000);
+        assert_eq!(body["messages"][1]["content"][1]["image_url"]["url"], "data:image/png;base64,b64");
+
+        let gemini = GeminiService::new("k".into(), "m".into());
+        for mime_type in ["image/png", "image/heic"] {
+            let body = gemini.request_body("b64", "prompt", mime_type, 1000);
+            assert_eq!(body["contents"][0]["parts"][1]["inline_data"]["mime_type"], mime_type);
+        }
+    }
+
+    #[test]
+    fn claude_body_sends_image_block_with_mime_type() {
+        let claude = ClaudeService::new("k".into(), "claude-3-5-sonnet-latest".into());
+        let body = claude.request_body("aGVsbG8=", "Describe", "image/webp", 1000);
+        assert_eq!(body["model"], "claude-3-5-sonnet-latest");
+        let content = &body["messages"][0]["content"];
+        assert_eq!(content[0]["type"], "image");
+        assert_eq!(content[0]["source"]["type"], "base64");
+        assert_eq!(content[0]["source"]["media_type"], "image/webp");
+        assert_eq!(content[0]["source"]["data"], "aGVsbG8=");
+        assert_eq!(content[1]["text"], "Describe");
+    }
+
+    // ── AiResult merge ───────────────────────────────────────────────
+
+    fn full_result() -> AiResult {
+        AiResult {
+            title: Some("Red barn".into()),
+            description: Some("A red barn in a snowy field.".into()),
+            tags: Some(vec!["barn".into(), "snow".into()]),
+            gps: Some(GpsCoords { latitude: 1.0, longitude: 2.0, altitude: None }),
+            subject: Some(vec!["barn".into()]),
+            date_taken: Some("2024-01-15".into()),
+            hierarchical_tags: Some(vec!["Places|Farm|barn".into()]),
+            titles: Some(BTreeMap::from([("fr".into(), "Grange rouge".into())])),
+            descriptions: None,
+            language: None,
+        }
+    }
+
+    #[test]
+    fn merge_empty_with_full_takes_full() {
+        for merged in [AiResult::default().merge(full_result()), full_result().merge(AiResult::default())] {
+            assert_eq!(merged.title.as_deref(), Some("Red barn"));
+            assert_eq!(merged.description.as_deref(), Some("A red barn in a snowy field."));
+            assert_eq!(merged.tags, Some(vec!["barn".to_string(), "snow".to_string()]));
+            assert_eq!(merged.gps.map(|g| (g.latitude, g.longitude)), Some((1.0, 2.0)));
+            assert_eq!(merged.subject, Some(vec!["barn".to_string()]));
+            assert_eq!(merged.date_taken.as_deref(), Some("2024-01-15"));
+            assert_eq!(merged.hierarchical_tags, Some(vec!["Places|Farm|barn".to_string()]));
+            assert_eq!(merged.titles.unwrap()["fr"], "Grange rouge");
+        }
+    }
+
+    #[test]
+    fn clamp_cuts_at_word_boundaries() {
+        let mut ai = AiResult {
+            title: Some("Golden Gate Bridge at dusk".into()),
+            description: Some("Fog rolls in, covering the bay.".into()),
+            titles: Some(BTreeMap::from([("de".to_string(), "Golden-Gate-Brücke in der Dämmerung".to_string())])),
+            ..Default::default()
+        };
+        assert!(ai.clamp(18, 13));
+        assert_eq!(ai.title.as_deref(), Some("Golden Gate Bridge"));
+        // The trailing comma goes with the cut
+        assert_eq!(ai.description.as_deref(), Some("Fog rolls in"));
+        assert_eq!(ai.titles.as_ref().unwrap()["de"], "Golden-Gate-Brücke");
+
+        // A single word longer than the limit is cut mid-word
+        let mut ai = AiResult { title: Some("Überraschungsei".into()), ..Default::default() };
+        assert!(ai.clamp(5, 254));
+        assert_eq!(ai.title.as_deref(), Some("Überr"));
+
+        let mut ai = full_result();
+        let before = (ai.title.clone(), ai.description.clone());
+        assert!(!ai.clamp(60, 254));
+        assert_eq!((ai.title, ai.description), before);
+    }
+
+    #[test]
+    fn merge_two_empty_stays_empty() {
+        let merged = AiResult::default().merge(AiResult { title: Some("  ".into()), tags: Some(vec![]), ..Default::default() });
+        assert!(merged.title.is_none());
+        assert!(merged.tags.is_none());
+        assert!(merged.gps.is_none());
+    }
+
+    #[test]
+    fn merge_conflicting_text_prefers_longer() {
+        let short = AiResult { title: Some("Barn".into()), description: Some("A long barn description.".into()), ..Default::default() };
+        let long = AiResult { title: Some("Red barn at dusk".into()), description: Some("Barn.".into()), ..Default::default() };
+        let merged = short.merge(long);
+        assert_eq!(merged.title.as_deref(), Some("Red barn at dusk"));
+        assert_eq!(merged.description.as_deref(), Some("A long barn description."));
+
+        // Ties keep the first result
+        let a = AiResult { title: Some("Barn".into()), ..Default::default() };
+        let b = AiResult { title: Some("Farm".into()), ..Default::default() };
+        assert_eq!(a.merge(b).title.as_deref(), Some("Barn"));
+    }
+
+    #[test]
+    fn merge_overlapping_tags_dedupes() {
+        let a = AiResult { tags: Some(vec!["Barn".into(), "snow".into()]), subject: Some(vec!["barn".into()]), ..Default::default() };
+        let b = AiResult { tags: Some(vec!["barn".into(), " dusk ".into(), "SNOW".into(), "".into()]), subject: Some(vec!["horse".into()]), ..Default::default() };
+        let merged = a.merge(b);
+        assert_eq!(merged.tags, Some(vec!["Barn".to_string(), "snow".to_string(), " dusk ".to_string()]));
+        assert_eq!(merged.subject, Some(vec!["barn".to_string(), "horse".to_string()]));
+    }
+
+    #[test]
+    fn merge_keeps_first_gps_and_translations() {
+        let other = AiResult {
+            gps: Some(GpsCoords { latitude: 9.0, longitude: 9.0, altitude: None }),
+            titles: Some(BTreeMap::from([("fr".into(), "Autre".into()), ("de".into(), "Scheune".into())])),
+            ..Default::default()
+        };
+        let merged = full_result().merge(other);
+        assert_eq!(merged.gps.map(|g| g.latitude), Some(1.0));
+        let titles = merged.titles.unwrap();
+        assert_eq!(titles["fr"], "Grange rouge");
+        assert_eq!(titles["de"], "Scheune");
+    }
+
     // ── AiResult default ─────────────────────────────────────────────
 
     #[test]
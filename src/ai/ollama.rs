@@ -0,0 +1,133 @@
+use anyhow::{Context, Result};
+use reqwest::{Client, RequestBuilder};
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use super::{
+    AiResult, AiService, DEFAULT_CONNECT_TIMEOUT, DEFAULT_MAX_TOKENS, IMAGE_PLACEHOLDER, apply_headers,
+    http_client, parse_ai_response, read_response, request_error,
+};
+
+/// Address of an Ollama server running on this machine with default settings.
+pub const DEFAULT_OLLAMA_URL: &str = "http://localhost:11434";
+
+/// Default total request timeout for Ollama. Longer than for hosted services,
+/// since the first request loads the model and CPU inference is slow.
+pub const DEFAULT_OLLAMA_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// A vision model (e.g. `llava`, `llama3.2-vision`) served by
+/// [Ollama](https://ollama.com), locally or on another host.
+pub struct OllamaService {
+    base_url: String,
+    model: String,
+    headers: BTreeMap<String, String>,
+    max_tokens: u32,
+    client: Client,
+}
+
+impl OllamaService {
+    /// Use `model` on the server at `base_url` (e.g. [`DEFAULT_OLLAMA_URL`]).
+    pub fn new(base_url: String, model: String) -> Self {
+        Self {
+            base_url,
+            model,
+            headers: BTreeMap::new(),
+            max_tokens: DEFAULT_MAX_TOKENS,
+            client: http_client(DEFAULT_OLLAMA_TIMEOUT, DEFAULT_CONNECT_TIMEOUT),
+        }
+    }
+
+    /// Send these extra HTTP headers with every request (e.g. for a reverse proxy).
+    pub fn with_headers(mut self, headers: BTreeMap<String, String>) -> Self {
+        self.headers = headers;
+        self
+    }
+
+    /// Limit the length of the model's response (default [`DEFAULT_MAX_TOKENS`]).
+    pub fn with_max_tokens(mut self, max_tokens: u32) -> Self {
+        self.max_tokens = max_tokens;
+        self
+    }
+
+    /// Give up on a request after `timeout` in total, or `connect_timeout`
+    /// to establish the connection (default 5 minutes and 10s).
+    pub fn with_timeouts(mut self, timeout: Duration, connect_timeout: Duration) -> Self {
+        self.client = http_client(timeout, connect_timeout);
+        self
+    }
+
+    /// Build the generate request for `body`.
+    pub(crate) fn request(&self, body: &serde_json::Value) -> RequestBuilder {
+        let url = format!("{}/api/generate", self.base_url.trim_end_matches('/'));
+        apply_headers(self.client.post(url).json(body), &self.headers)
+    }
+
+    /// Build the JSON request body for one image.
+    pub(crate) fn request_body(&self, image_base64: &str, prompt: &str, max_tokens: u32) -> serde_json::Value {
+        json!({
+            "model": self.model,
+            "prompt": prompt,
+            "images": [image_base64],
+            "stream": false,
+            "format": "json",
+            "options": {
+                "num_predict": max_tokens
+            }
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl AiService for OllamaService {
+    fn name(&self) -> &str {
+        "Ollama"
+    }
+
+    fn model(&self) -> Option<&str> {
+        Some(&self.model)
+    }
+
+    fn max_tokens(&self) -> Option<u32> {
+        Some(self.max_tokens)
+    }
+
+    fn supported_mime_types(&self) -> &[&str] {
+        &["image/jpeg", "image/png"]
+    }
+
+    fn request_preview(&self, prompt: &str, _mime_type: &str) -> Option<serde_json::Value> {
+        Some(self.request_body(IMAGE_PLACEHOLDER, prompt, self.max_tokens))
+    }
+
+    async fn analyze(&self, image_base64: &str, prompt: &str, mime_type: &str) -> Result<AiResult> {
+        self.analyze_with_max_tokens(image_base64, prompt, mime_type, self.max_tokens).await
+    }
+
+    async fn analyze_with_max_tokens(
+        &self,
+        image_base64: &str,
+        prompt: &str,
+        _mime_type: &str,
+        max_tokens: u32,
+    ) -> Result<AiResult> {
+        let body = self.request_body(image_base64, prompt, max_tokens);
+
+        let resp = self
+            .request(&body)
+            .send()
+            .await
+            .map_err(|e| request_error(self.name(), e))?;
+
+        let text = read_response(self.name(), resp).await?;
+
+        let json: serde_json::Value =
+            serde_json::from_str(&text).context("Failed to parse Ollama response JSON")?;
+
+        let content = json["response"]
+            .as_str()
+            .context("No content in Ollama response")?;
+
+        parse_ai_response(content)
+    }
+}
@@ -1,13 +1,26 @@
 use anyhow::{Context, Result};
-use reqwest::Client;
+use reqwest::{Client, RequestBuilder};
 use serde_json::json;
+use std::collections::BTreeMap;
+use std::time::Duration;
 
-use super::{AiResult, AiService, parse_ai_response};
+use super::{
+    AiResult, AiService, DEFAULT_CONNECT_TIMEOUT, DEFAULT_MAX_TOKENS, DEFAULT_TIMEOUT, IMAGE_PLACEHOLDER,
+    apply_headers, http_client, parse_ai_response, read_response, request_error,
+};
 
+/// OpenAI's API root, used unless [`OpenAiService::with_base_url`] sets another.
+pub const DEFAULT_OPENAI_URL: &str = "https://api.openai.com/v1";
+
+#[derive(Clone)]
 pub struct OpenAiService {
     api_key: String,
     model: String,
-    client: Client,
+    base_url: String,
+    api_version: Option<String>,
+    headers: BTreeMap<String, String>,
+    pub(super) max_tokens: u32,
+    pub(super) client: Client,
 }
 
 impl OpenAiService {
@@ -15,19 +28,81 @@ impl OpenAiService {
         Self {
             api_key,
             model,
-            client: Client::new(),
+            base_url: DEFAULT_OPENAI_URL.to_string(),
+            api_version: None,
+            headers: BTreeMap::new(),
+            max_tokens: DEFAULT_MAX_TOKENS,
+            client: http_client(DEFAULT_TIMEOUT, DEFAULT_CONNECT_TIMEOUT),
         }
     }
-}
 
-#[async_trait::async_trait]
-impl AiService for OpenAiService {
-    fn name(&self) -> &str {
-        "OpenAI"
+    /// Send these extra HTTP headers with every request (e.g. for a gateway).
+    pub fn with_headers(mut self, headers: BTreeMap<String, String>) -> Self {
+        self.headers = headers;
+        self
     }
 
-    async fn analyze(&self, image_base64: &str, prompt: &str, mime_type: &str) -> Result<AiResult> {
-        let body = json!({
+    /// Send requests to an OpenAI-compatible API at `base_url` (OpenRouter,
+    /// LocalAI, an Azure deployment) instead of [`DEFAULT_OPENAI_URL`].
+    /// Chat completions go to `{base_url}/chat/completions`; a URL that
+    /// already ends in `/chat/completions` is used as is.
+    pub fn with_base_url(mut self, base_url: String) -> Self {
+        self.base_url = base_url;
+        self
+    }
+
+    /// Talk to Azure OpenAI: add `?api-version={api_version}` to every
+    /// request and send the key in an `api-key` header instead of
+    /// `Authorization: Bearer`.
+    pub fn with_api_version(mut self, api_version: String) -> Self {
+        self.api_version = Some(api_version);
+        self
+    }
+
+    /// Limit the length of the model's response (default [`DEFAULT_MAX_TOKENS`]).
+    pub fn with_max_tokens(mut self, max_tokens: u32) -> Self {
+        self.max_tokens = max_tokens;
+        self
+    }
+
+    /// Give up on a request after `timeout` in total, or `connect_timeout`
+    /// to establish the connection (default 60s and 10s).
+    pub fn with_timeouts(mut self, timeout: Duration, connect_timeout: Duration) -> Self {
+        self.client = http_client(timeout, connect_timeout);
+        self
+    }
+
+    /// Build the chat completions request for `body`.
+    pub(crate) fn request(&self, body: &serde_json::Value) -> RequestBuilder {
+        self.authorized(self.client.post(self.url("chat/completions")).json(body))
+    }
+
+    /// The URL of `endpoint` (e.g. `files`) under the base URL.
+    pub(crate) fn url(&self, endpoint: &str) -> String {
+        let base = self.base_url.trim_end_matches('/');
+        let base = base.strip_suffix("/chat/completions").unwrap_or(base);
+        format!("{base}/{endpoint}")
+    }
+
+    /// Add the API key, the Azure API version and any extra headers to a request.
+    pub(crate) fn authorized(&self, builder: RequestBuilder) -> RequestBuilder {
+        let builder = match &self.api_version {
+            Some(version) => builder.query(&[("api-version", version)]).header("api-key", &self.api_key),
+            None if self.api_key.is_empty() => builder,
+            None => builder.header("Authorization", format!("Bearer {}", self.api_key)),
+        };
+        apply_headers(builder, &self.headers)
+    }
+
+    /// Build the JSON request body for one image.
+    pub(crate) fn request_body(
+        &self,
+        image_base64: &str,
+        prompt: &str,
+        mime_type: &str,
+        max_tokens: u32,
+    ) -> serde_json::Value {
+        json!({
             "model": self.model,
             "messages": [
                 {
@@ -51,25 +126,50 @@ impl AiService for OpenAiService {
                     ]
                 }
             ],
-            "max_tokens": 1000,
+            "max_tokens": max_tokens,
             "response_format": { "type": "json_object" }
-        });
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl AiService for OpenAiService {
+    fn name(&self) -> &str {
+        "OpenAI"
+    }
+
+    fn model(&self) -> Option<&str> {
+        Some(&self.model)
+    }
+
+    fn max_tokens(&self) -> Option<u32> {
+        Some(self.max_tokens)
+    }
+
+    fn request_preview(&self, prompt: &str, mime_type: &str) -> Option<serde_json::Value> {
+        Some(self.request_body(IMAGE_PLACEHOLDER, prompt, mime_type, self.max_tokens))
+    }
+
+    async fn analyze(&self, image_base64: &str, prompt: &str, mime_type: &str) -> Result<AiResult> {
+        self.analyze_with_max_tokens(image_base64, prompt, mime_type, self.max_tokens).await
+    }
+
+    async fn analyze_with_max_tokens(
+        &self,
+        image_base64: &str,
+        prompt: &str,
+        mime_type: &str,
+        max_tokens: u32,
+    ) -> Result<AiResult> {
+        let body = self.request_body(image_base64, prompt, mime_type, max_tokens);
 
         let resp = self
-            .client
-            .post("https://api.openai.com/v1/chat/completions")
-            .header("Authorization", format!("Bearer {}", self.api_key))
-            .json(&body)
+            .request(&body)
             .send()
             .await
-            .context("OpenAI request failed")?;
+            .map_err(|e| request_error(self.name(), e))?;
 
-        let status = resp.status();
-        let text = resp.text().await.context("Failed to read OpenAI response")?;
-
-        if !status.is_success() {
-            anyhow::bail!("OpenAI API error ({}): {}", status, text);
-        }
+        let text = read_response(self.name(), resp).await?;
 
         let json: serde_json::Value =
             serde_json::from_str(&text).context("Failed to parse OpenAI response JSON")?;
@@ -0,0 +1,408 @@
+//! OpenAI [Batch API](https://platform.openai.com/docs/guides/batch) client.
+//!
+//! Batch requests cost half as much as synchronous chat completions, but run
+//! asynchronously: OpenAI promises results within 24 hours, and a batch of any
+//! size usually takes minutes to hours. The flow is
+//!
+//! 1. build one JSONL line per image ([`OpenAiBatch::request_line`]),
+//! 2. upload the file and create a batch,
+//! 3. poll its status with backoff until it finishes,
+//! 4. download the output and map each line back by its `custom_id`.
+//!
+//! [`Pipeline::process_images_batched`](crate::pipeline::Pipeline::process_images_batched)
+//! drives this for a list of images.
+
+use anyhow::{Context, Result};
+use serde_json::json;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use super::{AiResult, AiService, OpenAiService, WEB_MIME_TYPES, parse_ai_response};
+
+/// The endpoint each batch line is sent to.
+const BATCH_ENDPOINT: &str = "/v1/chat/completions";
+/// OpenAI's limits on one batch input file.
+const MAX_BATCH_FILE_BYTES: usize = 190 * 1024 * 1024;
+const MAX_BATCH_REQUESTS: usize = 50_000;
+/// Timeout for uploading inputs and downloading results, which can be far
+/// larger than a chat completion and outlast the service's own timeout.
+const TRANSFER_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// One image to analyze in a batch.
+#[derive(Debug, Clone)]
+pub struct BatchRequest {
+    /// Caller-chosen ID used to match the result to the image.
+    pub custom_id: String,
+    pub image_base64: String,
+    pub prompt: String,
+    pub mime_type: String,
+}
+
+impl OpenAiService {
+    /// Analyze `(image, prompt)` pairs in one OpenAI batch and return the
+    /// results in the same order. A thin wrapper over [`OpenAiBatch::run`].
+    ///
+    /// This waits until the batch finishes: minutes to hours, not seconds.
+    /// It fails as a whole if any image can't be read or has no result; use
+    /// [`OpenAiBatch`] directly for per-image results.
+    ///
+    /// ```rust,no_run
+    /// use exif_ai::ai::{build_prompt, OpenAiService};
+    ///
+    /// # async fn example() -> anyhow::Result<()> {
+    /// let service = OpenAiService::new("sk-...".into(), "gpt-4o-mini".into());
+    /// let results = service.analyze_batch(&[("photo.jpg".into(), build_prompt())]).await?;
+    /// println!("{:?}", results[0].title);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn analyze_batch(&self, images: &[(PathBuf, String)]) -> Result<Vec<AiResult>> {
+        let requests = images
+            .iter()
+            .enumerate()
+            .map(|(i, (path, prompt))| {
+                let (image_base64, mime_type) = crate::pipeline::upload_image(path, None)
+                    .with_context(|| format!("Cannot read {}", path.display()))?;
+                Ok(BatchRequest { custom_id: i.to_string(), image_base64, prompt: prompt.clone(), mime_type: mime_type.into() })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut results = OpenAiBatch::new(self.clone()).run(&requests).await?;
+        images
+            .iter()
+            .enumerate()
+            .map(|(i, (path, _))| match results.remove(&i.to_string()) {
+                Some(result) => result.with_context(|| format!("Batch analysis of {} failed", path.display())),
+                None => anyhow::bail!("No batch result for {}", path.display()),
+            })
+            .collect()
+    }
+}
+
+/// Runs image analyses through the OpenAI Batch API.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use exif_ai::ai::{BatchRequest, OpenAiBatch, OpenAiService};
+///
+/// # async fn example() -> anyhow::Result<()> {
+/// let batch = OpenAiBatch::new(OpenAiService::new("sk-...".into(), "gpt-4o-mini".into()));
+/// let requests = vec![BatchRequest {
+///     custom_id: "0".into(),
+///     image_base64: "...".into(),
+///     prompt: exif_ai::ai::build_prompt(),
+///     mime_type: "image/jpeg".into(),
+/// }];
+/// for (id, result) in batch.run(&requests).await? {
+///     println!("{id}: {:?}", result.map(|ai| ai.title));
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct OpenAiBatch {
+    service: OpenAiService,
+    poll_interval: Duration,
+    max_poll_interval: Duration,
+}
+
+impl OpenAiBatch {
+    /// Send `service`'s requests (model, token limit, headers, base URL) as a batch.
+    pub fn new(service: OpenAiService) -> Self {
+        Self {
+            service,
+            poll_interval: Duration::from_secs(30),
+            max_poll_interval: Duration::from_secs(600),
+        }
+    }
+
+    /// Wait `initial` before the first status check, doubling the wait up to
+    /// `max` while the batch is still running. Defaults to 30s and 10 minutes.
+    pub fn with_poll_interval(mut self, initial: Duration, max: Duration) -> Self {
+        self.poll_interval = initial;
+        self.max_poll_interval = max.max(initial);
+        self
+    }
+
+    /// Name reported as the service that answered.
+    pub fn name(&self) -> &str {
+        "OpenAI Batch"
+    }
+
+    /// The model the batch runs.
+    pub fn model(&self) -> Option<&str> {
+        self.service.model()
+    }
+
+    /// Whether the batch can analyze images of this type.
+    pub fn supports_mime_type(&self, mime_type: &str) -> bool {
+        WEB_MIME_TYPES.contains(&mime_type)
+    }
+
+    /// The JSONL line for one request: the same chat completions body the
+    /// synchronous service sends, tagged with its `custom_id`.
+    pub fn request_line(&self, request: &BatchRequest) -> serde_json::Value {
+        json!({
+            "custom_id": request.custom_id,
+            "method": "POST",
+            "url": BATCH_ENDPOINT,
+            "body": self.service.request_body(
+                &request.image_base64,
+                &request.prompt,
+                &request.mime_type,
+                self.service.max_tokens,
+            ),
+        })
+    }
+
+    /// The batch input files for `requests`, split to stay within OpenAI's
+    /// size and request-count limits.
+    pub fn build_jsonl(&self, requests: &[BatchRequest]) -> Vec<String> {
+        let lines = requests.iter().map(|r| self.request_line(r).to_string());
+        chunk_lines(lines, MAX_BATCH_FILE_BYTES, MAX_BATCH_REQUESTS)
+    }
+
+    /// Submit `requests`, wait for the batch to finish and return each
+    /// request's result by `custom_id`.
+    ///
+    /// Requests missing from the output (e.g. when the batch expired first)
+    /// are absent from the map.
+    pub async fn run(&self, requests: &[BatchRequest]) -> Result<HashMap<String, Result<AiResult>>> {
+        let mut batch_ids = Vec::new();
+        for jsonl in self.build_jsonl(requests) {
+            let file_id = self.upload(jsonl).await?;
+            batch_ids.push(self.create(&file_id).await?);
+        }
+
+        let mut results = HashMap::new();
+        for batch_id in batch_ids {
+            let batch = self.wait(&batch_id).await?;
+            for key in ["output_file_id", "error_file_id"] {
+                if let Some(file_id) = batch[key].as_str() {
+                    results.extend(parse_output(&self.download(file_id).await?));
+                }
+            }
+        }
+        Ok(results)
+    }
+
+    /// Upload a JSONL input file; returns its file ID.
+    async fn upload(&self, jsonl: String) -> Result<String> {
+        let boundary = format!(
+            "exif-ai-batch-{:x}",
+            std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_nanos()
+        );
+        let mut body = format!(
+            "--{boundary}\r\nContent-Disposition: form-data; name=\"purpose\"\r\n\r\nbatch\r\n\
+             --{boundary}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"exif-ai-batch.jsonl\"\r\n\
+             Content-Type: application/jsonl\r\n\r\n"
+        );
+        body.push_str(&jsonl);
+        body.push_str(&format!("\r\n--{boundary}--\r\n"));
+
+        let request = self
+            .authorized(self.service.client.post(self.service.url("files")))
+            .header("Content-Type", format!("multipart/form-data; boundary={boundary}"))
+            .timeout(TRANSFER_TIMEOUT)
+            .body(body);
+        let file = send_json(request, "upload batch input").await?;
+        file["id"].as_str().map(String::from).context("No file ID in OpenAI upload response")
+    }
+
+    /// Create a batch for an uploaded input file; returns the batch ID.
+    async fn create(&self, file_id: &str) -> Result<String> {
+        let request = self.authorized(self.service.client.post(self.service.url("batches"))).json(&json!({
+            "input_file_id": file_id,
+            "endpoint": BATCH_ENDPOINT,
+            "completion_window": "24h",
+        }));
+        let batch = send_json(request, "create batch").await?;
+        let id = batch["id"].as_str().context("No batch ID in OpenAI response")?;
+        log::info!("Submitted OpenAI batch {id}");
+        Ok(id.to_string())
+    }
+
+    /// Poll a batch with backoff until it stops running; returns the batch object.
+    async fn wait(&self, batch_id: &str) -> Result<serde_json::Value> {
+        let mut interval = self.poll_interval;
+        loop {
+            tokio::time::sleep(interval).await;
+            let request = self.authorized(self.service.client.get(self.service.url(&format!("batches/{batch_id}"))));
+            let batch = send_json(request, "check batch status").await?;
+            let status = batch["status"].as_str().unwrap_or_default();
+            match status {
+                "completed" => return Ok(batch),
+                // An expired batch still returns the requests that finished
+                "expired" if batch["output_file_id"].is_string() => {
+                    log::warn!("OpenAI batch {batch_id} expired; using the results that finished");
+                    return Ok(batch);
+                }
+                "failed" | "expired" | "cancelling" | "cancelled" => {
+                    let errors = batch["errors"]["data"]
+                        .as_array()
+                        .map(|e| e.iter().filter_map(|e| e["message"].as_str()).collect::<Vec<_>>().join("; "))
+                        .unwrap_or_default();
+                    anyhow::bail!("OpenAI batch {batch_id} {status}: {errors}");
+                }
+                _ => {
+                    let counts = &batch["request_counts"];
+                    log::info!(
+                        "OpenAI batch {batch_id} {status} ({}/{} done)",
+                        counts["completed"].as_u64().unwrap_or(0) + counts["failed"].as_u64().unwrap_or(0),
+                        counts["total"].as_u64().unwrap_or(0),
+                    );
+                    interval = (interval * 2).min(self.max_poll_interval);
+                }
+            }
+        }
+    }
+
+    /// Download the contents of a batch output or error file.
+    async fn download(&self, file_id: &str) -> Result<String> {
+        let request = self
+            .authorized(self.service.client.get(self.service.url(&format!("files/{file_id}/content"))))
+            .timeout(TRANSFER_TIMEOUT);
+        let resp = request.send().await.context("OpenAI request failed")?;
+        let status = resp.status();
+        let text = resp.text().await.context("Failed to read OpenAI response")?;
+        if !status.is_success() {
+            anyhow::bail!("OpenAI API error ({status}) downloading batch results: {text}");
+        }
+        Ok(text)
+    }
+
+    fn authorized(&self, builder: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        self.service.authorized(builder)
+    }
+}
+
+/// Send a request and parse its JSON response, failing on HTTP errors.
+async fn send_json(request: reqwest::RequestBuilder, action: &str) -> Result<serde_json::Value> {
+    let resp = request.send().await.context("OpenAI request failed")?;
+    let status = resp.status();
+    let text = resp.text().await.context("Failed to read OpenAI response")?;
+    if !status.is_success() {
+        anyhow::bail!("OpenAI API error ({status}) trying to {action}: {text}");
+    }
+    serde_json::from_str(&text).context("Failed to parse OpenAI response JSON")
+}
+
+/// Join `lines` into JSONL files of at most `max_bytes` and `max_lines` each.
+fn chunk_lines(lines: impl Iterator<Item = String>, max_bytes: usize, max_lines: usize) -> Vec<String> {
+    let mut files = Vec::new();
+    let mut current = String::new();
+    let mut count = 0;
+    for line in lines {
+        if count > 0 && (current.len() + line.len() + 1 > max_bytes || count == max_lines) {
+            files.push(std::mem::take(&mut current));
+            count = 0;
+        }
+        current.push_str(&line);
+        current.push('\n');
+        count += 1;
+    }
+    if count > 0 {
+        files.push(current);
+    }
+    files
+}
+
+/// Map each line of a batch output (or error) file to its `custom_id`.
+fn parse_output(jsonl: &str) -> HashMap<String, Result<AiResult>> {
+    jsonl
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| {
+            let entry: serde_json::Value = serde_json::from_str(line).ok()?;
+            let id = entry["custom_id"].as_str()?.to_string();
+            let response = &entry["response"];
+            let result = if let Some(message) = entry["error"]["message"].as_str() {
+                Err(anyhow::anyhow!("OpenAI batch error: {message}"))
+            } else if response["status_code"].as_u64() != Some(200) {
+                Err(anyhow::anyhow!(
+                    "OpenAI API error ({}): {}",
+                    response["status_code"],
+                    response["body"]["error"]["message"].as_str().unwrap_or("no message")
+                ))
+            } else {
+                response["body"]["choices"][0]["message"]["content"]
+                    .as_str()
+                    .context("No content in OpenAI response")
+                    .and_then(parse_ai_response)
+            };
+            Some((id, result))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(id: &str) -> BatchRequest {
+        BatchRequest {
+            custom_id: id.into(),
+            image_base64: "aGVsbG8=".into(),
+            prompt: "Describe this image".into(),
+            mime_type: "image/png".into(),
+        }
+    }
+
+    #[test]
+    fn jsonl_lines_wrap_chat_completion_bodies() {
+        let batch = OpenAiBatch::new(OpenAiService::new("sk-test".into(), "gpt-4o-mini".into()).with_max_tokens(321));
+        let files = batch.build_jsonl(&[request("0"), request("7")]);
+        assert_eq!(files.len(), 1);
+
+        let lines: Vec<serde_json::Value> =
+            files[0].lines().map(|l| serde_json::from_str(l).expect("each line is JSON")).collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[1]["custom_id"], "7");
+        assert_eq!(lines[0]["method"], "POST");
+        assert_eq!(lines[0]["url"], "/v1/chat/completions");
+
+        let body = &lines[0]["body"];
+        assert_eq!(body["model"], "gpt-4o-mini");
+        assert_eq!(body["max_tokens"], 321);
+        assert_eq!(body["messages"][1]["content"][0]["text"], "Describe this image");
+        assert_eq!(body["messages"][1]["content"][1]["image_url"]["url"], "data:image/png;base64,aGVsbG8=");
+    }
+
+    #[test]
+    fn jsonl_split_at_limits() {
+        let lines = || ["a".repeat(10), "b".repeat(10), "c".repeat(10)].into_iter();
+        assert_eq!(chunk_lines(lines(), 1000, 2).len(), 2);
+        let files = chunk_lines(lines(), 25, 100);
+        assert_eq!(files, [format!("{}\n{}\n", "a".repeat(10), "b".repeat(10)), format!("{}\n", "c".repeat(10))]);
+        // A line over the limit still gets a file of its own
+        assert_eq!(chunk_lines(lines(), 5, 100).len(), 3);
+    }
+
+    #[test]
+    fn output_mapped_by_custom_id() {
+        let output = concat!(
+            r#"{"custom_id":"0","response":{"status_code":200,"body":{"choices":[{"message":{"content":"{\"title\":\"Barn\",\"description\":\"A red barn\"}"}}]}},"error":null}"#,
+            "\n",
+            r#"{"custom_id":"1","response":{"status_code":400,"body":{"error":{"message":"Invalid image"}}},"error":null}"#,
+            "\n",
+            r#"{"custom_id":"2","response":null,"error":{"code":"batch_expired","message":"This request could not be executed before the completion window expired."}}"#,
+            "\n",
+        );
+        let results = parse_output(output);
+        assert_eq!(results.len(), 3);
+        assert_eq!(results["0"].as_ref().unwrap().title.as_deref(), Some("Barn"));
+        assert!(results["1"].as_ref().unwrap_err().to_string().contains("Invalid image"));
+        assert!(results["2"].as_ref().unwrap_err().to_string().contains("completion window"));
+    }
+
+    #[tokio::test]
+    async fn analyze_batch_fails_before_upload_on_unreadable_image() {
+        // No server listens here, so reaching the upload would fail differently
+        let service = OpenAiService::new("sk-test".into(), "gpt-4o-mini".into())
+            .with_base_url("http://127.0.0.1:9".into());
+        let images = [(PathBuf::from("/nonexistent/photo.jpg"), "prompt".to_string())];
+        let err = service.analyze_batch(&images).await.unwrap_err();
+        assert_eq!(err.to_string(), "Cannot read /nonexistent/photo.jpg");
+    }
+}
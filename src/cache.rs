@@ -0,0 +1,214 @@
+//! Cache of AI results keyed by image content.
+//!
+//! Re-running over a folder would otherwise pay for every image again. An
+//! [`AnalysisCache`] maps the SHA-256 hash of an image file to the [`AiResult`]
+//! the AI returned for it, so an image whose bytes haven't changed is served
+//! from disk. Any change to the file gives it a new hash, and with it a fresh
+//! analysis.
+//!
+//! ```rust,no_run
+//! use exif_ai::cache::{AnalysisCache, cache_path};
+//! use exif_ai::config::Config;
+//! use exif_ai::pipeline::Pipeline;
+//! use std::path::Path;
+//!
+//! # fn example() -> anyhow::Result<()> {
+//! let config_path = Path::new("config.json");
+//! let config = Config::load(Some(config_path))?;
+//! let pipeline = Pipeline::builder()
+//!     .from_config(&config)
+//!     .analysis_cache(AnalysisCache::load(&cache_path(config_path))?)
+//!     .build()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::ai::AiResult;
+
+/// Name of the cache file, stored next to the config file.
+pub const CACHE_FILE_NAME: &str = "exif-ai-cache.json";
+
+/// [`ProcessResult::ai_service_used`](crate::pipeline::ProcessResult::ai_service_used)
+/// for images whose result came from the cache.
+pub const CACHE_SERVICE_NAME: &str = "cache";
+
+/// Bumped when the file layout or the key hash changes; older files are discarded.
+const CACHE_VERSION: u32 = 2;
+
+/// The cache file for the config at `config_path`.
+pub fn cache_path(config_path: &Path) -> PathBuf {
+    config_path.with_file_name(CACHE_FILE_NAME)
+}
+
+/// Hash a file's contents, as used for cache keys.
+pub fn hash_file(path: &Path) -> Result<String> {
+    use sha2::{Digest, Sha256};
+    let mut file = std::fs::File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher).with_context(|| format!("Failed to read {}", path.display()))?;
+    Ok(hasher.finalize().iter().map(|b| format!("{b:02x}")).collect())
+}
+
+#[derive(Deserialize)]
+struct CacheFile {
+    version: u32,
+    entries: BTreeMap<String, AiResult>,
+}
+
+/// AI results by content hash, loaded from and saved to a JSON file.
+///
+/// Safe to share between concurrently processed images. Changes are written
+/// by [`save`](Self::save), and on drop if they haven't been yet.
+#[derive(Debug)]
+pub struct AnalysisCache {
+    path: PathBuf,
+    state: Mutex<State>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    entries: BTreeMap<String, AiResult>,
+    dirty: bool,
+}
+
+impl AnalysisCache {
+    /// Load the cache at `path`. A missing file starts an empty cache, as
+    /// does one that can't be parsed (it is replaced on the next save).
+    pub fn load(path: &Path) -> Result<Self> {
+        let entries = match std::fs::read_to_string(path) {
+            Ok(contents) => match serde_json::from_str::<CacheFile>(&contents) {
+                Ok(file) if file.version == CACHE_VERSION => file.entries,
+                Ok(file) => {
+                    log::info!("Discarding analysis cache {} (version {})", path.display(), file.version);
+                    BTreeMap::new()
+                }
+                Err(e) => {
+                    log::warn!("Ignoring unreadable analysis cache {}: {e}", path.display());
+                    BTreeMap::new()
+                }
+            },
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => return Err(e).with_context(|| format!("Failed to read cache {}", path.display())),
+        };
+        Ok(Self { path: path.to_path_buf(), state: Mutex::new(State { entries, dirty: false }) })
+    }
+
+    /// The file the cache is saved to.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The cached result for a content hash.
+    pub fn get(&self, hash: &str) -> Option<AiResult> {
+        self.lock().entries.get(hash).cloned()
+    }
+
+    /// Remember `result` for a content hash, replacing any earlier one.
+    pub fn insert(&self, hash: String, result: AiResult) {
+        let mut state = self.lock();
+        state.entries.insert(hash, result);
+        state.dirty = true;
+    }
+
+    /// Number of cached results.
+    pub fn len(&self) -> usize {
+        self.lock().entries.len()
+    }
+
+    /// Whether the cache holds no results.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Write the cache to its file if it has changed since the last save.
+    pub fn save(&self) -> Result<()> {
+        let mut state = self.lock();
+        if !state.dirty {
+            return Ok(());
+        }
+        let contents = serde_json::to_vec(&serde_json::json!({
+            "version": CACHE_VERSION,
+            "entries": &state.entries,
+        }))?;
+        crate::exif::write_atomic(&self.path, &contents)
+            .with_context(|| format!("Failed to write cache {}", self.path.display()))?;
+        state.dirty = false;
+        Ok(())
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        // The state stays consistent even if a holder panicked
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Drop for AnalysisCache {
+    fn drop(&mut self) {
+        if let Err(e) = self.save() {
+            log::warn!("{e:#}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn titled(title: &str) -> AiResult {
+        AiResult { title: Some(title.into()), ..Default::default() }
+    }
+
+    #[test]
+    fn round_trips_through_its_file() {
+        let dir = TempDir::new().unwrap();
+        let path = cache_path(&dir.path().join("config.json"));
+        assert_eq!(path, dir.path().join(CACHE_FILE_NAME));
+
+        let cache = AnalysisCache::load(&path).unwrap();
+        assert!(cache.is_empty());
+        cache.insert("abc".into(), titled("Barn"));
+        cache.save().unwrap();
+
+        let cache = AnalysisCache::load(&path).unwrap();
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.get("abc").unwrap().title.as_deref(), Some("Barn"));
+        assert!(cache.get("def").is_none());
+    }
+
+    #[test]
+    fn saves_on_drop() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join(CACHE_FILE_NAME);
+        AnalysisCache::load(&path).unwrap().insert("abc".into(), titled("Barn"));
+        assert_eq!(AnalysisCache::load(&path).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn unreadable_or_outdated_files_start_empty() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join(CACHE_FILE_NAME);
+        for contents in ["not json", r#"{"version": 1, "entries": {"abc": {"title": "Barn"}}}"#] {
+            std::fs::write(&path, contents).unwrap();
+            assert!(AnalysisCache::load(&path).unwrap().is_empty());
+        }
+    }
+
+    #[test]
+    fn hash_changes_with_contents() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("a.jpg");
+        std::fs::write(&path, b"one").unwrap();
+        let first = hash_file(&path).unwrap();
+        assert_eq!(first, hash_file(&path).unwrap());
+        assert_eq!(first, "7692c3ad3540bb803c020b3aee66cd8887123234ea0c6e7143c0add73ff431ed");
+        std::fs::write(&path, b"two").unwrap();
+        assert_ne!(first, hash_file(&path).unwrap());
+    }
+}
@@ -1,7 +1,11 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use clap::Parser;
-use std::path::PathBuf;
+use std::io::Read;
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
 
+use exif_ai::ai::{cost, AiResult};
+use exif_ai::cache::{self, AnalysisCache};
 use exif_ai::{config, exif, pipeline};
 
 #[derive(Parser, Debug)]
@@ -23,18 +27,50 @@ struct Cli {
     #[arg(long)]
     init: bool,
 
+    /// Print the loaded config with API keys and credential headers masked, and exit
+    #[arg(long = "print-config")]
+    print_config: bool,
+
+    /// Print the prompt that would be sent (for PATH's metadata, if given) and exit
+    #[arg(long = "dump-prompt")]
+    dump_prompt: bool,
+
+    /// With --dump-prompt, print the full request body this service would send (e.g. openai, claude)
+    #[arg(long, value_name = "NAME", requires = "dump_prompt")]
+    service: Option<String>,
+
     /// Preview changes without writing to files
     #[arg(long)]
     dry_run: bool,
 
+    /// Replace existing title/description/tags for this run (overrides `exif_fields.overwrite_existing`)
+    #[arg(long, conflicts_with = "no_overwrite")]
+    overwrite: bool,
+
+    /// Only fill in fields the image doesn't already have for this run
+    #[arg(long = "no-overwrite")]
+    no_overwrite: bool,
+
     /// Output results as JSON
     #[arg(long)]
     json: bool,
 
+    /// Write a spreadsheet of the results to FILE (tab-separated if it ends in .tsv)
+    #[arg(long, value_name = "FILE")]
+    csv: Option<PathBuf>,
+
+    /// Explain, per image, why each field was written or skipped
+    #[arg(long)]
+    explain: bool,
+
     /// Verbose output
     #[arg(short, long)]
     verbose: bool,
 
+    /// Only log warnings and errors; progress and previews are suppressed
+    #[arg(short, long, conflicts_with = "verbose")]
+    quiet: bool,
+
     /// Display all EXIF metadata and exit
     #[arg(long = "show-exif")]
     show_exif: bool,
@@ -46,18 +82,72 @@ struct Cli {
     /// Download the local BLIP model for offline inference
     #[arg(long = "download-model")]
     download_model: bool,
+
+    /// Skip the AI and write only config-derived metadata (filename title, folder keyword, aspect class, copyright, artist, IPTC dates)
+    #[arg(long = "no-ai")]
+    no_ai: bool,
+
+    /// Call the AI for every image even if `output.enable_cache` is set (the cache is neither read nor updated)
+    #[arg(long = "no-cache")]
+    no_cache: bool,
+
+    /// Threads for local CPU inference (overrides `local.cpu_threads`)
+    #[arg(long, value_name = "N")]
+    threads: Option<NonZeroUsize>,
+
+    /// Descend at most N levels of subdirectories (0 = only the given directories' own files)
+    #[arg(long = "recursive-depth", value_name = "N")]
+    recursive_depth: Option<usize>,
+
+    /// Also collect hidden files and walk hidden directories (names starting with `.`)
+    #[arg(long = "include-hidden")]
+    include_hidden: bool,
+
+    /// Skip files and directories whose name matches a glob, e.g. `--exclude '*-thumbnail.jpg'` (repeatable)
+    #[arg(long, value_name = "PATTERN")]
+    exclude: Vec<String>,
+
+    /// Process only the first N images, in collected order
+    #[arg(long, value_name = "N", conflicts_with = "sample")]
+    limit: Option<NonZeroUsize>,
+
+    /// Process N images picked at random
+    #[arg(long, value_name = "N")]
+    sample: Option<NonZeroUsize>,
+
+    /// Read newline-delimited image paths from stdin (e.g. `find . -name '*.jpg' | exif-ai --stdin`)
+    #[arg(long)]
+    stdin: bool,
+
+    /// With --stdin, paths are NUL-delimited (`find -print0`, `fd -0`)
+    #[arg(long, requires = "stdin")]
+    null: bool,
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let cli = Cli::parse();
+    let mut cli = Cli::parse();
 
     // Set up logging
-    let log_level = if cli.verbose { "debug" } else { "info" };
+    // Logs go to stderr so that `--json` output on stdout stays machine-readable
+    let log_level = if cli.verbose {
+        "debug"
+    } else if cli.quiet {
+        "warn"
+    } else {
+        "info"
+    };
     env_logger::Builder::from_env(env_logger::Env::default().default_filter_or(log_level))
         .format_timestamp(None)
+        .target(env_logger::Target::Stderr)
         .init();
 
+    let collect_options = pipeline::CollectOptions {
+        max_depth: cli.recursive_depth,
+        include_hidden: cli.include_hidden,
+        exclude_patterns: cli.exclude.clone(),
+    };
+
     // Handle --download-model
     if cli.download_model {
         let config = config::Config::load(cli.config.as_deref())?;
@@ -76,6 +166,7 @@ async fn main() -> Result<()> {
     // Handle --init
     if cli.init {
         let config = config::Config::default();
+        config.validate()?;
         let path = cli.config.as_deref();
         config.save(path)?;
         let save_path = match path {
@@ -86,6 +177,55 @@ async fn main() -> Result<()> {
         return Ok(());
     }
 
+    // Handle --print-config
+    if cli.print_config {
+        let config = config::Config::load(cli.config.as_deref())?;
+        println!("{}", serde_json::to_string_pretty(&config.redacted())?);
+        return Ok(());
+    }
+
+    // Handle --stdin
+    if cli.stdin {
+        let stdin_paths = read_paths(std::io::stdin().lock(), cli.null)?;
+        cli.paths.extend(stdin_paths);
+    }
+
+    // Handle --dump-prompt
+    if cli.dump_prompt {
+        let mut config = config::Config::load(cli.config.as_deref())?;
+        if cli.overwrite {
+            config.exif_fields.overwrite_existing = true;
+        } else if cli.no_overwrite {
+            config.exif_fields.overwrite_existing = false;
+        }
+        // No service is called, so none needs to be configured unless one is named
+        let pipeline = pipeline::Pipeline::builder().from_config(&config).no_ai(cli.service.is_none()).build()?;
+        let dump = |path: Option<&Path>| -> Result<String> {
+            match &cli.service {
+                Some(service) => Ok(serde_json::to_string_pretty(&pipeline.request_preview(service, path)?)?),
+                None => Ok(pipeline.effective_prompt(path)),
+            }
+        };
+        if cli.paths.is_empty() {
+            println!("{}", dump(None)?);
+            return Ok(());
+        }
+        let images = pipeline::collect_images_with(&cli.paths, &collect_options);
+        if images.is_empty() {
+            anyhow::bail!("No supported image files found in the specified paths.");
+        }
+        for (i, image_path) in images.iter().enumerate() {
+            if images.len() > 1 {
+                if i > 0 {
+                    println!();
+                }
+                println!("=== {} ===", image_path.display());
+            }
+            println!("{}", dump(Some(image_path))?);
+        }
+        return Ok(());
+    }
+
     // Validate inputs for non-init commands
     if !cli.init && cli.paths.is_empty() {
         anyhow::bail!("No input files or directories specified. Use --help for usage.");
@@ -93,7 +233,7 @@ async fn main() -> Result<()> {
 
     // Handle --show-exif
     if cli.show_exif {
-        let images = pipeline::collect_images(&cli.paths);
+        let images = pipeline::collect_images_with(&cli.paths, &collect_options);
         if images.is_empty() {
             anyhow::bail!("No supported image files found in the specified paths.");
         }
@@ -105,7 +245,7 @@ async fn main() -> Result<()> {
 
     // Handle --clear-exif
     if cli.clear_exif {
-        let images = pipeline::collect_images(&cli.paths);
+        let images = pipeline::collect_images_with(&cli.paths, &collect_options);
         if images.is_empty() {
             anyhow::bail!("No supported image files found in the specified paths.");
         }
@@ -114,7 +254,14 @@ async fn main() -> Result<()> {
             match kind {
                 Some(k) => {
                     match exif::clear_exif(image_path, k) {
-                        Ok(()) => log::info!("Cleared EXIF: {}", image_path.display()),
+                        Ok(result) => {
+                            let removed = result.removed_blocks();
+                            if removed.is_empty() {
+                                log::info!("No metadata to clear: {}", image_path.display());
+                            } else {
+                                log::info!("Cleared {}: {}", removed.join(", "), image_path.display());
+                            }
+                        }
                         Err(e) => log::error!("Failed to clear {}: {e}", image_path.display()),
                     }
                 }
@@ -125,15 +272,39 @@ async fn main() -> Result<()> {
     }
 
     // Load config
-    let config = config::Config::load(cli.config.as_deref())?;
+    let mut config = config::Config::load(cli.config.as_deref())?;
+    if cli.overwrite {
+        config.exif_fields.overwrite_existing = true;
+    } else if cli.no_overwrite {
+        config.exif_fields.overwrite_existing = false;
+    }
+    if let Some(threads) = cli.threads {
+        config.ai_services.local.cpu_threads = Some(threads.get());
+    }
+    if cli.no_ai {
+        config.output.no_ai = true;
+    }
 
     // Collect images
-    let images = pipeline::collect_images(&cli.paths);
+    let mut images = pipeline::collect_images_with(&cli.paths, &collect_options);
     if images.is_empty() {
         anyhow::bail!("No supported image files found in the specified paths.");
     }
 
     log::info!("Found {} image(s) to process", images.len());
+    let found = images.len();
+    if let Some(limit) = cli.limit {
+        images.truncate(limit.get());
+    } else if let Some(sample) = cli.sample {
+        let seed = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or_default();
+        images = pipeline::sample_images(&images, sample.get(), seed);
+    }
+    if images.len() < found {
+        log::info!("Processing {} of {found} image(s)", images.len());
+    }
     if cli.dry_run || config.output.dry_run {
         log::info!("DRY RUN — no files will be modified");
     }
@@ -143,6 +314,15 @@ async fn main() -> Result<()> {
     if cli.dry_run {
         builder = builder.dry_run(true);
     }
+    if config.output.enable_cache && !cli.no_cache && !config.output.no_ai {
+        let config_path = match &cli.config {
+            Some(path) => path.clone(),
+            None => config::Config::config_path()?,
+        };
+        let cache = AnalysisCache::load(&cache::cache_path(&config_path))?;
+        log::info!("Analysis cache: {} ({} entries)", cache.path().display(), cache.len());
+        builder = builder.analysis_cache(cache);
+    }
     let pipeline = match builder.build() {
         Ok(p) => p,
         Err(_) => {
@@ -152,70 +332,78 @@ async fn main() -> Result<()> {
         }
     };
 
-    log::info!(
-        "AI chain: {}",
-        pipeline.service_names().join(" → ")
-    );
-
-    // Process each image
-    let mut results = Vec::new();
-    let total = images.len();
-
-    for (i, image_path) in images.iter().enumerate() {
+    if config.output.no_ai {
+        log::info!("AI disabled — writing config-derived metadata only");
+    } else {
         log::info!(
-            "[{}/{}] Processing: {}",
-            i + 1,
-            total,
-            image_path.display()
+            "AI chain: {}",
+            pipeline.service_names().join(" → ")
         );
+    }
 
-        let result = pipeline.process_image(image_path).await;
-
-        // Print result
-        if let Some(ref err) = result.error {
-            log::error!("  Error: {err}");
-        } else {
-            if let Some(ref service) = result.ai_service_used {
-                log::info!("  AI service: {service}");
-            }
+    // In dry-run mode, estimate what a real run would cost with the first paid service
+    let dry_run = cli.dry_run || config.output.dry_run;
+    let priced = if dry_run && !config.output.no_ai { priced_model(&config) } else { None };
+    let mut estimated_usd = 0.0;
+    let mut estimated_images = 0;
+
+    // Batch mode: analyze everything up front; failed images fall back to the chain
+    let batched = if pipeline.batch_enabled() {
+        log::info!("Batch mode: waiting for OpenAI batch results (can take up to 24 hours)");
+        pipeline.analyze_batch(&images).await
+    } else {
+        Default::default()
+    };
 
-            // Show EXIF preview table
-            if cli.dry_run || config.output.dry_run {
-                print_exif_preview(&result);
+    // Process the images, up to max_concurrency at a time
+    let total = images.len();
+    let results = pipeline
+        .process_images_with(&images, batched, config.output.max_concurrency, |i, result| {
+            let image_path = &images[i];
+            // Print result
+            if let Some(err) = &result.error {
+                log::error!("  Error: {err}");
             } else {
-                let mut written = Vec::new();
-                if result.title_written {
-                    written.push("title");
-                }
-                if result.description_written {
-                    written.push("description");
-                }
-                if result.tags_written {
-                    written.push("tags");
-                }
-                if result.gps_written {
-                    written.push("gps");
-                }
-                if result.subject_written {
-                    written.push("subject");
+                if let Some(service) = &result.ai_service_used {
+                    log::info!("  AI service: {service}");
                 }
 
-                if !written.is_empty() {
-                    log::info!("  Wrote: {}", written.join(", "));
-                }
+                // Show EXIF preview table
+                if dry_run {
+                    if !cli.quiet {
+                        print_exif_preview(result, &config.exif_fields);
+                    }
+                    if let Some((model, price)) = &priced
+                        && let Some((w, h)) = cost::image_dimensions(image_path, &result.existing_exif)
+                    {
+                        let (w, h) = pipeline::upload_dimensions(w, h, config.output.max_upload_dimension);
+                        let estimate = cost::estimate(model, w, h, price);
+                        estimated_usd += estimate.usd;
+                        estimated_images += 1;
+                        if !cli.quiet {
+                            eprintln!(
+                                "  {DIM}Estimated cost: ~{} tokens, ${:.5} ({model}, {w}×{h}){RESET}",
+                                estimate.total_tokens(),
+                                estimate.usd
+                            );
+                        }
+                    }
+                } else {
+                    log::info!("  {}", result.summary_line());
 
-                if let Some(ref sidecar) = result.sidecar_path {
-                    log::info!("  Sidecar XMP: {}", sidecar.display());
+                    if let Some(sidecar) = &result.sidecar_path {
+                        log::info!("  Sidecar XMP: {} (original not modified)", sidecar.display());
+                    }
                 }
+            }
 
-                if !result.skipped_fields.is_empty() {
-                    log::info!("  Skipped: {}", result.skipped_fields.join(", "));
+            if cli.explain {
+                for line in result.explain() {
+                    eprintln!("  {line}");
                 }
             }
-        }
-
-        results.push(result);
-    }
+        })
+        .await;
 
     // JSON output
     if cli.json {
@@ -225,15 +413,28 @@ async fn main() -> Result<()> {
                 serde_json::json!({
                     "path": r.path.display().to_string(),
                     "ai_service": r.ai_service_used,
+                    "field_sources": r.field_sources,
                     "ai_result": r.ai_result,
                     "title_written": r.title_written,
                     "description_written": r.description_written,
                     "tags_written": r.tags_written,
                     "gps_written": r.gps_written,
                     "subject_written": r.subject_written,
+                    "date_written": r.date_written,
+                    "copyright_written": r.copyright_written,
+                    "artist_written": r.artist_written,
                     "skipped_fields": r.skipped_fields,
+                    "skipped": r.skipped_fields.iter().map(|s| serde_json::json!({
+                        "field": s.field,
+                        "reason": s.reason,
+                    })).collect::<Vec<_>>(),
                     "sidecar_path": r.sidecar_path.as_ref().map(|p| p.display().to_string()),
-                    "error": r.error,
+                    "json_sidecar_path": r.json_sidecar_path.as_ref().map(|p| p.display().to_string()),
+                    "write_mode": r.write_mode,
+                    "missing_fields": r.missing_fields,
+                    "title_from_filename": r.title_from_filename,
+                    "provenance": r.provenance,
+                    "error": r.error.as_ref().map(ToString::to_string),
                 })
             })
             .collect();
@@ -241,10 +442,23 @@ async fn main() -> Result<()> {
         println!("{}", serde_json::to_string_pretty(&json_results)?);
     }
 
+    if let Some(csv_path) = &cli.csv {
+        pipeline::write_csv_report(&results, csv_path)?;
+        log::info!("CSV report written: {}", csv_path.display());
+    }
+
     // Summary
     let success = results.iter().filter(|r| r.error.is_none()).count();
     let failed = results.iter().filter(|r| r.error.is_some()).count();
     log::info!("Done: {success} succeeded, {failed} failed out of {total} images");
+    if let Some((model, _)) = &priced {
+        log::info!(
+            "Estimated cost of a real run with {model}: ${estimated_usd:.4} for {estimated_images} image(s)"
+        );
+        if estimated_images < success {
+            log::info!("  {} image(s) with unknown dimensions not included", success - estimated_images);
+        }
+    }
 
     Ok(())
 }
@@ -255,17 +469,67 @@ const DIM: &str = "\x1b[2m";
 const RESET: &str = "\x1b[0m";
 const BOLD: &str = "\x1b[1m";
 
+/// Model and price of the first service in the chain that would be used for a real run.
+///
+/// Returns `None` when that service is free (Cloudflare, Ollama, local) or its model isn't priced.
+fn priced_model(config: &config::Config) -> Option<(String, cost::ModelPrice)> {
+    let services = &config.ai_services;
+    let model = config.enabled_services().into_iter().find_map(|name| match name.as_str() {
+        "openai" if !services.openai.api_key.is_empty() => Some(Some(&services.openai.model)),
+        "gemini" if !services.gemini.api_key.is_empty() => Some(Some(&services.gemini.model)),
+        "claude" if !services.claude.api_key.is_empty() => Some(Some(&services.claude.model)),
+        "cloudflare" if !services.cloudflare.api_token.is_empty() => Some(None),
+        "ollama" => Some(None),
+        "local" => Some(None),
+        _ => None,
+    })??;
+    cost::model_price(model).map(|price| (model.clone(), price))
+}
+
+/// Split `reader` into paths on newlines (or NUL bytes), skipping empty entries.
+///
+/// Paths are taken verbatim apart from a trailing `\r` in newline mode, so
+/// names with spaces work. Input is read as bytes, so names that aren't
+/// valid UTF-8 are kept as-is on Unix (and converted lossily elsewhere).
+fn read_paths(mut reader: impl Read, null: bool) -> Result<Vec<PathBuf>> {
+    let mut input = Vec::new();
+    reader
+        .read_to_end(&mut input)
+        .context("Failed to read paths from stdin")?;
+    let separator = if null { b'\0' } else { b'\n' };
+    Ok(input
+        .split(|&b| b == separator)
+        .map(|line| if null { line } else { line.strip_suffix(b"\r").unwrap_or(line) })
+        .filter(|line| !line.is_empty())
+        .map(path_from_bytes)
+        .collect())
+}
+
+#[cfg(unix)]
+fn path_from_bytes(bytes: &[u8]) -> PathBuf {
+    use std::os::unix::ffi::OsStrExt;
+    PathBuf::from(std::ffi::OsStr::from_bytes(bytes))
+}
+
+#[cfg(not(unix))]
+fn path_from_bytes(bytes: &[u8]) -> PathBuf {
+    PathBuf::from(String::from_utf8_lossy(bytes).into_owned())
+}
+
 /// Print an EXIF preview table showing existing data and new AI values for dry-run mode.
-fn print_exif_preview(result: &pipeline::ProcessResult) {
+///
+/// Written to stderr alongside the progress log, keeping stdout for `--json`.
+fn print_exif_preview(result: &pipeline::ProcessResult, fields: &config::ExifFields) {
+    let defaults = &fields.metadata_defaults;
     let existing = &result.existing_exif;
     let ai = match &result.ai_result {
         Some(ai) => ai,
         None => return,
     };
 
-    println!();
-    println!("  {BOLD}EXIF Data:{RESET}");
-    println!("  {DIM}{}{RESET}", "─".repeat(72));
+    eprintln!();
+    eprintln!("  {BOLD}EXIF Data:{RESET}");
+    eprintln!("  {DIM}{}{RESET}", "─".repeat(72));
 
     // --- Existing standard EXIF fields ---
     print_existing("Make", existing.make.as_deref());
@@ -294,6 +558,9 @@ fn print_exif_preview(result: &pipeline::ProcessResult) {
         let lon = existing.gps_longitude.unwrap_or(0.0);
         print_existing_val("GPSLatitude", &format!("{lat:.6}"));
         print_existing_val("GPSLongitude", &format!("{lon:.6}"));
+        if let Some(alt) = existing.gps_altitude {
+            print_existing_val("GPSAltitude", &format!("{alt:.1} m"));
+        }
     }
 
     // --- Existing writable fields (may be overwritten) ---
@@ -303,9 +570,9 @@ fn print_exif_preview(result: &pipeline::ProcessResult) {
     print_existing("XPSubject", existing.subject.as_deref());
 
     // --- Separator before new AI values ---
-    println!("  {DIM}{}{RESET}", "─".repeat(72));
-    println!("  {BOLD}New (AI-generated):{RESET}");
-    println!("  {DIM}{}{RESET}", "─".repeat(72));
+    eprintln!("  {DIM}{}{RESET}", "─".repeat(72));
+    eprintln!("  {BOLD}New (AI-generated):{RESET}");
+    eprintln!("  {DIM}{}{RESET}", "─".repeat(72));
 
     // Title
     if let Some(ref title) = ai.title {
@@ -313,7 +580,7 @@ fn print_exif_preview(result: &pipeline::ProcessResult) {
             print_new("ImageDescription", title);
             print_new("XPTitle", title);
         } else {
-            print_skipped("ImageDescription", "(exists, skipped)");
+            print_skipped("ImageDescription", skip_note(result, exif::Field::Title));
         }
     }
 
@@ -323,7 +590,7 @@ fn print_exif_preview(result: &pipeline::ProcessResult) {
             print_new("UserComment", desc);
             print_new("XPComment", desc);
         } else {
-            print_skipped("UserComment", "(exists, skipped)");
+            print_skipped("UserComment", skip_note(result, exif::Field::Description));
         }
     }
 
@@ -333,19 +600,19 @@ fn print_exif_preview(result: &pipeline::ProcessResult) {
         if result.tags_written {
             print_new("XPKeywords", &kw);
         } else {
-            print_skipped("XPKeywords", "(exists, skipped)");
+            print_skipped("XPKeywords", skip_note(result, exif::Field::Tags));
         }
     }
 
     // Subject
-    if let Some(ref subjects) = ai.subject {
-        if !subjects.is_empty() {
-            let subj = subjects.join("; ");
-            if result.subject_written {
-                print_new("XPSubject", &subj);
-            } else {
-                print_skipped("XPSubject", "(exists, skipped)");
-            }
+    if let Some(ref subjects) = ai.subject
+        && !subjects.is_empty()
+    {
+        let subj = subjects.join("; ");
+        if result.subject_written {
+            print_new("XPSubject", &subj);
+        } else {
+            print_skipped("XPSubject", skip_note(result, exif::Field::Subject));
         }
     }
 
@@ -354,14 +621,71 @@ fn print_exif_preview(result: &pipeline::ProcessResult) {
         if result.gps_written {
             print_new("GPSLatitude", &format!("{:.6}", gps.latitude));
             print_new("GPSLongitude", &format!("{:.6}", gps.longitude));
-        } else if existing.has_gps {
-            print_skipped("GPS", "(exists, skipped)");
+            if let Some(alt) = gps.altitude {
+                print_new("GPSAltitude", &format!("{alt:.1} m"));
+            }
+        } else {
+            print_skipped("GPS", skip_note(result, exif::Field::Gps));
         }
     }
 
-    println!("  {DIM}{}{RESET}", "─".repeat(72));
-    println!("  {GREEN}*{RESET} = new value to be written");
-    println!();
+    // Date
+    if let Some(ref date) = ai.date_taken {
+        if result.date_written {
+            print_new("DateTimeOriginal", date);
+            print_new("CreateDate", date);
+        } else {
+            print_skipped("DateTimeOriginal", skip_note(result, exif::Field::Date));
+        }
+    }
+
+    // Copyright and Artist come from the config, not the AI
+    if let (true, Some(copyright)) = (result.copyright_written, &defaults.copyright) {
+        print_new("Copyright", copyright);
+    }
+    if let (true, Some(artist)) = (result.artist_written, &defaults.artist) {
+        print_new("Artist", artist);
+    }
+
+    if result.write_mode == pipeline::WriteMode::Sidecar {
+        print_sidecar_preview(result, ai, fields);
+    }
+
+    eprintln!("  {DIM}{}{RESET}", "─".repeat(72));
+    eprintln!("  {GREEN}*{RESET} = new value to be written");
+    eprintln!();
+}
+
+/// Show the XMP properties a sidecar write would change, and what happens to
+/// an existing sidecar.
+fn print_sidecar_preview(result: &pipeline::ProcessResult, ai: &AiResult, fields: &config::ExifFields) {
+    // Only what the writer decided to write, so augment and skip rules match
+    let written = AiResult {
+        title: ai.title.clone().filter(|_| result.title_written),
+        description: ai.description.clone().filter(|_| result.description_written),
+        tags: ai.tags.clone().filter(|_| result.tags_written),
+        hierarchical_tags: ai.hierarchical_tags.clone().filter(|_| result.tags_written),
+        ..ai.clone()
+    };
+    let preview = exif::preview_sidecar_xmp(&result.path, &written, &exif::ExifData::default(), fields);
+    let status = if preview.updates_existing() {
+        "exists, will be updated"
+    } else if preview.exists {
+        "exists but is not XMP, will be replaced"
+    } else {
+        "new file"
+    };
+
+    eprintln!("  {DIM}{}{RESET}", "─".repeat(72));
+    eprintln!("  {BOLD}XMP sidecar:{RESET} {} ({status})", preview.path.display());
+    eprintln!("  {DIM}{}{RESET}", "─".repeat(72));
+    let changes = preview.changes();
+    if changes.is_empty() {
+        print_skipped("(no changes)", "sidecar already up to date");
+    }
+    for (name, value) in changes {
+        print_new(name, &value);
+    }
 }
 
 /// Max width for the value column before wrapping.
@@ -371,10 +695,10 @@ const INDENT: &str = "                           ";
 
 /// Print an existing EXIF field row.
 fn print_existing(tag: &str, value: Option<&str>) {
-    if let Some(val) = value {
-        if !val.is_empty() {
-            print_existing_val(tag, val);
-        }
+    if let Some(val) = value
+        && !val.is_empty()
+    {
+        print_existing_val(tag, val);
     }
 }
 
@@ -384,9 +708,9 @@ fn print_existing_val(tag: &str, val: &str) {
     let lines = wrap_text(val, VAL_WIDTH);
     for (i, line) in lines.iter().enumerate() {
         if i == 0 {
-            println!("  {tag_col} : {line}");
+            eprintln!("  {tag_col} : {line}");
         } else {
-            println!("  {INDENT}{line}");
+            eprintln!("  {INDENT}{line}");
         }
     }
 }
@@ -398,22 +722,33 @@ fn print_new(tag: &str, val: &str) {
     for (i, line) in lines.iter().enumerate() {
         if i == 0 {
             if lines.len() == 1 {
-                println!("  {GREEN}{tag_col} : {line} *{RESET}");
+                eprintln!("  {GREEN}{tag_col} : {line} *{RESET}");
             } else {
-                println!("  {GREEN}{tag_col} : {line}{RESET}");
+                eprintln!("  {GREEN}{tag_col} : {line}{RESET}");
             }
         } else if i == lines.len() - 1 {
-            println!("  {GREEN}{INDENT}{line} *{RESET}");
+            eprintln!("  {GREEN}{INDENT}{line} *{RESET}");
         } else {
-            println!("  {GREEN}{INDENT}{line}{RESET}");
+            eprintln!("  {GREEN}{INDENT}{line}{RESET}");
         }
     }
 }
 
+/// Describe why a field was not written, for the dry-run preview.
+fn skip_note(result: &pipeline::ProcessResult, field: exif::Field) -> &'static str {
+    let reason = result.skipped_fields.iter().find(|s| s.field == field).map(|s| s.reason);
+    match reason {
+        Some(exif::SkipReason::Disabled) => "(disabled, skipped)",
+        Some(exif::SkipReason::Empty) => "(empty, skipped)",
+        Some(exif::SkipReason::FormatUnsupported) => "(unsupported by format, skipped)",
+        _ => "(exists, skipped)",
+    }
+}
+
 /// Print a skipped field row (dimmed).
 fn print_skipped(tag: &str, reason: &str) {
     let tag_col = format!("{:<22}", tag);
-    println!("  {DIM}{tag_col} : {reason}{RESET}");
+    eprintln!("  {DIM}{tag_col} : {reason}{RESET}");
 }
 
 /// Print full EXIF metadata for a file, organized by section.
@@ -428,7 +763,12 @@ fn print_full_exif(path: &std::path::Path) -> Result<()> {
     let camera_fields: Vec<(&str, Option<&str>)> = vec![
         ("Make", data.make.as_deref()),
         ("Model", data.model.as_deref()),
+        ("LensMake", data.lens_make.as_deref()),
         ("LensModel", data.lens_model.as_deref()),
+        ("LensSpecification", data.lens_specification.as_deref()),
+        ("BodySerialNumber", data.body_serial_number.as_deref()),
+        ("LensSerialNumber", data.lens_serial_number.as_deref()),
+        ("ImageUniqueID", data.image_unique_id.as_deref()),
         ("Software", data.software.as_deref()),
     ];
     if camera_fields.iter().any(|(_, v)| v.is_some()) {
@@ -445,6 +785,7 @@ fn print_full_exif(path: &std::path::Path) -> Result<()> {
     // --- Capture Settings ---
     let capture_fields: Vec<(&str, Option<&str>)> = vec![
         ("DateTimeOriginal", data.date_time.as_deref()),
+        ("ExposureProgram", data.exposure_program.as_deref()),
         ("ExposureTime", data.exposure_time.as_deref()),
         ("FNumber", data.f_number.as_deref()),
         ("ISO", data.iso.as_deref()),
@@ -496,11 +837,15 @@ fn print_full_exif(path: &std::path::Path) -> Result<()> {
     }
 
     // --- AI / Descriptive Metadata ---
+    let rating = data.rating.map(rating_label);
     let desc_fields: Vec<(&str, Option<&str>)> = vec![
         ("ImageDescription", data.title.as_deref()),
         ("UserComment", data.description.as_deref()),
         ("XPKeywords", data.keywords.as_deref()),
         ("XPSubject", data.subject.as_deref()),
+        ("Artist", data.artist.as_deref()),
+        ("Copyright", data.copyright.as_deref()),
+        ("Rating", rating.as_deref()),
     ];
     if desc_fields.iter().any(|(_, v)| v.is_some()) {
         println!("  {BOLD}Descriptive Metadata{RESET}");
@@ -523,7 +868,8 @@ fn print_full_exif(path: &std::path::Path) -> Result<()> {
         || data.title.is_some()
         || data.description.is_some()
         || data.keywords.is_some()
-        || data.software.is_some();
+        || data.software.is_some()
+        || desc_fields.iter().any(|(_, v)| v.is_some());
     if !has_any {
         println!("  {DIM}(no EXIF metadata found){RESET}");
         println!();
@@ -532,6 +878,17 @@ fn print_full_exif(path: &std::path::Path) -> Result<()> {
     Ok(())
 }
 
+/// A star rating as stars, e.g. `★★★★☆ (4/5)`; `-1` means rejected.
+fn rating_label(rating: i8) -> String {
+    match rating {
+        ..0 => "rejected".to_string(),
+        _ => {
+            let stars = rating.min(5) as usize;
+            format!("{}{} ({stars}/5)", "★".repeat(stars), "☆".repeat(5 - stars))
+        }
+    }
+}
+
 /// Print a single row in the EXIF display table.
 fn print_row(tag: &str, val: &str) {
     let tag_col = format!("{:<22}", tag);
@@ -1,7 +1,26 @@
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 
+use crate::ai::{DescriptionFormat, TagStyle};
+use crate::exif::Field;
+use crate::pipeline::SymlinkPolicy;
+
+/// Service names accepted in [`Config::service_order`].
+pub const KNOWN_SERVICES: &[&str] = &["openai", "gemini", "claude", "cloudflare", "ollama", "local"];
+
+/// Placeholder for secrets in [`Config::redacted`].
+const REDACTED: &str = "********";
+
+/// Whether a header's value is likely a credential and must not be printed.
+fn is_sensitive_header(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    ["auth", "token", "key", "secret", "cookie", "session", "password", "signature"]
+        .iter()
+        .any(|word| name.contains(word))
+}
+
 /// Top-level configuration for the exif-ai library.
 ///
 /// Controls which AI services to use, which metadata fields to write,
@@ -22,7 +41,7 @@ use std::path::{Path, PathBuf};
 /// ```
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
-    /// AI service configurations (OpenAI, Gemini, Cloudflare).
+    /// AI service configurations (OpenAI, Gemini, Claude, Cloudflare, Ollama, local BLIP).
     pub ai_services: AiServices,
     /// Order in which AI services are tried (failover chain).
     pub service_order: Vec<String>,
@@ -30,6 +49,31 @@ pub struct Config {
     pub exif_fields: ExifFields,
     /// Output behavior (dry run, backups, logging).
     pub output: OutputConfig,
+    /// Maximum lengths of AI titles and descriptions.
+    #[serde(default)]
+    pub limits: Limits,
+    /// Language (BCP-47, e.g. `"de"`) the AI writes the title, description,
+    /// tags and subjects in. The title and description are also tagged with it
+    /// in XMP, next to `x-default`. `None` leaves the choice to the model.
+    #[serde(default)]
+    pub language: Option<String>,
+}
+
+/// Maximum lengths, in characters, of the text written from the AI result.
+/// Longer values (and their translations) are cut at a word boundary.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Limits {
+    /// Defaults to 60, the length the prompt asks for.
+    pub max_title_chars: usize,
+    /// Defaults to 254, the length the prompt asks for.
+    pub max_description_chars: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self { max_title_chars: 60, max_description_chars: 254 }
+    }
 }
 
 /// Configuration for all available AI services.
@@ -37,8 +81,12 @@ pub struct Config {
 pub struct AiServices {
     pub openai: OpenAiConfig,
     pub gemini: GeminiConfig,
+    #[serde(default)]
+    pub claude: ClaudeConfig,
     pub cloudflare: CloudflareConfig,
     #[serde(default)]
+    pub ollama: OllamaConfig,
+    #[serde(default)]
     pub local: LocalConfig,
 }
 
@@ -48,6 +96,32 @@ pub struct OpenAiConfig {
     pub api_key: String,
     pub model: String,
     pub enabled: bool,
+    /// Extra HTTP headers sent with every request (e.g. `X-Request-ID` or an
+    /// auth gateway token), in addition to the service's own headers.
+    #[serde(default)]
+    pub headers: Option<BTreeMap<String, String>>,
+    /// Seconds a request may take in total before it fails and the next
+    /// service is tried. Defaults to 60.
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+    /// Seconds allowed to connect to the service. Defaults to 10.
+    #[serde(default = "default_connect_timeout_secs")]
+    pub connect_timeout_secs: u64,
+    /// Send requests through the Batch API instead of one at a time: half the
+    /// price, but results can take up to 24 hours. Only used by the CLI.
+    #[serde(default)]
+    pub batch: bool,
+    /// Root of an OpenAI-compatible API to use instead of OpenAI's, e.g.
+    /// `https://openrouter.ai/api/v1`, a LocalAI server, or an Azure
+    /// deployment (`https://my-resource.openai.azure.com/openai/deployments/gpt-4o`).
+    /// With a base URL the API key may be empty.
+    #[serde(default)]
+    pub base_url: Option<String>,
+    /// Azure OpenAI API version (e.g. `2024-06-01`). When set, it is sent as
+    /// the `api-version` query parameter and the key goes in an `api-key`
+    /// header instead of `Authorization: Bearer`.
+    #[serde(default)]
+    pub api_version: Option<String>,
 }
 
 /// Google Gemini service configuration.
@@ -56,6 +130,49 @@ pub struct GeminiConfig {
     pub api_key: String,
     pub model: String,
     pub enabled: bool,
+    /// Extra HTTP headers sent with every request (e.g. `X-Request-ID` or an
+    /// auth gateway token), in addition to the service's own headers.
+    #[serde(default)]
+    pub headers: Option<BTreeMap<String, String>>,
+    /// Seconds a request may take in total before it fails and the next
+    /// service is tried. Defaults to 60.
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+    /// Seconds allowed to connect to the service. Defaults to 10.
+    #[serde(default = "default_connect_timeout_secs")]
+    pub connect_timeout_secs: u64,
+}
+
+/// Anthropic Claude service configuration.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClaudeConfig {
+    pub api_key: String,
+    pub model: String,
+    pub enabled: bool,
+    /// Extra HTTP headers sent with every request (e.g. `X-Request-ID` or an
+    /// auth gateway token), in addition to the service's own headers.
+    #[serde(default)]
+    pub headers: Option<BTreeMap<String, String>>,
+    /// Seconds a request may take in total before it fails and the next
+    /// service is tried. Defaults to 60.
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+    /// Seconds allowed to connect to the service. Defaults to 10.
+    #[serde(default = "default_connect_timeout_secs")]
+    pub connect_timeout_secs: u64,
+}
+
+impl Default for ClaudeConfig {
+    fn default() -> Self {
+        Self {
+            api_key: String::new(),
+            model: "claude-3-5-sonnet-latest".to_string(),
+            enabled: false,
+            headers: None,
+            timeout_secs: default_timeout_secs(),
+            connect_timeout_secs: default_connect_timeout_secs(),
+        }
+    }
 }
 
 /// Cloudflare Workers AI service configuration.
@@ -65,29 +182,71 @@ pub struct CloudflareConfig {
     pub api_token: String,
     pub model: String,
     pub enabled: bool,
+    /// Extra HTTP headers sent with every request (e.g. `X-Request-ID` or an
+    /// auth gateway token), in addition to the service's own headers.
+    #[serde(default)]
+    pub headers: Option<BTreeMap<String, String>>,
+    /// Seconds a request may take in total before it fails and the next
+    /// service is tried. Defaults to 60.
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+    /// Seconds allowed to connect to the service. Defaults to 10.
+    #[serde(default = "default_connect_timeout_secs")]
+    pub connect_timeout_secs: u64,
 }
 
-/// Local BLIP model configuration.
-///
-/// When enabled, runs a BLIP image-captioning model on-device.
-/// No API keys or network access required after the initial model download.
+/// Ollama server configuration, for vision models such as `llava` or
+/// `llama3.2-vision` served locally or on another host.
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct LocalConfig {
-    /// Path to the directory containing the model and tokenizer files.
-    /// If empty, uses the default cache directory (~/.cache/exif-ai or platform equivalent).
-    pub model_path: String,
+pub struct OllamaConfig {
+    /// Address of the Ollama server, e.g. `http://gpu-box.lan:11434` for a
+    /// remote host. Defaults to `http://localhost:11434`.
+    pub base_url: String,
+    pub model: String,
     pub enabled: bool,
+    /// Extra HTTP headers sent with every request (e.g. for a reverse proxy
+    /// in front of a remote server).
+    #[serde(default)]
+    pub headers: Option<BTreeMap<String, String>>,
+    /// Seconds a request may take in total before it fails and the next
+    /// service is tried. Defaults to 300, since the first request loads the model.
+    #[serde(default = "default_ollama_timeout_secs")]
+    pub timeout_secs: u64,
+    /// Seconds allowed to connect to the service. Defaults to 10.
+    #[serde(default = "default_connect_timeout_secs")]
+    pub connect_timeout_secs: u64,
 }
 
-impl Default for LocalConfig {
+impl Default for OllamaConfig {
     fn default() -> Self {
         Self {
-            model_path: String::new(),
+            base_url: crate::ai::DEFAULT_OLLAMA_URL.to_string(),
+            model: "llava".to_string(),
             enabled: false,
+            headers: None,
+            timeout_secs: default_ollama_timeout_secs(),
+            connect_timeout_secs: default_connect_timeout_secs(),
         }
     }
 }
 
+/// Local BLIP model configuration.
+///
+/// When enabled, runs a BLIP image-captioning model on-device.
+/// No API keys or network access required after the initial model download.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LocalConfig {
+    /// Path to the directory containing the model and tokenizer files.
+    /// If empty, uses the default cache directory (~/.cache/exif-ai or platform equivalent).
+    pub model_path: String,
+    pub enabled: bool,
+    /// Threads used for CPU inference. `None` lets candle use every logical
+    /// core (or `RAYON_NUM_THREADS`); the physical core count is usually faster
+    /// on hyperthreaded or multi-socket machines. Ignored on the Metal GPU.
+    #[serde(default)]
+    pub cpu_threads: Option<usize>,
+}
+
 /// Controls which metadata fields are written to images.
 ///
 /// Each `write_*` flag enables or disables writing that field.
@@ -105,6 +264,7 @@ impl Default for LocalConfig {
 ///     write_gps: false,       // don't write GPS
 ///     write_subject: false,   // don't write subject
 ///     overwrite_existing: false, // preserve existing values
+///     ..Default::default()
 /// };
 /// ```
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -121,6 +281,95 @@ pub struct ExifFields {
     pub write_subject: bool,
     /// If `true`, overwrite existing metadata values. If `false`, skip fields that already have data.
     pub overwrite_existing: bool,
+    /// Write IPTC DateCreated (2:55) and TimeCreated (2:60) from the image's
+    /// `DateTimeOriginal` whenever the IPTC block is written.
+    #[serde(default)]
+    pub write_iptc_dates: bool,
+    /// Ask the AI for the capture date when the image shows one (a dated
+    /// newspaper, event signage) and write it as EXIF `DateTimeOriginal` and
+    /// `CreateDate`. Only images without any capture date are dated.
+    #[serde(default)]
+    pub write_date: bool,
+    /// Write `metadata_defaults.copyright` as EXIF `Copyright`, XMP `dc:rights`
+    /// and IPTC Copyright Notice (2:116) on every image.
+    #[serde(default)]
+    pub write_copyright: bool,
+    /// Write `metadata_defaults.artist` as EXIF `Artist`, XMP `dc:creator` and
+    /// IPTC By-line (2:80) on every image.
+    #[serde(default)]
+    pub write_artist: bool,
+    /// Extra languages (e.g. `["fr", "de"]`) to request from the AI and write as
+    /// `xml:lang` alternatives in XMP dc:title/dc:description. Empty writes only `x-default`.
+    #[serde(default)]
+    pub languages: Vec<String>,
+    /// Also mirror the title and description into XMP `tiff:ImageDescription`
+    /// and `exif:UserComment`, for viewers that ignore `dc:title`/`dc:description`.
+    #[serde(default)]
+    pub xmp_exif_mirror: bool,
+    /// Which keyword destinations `write_tags` fills. All are on by default.
+    #[serde(default)]
+    pub keyword_targets: KeywordTargets,
+    /// Static workflow values written alongside the AI metadata.
+    #[serde(default)]
+    pub metadata_defaults: MetadataDefaults,
+    /// For HEIC/RAW, also embed title, description, keywords and GPS in the
+    /// file itself with `exiftool` when it is on `PATH`. The `.xmp` sidecar is
+    /// written either way.
+    #[serde(default)]
+    pub use_exiftool_if_available: bool,
+}
+
+/// Fixed values, e.g. for newsroom or agency ingest, written to IPTC and XMP
+/// whenever those blocks are written. They are not AI-derived.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MetadataDefaults {
+    /// IPTC Special Instructions (2:40) and XMP `photoshop:Instructions`.
+    pub instructions: Option<String>,
+    /// IPTC Urgency (2:10) and XMP `photoshop:Urgency`, from 1 (most urgent)
+    /// to 8 (least urgent).
+    pub urgency: Option<u8>,
+    /// Copyright notice, e.g. `"© 2024 Jane Doe"`. Written when `write_copyright` is on.
+    pub copyright: Option<String>,
+    /// Photographer or creator name. Written when `write_artist` is on.
+    pub artist: Option<String>,
+}
+
+/// Destinations for AI tags. Different tools read keywords from different places,
+/// so by default every one the image format supports is written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KeywordTargets {
+    /// EXIF XPKeywords — Windows Explorer "Tags".
+    pub xp_keywords: bool,
+    /// XMP `dc:subject` — macOS, Lightroom, digiKam.
+    pub dc_subject: bool,
+    /// XMP `MicrosoftPhoto:LastKeywordXMP` — Windows Photos and Explorer in some locales.
+    pub microsoft_photo: bool,
+    /// IPTC Keywords (2:25), JPEG only — older DAMs and news tools.
+    pub iptc: bool,
+    /// XMP `lr:hierarchicalSubject` — Lightroom and digiKam keyword trees.
+    /// Asks the AI for `|`-separated paths such as `Nature|Birds|Owl`, written
+    /// next to the flat keywords. Off by default.
+    pub lr_hierarchical_subject: bool,
+}
+
+impl Default for KeywordTargets {
+    fn default() -> Self {
+        Self { xp_keywords: true, dc_subject: true, microsoft_photo: true, iptc: true, lr_hierarchical_subject: false }
+    }
+}
+
+impl KeywordTargets {
+    /// Whether any destination is enabled.
+    pub fn any(&self) -> bool {
+        self.xp_keywords || self.dc_subject || self.microsoft_photo || self.iptc
+    }
+
+    /// Whether any XMP destination is enabled.
+    pub fn any_xmp(&self) -> bool {
+        self.dc_subject || self.microsoft_photo
+    }
 }
 
 impl Default for ExifFields {
@@ -132,6 +381,29 @@ impl Default for ExifFields {
             write_gps: true,
             write_subject: true,
             overwrite_existing: false,
+            write_iptc_dates: false,
+            write_date: false,
+            write_copyright: false,
+            write_artist: false,
+            languages: Vec::new(),
+            xmp_exif_mirror: false,
+            keyword_targets: KeywordTargets::default(),
+            metadata_defaults: MetadataDefaults::default(),
+            use_exiftool_if_available: false,
+        }
+    }
+}
+
+impl ExifFields {
+    /// Whether the `write_*` flag for `field` is on.
+    pub fn writes(&self, field: Field) -> bool {
+        match field {
+            Field::Title => self.write_title,
+            Field::Description => self.write_description,
+            Field::Tags => self.write_tags,
+            Field::Gps => self.write_gps,
+            Field::Subject => self.write_subject,
+            Field::Date => self.write_date,
         }
     }
 }
@@ -143,8 +415,176 @@ pub struct OutputConfig {
     pub dry_run: bool,
     /// If `true`, create a `.bak` backup before modifying an image.
     pub backup_originals: bool,
+    /// If `true`, store only the original EXIF/XMP/IPTC blocks in a small
+    /// `.metabak` file before writing, instead of copying the whole image.
+    /// Takes precedence over `backup_originals`. TIFF still gets a full `.bak`.
+    #[serde(default)]
+    pub backup_metadata_only: bool,
     /// Optional path to a log file.
     pub log_file: Option<String>,
+    /// If `true`, pass an image's existing title/description to the AI and ask
+    /// it to refine them. The blended result replaces the originals, independent
+    /// of `overwrite_existing`.
+    #[serde(default)]
+    pub augment_existing: bool,
+    /// Fields the AI must return (e.g. `["title", "description", "tags"]`).
+    /// Images missing any of them are flagged as errors and left unwritten.
+    #[serde(default)]
+    pub required_ai_fields: Vec<Field>,
+    /// If `true`, images missing required fields are still written and only
+    /// logged as a warning.
+    #[serde(default)]
+    pub allow_partial: bool,
+    /// If `true`, write only XMP for native formats and leave EXIF and IPTC
+    /// untouched (TIFF gets a `.xmp` sidecar instead).
+    #[serde(default)]
+    pub xmp_only: bool,
+    /// If `true`, write a `.xmp` sidecar for every format, JPEG included, and
+    /// never modify the image itself. `use_exiftool_if_available` is ignored.
+    #[serde(default)]
+    pub always_sidecar: bool,
+    /// If `true`, write a `<image>.exif-ai.json` file next to each image with
+    /// the full AI result and which fields were written. Skipped on dry run.
+    #[serde(default)]
+    pub write_json_sidecar: bool,
+    /// If `true`, write the AI result only to a `<image>.json` file (e.g.
+    /// `photo.jpg.json`) and leave the image and any `.xmp` sidecar untouched,
+    /// for ingestion pipelines of your own. Takes precedence over `xmp_only`.
+    /// Also accepted under its former name, `json_only`.
+    #[serde(default, alias = "json_only")]
+    pub sidecar_json: bool,
+    /// If `true`, use the filename in Title Case (`sunset-over-bay.jpg` →
+    /// `Sunset Over Bay`) as the title when the AI returns none.
+    #[serde(default)]
+    pub filename_fallback_title: bool,
+    /// If `true`, add a keyword derived from the image's folder name to its
+    /// tags (`2024-06-paris/` → `paris 2024`).
+    #[serde(default)]
+    pub folder_keyword: bool,
+    /// If `true`, add `landscape`, `portrait` or `square` to each image's tags,
+    /// based on its dimensions. Needs no AI.
+    #[serde(default)]
+    pub write_aspect_class: bool,
+    /// If `true`, skip the AI entirely and only write metadata derived from the
+    /// config and the file (filename title, folder keyword, aspect class,
+    /// copyright, artist, IPTC dates). Existing GPS is left as-is; no place
+    /// names are derived from it. No AI service needs to be configured, and
+    /// `required_ai_fields` is not enforced.
+    #[serde(default)]
+    pub no_ai: bool,
+    /// Maximum seconds the whole AI service chain may spend on one image,
+    /// including retries and failover. `None` means no limit.
+    #[serde(default)]
+    pub per_image_timeout_secs: Option<u64>,
+    /// If `true`, when a service's response can't be parsed as JSON, send it
+    /// back once with an instruction to return only the JSON object.
+    #[serde(default)]
+    pub reprompt_on_parse_error: bool,
+    /// If `true`, keep going down the service chain after a service answers
+    /// and fill each field from the first service that returns it, until
+    /// title, description, tags and every `required_ai_fields` entry are
+    /// set. Each extra service is another paid request.
+    #[serde(default)]
+    pub merge_services: bool,
+    /// Output token limit for the remote services. `None` uses
+    /// [`DEFAULT_MAX_TOKENS`](crate::ai::DEFAULT_MAX_TOKENS). A response cut
+    /// off at the limit is retried once at twice the limit.
+    #[serde(default)]
+    pub max_tokens: Option<u32>,
+    /// Downscale images whose longest edge exceeds this many pixels before
+    /// sending them to the AI, re-encoded as JPEG. The metadata is still
+    /// written to the original file. Formats the `image` crate can't decode
+    /// (RAW, HEIC, TIFF) are sent as-is. `None` sends the original bytes.
+    #[serde(default)]
+    pub max_upload_dimension: Option<u32>,
+    /// How often to retry a service that is rate limited or answers with a
+    /// server error (500, 502, 503, 504) before moving on to the next one.
+    /// `0` disables retrying. Defaults to 2.
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+    /// Wait before the first retry, in milliseconds; it doubles with each
+    /// further retry, plus random jitter. A `Retry-After` sent by the service
+    /// takes precedence. Defaults to 500.
+    #[serde(default = "default_base_backoff_ms")]
+    pub base_backoff_ms: u64,
+    /// If `true`, log a warning for each field the image's format can't hold
+    /// (e.g. GPS in a WebP). Such fields are always reported in `skipped_fields`
+    /// as `format-unsupported`.
+    #[serde(default)]
+    pub warn_unsupported_fields: bool,
+    /// Ask for tags as single `"keywords"` or multi-word `"phrases"`, and
+    /// normalize the AI's tags to match. `None` leaves tags as returned.
+    #[serde(default)]
+    pub tag_style: Option<TagStyle>,
+    /// If `true`, record a provenance block (crate version, service, model and
+    /// SHA-256 of the prompt) in each result and in the `.exif-ai.json` file,
+    /// which is then written even if `write_json_sidecar` is off.
+    #[serde(default)]
+    pub write_provenance: bool,
+    /// Post-processing for AI descriptions: `"as-is"` (default),
+    /// `"strip-trailing-period"`, `"first-sentence-only"` or `"sentence-case"`.
+    #[serde(default)]
+    pub description_format: DescriptionFormat,
+    /// What to do with images that are symlinks: `"follow"` (write to the
+    /// link's target), `"skip"` or `"copy"` (replace the link with a modified
+    /// copy, leaving the target untouched).
+    #[serde(default)]
+    pub symlink_policy: SymlinkPolicy,
+    /// Existing EXIF fields that may be included in the prompt as context,
+    /// by name (see [`PROMPT_CONTEXT_FIELDS`](crate::exif::PROMPT_CONTEXT_FIELDS)).
+    /// Only these are ever sent to the AI service; GPS and serial numbers
+    /// are left out unless listed. Defaults to `make`, `model`,
+    /// `focal_length` and `date_time`.
+    #[serde(default = "default_prompt_context_fields")]
+    pub prompt_context_fields: Vec<String>,
+    /// Prompt sent to the AI instead of the built-in SEO one, e.g. asking for
+    /// accessibility alt-text. It must still ask for the same JSON object
+    /// (`title`, `description`, `tags`, `gps`, `subject`). Camera context,
+    /// languages, tag style and augment mode are appended as usual. `None`
+    /// uses the built-in prompt.
+    #[serde(default)]
+    pub prompt: Option<String>,
+    /// How many images to process at the same time. Raising it speeds up
+    /// runs against remote services, at the cost of more simultaneous
+    /// requests (and rate limiting). Defaults to 1, one image at a time.
+    #[serde(default = "default_max_concurrency")]
+    pub max_concurrency: usize,
+    /// If `true`, the CLI and GUI keep each image's AI result in
+    /// `exif-ai-cache.json` next to the config file, keyed by a hash of the
+    /// image's bytes, and reuse it instead of calling the AI again while the
+    /// file is unchanged. With `overwrite_existing` on, images are always
+    /// re-analyzed. See [`AnalysisCache`](crate::cache::AnalysisCache).
+    #[serde(default)]
+    pub enable_cache: bool,
+}
+
+fn default_timeout_secs() -> u64 {
+    crate::ai::DEFAULT_TIMEOUT.as_secs()
+}
+
+fn default_ollama_timeout_secs() -> u64 {
+    crate::ai::DEFAULT_OLLAMA_TIMEOUT.as_secs()
+}
+
+fn default_connect_timeout_secs() -> u64 {
+    crate::ai::DEFAULT_CONNECT_TIMEOUT.as_secs()
+}
+
+fn default_max_retries() -> u32 {
+    crate::ai::RetryPolicy::default().max_retries
+}
+
+fn default_max_concurrency() -> usize {
+    1
+}
+
+fn default_base_backoff_ms() -> u64 {
+    crate::ai::RetryPolicy::default().base_backoff.as_millis() as u64
+}
+
+/// The non-identifying camera fields shared with the AI by default.
+pub(crate) fn default_prompt_context_fields() -> Vec<String> {
+    ["make", "model", "focal_length", "date_time"].map(String::from).to_vec()
 }
 
 impl Default for Config {
@@ -155,39 +595,78 @@ impl Default for Config {
                     api_key: String::new(),
                     model: "gpt-4o-mini".to_string(),
                     enabled: true,
+                    headers: None,
+                    timeout_secs: default_timeout_secs(),
+                    connect_timeout_secs: default_connect_timeout_secs(),
+                    batch: false,
+                    base_url: None,
+                    api_version: None,
                 },
                 gemini: GeminiConfig {
                     api_key: String::new(),
                     model: "gemini-2.0-flash".to_string(),
                     enabled: false,
+                    headers: None,
+                    timeout_secs: default_timeout_secs(),
+                    connect_timeout_secs: default_connect_timeout_secs(),
                 },
+                claude: ClaudeConfig::default(),
                 cloudflare: CloudflareConfig {
                     account_id: String::new(),
                     api_token: String::new(),
                     model: "@cf/llava-hf/llava-1.5-7b-hf".to_string(),
                     enabled: false,
+                    headers: None,
+                    timeout_secs: default_timeout_secs(),
+                    connect_timeout_secs: default_connect_timeout_secs(),
                 },
+                ollama: OllamaConfig::default(),
                 local: LocalConfig::default(),
             },
             service_order: vec![
                 "local".to_string(),
                 "openai".to_string(),
                 "gemini".to_string(),
+                "claude".to_string(),
                 "cloudflare".to_string(),
+                "ollama".to_string(),
             ],
-            exif_fields: ExifFields {
-                write_title: true,
-                write_description: true,
-                write_tags: true,
-                write_gps: true,
-                write_subject: true,
-                overwrite_existing: false,
-            },
+            exif_fields: ExifFields::default(),
             output: OutputConfig {
                 dry_run: false,
                 backup_originals: true,
+                backup_metadata_only: false,
                 log_file: None,
+                augment_existing: false,
+                required_ai_fields: Vec::new(),
+                allow_partial: false,
+                xmp_only: false,
+                always_sidecar: false,
+                write_json_sidecar: false,
+                sidecar_json: false,
+                filename_fallback_title: false,
+                folder_keyword: false,
+                write_aspect_class: false,
+                no_ai: false,
+                per_image_timeout_secs: None,
+                reprompt_on_parse_error: false,
+                merge_services: false,
+                max_tokens: None,
+                max_upload_dimension: None,
+                max_retries: default_max_retries(),
+                base_backoff_ms: default_base_backoff_ms(),
+                max_concurrency: default_max_concurrency(),
+                warn_unsupported_fields: false,
+                tag_style: None,
+                write_provenance: false,
+                description_format: DescriptionFormat::AsIs,
+                symlink_policy: SymlinkPolicy::Follow,
+                prompt_context_fields: default_prompt_context_fields(),
+                prompt: None,
+                enable_cache: false,
             },
+            limits: Limits::default(),
+            language: None,
         }
     }
 }
@@ -219,11 +698,220 @@ impl Config {
 
         let contents =
             std::fs::read_to_string(&config_path).context("Failed to read config file")?;
-        let config: Config =
+        let mut config: Config =
             serde_json::from_str(&contents).context("Failed to parse config file")?;
+        config.validate()?;
+        // Duplicates were reported by validate(); only the first entry counts
+        let mut seen = Vec::new();
+        config.service_order.retain(|name| {
+            let first = !seen.contains(name);
+            seen.push(name.clone());
+            first
+        });
         Ok(config)
     }
 
+    /// Check the config for mistakes.
+    ///
+    /// Problems that would make the config behave unexpectedly (unknown
+    /// service names, empty model names, malformed language tags,
+    /// a zero timeout, invalid HTTP headers, an invalid Ollama URL)
+    /// are returned as an error. Softer problems, such as an enabled service
+    /// without credentials or a service listed twice, are logged as warnings;
+    /// see [`Config::warnings`].
+    pub fn validate(&self) -> Result<()> {
+        let mut errors = Vec::new();
+
+        for name in &self.service_order {
+            if !KNOWN_SERVICES.contains(&name.as_str()) {
+                errors.push(format!(
+                    "service_order: unknown service \"{name}\" (expected one of {})",
+                    KNOWN_SERVICES.join(", ")
+                ));
+            }
+        }
+
+        let services = &self.ai_services;
+        for (name, enabled, model) in [
+            ("openai", services.openai.enabled, &services.openai.model),
+            ("gemini", services.gemini.enabled, &services.gemini.model),
+            ("claude", services.claude.enabled, &services.claude.model),
+            ("cloudflare", services.cloudflare.enabled, &services.cloudflare.model),
+            ("ollama", services.ollama.enabled, &services.ollama.model),
+        ] {
+            if enabled && model.trim().is_empty() {
+                errors.push(format!("ai_services.{name}.model must not be empty"));
+            }
+        }
+
+        for (name, headers) in [
+            ("openai", &services.openai.headers),
+            ("gemini", &services.gemini.headers),
+            ("claude", &services.claude.headers),
+            ("cloudflare", &services.cloudflare.headers),
+            ("ollama", &services.ollama.headers),
+        ] {
+            for (header, value) in headers.iter().flatten() {
+                if reqwest::header::HeaderName::from_bytes(header.as_bytes()).is_err() {
+                    errors.push(format!("ai_services.{name}.headers: invalid header name \"{header}\""));
+                } else if reqwest::header::HeaderValue::from_str(value).is_err() {
+                    errors.push(format!("ai_services.{name}.headers: invalid value for \"{header}\""));
+                }
+            }
+        }
+
+        for (name, timeout, connect_timeout) in [
+            ("openai", services.openai.timeout_secs, services.openai.connect_timeout_secs),
+            ("gemini", services.gemini.timeout_secs, services.gemini.connect_timeout_secs),
+            ("claude", services.claude.timeout_secs, services.claude.connect_timeout_secs),
+            ("cloudflare", services.cloudflare.timeout_secs, services.cloudflare.connect_timeout_secs),
+            ("ollama", services.ollama.timeout_secs, services.ollama.connect_timeout_secs),
+        ] {
+            if timeout == 0 {
+                errors.push(format!("ai_services.{name}.timeout_secs must be at least 1"));
+            }
+            if connect_timeout == 0 {
+                errors.push(format!("ai_services.{name}.connect_timeout_secs must be at least 1"));
+            }
+        }
+
+        if let Some(base_url) = services.openai.base_url.as_ref().filter(|_| services.openai.enabled)
+            && reqwest::Url::parse(base_url).is_err()
+        {
+            errors.push(format!("ai_services.openai.base_url is not a valid URL: \"{base_url}\""));
+        }
+
+        if services.ollama.enabled && reqwest::Url::parse(&services.ollama.base_url).is_err() {
+            errors.push(format!("ai_services.ollama.base_url is not a valid URL: \"{}\"", services.ollama.base_url));
+        }
+
+        if self.output.per_image_timeout_secs == Some(0) {
+            errors.push("output.per_image_timeout_secs must be at least 1 (or null for no limit)".to_string());
+        }
+
+        if self.output.max_upload_dimension == Some(0) {
+            errors.push("output.max_upload_dimension must be at least 1 (or null to send originals)".to_string());
+        }
+
+        if self.limits.max_title_chars == 0 {
+            errors.push("limits.max_title_chars must be at least 1".to_string());
+        }
+        if self.limits.max_description_chars == 0 {
+            errors.push("limits.max_description_chars must be at least 1".to_string());
+        }
+
+        if self.output.max_tokens == Some(0) {
+            errors.push("output.max_tokens must be at least 1 (or null for the default)".to_string());
+        }
+
+        if self.output.prompt.as_ref().is_some_and(|p| p.trim().is_empty()) {
+            errors.push("output.prompt must not be empty (or null for the built-in prompt)".to_string());
+        }
+
+        if self.output.max_concurrency == 0 {
+            errors.push("output.max_concurrency must be at least 1".to_string());
+        }
+
+        if self.ai_services.local.cpu_threads == Some(0) {
+            errors.push("ai_services.local.cpu_threads must be at least 1 (or null for the default)".to_string());
+        }
+
+        for lang in &self.exif_fields.languages {
+            if !crate::exif::is_valid_lang_tag(lang) {
+                errors.push(format!("exif_fields.languages: invalid language tag \"{lang}\""));
+            }
+        }
+        if let Some(lang) = &self.language
+            && !crate::exif::is_valid_lang_tag(lang)
+        {
+            errors.push(format!("language: invalid language tag \"{lang}\""));
+        }
+
+        for field in &self.output.prompt_context_fields {
+            if !crate::exif::PROMPT_CONTEXT_FIELDS.iter().any(|(name, _)| name == field) {
+                errors.push(format!("output.prompt_context_fields: unknown field \"{field}\""));
+            }
+        }
+
+        if let Some(urgency) = self.exif_fields.metadata_defaults.urgency
+            && !(1..=8).contains(&urgency)
+        {
+            errors.push(format!("exif_fields.metadata_defaults.urgency must be between 1 and 8, got {urgency}"));
+        }
+
+        let defaults = &self.exif_fields.metadata_defaults;
+        for (flag, enabled, name, value) in [
+            ("write_copyright", self.exif_fields.write_copyright, "copyright", &defaults.copyright),
+            ("write_artist", self.exif_fields.write_artist, "artist", &defaults.artist),
+        ] {
+            if enabled && value.as_deref().is_none_or(|v| v.trim().is_empty()) {
+                errors.push(format!("exif_fields.{flag} needs a non-empty exif_fields.metadata_defaults.{name}"));
+            }
+        }
+
+        for warning in self.warnings() {
+            log::warn!("{warning}");
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            anyhow::bail!("Invalid config:\n  {}", errors.join("\n  "))
+        }
+    }
+
+    /// Non-fatal config problems: enabled services missing credentials or
+    /// absent from `service_order`, services listed more than once, and
+    /// having no usable service at all.
+    pub fn warnings(&self) -> Vec<String> {
+        let services = &self.ai_services;
+        let mut warnings = Vec::new();
+
+        if services.openai.enabled && services.openai.api_key.trim().is_empty() && services.openai.base_url.is_none() {
+            warnings.push("OpenAI is enabled but ai_services.openai.api_key is empty".to_string());
+        }
+        if services.gemini.enabled && services.gemini.api_key.trim().is_empty() {
+            warnings.push("Gemini is enabled but ai_services.gemini.api_key is empty".to_string());
+        }
+        if services.claude.enabled && services.claude.api_key.trim().is_empty() {
+            warnings.push("Claude is enabled but ai_services.claude.api_key is empty".to_string());
+        }
+        if services.cloudflare.enabled
+            && (services.cloudflare.account_id.trim().is_empty()
+                || services.cloudflare.api_token.trim().is_empty())
+        {
+            warnings.push(
+                "Cloudflare is enabled but ai_services.cloudflare.account_id or api_token is empty"
+                    .to_string(),
+            );
+        }
+
+        for (name, enabled) in [
+            ("openai", services.openai.enabled),
+            ("gemini", services.gemini.enabled),
+            ("claude", services.claude.enabled),
+            ("cloudflare", services.cloudflare.enabled),
+            ("ollama", services.ollama.enabled),
+            ("local", services.local.enabled),
+        ] {
+            if enabled && !self.service_order.iter().any(|s| s == name) {
+                warnings.push(format!("{name} is enabled but not listed in service_order"));
+            }
+        }
+
+        for (i, name) in self.service_order.iter().enumerate() {
+            if self.service_order[..i].contains(name) && !self.service_order[i + 1..].contains(name) {
+                warnings.push(format!("service_order: \"{name}\" is listed more than once; only the first is used"));
+            }
+        }
+
+        if self.enabled_services().is_empty() {
+            warnings.push("No AI services are enabled".to_string());
+        }
+
+        warnings
+    }
+
     /// Save config to the given path, or to the default location.
     pub fn save(&self, path: Option<&Path>) -> Result<()> {
         let config_path = match path {
@@ -237,6 +925,37 @@ impl Config {
         Ok(())
     }
 
+    /// A copy of the config that is safe to print: API keys, tokens and the
+    /// values of credential-like headers (`Authorization`, `*-Token`, `*-Key`,
+    /// cookies, …) are replaced by `"********"`.
+    pub fn redacted(&self) -> Config {
+        fn mask(secret: &mut String) {
+            if !secret.is_empty() {
+                *secret = REDACTED.to_string();
+            }
+        }
+        fn mask_headers(headers: &mut Option<BTreeMap<String, String>>) {
+            for (name, value) in headers.iter_mut().flatten() {
+                if is_sensitive_header(name) {
+                    *value = REDACTED.to_string();
+                }
+            }
+        }
+
+        let mut config = self.clone();
+        let services = &mut config.ai_services;
+        mask(&mut services.openai.api_key);
+        mask(&mut services.gemini.api_key);
+        mask(&mut services.claude.api_key);
+        mask(&mut services.cloudflare.api_token);
+        mask_headers(&mut services.openai.headers);
+        mask_headers(&mut services.gemini.headers);
+        mask_headers(&mut services.claude.headers);
+        mask_headers(&mut services.cloudflare.headers);
+        mask_headers(&mut services.ollama.headers);
+        config
+    }
+
     /// Get the ordered list of enabled AI services.
     pub fn enabled_services(&self) -> Vec<String> {
         self.service_order
@@ -244,7 +963,9 @@ impl Config {
             .filter(|name| match name.as_str() {
                 "openai" => self.ai_services.openai.enabled,
                 "gemini" => self.ai_services.gemini.enabled,
+                "claude" => self.ai_services.claude.enabled,
                 "cloudflare" => self.ai_services.cloudflare.enabled,
+                "ollama" => self.ai_services.ollama.enabled,
                 "local" => self.ai_services.local.enabled,
                 _ => false,
             })
@@ -265,12 +986,15 @@ mod tests {
         let config = Config::default();
         assert!(config.ai_services.openai.enabled);
         assert!(!config.ai_services.gemini.enabled);
+        assert!(!config.ai_services.claude.enabled);
         assert!(!config.ai_services.cloudflare.enabled);
+        assert!(!config.ai_services.ollama.enabled);
+        assert_eq!(config.ai_services.ollama.base_url, "http://localhost:11434");
         assert!(config.ai_services.openai.api_key.is_empty());
         assert_eq!(config.ai_services.openai.model, "gpt-4o-mini");
         assert!(!config.ai_services.local.enabled);
         assert!(config.ai_services.local.model_path.is_empty());
-        assert_eq!(config.service_order, vec!["local", "openai", "gemini", "cloudflare"]);
+        assert_eq!(config.service_order, vec!["local", "openai", "gemini", "claude", "cloudflare", "ollama"]);
     }
 
     #[test]
@@ -282,6 +1006,8 @@ mod tests {
         assert!(config.exif_fields.write_gps);
         assert!(config.exif_fields.write_subject);
         assert!(!config.exif_fields.overwrite_existing);
+        assert!(!config.exif_fields.write_copyright);
+        assert!(!config.exif_fields.write_artist);
     }
 
     #[test]
@@ -290,6 +1016,7 @@ mod tests {
         assert!(!config.output.dry_run);
         assert!(config.output.backup_originals);
         assert!(config.output.log_file.is_none());
+        assert_eq!((config.output.max_retries, config.output.base_backoff_ms), (2, 500));
     }
 
     // ── Config::save / Config::load round-trip ───────────────────────
@@ -365,6 +1092,325 @@ mod tests {
         assert!(enabled.is_empty());
     }
 
+    // ── Config::validate ─────────────────────────────────────────────
+
+    #[test]
+    fn validate_accepts_defaults() {
+        Config::default().validate().unwrap();
+    }
+
+    #[test]
+    fn validate_rejects_unknown_service() {
+        let mut config = Config::default();
+        config.service_order.push("mistral".to_string());
+        let err = config.validate().unwrap_err().to_string();
+        assert!(err.contains("unknown service \"mistral\""), "{err}");
+    }
+
+    #[test]
+    fn duplicate_services_warn_and_load_once() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("dup_order.json");
+        let mut config = Config::default();
+        config.ai_services.openai.api_key = "sk-test".to_string();
+        config.service_order = ["openai", "gemini", "openai", "openai"].map(String::from).to_vec();
+        config.validate().unwrap();
+        assert_eq!(
+            config.warnings(),
+            vec!["service_order: \"openai\" is listed more than once; only the first is used"]
+        );
+
+        config.save(Some(&path)).unwrap();
+        let loaded = Config::load(Some(&path)).unwrap();
+        assert_eq!(loaded.service_order, vec!["openai", "gemini"]);
+    }
+
+    #[test]
+    fn validate_rejects_empty_model_for_enabled_service() {
+        let mut config = Config::default();
+        config.ai_services.openai.model = "  ".to_string();
+        let err = config.validate().unwrap_err().to_string();
+        assert!(err.contains("ai_services.openai.model"), "{err}");
+
+        // Disabled services don't need a model.
+        let mut config = Config::default();
+        config.ai_services.gemini.model.clear();
+        config.validate().unwrap();
+    }
+
+    #[test]
+    fn validate_rejects_zero_service_timeouts() {
+        let mut config = Config::default();
+        config.ai_services.gemini.timeout_secs = 0;
+        config.ai_services.cloudflare.connect_timeout_secs = 0;
+        let err = config.validate().unwrap_err().to_string();
+        assert!(err.contains("ai_services.gemini.timeout_secs"), "{err}");
+        assert!(err.contains("ai_services.cloudflare.connect_timeout_secs"), "{err}");
+    }
+
+    #[test]
+    fn validate_rejects_invalid_ollama_url() {
+        let mut config = Config::default();
+        config.ai_services.ollama.base_url = "localhost 11434".to_string();
+        config.validate().unwrap(); // not checked while disabled
+
+        config.ai_services.ollama.enabled = true;
+        let err = config.validate().unwrap_err().to_string();
+        assert!(err.contains("ai_services.ollama.base_url"), "{err}");
+
+        config.ai_services.ollama.base_url = "http://gpu-box.lan:11434".to_string();
+        config.validate().unwrap();
+    }
+
+    #[test]
+    fn validate_rejects_invalid_openai_base_url() {
+        let mut config = Config::default();
+        config.ai_services.openai.base_url = Some("my-resource.openai.azure.com".to_string());
+        let err = config.validate().unwrap_err().to_string();
+        assert!(err.contains("ai_services.openai.base_url"), "{err}");
+
+        config.ai_services.openai.base_url = Some("http://localhost:8080/v1".to_string());
+        config.validate().unwrap();
+        // A self-hosted gateway doesn't need a key
+        assert!(!config.warnings().iter().any(|w| w.contains("openai.api_key")));
+    }
+
+    #[test]
+    fn validate_rejects_invalid_language_tag() {
+        let mut config = Config::default();
+        config.exif_fields.languages = vec!["fr".to_string(), "pt_BR".to_string()];
+        let err = config.validate().unwrap_err().to_string();
+        assert!(err.contains("\"pt_BR\""), "{err}");
+        assert!(!err.contains("\"fr\""), "{err}");
+
+        let mut config = Config { language: Some("de DE".to_string()), ..Default::default() };
+        let err = config.validate().unwrap_err().to_string();
+        assert!(err.contains("language: invalid language tag \"de DE\""), "{err}");
+        config.language = Some("de-CH".to_string());
+        config.validate().unwrap();
+    }
+
+    fn headers(pairs: &[(&str, &str)]) -> Option<BTreeMap<String, String>> {
+        Some(pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect())
+    }
+
+    #[test]
+    fn validate_rejects_invalid_headers() {
+        let mut config = Config::default();
+        config.ai_services.openai.headers = headers(&[("X-Request-ID", "abc")]);
+        config.validate().unwrap();
+
+        config.ai_services.gemini.headers = headers(&[("Bad Header", "x")]);
+        config.ai_services.cloudflare.headers = headers(&[("X-Trace", "line\nbreak")]);
+        let err = config.validate().unwrap_err().to_string();
+        assert!(err.contains("ai_services.gemini.headers: invalid header name \"Bad Header\""), "{err}");
+        assert!(err.contains("ai_services.cloudflare.headers: invalid value for \"X-Trace\""), "{err}");
+    }
+
+    #[test]
+    fn headers_default_to_none() {
+        let json = r#"{ "api_key": "k", "model": "m", "enabled": true }"#;
+        let openai: OpenAiConfig = serde_json::from_str(json).unwrap();
+        assert!(openai.headers.is_none());
+        assert!(!openai.batch);
+        assert_eq!((openai.timeout_secs, openai.connect_timeout_secs), (60, 10));
+        let ollama: OllamaConfig = serde_json::from_str(r#"{ "base_url": "u", "model": "m", "enabled": true }"#).unwrap();
+        assert_eq!(ollama.timeout_secs, 300);
+    }
+
+    #[test]
+    fn redacted_masks_secrets_and_sensitive_headers() {
+        let mut config = Config::default();
+        config.ai_services.openai.api_key = "sk-secret".into();
+        config.ai_services.openai.headers = headers(&[
+            ("X-Request-ID", "req-1"),
+            ("Authorization", "Bearer gw"),
+            ("X-Api-Key", "k"),
+            ("X-Gateway-Token", "t"),
+        ]);
+        config.ai_services.cloudflare.api_token = "cf-secret".into();
+
+        let redacted = config.redacted();
+        let services = &redacted.ai_services;
+        assert_eq!(services.openai.api_key, "********");
+        assert_eq!(services.cloudflare.api_token, "********");
+        assert_eq!(services.gemini.api_key, "", "empty keys stay empty");
+        let headers = services.openai.headers.as_ref().unwrap();
+        assert_eq!(headers["X-Request-ID"], "req-1");
+        assert_eq!(headers["Authorization"], "********");
+        assert_eq!(headers["X-Api-Key"], "********");
+        assert_eq!(headers["X-Gateway-Token"], "********");
+
+        let printed = serde_json::to_string(&redacted).unwrap();
+        assert!(!printed.contains("secret") && !printed.contains("Bearer gw"), "{printed}");
+        // The original is untouched
+        assert_eq!(config.ai_services.openai.api_key, "sk-secret");
+    }
+
+    #[test]
+    fn validate_rejects_zero_cpu_threads() {
+        let mut config = Config::default();
+        config.ai_services.local.cpu_threads = Some(0);
+        let err = config.validate().unwrap_err().to_string();
+        assert!(err.contains("cpu_threads"), "{err}");
+
+        config.ai_services.local.cpu_threads = Some(4);
+        config.validate().unwrap();
+    }
+
+    #[test]
+    fn validate_rejects_zero_timeout() {
+        let mut config = Config::default();
+        config.output.per_image_timeout_secs = Some(0);
+        let err = config.validate().unwrap_err().to_string();
+        assert!(err.contains("per_image_timeout_secs"), "{err}");
+
+        config.output.per_image_timeout_secs = Some(30);
+        config.validate().unwrap();
+    }
+
+    #[test]
+    fn validate_rejects_zero_max_upload_dimension() {
+        let mut config = Config::default();
+        config.output.max_upload_dimension = Some(0);
+        let err = config.validate().unwrap_err().to_string();
+        assert!(err.contains("max_upload_dimension"), "{err}");
+
+        config.output.max_upload_dimension = Some(1568);
+        config.validate().unwrap();
+    }
+
+    #[test]
+    fn validate_rejects_zero_max_tokens() {
+        let mut config = Config::default();
+        config.output.max_tokens = Some(0);
+        let err = config.validate().unwrap_err().to_string();
+        assert!(err.contains("output.max_tokens"), "{err}");
+
+        config.output.max_tokens = Some(4000);
+        config.validate().unwrap();
+    }
+
+    #[test]
+    fn sidecar_json_accepts_its_former_name() {
+        let mut value = serde_json::to_value(Config::default()).unwrap();
+        let output = value["output"].as_object_mut().unwrap();
+        output.remove("sidecar_json");
+        output.insert("json_only".into(), true.into());
+        let config: Config = serde_json::from_value(value).unwrap();
+        assert!(config.output.sidecar_json);
+    }
+
+    #[test]
+    fn limits_default_to_the_prompt_lengths() {
+        let config: Config = serde_json::from_value(serde_json::to_value(Config::default()).unwrap()).unwrap();
+        assert_eq!(config.limits, Limits { max_title_chars: 60, max_description_chars: 254 });
+
+        let mut config = Config::default();
+        config.limits.max_title_chars = 0;
+        let err = config.validate().unwrap_err().to_string();
+        assert!(err.contains("limits.max_title_chars"), "{err}");
+    }
+
+    #[test]
+    fn validate_rejects_empty_prompt() {
+        let mut config = Config::default();
+        config.output.prompt = Some("  ".into());
+        let err = config.validate().unwrap_err().to_string();
+        assert!(err.contains("output.prompt"), "{err}");
+
+        config.output.prompt = Some("Write alt-text as JSON.".into());
+        config.validate().unwrap();
+    }
+
+    #[test]
+    fn validate_rejects_zero_concurrency() {
+        let mut config = Config::default();
+        assert_eq!(config.output.max_concurrency, 1);
+        config.output.max_concurrency = 0;
+        let err = config.validate().unwrap_err().to_string();
+        assert!(err.contains("output.max_concurrency"), "{err}");
+
+        config.output.max_concurrency = 4;
+        config.validate().unwrap();
+    }
+
+    #[test]
+    fn validate_requires_credit_values() {
+        let mut config = Config::default();
+        config.exif_fields.write_copyright = true;
+        config.exif_fields.write_artist = true;
+        config.exif_fields.metadata_defaults.artist = Some("  ".to_string());
+        let err = config.validate().unwrap_err().to_string();
+        assert!(err.contains("write_copyright needs a non-empty exif_fields.metadata_defaults.copyright"), "{err}");
+        assert!(err.contains("write_artist needs a non-empty exif_fields.metadata_defaults.artist"), "{err}");
+
+        config.exif_fields.metadata_defaults.copyright = Some("© 2024 Jane Doe".to_string());
+        config.exif_fields.metadata_defaults.artist = Some("Jane Doe".to_string());
+        config.validate().unwrap();
+    }
+
+    #[test]
+    fn validate_reports_all_errors() {
+        let mut config = Config {
+            service_order: vec!["nope".to_string()],
+            ..Default::default()
+        };
+        config.exif_fields.languages = vec!["x-default".to_string()];
+        config.exif_fields.metadata_defaults.urgency = Some(9);
+        config.output.prompt_context_fields.push("owner".to_string());
+        let err = config.validate().unwrap_err().to_string();
+        assert!(err.contains("\"nope\""), "{err}");
+        assert!(err.contains("\"x-default\""), "{err}");
+        assert!(err.contains("urgency"), "{err}");
+        assert!(err.contains("prompt_context_fields: unknown field \"owner\""), "{err}");
+    }
+
+    #[test]
+    fn warnings_flag_missing_credentials() {
+        let mut config = Config::default();
+        config.ai_services.gemini.enabled = true;
+        config.ai_services.cloudflare.enabled = true;
+        config.ai_services.cloudflare.account_id = "acct".to_string();
+        let warnings = config.warnings();
+        assert!(warnings.iter().any(|w| w.contains("openai.api_key")));
+        assert!(warnings.iter().any(|w| w.contains("gemini.api_key")));
+        assert!(warnings.iter().any(|w| w.contains("cloudflare")));
+        // Missing credentials are warnings, not errors.
+        config.validate().unwrap();
+
+        config.ai_services.openai.api_key = "sk-test".to_string();
+        config.ai_services.gemini.api_key = "g-test".to_string();
+        config.ai_services.cloudflare.api_token = "cf-test".to_string();
+        assert!(config.warnings().is_empty(), "{:?}", config.warnings());
+    }
+
+    #[test]
+    fn warnings_flag_enabled_service_missing_from_order() {
+        let mut config = Config {
+            service_order: vec!["gemini".to_string()],
+            ..Default::default()
+        };
+        config.ai_services.openai.api_key = "sk-test".to_string();
+        let warnings = config.warnings();
+        assert!(warnings.iter().any(|w| w.contains("openai is enabled but not listed")));
+        assert!(warnings.iter().any(|w| w == "No AI services are enabled"));
+    }
+
+    #[test]
+    fn load_rejects_invalid_config() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("bad_order.json");
+        let config = Config {
+            service_order: vec!["openia".to_string()],
+            ..Default::default()
+        };
+        config.save(Some(&path)).unwrap();
+
+        let err = format!("{:#}", Config::load(Some(&path)).unwrap_err());
+        assert!(err.contains("unknown service \"openia\""), "{err}");
+    }
+
     // ── Serialization ────────────────────────────────────────────────
 
     #[test]
@@ -407,5 +1453,8 @@ mod tests {
         assert!(config.exif_fields.overwrite_existing);
         assert!(config.output.dry_run);
         assert!(!config.output.backup_originals);
+        // Configs written before Claude support get it disabled
+        assert!(!config.ai_services.claude.enabled);
+        assert_eq!(config.ai_services.claude.model, "claude-3-5-sonnet-latest");
     }
 }
@@ -0,0 +1,183 @@
+//! Pluggable image decoding for previews and model input.
+//!
+//! The crate decodes pixels with the `image` crate and, on macOS, falls back to
+//! `sips` for HEIC/RAW/AVIF. Neither covers every format on every platform, so
+//! callers can register a [`DecoderHook`] — e.g. backed by libraw, libheif or an
+//! external converter — without the crate depending on heavy native libraries.
+//!
+//! ```rust,no_run
+//! use exif_ai::decode::Decoders;
+//! use std::path::Path;
+//!
+//! let decoders = Decoders::new().with_hook(|path: &Path| {
+//!     // Convert with an external tool and load the result
+//!     let out = std::env::temp_dir().join("converted.png");
+//!     let ok = std::process::Command::new("my-raw-converter").arg(path).arg(&out).status().ok()?;
+//!     if ok.success() { image::open(&out).ok() } else { None }
+//! });
+//! let preview = decoders.decode(Path::new("photo.cr3"));
+//! ```
+
+use std::path::Path;
+use std::sync::Arc;
+
+use image::DynamicImage;
+
+/// Decodes an image file into pixels.
+///
+/// Return `None` for files the hook doesn't handle; the next hook (and finally
+/// [`DefaultDecoder`]) is tried. Closures `Fn(&Path) -> Option<DynamicImage>`
+/// implement this trait.
+pub trait DecoderHook: Send + Sync {
+    fn decode(&self, path: &Path) -> Option<DynamicImage>;
+}
+
+impl<F> DecoderHook for F
+where
+    F: Fn(&Path) -> Option<DynamicImage> + Send + Sync,
+{
+    fn decode(&self, path: &Path) -> Option<DynamicImage> {
+        self(path)
+    }
+}
+
+/// The built-in decoder: the `image` crate, then `sips` on macOS.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DefaultDecoder;
+
+impl DecoderHook for DefaultDecoder {
+    fn decode(&self, path: &Path) -> Option<DynamicImage> {
+        let bytes = std::fs::read(path).ok()?;
+        image::load_from_memory(&bytes).ok().or_else(|| decode_with_sips(path))
+    }
+}
+
+/// Convert HEIC/RAW/AVIF to JPEG with macOS `sips` and decode that.
+#[cfg(target_os = "macos")]
+fn decode_with_sips(path: &Path) -> Option<DynamicImage> {
+    let tmp = std::env::temp_dir().join(format!("exif_ai_decode_{}.jpg", std::process::id()));
+    let status = std::process::Command::new("sips")
+        .args(["-s", "format", "jpeg", "-s", "formatOptions", "70"])
+        .arg(path)
+        .arg("--out")
+        .arg(&tmp)
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status();
+    if !status.is_ok_and(|s| s.success()) {
+        return None;
+    }
+    let bytes = std::fs::read(&tmp).ok();
+    let _ = std::fs::remove_file(&tmp);
+    image::load_from_memory(&bytes?).ok()
+}
+
+#[cfg(not(target_os = "macos"))]
+fn decode_with_sips(_path: &Path) -> Option<DynamicImage> {
+    None
+}
+
+/// Registered decoder hooks, tried in order before the [`DefaultDecoder`].
+///
+/// Cheap to clone; hooks are shared.
+#[derive(Clone, Default)]
+pub struct Decoders {
+    hooks: Vec<Arc<dyn DecoderHook>>,
+}
+
+impl Decoders {
+    /// Only the [`DefaultDecoder`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Try `hook` after the hooks registered so far, before the default decoder.
+    pub fn with_hook(mut self, hook: impl DecoderHook + 'static) -> Self {
+        self.hooks.push(Arc::new(hook));
+        self
+    }
+
+    /// Number of registered hooks, not counting the default decoder.
+    pub fn hook_count(&self) -> usize {
+        self.hooks.len()
+    }
+
+    /// Decode `path` with the first hook that handles it, falling back to
+    /// the [`DefaultDecoder`].
+    pub fn decode(&self, path: &Path) -> Option<DynamicImage> {
+        self.hooks
+            .iter()
+            .find_map(|hook| hook.decode(path))
+            .or_else(|| DefaultDecoder.decode(path))
+    }
+}
+
+impl std::fmt::Debug for Decoders {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Decoders").field("hooks", &self.hooks.len()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn data_path(name: &str) -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("data").join(name)
+    }
+
+    /// Decodes `.raw` files to a 2×1 image and counts its calls.
+    struct MockRaw {
+        calls: Arc<AtomicUsize>,
+    }
+
+    impl DecoderHook for MockRaw {
+        fn decode(&self, path: &Path) -> Option<DynamicImage> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            (path.extension()? == "raw").then(|| DynamicImage::new_rgb8(2, 1))
+        }
+    }
+
+    #[test]
+    fn hook_handles_its_format() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let decoders = Decoders::new().with_hook(MockRaw { calls: calls.clone() });
+
+        let img = decoders.decode(Path::new("/nonexistent/photo.raw")).unwrap();
+        assert_eq!((img.width(), img.height()), (2, 1));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn unhandled_files_fall_back_to_default() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let decoders = Decoders::new().with_hook(MockRaw { calls: calls.clone() });
+
+        let img = decoders.decode(&data_path("test_canon_powershot.jpg")).expect("default decoder");
+        assert!(img.width() > 0);
+        assert_eq!(calls.load(Ordering::SeqCst), 1, "hook is asked first");
+
+        assert!(decoders.decode(Path::new("/nonexistent/photo.jpg")).is_none());
+    }
+
+    #[test]
+    fn hooks_tried_in_registration_order() {
+        let second = Arc::new(AtomicUsize::new(0));
+        let decoders = Decoders::new()
+            .with_hook(|_: &Path| Some(DynamicImage::new_rgb8(1, 1)))
+            .with_hook(MockRaw { calls: second.clone() });
+        assert_eq!(decoders.hook_count(), 2);
+
+        let img = decoders.decode(Path::new("photo.raw")).unwrap();
+        assert_eq!(img.width(), 1);
+        assert_eq!(second.load(Ordering::SeqCst), 0, "first hook wins");
+    }
+
+    #[test]
+    fn default_decoder_reads_web_formats() {
+        assert!(DefaultDecoder.decode(&data_path("test_canon_powershot.jpg")).is_some());
+        assert!(Decoders::new().decode(Path::new("/nonexistent.png")).is_none());
+    }
+}
@@ -0,0 +1,150 @@
+//! Typed failures of the processing pipeline.
+//!
+//! [`ProcessResult::error`](crate::pipeline::ProcessResult::error) holds an
+//! [`ExifAiError`], so callers can tell an unreadable image from an AI outage
+//! without parsing messages. Its `Display` gives the same text as before.
+//!
+//! ```rust,no_run
+//! use exif_ai::error::ExifAiError;
+//! use exif_ai::pipeline::Pipeline;
+//! use std::path::Path;
+//!
+//! # async fn example(pipeline: &Pipeline) {
+//! let result = pipeline.process_image(Path::new("photo.jpg")).await;
+//! match &result.error {
+//!     Some(ExifAiError::AllServicesFailed(errors)) => eprintln!("AI down: {errors:?}"),
+//!     Some(ExifAiError::Io { kind: std::io::ErrorKind::PermissionDenied, path, .. }) => {
+//!         eprintln!("{} is not writable", path.display())
+//!     }
+//!     Some(e) => eprintln!("{e}"),
+//!     None => {}
+//! }
+//! # }
+//! ```
+
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::exif::Field;
+
+/// Why an image could not be processed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExifAiError {
+    /// The image is empty, truncated, or not an image the upload could
+    /// decode. `message` describes the problem.
+    Read { path: PathBuf, message: String },
+    /// Metadata, the JSON sidecar or a symlink's copy couldn't be built or
+    /// written for a reason other than the filesystem (e.g. a malformed file).
+    Write { path: PathBuf, message: String },
+    /// The filesystem refused a read or write: the image or its directory is
+    /// missing or not writable, or the disk is full. `kind` tells which.
+    Io { path: PathBuf, kind: io::ErrorKind, message: String },
+    /// Every AI service that accepts the image failed, with one
+    /// `"<service>: <error>"` entry per service.
+    AllServicesFailed(Vec<String>),
+    /// No configured AI service accepts the image's format.
+    Unsupported { path: PathBuf, mime_type: String },
+    /// The AI took longer than `output.per_image_timeout_secs`.
+    Timeout(Duration),
+    /// The AI result lacks fields from `output.required_ai_fields`.
+    MissingFields(Vec<Field>),
+}
+
+impl ExifAiError {
+    /// The image the error is about, where the error names one.
+    pub fn path(&self) -> Option<&PathBuf> {
+        match self {
+            ExifAiError::Read { path, .. }
+            | ExifAiError::Write { path, .. }
+            | ExifAiError::Io { path, .. }
+            | ExifAiError::Unsupported { path, .. } => Some(path),
+            _ => None,
+        }
+    }
+
+    /// `Io` when `error` was caused by an [`io::Error`], otherwise the error
+    /// `other` builds. `message` becomes the `Display` text either way.
+    pub(crate) fn io_or(
+        path: &Path,
+        error: &anyhow::Error,
+        message: String,
+        other: fn(PathBuf, String) -> Self,
+    ) -> Self {
+        match error.chain().find_map(|e| e.downcast_ref::<io::Error>()) {
+            Some(e) => ExifAiError::Io { path: path.to_path_buf(), kind: e.kind(), message },
+            None => other(path.to_path_buf(), message),
+        }
+    }
+
+    /// Whether the AI (rather than the image or the disk) was at fault, so
+    /// a later run may succeed.
+    pub fn is_ai_failure(&self) -> bool {
+        matches!(
+            self,
+            ExifAiError::AllServicesFailed(_) | ExifAiError::Timeout(_) | ExifAiError::MissingFields(_)
+        )
+    }
+}
+
+impl fmt::Display for ExifAiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExifAiError::Read { message, .. } | ExifAiError::Write { message, .. } | ExifAiError::Io { message, .. } => {
+                f.write_str(message)
+            }
+            ExifAiError::AllServicesFailed(errors) => write!(f, "All AI services failed: {}", errors.join("; ")),
+            ExifAiError::Unsupported { mime_type, .. } => write!(f, "No configured AI service supports {mime_type}"),
+            ExifAiError::Timeout(limit) => write!(f, "AI analysis timed out after {}s", limit.as_secs_f32()),
+            ExifAiError::MissingFields(fields) => {
+                let names: Vec<&str> = fields.iter().map(Field::as_str).collect();
+                write!(f, "AI result missing required field(s): {}", names.join(", "))
+            }
+        }
+    }
+}
+
+impl std::error::Error for ExifAiError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_keeps_the_original_messages() {
+        let services = ExifAiError::AllServicesFailed(vec!["OpenAI: 500".into(), "Gemini: quota".into()]);
+        assert_eq!(services.to_string(), "All AI services failed: OpenAI: 500; Gemini: quota");
+        assert!(services.is_ai_failure());
+        assert_eq!(services.path(), None);
+
+        let missing = ExifAiError::MissingFields(vec![Field::Tags, Field::Gps]);
+        assert_eq!(missing.to_string(), "AI result missing required field(s): tags, gps");
+
+        let read = ExifAiError::Read { path: "a.jpg".into(), message: "Image file is empty".into() };
+        assert_eq!(read.to_string(), "Image file is empty");
+        assert_eq!(read.path(), Some(&PathBuf::from("a.jpg")));
+        assert!(!read.is_ai_failure());
+    }
+
+    #[test]
+    fn io_or_keeps_the_io_error_kind() {
+        let denied = anyhow::Error::from(io::Error::from(io::ErrorKind::PermissionDenied)).context("Failed to write");
+        let error = ExifAiError::io_or(Path::new("a.jpg"), &denied, "Failed to write metadata".into(), |path, message| {
+            ExifAiError::Write { path, message }
+        });
+        assert_eq!(
+            error,
+            ExifAiError::Io {
+                path: "a.jpg".into(),
+                kind: io::ErrorKind::PermissionDenied,
+                message: "Failed to write metadata".into(),
+            }
+        );
+        assert_eq!(error.to_string(), "Failed to write metadata");
+
+        let parse = anyhow::anyhow!("Failed to parse JPEG");
+        let error = ExifAiError::io_or(Path::new("a.jpg"), &parse, "bad".into(), |path, message| ExifAiError::Write { path, message });
+        assert!(matches!(error, ExifAiError::Write { .. }));
+    }
+}
@@ -0,0 +1,403 @@
+//! Metadata-only backups.
+//!
+//! [`backup_metadata`] stores just an image's EXIF/XMP/IPTC blocks in a small
+//! `<image>.metabak` file, and [`restore_metadata`] puts them back. For large
+//! files this is far cheaper than the full `.bak` copy made by `backup_originals`,
+//! since no pixel data is duplicated.
+//!
+//! | Format | Blocks stored |
+//! |--------|---------------|
+//! | JPEG | APP1 EXIF and XMP segments, APP13 (IPTC) segments |
+//! | PNG | `eXIf` chunk, XMP `iTXt` chunk |
+//! | WebP | `EXIF` and `XMP ` chunks |
+//! | Sidecar (HEIC, AVIF, RAW) | The existing `.xmp` sidecar, if any |
+//!
+//! TIFF is not supported: its metadata lives in the same IFD structure as the
+//! pixel data, so use a full backup instead.
+
+use anyhow::{Context, Result, bail};
+use img_parts::jpeg::{Jpeg, JpegSegment};
+use img_parts::png::{Png, PngChunk};
+use img_parts::riff::{RiffChunk, RiffContent};
+use img_parts::webp::WebP;
+use img_parts::{Bytes, ImageEXIF};
+use std::path::{Path, PathBuf};
+
+use crate::pipeline::ImageKind;
+use super::writer::write_atomic;
+
+const MAGIC: &[u8; 8] = b"EXAIMB\0\x01";
+const XMP_APP1_PREFIX: &[u8] = b"http://ns.adobe.com/xap/1.0/\0";
+const XMP_EXTENSION_PREFIX: &[u8] = b"http://ns.adobe.com/xmp/extension/\0";
+const EXIF_APP1_PREFIX: &[u8] = b"Exif\0\0";
+const PNG_XMP_KEYWORD: &[u8] = b"XML:com.adobe.xmp";
+
+/// Path of the metadata backup for `path` (e.g. `photo.jpg.metabak`).
+pub fn metadata_backup_path(path: &Path) -> PathBuf {
+    path.with_extension(format!(
+        "{}.metabak",
+        path.extension().unwrap_or_default().to_string_lossy()
+    ))
+}
+
+/// Store the metadata blocks of `path` in its `.metabak` file.
+///
+/// An existing backup is kept as-is, so repeated runs never replace the
+/// original metadata with already-modified metadata.
+///
+/// # Errors
+///
+/// Returns an error for TIFF files, or if the image can't be read or parsed.
+pub fn backup_metadata(path: &Path, kind: ImageKind) -> Result<PathBuf> {
+    let backup_path = metadata_backup_path(path);
+    if backup_path.exists() {
+        return Ok(backup_path);
+    }
+
+    let blocks = match kind {
+        ImageKind::Jpeg => {
+            let jpeg = read_jpeg(path)?;
+            jpeg.segments()
+                .iter()
+                .filter(|seg| is_jpeg_metadata(seg))
+                .map(|seg| Block { id: [b'A', b'P', b'P', seg.marker()], data: seg.contents().to_vec() })
+                .collect()
+        }
+        ImageKind::Png => {
+            let png = read_png(path)?;
+            png.chunks()
+                .iter()
+                .filter(|chunk| is_png_metadata(chunk))
+                .map(|chunk| Block { id: chunk.kind(), data: chunk.contents().to_vec() })
+                .collect()
+        }
+        ImageKind::WebP => {
+            let webp = read_webp(path)?;
+            [*b"EXIF", *b"XMP "]
+                .into_iter()
+                .filter_map(|id| {
+                    let data = webp.chunk_by_id(id)?.content().data()?;
+                    Some(Block { id, data: data.to_vec() })
+                })
+                .collect()
+        }
+        ImageKind::Sidecar => {
+            let xmp_path = path.with_extension("xmp");
+            if xmp_path.exists() {
+                let data = std::fs::read(&xmp_path)
+                    .with_context(|| format!("Failed to read {}", xmp_path.display()))?;
+                vec![Block { id: *b"XMP ", data }]
+            } else {
+                Vec::new()
+            }
+        }
+        ImageKind::Tiff => {
+            bail!("Metadata-only backup is not supported for TIFF — EXIF is integral to the TIFF structure")
+        }
+    };
+
+    write_atomic(&backup_path, &encode(kind, &blocks))
+        .with_context(|| format!("Failed to write {}", backup_path.display()))?;
+    log::debug!("Metadata backup created: {}", backup_path.display());
+    Ok(backup_path)
+}
+
+/// Re-apply the metadata stored by [`backup_metadata`] to `path`.
+///
+/// Metadata blocks currently in the image are replaced by the backed-up ones;
+/// pixel data and unrelated segments are left alone. For sidecar formats, the
+/// backed-up `.xmp` is restored, or the sidecar removed if there was none.
+/// The `.metabak` file is kept.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use exif_ai::exif::restore_metadata;
+/// use std::path::Path;
+///
+/// restore_metadata(Path::new("photo.jpg"))?;
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+pub fn restore_metadata(path: &Path) -> Result<()> {
+    let backup_path = metadata_backup_path(path);
+    let raw = std::fs::read(&backup_path)
+        .with_context(|| format!("No metadata backup found at {}", backup_path.display()))?;
+    let (kind, blocks) = decode(&raw).with_context(|| format!("Invalid metadata backup {}", backup_path.display()))?;
+
+    let output = match kind {
+        ImageKind::Jpeg => {
+            let mut jpeg = read_jpeg(path)?;
+            let segments = jpeg.segments_mut();
+            // Put the blocks back where the current metadata sits, or after any JFIF (APP0) header
+            let insert_at = segments
+                .iter()
+                .position(is_jpeg_metadata)
+                .unwrap_or_else(|| segments.iter().take_while(|seg| seg.marker() == 0xE0).count());
+            segments.retain(|seg| !is_jpeg_metadata(seg));
+            let restored = blocks
+                .into_iter()
+                .map(|block| JpegSegment::new_with_contents(block.id[3], Bytes::from(block.data)));
+            segments.splice(insert_at..insert_at, restored);
+            jpeg.encoder().bytes()
+        }
+        ImageKind::Png => {
+            let mut png = read_png(path)?;
+            let chunks = png.chunks_mut();
+            chunks.retain(|chunk| !is_png_metadata(chunk));
+            let insert_at = chunks.iter().position(|c| c.kind() == *b"IDAT").unwrap_or(chunks.len());
+            let restored = blocks
+                .into_iter()
+                .map(|block| PngChunk::new(block.id, Bytes::from(block.data)));
+            chunks.splice(insert_at..insert_at, restored);
+            png.encoder().bytes()
+        }
+        ImageKind::WebP => {
+            let mut webp = read_webp(path)?;
+            webp.set_exif(None);
+            webp.remove_chunks_by_id(*b"XMP ");
+            for block in blocks {
+                match &block.id {
+                    b"EXIF" => webp.set_exif(Some(Bytes::from(block.data))),
+                    _ => webp.chunks_mut().push(RiffChunk::new(block.id, RiffContent::Data(Bytes::from(block.data)))),
+                }
+            }
+            webp.encoder().bytes()
+        }
+        ImageKind::Sidecar => {
+            let xmp_path = path.with_extension("xmp");
+            match blocks.into_iter().next() {
+                Some(block) => write_atomic(&xmp_path, &block.data)
+                    .with_context(|| format!("Failed to write {}", xmp_path.display()))?,
+                None if xmp_path.exists() => std::fs::remove_file(&xmp_path)
+                    .with_context(|| format!("Failed to remove sidecar {}", xmp_path.display()))?,
+                None => {}
+            }
+            log::info!("Restored metadata: {}", path.display());
+            return Ok(());
+        }
+        ImageKind::Tiff => bail!("Metadata-only backups are not supported for TIFF"),
+    };
+
+    write_atomic(path, &output).with_context(|| format!("Failed to write {}", path.display()))?;
+    log::info!("Restored metadata: {}", path.display());
+    Ok(())
+}
+
+/// One stored metadata block. `id` is the PNG/RIFF chunk type, or `APP` plus
+/// the marker byte for JPEG segments.
+struct Block {
+    id: [u8; 4],
+    data: Vec<u8>,
+}
+
+fn is_jpeg_metadata(seg: &JpegSegment) -> bool {
+    let data = seg.contents();
+    match seg.marker() {
+        0xE1 => {
+            data.starts_with(EXIF_APP1_PREFIX)
+                || data.starts_with(XMP_APP1_PREFIX)
+                || data.starts_with(XMP_EXTENSION_PREFIX)
+        }
+        0xED => true,
+        _ => false,
+    }
+}
+
+fn is_png_metadata(chunk: &PngChunk) -> bool {
+    match &chunk.kind() {
+        b"eXIf" => true,
+        b"iTXt" => chunk.contents().starts_with(PNG_XMP_KEYWORD),
+        _ => false,
+    }
+}
+
+fn read_jpeg(path: &Path) -> Result<Jpeg> {
+    let bytes = std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    Jpeg::from_bytes(Bytes::from(bytes)).map_err(|e| anyhow::anyhow!("Failed to parse JPEG: {e}"))
+}
+
+fn read_png(path: &Path) -> Result<Png> {
+    let bytes = std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    Png::from_bytes(Bytes::from(bytes)).map_err(|e| anyhow::anyhow!("Failed to parse PNG: {e}"))
+}
+
+fn read_webp(path: &Path) -> Result<WebP> {
+    let bytes = std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    WebP::from_bytes(Bytes::from(bytes)).map_err(|e| anyhow::anyhow!("Failed to parse WebP: {e}"))
+}
+
+/// `MAGIC`, a kind byte, then `[id: 4][len: u32 BE][data]` per block.
+fn encode(kind: ImageKind, blocks: &[Block]) -> Vec<u8> {
+    let mut out = MAGIC.to_vec();
+    out.push(match kind {
+        ImageKind::Jpeg => b'J',
+        ImageKind::Png => b'P',
+        ImageKind::WebP => b'W',
+        ImageKind::Tiff => b'T',
+        ImageKind::Sidecar => b'S',
+    });
+    for block in blocks {
+        out.extend_from_slice(&block.id);
+        out.extend_from_slice(&(block.data.len() as u32).to_be_bytes());
+        out.extend_from_slice(&block.data);
+    }
+    out
+}
+
+fn decode(raw: &[u8]) -> Result<(ImageKind, Vec<Block>)> {
+    let rest = raw.strip_prefix(MAGIC.as_slice()).context("Not an exif-ai metadata backup")?;
+    let (&kind, mut rest) = rest.split_first().context("Truncated metadata backup")?;
+    let kind = match kind {
+        b'J' => ImageKind::Jpeg,
+        b'P' => ImageKind::Png,
+        b'W' => ImageKind::WebP,
+        b'T' => ImageKind::Tiff,
+        b'S' => ImageKind::Sidecar,
+        other => bail!("Unknown image kind {other:#x}"),
+    };
+
+    let mut blocks = Vec::new();
+    while !rest.is_empty() {
+        if rest.len() < 8 {
+            bail!("Truncated metadata backup");
+        }
+        let id = rest[..4].try_into().unwrap();
+        let len = u32::from_be_bytes(rest[4..8].try_into().unwrap()) as usize;
+        let data = rest.get(8..8 + len).context("Truncated metadata backup")?;
+        blocks.push(Block { id, data: data.to_vec() });
+        rest = &rest[8 + len..];
+    }
+    Ok((kind, blocks))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::AiResult;
+    use crate::config::ExifFields;
+    use crate::exif::{read_exif, write_exif};
+    use tempfile::TempDir;
+
+    fn copy_to_temp(name: &str) -> (TempDir, PathBuf) {
+        let dir = TempDir::new().unwrap();
+        let src = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("data").join(name);
+        let dst = dir.path().join(name);
+        std::fs::copy(&src, &dst).unwrap();
+        (dir, dst)
+    }
+
+    fn ai_result() -> AiResult {
+        AiResult {
+            title: Some("New Title".into()),
+            description: Some("New description".into()),
+            tags: Some(vec!["one".into(), "two".into()]),
+            ..Default::default()
+        }
+    }
+
+    fn fields() -> ExifFields {
+        ExifFields { overwrite_existing: true, ..Default::default() }
+    }
+
+    #[test]
+    fn metadata_backup_path_appends_extension() {
+        assert_eq!(metadata_backup_path(Path::new("/a/photo.jpg")), PathBuf::from("/a/photo.jpg.metabak"));
+    }
+
+    #[test]
+    fn jpeg_backup_and_restore_round_trip() {
+        let (_dir, path) = copy_to_temp("test_canon_powershot.jpg");
+        let original = std::fs::read(&path).unwrap();
+        let before = read_exif(&path).unwrap();
+
+        let backup = backup_metadata(&path, ImageKind::Jpeg).unwrap();
+        // Only the metadata is stored, not the pixels
+        assert!(std::fs::metadata(&backup).unwrap().len() < original.len() as u64 / 2);
+
+        write_exif(&path, &ai_result(), &before, &fields(), false, ImageKind::Jpeg, false).unwrap();
+        assert_eq!(read_exif(&path).unwrap().title.as_deref(), Some("New Title"));
+
+        restore_metadata(&path).unwrap();
+        let after = read_exif(&path).unwrap();
+        assert_eq!(after.title, before.title);
+        assert_eq!(after.description, before.description);
+        assert_eq!(after.keywords, before.keywords);
+        assert_eq!(after.make, before.make);
+        assert_eq!(after.date_time, before.date_time);
+        assert_eq!(std::fs::read(&path).unwrap(), original);
+    }
+
+    #[test]
+    fn existing_backup_is_not_replaced() {
+        let (_dir, path) = copy_to_temp("test_canon_powershot.jpg");
+        let backup = backup_metadata(&path, ImageKind::Jpeg).unwrap();
+        let first = std::fs::read(&backup).unwrap();
+
+        write_exif(&path, &ai_result(), &read_exif(&path).unwrap(), &fields(), false, ImageKind::Jpeg, false).unwrap();
+        backup_metadata(&path, ImageKind::Jpeg).unwrap();
+        assert_eq!(std::fs::read(&backup).unwrap(), first);
+    }
+
+    #[test]
+    fn png_backup_and_restore_round_trip() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("plain.png");
+        image::RgbImage::new(8, 8).save(&path).unwrap();
+        let original = std::fs::read(&path).unwrap();
+
+        backup_metadata(&path, ImageKind::Png).unwrap();
+        write_exif(&path, &ai_result(), &Default::default(), &fields(), false, ImageKind::Png, false).unwrap();
+        assert_ne!(std::fs::read(&path).unwrap(), original);
+
+        restore_metadata(&path).unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), original);
+    }
+
+    #[test]
+    fn sidecar_restore_removes_new_sidecar() {
+        let (_dir, path) = copy_to_temp("test.heic");
+        let original = std::fs::read(&path).unwrap();
+
+        backup_metadata(&path, ImageKind::Sidecar).unwrap();
+        write_exif(&path, &ai_result(), &Default::default(), &fields(), false, ImageKind::Sidecar, false).unwrap();
+        assert!(path.with_extension("xmp").exists());
+
+        restore_metadata(&path).unwrap();
+        assert!(!path.with_extension("xmp").exists());
+        assert_eq!(std::fs::read(&path).unwrap(), original);
+    }
+
+    #[test]
+    fn sidecar_restore_brings_back_previous_sidecar() {
+        let (_dir, path) = copy_to_temp("test.heic");
+        let xmp_path = path.with_extension("xmp");
+        std::fs::write(&xmp_path, "<x:xmpmeta>old</x:xmpmeta>").unwrap();
+
+        backup_metadata(&path, ImageKind::Sidecar).unwrap();
+        write_exif(&path, &ai_result(), &Default::default(), &fields(), false, ImageKind::Sidecar, false).unwrap();
+        restore_metadata(&path).unwrap();
+        assert_eq!(std::fs::read_to_string(&xmp_path).unwrap(), "<x:xmpmeta>old</x:xmpmeta>");
+    }
+
+    #[test]
+    fn tiff_is_rejected() {
+        let (_dir, path) = copy_to_temp("test.tiff");
+        assert!(backup_metadata(&path, ImageKind::Tiff).is_err());
+        assert!(!metadata_backup_path(&path).exists());
+    }
+
+    #[test]
+    fn restore_without_backup_fails() {
+        let (_dir, path) = copy_to_temp("test_canon_powershot.jpg");
+        let err = restore_metadata(&path).unwrap_err();
+        assert!(err.to_string().contains("No metadata backup"));
+    }
+
+    #[test]
+    fn decode_rejects_garbage() {
+        assert!(decode(b"not a backup").is_err());
+        let mut raw = encode(ImageKind::Jpeg, &[Block { id: *b"APP\xE1", data: vec![1, 2, 3] }]);
+        raw.truncate(raw.len() - 1);
+        assert!(decode(&raw).is_err());
+    }
+}
@@ -0,0 +1,505 @@
+//! Primary-item EXIF lookup for HEIF containers (HEIC, HEIF, AVIF).
+//!
+//! A HEIF file can hold several images — a Live Photo still, burst frames,
+//! thumbnails, depth maps — and each may carry its own `Exif` item. `nom-exif`
+//! returns the first `Exif` item listed in the container, which is not always the
+//! one describing the image viewers show. [`primary_exif`] follows the container's
+//! own links instead: `pitm` names the primary item, and the `Exif` item whose
+//! `cdsc` ("content describes") reference targets it holds that image's metadata.
+
+use anyhow::{Context, Result, bail};
+use std::collections::HashMap;
+use std::io::{Read, Seek, SeekFrom};
+
+/// Upper bound on the `meta` box and EXIF payload we read into memory.
+const MAX_BOX_SIZE: u64 = 16 * 1024 * 1024;
+
+/// TIFF-structured EXIF bytes describing the primary image of a HEIF file.
+///
+/// Returns `None` when the file isn't HEIF, or when no `Exif` item is linked to
+/// the primary item — callers should then fall back to a plain EXIF scan.
+pub(crate) fn primary_exif<R: Read + Seek>(reader: &mut R) -> Result<Option<Vec<u8>>> {
+    let Some(meta) = read_meta_box(reader)? else {
+        return Ok(None);
+    };
+    let meta = Meta::parse(&meta)?;
+
+    let Some(primary) = meta.primary else {
+        return Ok(None);
+    };
+    let Some(exif_id) = meta.exif_items.iter().copied().find(|id| {
+        meta.describes.get(id).is_some_and(|targets| targets.contains(&primary))
+    }) else {
+        return Ok(None);
+    };
+    let location = meta.locations.get(&exif_id).context("Exif item has no iloc entry")?;
+
+    let mut payload = Vec::new();
+    for &(offset, length) in &location.extents {
+        let start = location.base_offset.checked_add(offset).context("Exif item offset overflows")?;
+        let total = (payload.len() as u64).checked_add(length);
+        if length == 0 || length > MAX_BOX_SIZE || total.is_none_or(|total| total > MAX_BOX_SIZE) {
+            bail!("Unsupported Exif item extent length {length}");
+        }
+        match location.construction_method {
+            0 => {
+                reader.seek(SeekFrom::Start(start))?;
+                let mut extent = vec![0; length as usize];
+                reader.read_exact(&mut extent).context("Exif item extends past end of file")?;
+                payload.extend_from_slice(&extent);
+            }
+            1 => {
+                let idat = meta.idat.context("Exif item stored in missing idat box")?;
+                let extent = usize::try_from(start)
+                    .ok()
+                    .and_then(|start| idat.get(start..start.checked_add(length as usize)?))
+                    .context("Exif item extends past end of idat")?;
+                payload.extend_from_slice(extent);
+            }
+            method => bail!("Unsupported iloc construction method {method}"),
+        }
+    }
+
+    // Payload: u32 offset to the TIFF header, then the EXIF block
+    let mut bytes = Bytes::new(&payload);
+    let header_offset = bytes.u32()? as usize;
+    let tiff = payload
+        .get(4 + header_offset..)
+        .filter(|tiff| tiff.starts_with(b"II") || tiff.starts_with(b"MM"))
+        .context("Exif item does not contain a TIFF header")?;
+    Ok(Some(tiff.to_vec()))
+}
+
+/// Body of the top-level `meta` box, or `None` if the file doesn't start with `ftyp`.
+fn read_meta_box<R: Read + Seek>(reader: &mut R) -> Result<Option<Vec<u8>>> {
+    reader.seek(SeekFrom::Start(0))?;
+    let mut first = true;
+    loop {
+        let mut header = [0u8; 8];
+        if reader.read_exact(&mut header).is_err() {
+            return Ok(None);
+        }
+        let mut size = u32::from_be_bytes(header[..4].try_into().unwrap()) as u64;
+        let kind = &header[4..];
+        let mut header_len = 8;
+        if size == 1 {
+            let mut large = [0u8; 8];
+            reader.read_exact(&mut large)?;
+            size = u64::from_be_bytes(large);
+            header_len = 16;
+        }
+        if first && kind != b"ftyp" {
+            return Ok(None);
+        }
+        first = false;
+        // size 0 means "extends to end of file" — only used for a trailing mdat
+        if size == 0 {
+            return Ok(None);
+        }
+        let body_len = size.checked_sub(header_len).context("Invalid box size")?;
+
+        if kind == b"meta" {
+            if body_len > MAX_BOX_SIZE {
+                bail!("meta box too large ({body_len} bytes)");
+            }
+            let mut body = vec![0; body_len as usize];
+            reader.read_exact(&mut body).context("Truncated meta box")?;
+            return Ok(Some(body));
+        }
+        let skip = i64::try_from(body_len).context("Invalid box size")?;
+        reader.seek(SeekFrom::Current(skip))?;
+    }
+}
+
+/// Where an item's data lives, from the `iloc` box.
+#[derive(Debug, Default)]
+struct Location {
+    /// 0 = file offset, 1 = offset into the `idat` box.
+    construction_method: u8,
+    base_offset: u64,
+    /// `(offset, length)` pairs, relative to `base_offset`.
+    extents: Vec<(u64, u64)>,
+}
+
+/// The parts of a HEIF `meta` box needed to find the primary image's EXIF.
+#[derive(Debug, Default)]
+struct Meta<'a> {
+    primary: Option<u32>,
+    exif_items: Vec<u32>,
+    /// `cdsc` references: item → items it describes.
+    describes: HashMap<u32, Vec<u32>>,
+    locations: HashMap<u32, Location>,
+    idat: Option<&'a [u8]>,
+}
+
+impl<'a> Meta<'a> {
+    fn parse(body: &'a [u8]) -> Result<Self> {
+        let mut meta = Meta::default();
+        // `meta` is a full box: skip version and flags
+        for (kind, data) in child_boxes(body.get(4..).context("Truncated meta box")?)? {
+            match &kind {
+                b"pitm" => {
+                    let mut b = Bytes::new(data);
+                    let version = b.full_box()?;
+                    meta.primary = Some(b.id(version == 0)?);
+                }
+                b"iinf" => {
+                    let mut b = Bytes::new(data);
+                    let version = b.full_box()?;
+                    if version == 0 { b.u16()?; } else { b.u32()?; }
+                    for (kind, infe) in child_boxes(b.rest())? {
+                        if &kind != b"infe" {
+                            continue;
+                        }
+                        let mut b = Bytes::new(infe);
+                        let version = b.full_box()?;
+                        // Versions 0 and 1 predate item types; no Exif items there
+                        if version < 2 {
+                            continue;
+                        }
+                        let id = b.id(version == 2)?;
+                        b.u16()?; // item_protection_index
+                        if b.take(4)? == b"Exif" {
+                            meta.exif_items.push(id);
+                        }
+                    }
+                }
+                b"iref" => {
+                    let mut b = Bytes::new(data);
+                    let version = b.full_box()?;
+                    for (kind, reference) in child_boxes(b.rest())? {
+                        if &kind != b"cdsc" {
+                            continue;
+                        }
+                        let mut b = Bytes::new(reference);
+                        let from = b.id(version == 0)?;
+                        let count = b.u16()?;
+                        let targets = meta.describes.entry(from).or_default();
+                        for _ in 0..count {
+                            targets.push(b.id(version == 0)?);
+                        }
+                    }
+                }
+                b"iloc" => meta.locations = parse_iloc(data)?,
+                b"idat" => meta.idat = Some(data),
+                _ => {}
+            }
+        }
+        Ok(meta)
+    }
+}
+
+fn parse_iloc(data: &[u8]) -> Result<HashMap<u32, Location>> {
+    let mut b = Bytes::new(data);
+    let version = b.full_box()?;
+    let sizes = b.u8()?;
+    let (offset_size, length_size) = (sizes >> 4, sizes & 0x0f);
+    let sizes = b.u8()?;
+    let base_offset_size = sizes >> 4;
+    let index_size = if version >= 1 { sizes & 0x0f } else { 0 };
+    let count = if version < 2 { b.u16()? as u32 } else { b.u32()? };
+
+    let mut locations = HashMap::new();
+    for _ in 0..count {
+        let id = b.id(version < 2)?;
+        let construction_method = if version >= 1 { (b.u16()? & 0x0f) as u8 } else { 0 };
+        b.u16()?; // data_reference_index
+        let base_offset = b.sized(base_offset_size)?;
+        let extent_count = b.u16()?;
+        let mut extents = Vec::with_capacity(extent_count as usize);
+        for _ in 0..extent_count {
+            b.sized(index_size)?;
+            extents.push((b.sized(offset_size)?, b.sized(length_size)?));
+        }
+        locations.insert(id, Location { construction_method, base_offset, extents });
+    }
+    Ok(locations)
+}
+
+/// Split `data` into `(type, body)` pairs of consecutive boxes.
+fn child_boxes(data: &[u8]) -> Result<Vec<([u8; 4], &[u8])>> {
+    let mut boxes = Vec::new();
+    let mut b = Bytes::new(data);
+    while !b.rest().is_empty() {
+        let mut size = b.u32()? as u64;
+        let kind: [u8; 4] = b.take(4)?.try_into().unwrap();
+        let mut header_len = 8;
+        if size == 1 {
+            size = b.u64()?;
+            header_len = 16;
+        } else if size == 0 {
+            size = b.rest().len() as u64 + header_len;
+        }
+        let body_len = size.checked_sub(header_len).context("Invalid box size")?;
+        boxes.push((kind, b.take(body_len as usize)?));
+    }
+    Ok(boxes)
+}
+
+/// Big-endian cursor over a box body.
+struct Bytes<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Bytes<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn take(&mut self, n: usize) -> Result<&'a [u8]> {
+        let slice = self
+            .data
+            .get(self.pos..self.pos.saturating_add(n))
+            .context("Truncated HEIF box")?;
+        self.pos += n;
+        Ok(slice)
+    }
+
+    fn rest(&self) -> &'a [u8] {
+        &self.data[self.pos..]
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16> {
+        Ok(u16::from_be_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64> {
+        Ok(u64::from_be_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    /// An unsigned integer of 0, 4 or 8 bytes, as used by `iloc`.
+    fn sized(&mut self, size: u8) -> Result<u64> {
+        match size {
+            0 => Ok(0),
+            4 => Ok(self.u32()? as u64),
+            8 => self.u64(),
+            other => bail!("Unsupported iloc field size {other}"),
+        }
+    }
+
+    /// An item ID: 16-bit in older box versions, 32-bit otherwise.
+    fn id(&mut self, short: bool) -> Result<u32> {
+        if short { Ok(self.u16()? as u32) } else { self.u32() }
+    }
+
+    /// Full-box header; returns the version and skips the flags.
+    fn full_box(&mut self) -> Result<u8> {
+        let version = self.u8()?;
+        self.take(3)?;
+        Ok(version)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn bx(kind: &[u8; 4], body: &[u8]) -> Vec<u8> {
+        let mut out = ((body.len() + 8) as u32).to_be_bytes().to_vec();
+        out.extend_from_slice(kind);
+        out.extend_from_slice(body);
+        out
+    }
+
+    fn full_bx(kind: &[u8; 4], version: u8, body: &[u8]) -> Vec<u8> {
+        let mut data = vec![version, 0, 0, 0];
+        data.extend_from_slice(body);
+        bx(kind, &data)
+    }
+
+    /// Little-endian TIFF with IFD0 `Make` and a GPS IFD holding `lat`/`lon` (whole degrees, N/E).
+    pub(crate) fn tiff_with(make: &str, lat: u32, lon: u32) -> Vec<u8> {
+        let mut make = make.as_bytes().to_vec();
+        make.push(0);
+        // Layout: header(8) | IFD0 (2 + 2*12 + 4 = 30) | GPS IFD (2 + 4*12 + 4 = 54) | make | lat | lon
+        let gps_ifd = 8 + 30;
+        let make_at = gps_ifd + 54;
+        let lat_at = make_at + make.len() as u32;
+        let lon_at = lat_at + 24;
+
+        let mut t = b"II*\0".to_vec();
+        t.extend_from_slice(&8u32.to_le_bytes());
+        let entry = |t: &mut Vec<u8>, tag: u16, kind: u16, count: u32, value: [u8; 4]| {
+            t.extend_from_slice(&tag.to_le_bytes());
+            t.extend_from_slice(&kind.to_le_bytes());
+            t.extend_from_slice(&count.to_le_bytes());
+            t.extend_from_slice(&value);
+        };
+        t.extend_from_slice(&2u16.to_le_bytes());
+        entry(&mut t, 0x010F, 2, make.len() as u32, make_at.to_le_bytes());
+        entry(&mut t, 0x8825, 4, 1, gps_ifd.to_le_bytes());
+        t.extend_from_slice(&0u32.to_le_bytes());
+        t.extend_from_slice(&4u16.to_le_bytes());
+        entry(&mut t, 0x0001, 2, 2, *b"N\0\0\0");
+        entry(&mut t, 0x0002, 5, 3, lat_at.to_le_bytes());
+        entry(&mut t, 0x0003, 2, 2, *b"E\0\0\0");
+        entry(&mut t, 0x0004, 5, 3, lon_at.to_le_bytes());
+        t.extend_from_slice(&0u32.to_le_bytes());
+        t.extend_from_slice(&make);
+        for degrees in [lat, lon] {
+            for (num, den) in [(degrees, 1u32), (0, 1), (0, 1)] {
+                t.extend_from_slice(&num.to_le_bytes());
+                t.extend_from_slice(&den.to_le_bytes());
+            }
+        }
+        t
+    }
+
+    /// A burst-style HEIC: image items 1 and 2, primary item 2, and one Exif item
+    /// per image. The Exif item for the *non-primary* frame is listed first.
+    pub(crate) fn multi_image_heic(primary_tiff: &[u8], other_tiff: &[u8]) -> Vec<u8> {
+        let exif_payload = |tiff: &[u8]| {
+            let mut p = 0u32.to_be_bytes().to_vec();
+            p.extend_from_slice(tiff);
+            p
+        };
+        let other = exif_payload(other_tiff);
+        let primary = exif_payload(primary_tiff);
+
+        let infe = |id: u16, kind: &[u8; 4]| {
+            let mut body = id.to_be_bytes().to_vec();
+            body.extend_from_slice(&0u16.to_be_bytes());
+            body.extend_from_slice(kind);
+            body.push(0); // item_name
+            full_bx(b"infe", 2, &body)
+        };
+        let mut iinf = 4u16.to_be_bytes().to_vec();
+        for (id, kind) in [(1, b"hvc1"), (2, b"hvc1"), (3, b"Exif"), (4, b"Exif")] {
+            iinf.extend(infe(id, kind));
+        }
+
+        let cdsc = |from: u16, to: u16| {
+            let mut body = from.to_be_bytes().to_vec();
+            body.extend_from_slice(&1u16.to_be_bytes());
+            body.extend_from_slice(&to.to_be_bytes());
+            bx(b"cdsc", &body)
+        };
+        let mut iref = cdsc(3, 1);
+        iref.extend(cdsc(4, 2));
+
+        // iloc v1 with 4-byte offsets/lengths; offsets are patched once the mdat position is known
+        let iloc = |mdat_data: u32| {
+            let mut body = vec![0x44, 0x00];
+            body.extend_from_slice(&2u16.to_be_bytes());
+            for (id, offset, len) in [
+                (3u16, mdat_data, other.len() as u32),
+                (4u16, mdat_data + other.len() as u32, primary.len() as u32),
+            ] {
+                body.extend_from_slice(&id.to_be_bytes());
+                body.extend_from_slice(&0u16.to_be_bytes()); // construction method
+                body.extend_from_slice(&0u16.to_be_bytes()); // data reference index
+                body.extend_from_slice(&1u16.to_be_bytes()); // extent count
+                body.extend_from_slice(&offset.to_be_bytes());
+                body.extend_from_slice(&len.to_be_bytes());
+            }
+            full_bx(b"iloc", 1, &body)
+        };
+
+        let ftyp = bx(b"ftyp", b"heic\0\0\0\0mif1heic");
+        let build = |mdat_data: u32| {
+            let mut meta = full_bx(b"hdlr", 0, b"\0\0\0\0pict\0\0\0\0\0\0\0\0\0\0\0\0\0");
+            meta.extend(full_bx(b"pitm", 0, &2u16.to_be_bytes()));
+            meta.extend(full_bx(b"iinf", 0, &iinf));
+            meta.extend(full_bx(b"iref", 0, &iref));
+            meta.extend(iloc(mdat_data));
+            full_bx(b"meta", 0, &meta)
+        };
+        let meta_len = build(0).len();
+        let mdat_data = (ftyp.len() + meta_len + 8) as u32;
+
+        let mut file = ftyp;
+        file.extend(build(mdat_data));
+        let mut mdat = other.clone();
+        mdat.extend_from_slice(&primary);
+        file.extend(bx(b"mdat", &mdat));
+        file
+    }
+
+    #[test]
+    fn picks_exif_linked_to_primary_item() {
+        let primary = tiff_with("Primary", 10, 20);
+        let other = tiff_with("Burst", 30, 40);
+        let file = multi_image_heic(&primary, &other);
+
+        let tiff = primary_exif(&mut Cursor::new(file)).unwrap().unwrap();
+        assert_eq!(tiff, primary);
+    }
+
+    #[test]
+    fn real_heic_primary_exif_found() {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("data/test.heic");
+        let tiff = primary_exif(&mut std::fs::File::open(path).unwrap()).unwrap().unwrap();
+        assert!(tiff.starts_with(b"MM") || tiff.starts_with(b"II"));
+    }
+
+    #[test]
+    fn non_heif_returns_none() {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("data/test_canon_powershot.jpg");
+        assert!(primary_exif(&mut std::fs::File::open(path).unwrap()).unwrap().is_none());
+        assert!(primary_exif(&mut Cursor::new(Vec::new())).unwrap().is_none());
+    }
+
+    #[test]
+    fn truncated_meta_is_an_error() {
+        let mut file = bx(b"ftyp", b"heic\0\0\0\0mif1heic");
+        file.extend_from_slice(&100u32.to_be_bytes());
+        file.extend_from_slice(b"meta\0\0\0\0");
+        assert!(primary_exif(&mut Cursor::new(file)).is_err());
+    }
+
+    /// A HEIC whose only Exif item describes the primary item and is stored
+    /// in `extents`, given as 8-byte `(offset, length)` pairs.
+    fn heic_with_extents(extents: &[(u64, u64)]) -> Vec<u8> {
+        let mut infe = 3u16.to_be_bytes().to_vec();
+        infe.extend_from_slice(&0u16.to_be_bytes());
+        infe.extend_from_slice(b"Exif\0");
+        let mut iinf = 1u16.to_be_bytes().to_vec();
+        iinf.extend(full_bx(b"infe", 2, &infe));
+        let iref = bx(b"cdsc", &[0, 3, 0, 1, 0, 1]);
+
+        let mut iloc = vec![0x88, 0x00];
+        iloc.extend_from_slice(&1u16.to_be_bytes());
+        iloc.extend_from_slice(&3u16.to_be_bytes());
+        iloc.extend_from_slice(&0u16.to_be_bytes()); // construction method
+        iloc.extend_from_slice(&0u16.to_be_bytes()); // data reference index
+        iloc.extend_from_slice(&(extents.len() as u16).to_be_bytes());
+        for (offset, length) in extents {
+            iloc.extend_from_slice(&offset.to_be_bytes());
+            iloc.extend_from_slice(&length.to_be_bytes());
+        }
+
+        let mut meta = full_bx(b"pitm", 0, &1u16.to_be_bytes());
+        meta.extend(full_bx(b"iinf", 0, &iinf));
+        meta.extend(full_bx(b"iref", 0, &iref));
+        meta.extend(full_bx(b"iloc", 1, &iloc));
+        let mut file = bx(b"ftyp", b"heic\0\0\0\0mif1heic");
+        file.extend(full_bx(b"meta", 0, &meta));
+        file
+    }
+
+    #[test]
+    fn oversized_extents_are_an_error() {
+        for extents in [vec![(0, 1), (0, u64::MAX)], vec![(0, u64::MAX)], vec![(0, MAX_BOX_SIZE + 1)]] {
+            assert!(primary_exif(&mut Cursor::new(heic_with_extents(&extents))).is_err(), "{extents:?}");
+        }
+    }
+
+    #[test]
+    fn huge_largesize_is_an_error() {
+        let mut file = bx(b"ftyp", b"heic\0\0\0\0mif1heic");
+        file.extend_from_slice(&1u32.to_be_bytes());
+        file.extend_from_slice(b"free");
+        file.extend_from_slice(&(u64::MAX - 15).to_be_bytes());
+        assert!(primary_exif(&mut Cursor::new(file)).is_err());
+    }
+}
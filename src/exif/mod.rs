@@ -9,8 +9,15 @@
 //! JPEG gets EXIF+XMP+IPTC, PNG gets XMP, WebP gets EXIF+XMP, TIFF gets EXIF,
 //! and HEIC/RAW formats get a sidecar `.xmp` file.
 
+mod backup;
+mod heif;
 mod reader;
 mod writer;
 
-pub use reader::{ExifData, read_exif};
-pub use writer::{clear_exif, write_exif, WriteResult};
+pub use backup::{backup_metadata, metadata_backup_path, restore_metadata};
+pub use reader::{ExifData, PROMPT_CONTEXT_FIELDS, read_exif};
+pub use writer::{
+    clear_exif, preview_sidecar_xmp, write_exif, ClearResult, ExiftoolRun, Field, SidecarPreview, SkipReason, SkippedField,
+    WriteResult,
+};
+pub(crate) use writer::{is_valid_lang_tag, write_atomic, ALL_FIELDS};
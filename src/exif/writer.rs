@@ -7,22 +7,38 @@ use little_exif::exif_tag::{ExifTag, ExifTagGroup};
 use little_exif::exif_tag_format::ExifTagFormat;
 use little_exif::filetype::FileExtension;
 use little_exif::metadata::Metadata;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::io::Write;
 use std::path::{Path, PathBuf};
 
 use crate::ai::{AiResult, GpsCoords};
-use crate::config::ExifFields;
+use crate::config::{ExifFields, MetadataDefaults};
 use crate::pipeline::ImageKind;
-use super::reader::ExifData;
+use super::reader::{self, ExifData};
 
 // EXIF tag IDs for tags not natively supported by little_exif
 const TAG_XP_TITLE: u16 = 0x9C9B;
 const TAG_XP_COMMENT: u16 = 0x9C9C;
 const TAG_XP_KEYWORDS: u16 = 0x9C9E;
 const TAG_XP_SUBJECT: u16 = 0x9C9F;
+const TAG_DATE_TIME_ORIGINAL: u16 = 0x9003;
+const TAG_CREATE_DATE: u16 = 0x9004;
+const TAG_ARTIST: u16 = 0x013B;
+const TAG_COPYRIGHT: u16 = 0x8298;
+const TAG_GPS_VERSION_ID: u16 = 0x0000;
 const TAG_GPS_LATITUDE_REF: u16 = 0x0001;
 const TAG_GPS_LATITUDE: u16 = 0x0002;
 const TAG_GPS_LONGITUDE_REF: u16 = 0x0003;
 const TAG_GPS_LONGITUDE: u16 = 0x0004;
+const TAG_GPS_ALTITUDE_REF: u16 = 0x0005;
+const TAG_GPS_ALTITUDE: u16 = 0x0006;
+const TAG_GPS_MAP_DATUM: u16 = 0x0012;
+
+/// GPSVersionID and GPSMapDatum written with new coordinates. Strict
+/// consumers (some mapping and GPS-device imports) reject a GPS IFD without them.
+const GPS_VERSION: [u8; 4] = [2, 3, 0, 0];
+const GPS_MAP_DATUM: &str = "WGS-84";
 
 // little_exif as_u8_vec(JPEG) returns: [APP1 marker 2B][length 2B][Exif\0\0 6B][TIFF data]
 // img-parts set_exif() expects just the TIFF data (after Exif\0\0)
@@ -45,10 +61,198 @@ pub struct WriteResult {
     pub gps_written: bool,
     /// Whether subject identification was written (XPSubject).
     pub subject_written: bool,
-    /// Fields that were skipped (with reason), e.g. `"title (existing)"`.
-    pub skipped_fields: Vec<String>,
+    /// Whether a capture date was written (DateTimeOriginal + CreateDate).
+    pub date_written: bool,
+    /// Whether the configured copyright notice was written (Copyright + dc:rights).
+    pub copyright_written: bool,
+    /// Whether the configured artist was written (Artist + dc:creator).
+    pub artist_written: bool,
+    /// Fields that were skipped, with the reason each one was skipped.
+    pub skipped_fields: Vec<SkippedField>,
     /// Path to sidecar XMP file if one was written (for HEIC/RAW formats).
     pub sidecar_path: Option<PathBuf>,
+    /// The `exiftool` run that embedded the metadata in a HEIC/RAW file, when
+    /// [`ExifFields::use_exiftool_if_available`] is on and exiftool was found.
+    pub exiftool: Option<ExiftoolRun>,
+}
+
+impl WriteResult {
+    /// The `*_written` flag for `field`.
+    fn written_flag(&mut self, field: Field) -> &mut bool {
+        match field {
+            Field::Title => &mut self.title_written,
+            Field::Description => &mut self.description_written,
+            Field::Tags => &mut self.tags_written,
+            Field::Subject => &mut self.subject_written,
+            Field::Gps => &mut self.gps_written,
+            Field::Date => &mut self.date_written,
+        }
+    }
+}
+
+/// The metadata blocks a format writer actually stored.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct Blocks {
+    exif: bool,
+    xmp: bool,
+    iptc: bool,
+}
+
+impl Blocks {
+    /// Whether any stored block holds `field`: EXIF holds every field
+    /// (keywords as XPKeywords), XMP and IPTC the title, description and their
+    /// keyword targets.
+    fn hold(self, field: Field, fields: &ExifFields) -> bool {
+        let targets = fields.keyword_targets;
+        let in_exif = self.exif && (field != Field::Tags || targets.xp_keywords);
+        let in_xmp = self.xmp && match field {
+            Field::Title | Field::Description => true,
+            Field::Tags => targets.any_xmp(),
+            _ => false,
+        };
+        let in_iptc = self.iptc && match field {
+            Field::Title | Field::Description => true,
+            Field::Tags => targets.iptc,
+            _ => false,
+        };
+        in_exif || in_xmp || in_iptc
+    }
+
+    fn any(self) -> bool {
+        self.exif || self.xmp || self.iptc
+    }
+}
+
+/// Outcome of running `exiftool` on an image.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExiftoolRun {
+    /// exiftool's exit code; `None` if it couldn't be started or was killed.
+    pub exit_code: Option<i32>,
+    /// What exiftool printed to stderr (warnings and errors), trimmed.
+    pub stderr: String,
+}
+
+impl ExiftoolRun {
+    /// Whether exiftool exited successfully.
+    pub fn success(&self) -> bool {
+        self.exit_code == Some(0)
+    }
+}
+
+/// A descriptive metadata field managed by the writer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Field {
+    Title,
+    Description,
+    Tags,
+    Gps,
+    Subject,
+    Date,
+}
+
+impl Field {
+    /// The lowercase name used in config, logs and JSON, e.g. `"gps"`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Field::Title => "title",
+            Field::Description => "description",
+            Field::Tags => "tags",
+            Field::Gps => "gps",
+            Field::Subject => "subject",
+            Field::Date => "date",
+        }
+    }
+}
+
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Why the writer left a field alone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SkipReason {
+    /// The image already has a value and `overwrite_existing` is `false`.
+    Existing,
+    /// Writing this field is turned off in [`ExifFields`].
+    Disabled,
+    /// The AI returned the field but with no usable content.
+    Empty,
+    /// The AI returned a value that can't be written, e.g. a date that is
+    /// not ISO 8601.
+    Invalid,
+    /// The target format (or write mode) has nowhere to store the field,
+    /// e.g. GPS in a PNG or WebP, which only receive XMP.
+    #[serde(rename = "format-unsupported")]
+    FormatUnsupported,
+}
+
+impl fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            SkipReason::Existing => "existing",
+            SkipReason::Disabled => "disabled",
+            SkipReason::Empty => "empty",
+            SkipReason::Invalid => "invalid",
+            SkipReason::FormatUnsupported => "format-unsupported",
+        })
+    }
+}
+
+/// A field the writer skipped, and why.
+///
+/// Displays (and serializes) as the human-readable text used in logs,
+/// e.g. `"title (existing)"` or `"gps (existing coordinates)"`. Match on
+/// [`field`](Self::field) and [`reason`](Self::reason) for programmatic use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SkippedField {
+    pub field: Field,
+    pub reason: SkipReason,
+}
+
+impl SkippedField {
+    pub fn new(field: Field, reason: SkipReason) -> Self {
+        Self { field, reason }
+    }
+
+    /// A full sentence explaining the skip, e.g.
+    /// `"title skipped: existing value present and overwrite=false"`.
+    pub fn explanation(&self) -> String {
+        match (self.field, self.reason) {
+            (Field::Gps, SkipReason::Existing) => "gps skipped: image already geotagged".to_string(),
+            (Field::Gps, SkipReason::Invalid) => "gps skipped: coordinates out of range".to_string(),
+            (Field::Date, SkipReason::Existing) => "date skipped: image already has a capture date".to_string(),
+            (field, SkipReason::Existing) => format!("{field} skipped: existing value present and overwrite=false"),
+            (Field::Tags, SkipReason::Disabled) => {
+                "tags skipped: write_tags=false or no keyword_targets enabled".to_string()
+            }
+            (field, SkipReason::Disabled) => format!("{field} skipped: write_{field}=false"),
+            (field, SkipReason::Empty) => format!("{field} skipped: AI returned an empty value"),
+            (field, SkipReason::Invalid) => format!("{field} skipped: AI returned a value that can't be written"),
+            (field, SkipReason::FormatUnsupported) => {
+                format!("{field} skipped: the image format has nowhere to store it")
+            }
+        }
+    }
+}
+
+impl fmt::Display for SkippedField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.field, self.reason) {
+            (Field::Gps, SkipReason::Existing) => write!(f, "gps (existing coordinates)"),
+            (Field::Gps, SkipReason::Invalid) => write!(f, "gps (invalid coordinates)"),
+            (field, reason) => write!(f, "{field} ({reason})"),
+        }
+    }
+}
+
+impl Serialize for SkippedField {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
 }
 
 /// Encode a string as UTF-16LE bytes (used for XP* tags).
@@ -76,18 +280,49 @@ fn make_xp_tag(tag_id: u16, value: &str) -> Option<ExifTag> {
     .ok()
 }
 
-/// Encode a GPS rational value as raw bytes (3 rationals = 24 bytes, little-endian).
-fn encode_gps_rational(degrees: u32, minutes: u32, seconds_num: u32, seconds_den: u32) -> Vec<u8> {
+/// Denominator of the seconds rational: coordinates keep 1/10000 of an
+/// arc-second, about 3 mm on the ground.
+const GPS_SECONDS_DENOMINATOR: u32 = 10_000;
+
+/// Split a coordinate into degree, minute and second rationals.
+///
+/// The absolute value is rounded once, to the nearest 1/10000 arc-second, and
+/// then divided into whole degrees and minutes, so a value just under a
+/// minute boundary carries over instead of producing 60 seconds.
+fn to_dms_rationals(coord: f64) -> [(u32, u32); 3] {
+    let per_second = u64::from(GPS_SECONDS_DENOMINATOR);
+    let total = (coord.abs() * 3600.0 * per_second as f64).round() as u64;
+    let degrees = total / (3600 * per_second);
+    let minutes = total / (60 * per_second) % 60;
+    let seconds = total % (60 * per_second);
+    [
+        (degrees as u32, 1),
+        (minutes as u32, 1),
+        (seconds as u32, GPS_SECONDS_DENOMINATOR),
+    ]
+}
+
+/// Encode a coordinate as a GPS rational value (3 rationals = 24 bytes, little-endian).
+fn encode_gps_rational(coord: f64) -> Vec<u8> {
     let mut bytes = Vec::with_capacity(24);
-    bytes.extend_from_slice(&degrees.to_le_bytes());
-    bytes.extend_from_slice(&1u32.to_le_bytes());
-    bytes.extend_from_slice(&minutes.to_le_bytes());
-    bytes.extend_from_slice(&1u32.to_le_bytes());
-    bytes.extend_from_slice(&seconds_num.to_le_bytes());
-    bytes.extend_from_slice(&seconds_den.to_le_bytes());
+    for (numerator, denominator) in to_dms_rationals(coord) {
+        bytes.extend_from_slice(&numerator.to_le_bytes());
+        bytes.extend_from_slice(&denominator.to_le_bytes());
+    }
     bytes
 }
 
+/// Encode an altitude as `GPSAltitudeRef` (0 above sea level, 1 below) and
+/// the raw bytes of the unsigned `GPSAltitude` rational, in centimeters.
+fn encode_gps_altitude(altitude: f64) -> (u8, Vec<u8>) {
+    let reference = if altitude < 0.0 { 1 } else { 0 };
+    let centimeters = (altitude.abs() * 100.0).round().min(u32::MAX as f64) as u32;
+    let mut bytes = Vec::with_capacity(8);
+    bytes.extend_from_slice(&centimeters.to_le_bytes());
+    bytes.extend_from_slice(&100u32.to_le_bytes());
+    (reference, bytes)
+}
+
 /// Load existing EXIF metadata from a file path using little_exif.
 /// Returns None if it can't parse (instead of losing data).
 fn load_existing_metadata(path: &Path) -> Option<Metadata> {
@@ -122,16 +357,62 @@ fn load_existing_metadata(path: &Path) -> Option<Metadata> {
     }
 }
 
+/// Result of clearing metadata from an image file: which blocks were found
+/// and removed.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ClearResult {
+    /// Whether EXIF was removed (JPEG APP1, PNG `eXIf`, WebP `EXIF`, or the
+    /// EXIF/GPS tags of a TIFF).
+    pub exif_removed: bool,
+    /// Whether XMP was removed.
+    pub xmp_removed: bool,
+    /// Whether IPTC was removed (JPEG APP13, or the IPTC/Photoshop tags of a TIFF).
+    pub iptc_removed: bool,
+    /// Whether PNG `tEXt`/`zTXt`/`iTXt` text chunks other than XMP were removed.
+    pub text_removed: bool,
+    /// Path of the sidecar `.xmp` file that was deleted (HEIC/RAW).
+    pub sidecar_removed: Option<PathBuf>,
+}
+
+impl ClearResult {
+    /// Names of the removed blocks, e.g. `["EXIF", "XMP"]`; empty if the
+    /// image had no metadata to clear.
+    pub fn removed_blocks(&self) -> Vec<&'static str> {
+        [
+            (self.exif_removed, "EXIF"),
+            (self.xmp_removed, "XMP"),
+            (self.iptc_removed, "IPTC"),
+            (self.text_removed, "text"),
+            (self.sidecar_removed.is_some(), "sidecar"),
+        ]
+        .into_iter()
+        .filter_map(|(removed, name)| removed.then_some(name))
+        .collect()
+    }
+}
+
 /// Clear all EXIF/XMP/IPTC metadata from an image file.
 ///
-/// Supports JPEG, PNG, WebP, and TIFF formats. For sidecar formats (HEIC, RAW),
-/// this removes the `.xmp` sidecar file if it exists.
+/// | ImageKind | Removed |
+/// |-----------|---------|
+/// | `Jpeg` | APP1 EXIF and XMP segments, APP13 (IPTC) segments |
+/// | `Png` | `eXIf`, `tEXt`, `zTXt` and `iTXt` chunks |
+/// | `WebP` | `EXIF` and `XMP ` chunks |
+/// | `Tiff` | Descriptive, camera, date, EXIF, GPS, XMP and IPTC tags |
+/// | `Sidecar` | The `.xmp` sidecar file, if it exists (HEIC/RAW) |
+///
+/// Pixel data is never touched. TIFF keeps the structural tags it needs to
+/// decode (dimensions, strips, orientation, resolution, color profile).
 ///
 /// # Arguments
 ///
 /// * `path` — Path to the image file
 /// * `image_kind` — The format of the image
 ///
+/// # Returns
+///
+/// A [`ClearResult`] reporting which metadata blocks were removed.
+///
 /// # Example
 ///
 /// ```rust,no_run
@@ -140,11 +421,13 @@ fn load_existing_metadata(path: &Path) -> Option<Metadata> {
 /// use std::path::Path;
 ///
 /// let path = Path::new("photo.jpg");
-/// clear_exif(path, ImageKind::Jpeg)?;
+/// let result = clear_exif(path, ImageKind::Jpeg)?;
+/// println!("Removed: {}", result.removed_blocks().join(", "));
 /// # Ok::<(), anyhow::Error>(())
 /// ```
-pub fn clear_exif(path: &Path, image_kind: ImageKind) -> Result<()> {
-    match image_kind {
+pub fn clear_exif(path: &Path, image_kind: ImageKind) -> Result<ClearResult> {
+    let mut result = ClearResult::default();
+    let output = match image_kind {
         ImageKind::Jpeg => {
             let file_bytes = std::fs::read(path)
                 .with_context(|| format!("Failed to read {}", path.display()))?;
@@ -152,28 +435,26 @@ pub fn clear_exif(path: &Path, image_kind: ImageKind) -> Result<()> {
                 .map_err(|e| anyhow::anyhow!("Failed to parse JPEG: {e}"))?;
 
             // Remove EXIF (APP1 EXIF)
+            result.exif_removed = jpeg.exif().is_some();
             jpeg.set_exif(None);
 
-            // Remove XMP (APP1 XMP) and IPTC (APP13) segments
-            let segments = jpeg.segments_mut();
-            segments.retain(|seg| {
-                let marker = seg.marker();
+            // Remove XMP (APP1 XMP, including extended XMP) and IPTC (APP13) segments
+            jpeg.segments_mut().retain(|seg| {
                 let data = seg.contents();
-                // Remove APP1 XMP segments
-                if marker == 0xE1 && data.len() > 29 && data.starts_with(b"http://ns.adobe.com/xap/1.0/\0") {
-                    return false;
-                }
-                // Remove APP13 IPTC segments
-                if marker == 0xED {
-                    return false;
+                match seg.marker() {
+                    0xE1 if data.starts_with(XMP_HEADER) || data.starts_with(XMP_EXTENSION_HEADER) => {
+                        result.xmp_removed = true;
+                        false
+                    }
+                    0xED => {
+                        result.iptc_removed = true;
+                        false
+                    }
+                    _ => true,
                 }
-                true
             });
 
-            let mut out = Vec::new();
-            jpeg.encoder().write_to(&mut out)?;
-            std::fs::write(path, out)
-                .with_context(|| format!("Failed to write {}", path.display()))?;
+            jpeg.encoder().bytes()
         }
         ImageKind::Png => {
             use img_parts::png::Png;
@@ -181,11 +462,16 @@ pub fn clear_exif(path: &Path, image_kind: ImageKind) -> Result<()> {
                 .with_context(|| format!("Failed to read {}", path.display()))?;
             let mut png = Png::from_bytes(Bytes::from(file_bytes))
                 .map_err(|e| anyhow::anyhow!("Failed to parse PNG: {e}"))?;
-            png.set_exif(None);
-            let mut out = Vec::new();
-            png.encoder().write_to(&mut out)?;
-            std::fs::write(path, out)
-                .with_context(|| format!("Failed to write {}", path.display()))?;
+            png.chunks_mut().retain(|chunk| {
+                match &chunk.kind() {
+                    b"eXIf" => result.exif_removed = true,
+                    b"iTXt" if chunk.contents().starts_with(b"XML:com.adobe.xmp\0") => result.xmp_removed = true,
+                    b"tEXt" | b"zTXt" | b"iTXt" => result.text_removed = true,
+                    _ => return true,
+                }
+                false
+            });
+            png.encoder().bytes()
         }
         ImageKind::WebP => {
             use img_parts::webp::WebP;
@@ -193,14 +479,18 @@ pub fn clear_exif(path: &Path, image_kind: ImageKind) -> Result<()> {
                 .with_context(|| format!("Failed to read {}", path.display()))?;
             let mut webp = WebP::from_bytes(Bytes::from(file_bytes))
                 .map_err(|e| anyhow::anyhow!("Failed to parse WebP: {e}"))?;
+            result.exif_removed = webp.exif().is_some();
+            result.xmp_removed = webp.chunk_by_id(*b"XMP ").is_some();
+            // set_exif also clears the EXIF flag in the VP8X header
             webp.set_exif(None);
-            let mut out = Vec::new();
-            webp.encoder().write_to(&mut out)?;
-            std::fs::write(path, out)
-                .with_context(|| format!("Failed to write {}", path.display()))?;
+            webp.remove_chunks_by_id(*b"XMP ");
+            webp.encoder().bytes()
         }
         ImageKind::Tiff => {
-            anyhow::bail!("Clearing EXIF from TIFF files is not supported — EXIF is integral to the TIFF structure");
+            let mut file_bytes = std::fs::read(path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            result = clear_tiff_metadata(&mut file_bytes)?;
+            Bytes::from(file_bytes)
         }
         ImageKind::Sidecar => {
             // Remove the sidecar XMP file if it exists
@@ -209,12 +499,150 @@ pub fn clear_exif(path: &Path, image_kind: ImageKind) -> Result<()> {
                 std::fs::remove_file(&xmp_path)
                     .with_context(|| format!("Failed to remove sidecar {}", xmp_path.display()))?;
                 log::info!("Removed sidecar: {}", xmp_path.display());
+                result.sidecar_removed = Some(xmp_path);
             } else {
                 log::info!("No sidecar XMP found for {}", path.display());
             }
+            return Ok(result);
         }
+    };
+
+    if result.removed_blocks().is_empty() {
+        log::debug!("No metadata to clear in {}", path.display());
+        return Ok(result);
     }
-    Ok(())
+    write_atomic(path, &output).with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(result)
+}
+
+/// IFD tags removed from TIFFs by [`clear_exif`]: descriptive text, camera and
+/// software identity, dates, and the pointers to the EXIF, GPS, XMP and IPTC blocks.
+const TIFF_METADATA_TAGS: &[u16] = &[
+    0x010E, // ImageDescription
+    0x010F, // Make
+    0x0110, // Model
+    0x0131, // Software
+    0x0132, // DateTime
+    TAG_ARTIST,
+    0x013C, // HostComputer
+    TIFF_TAG_XMP,
+    TAG_COPYRIGHT,
+    TIFF_TAG_IPTC,
+    TIFF_TAG_PHOTOSHOP,
+    TIFF_TAG_EXIF_IFD,
+    TIFF_TAG_GPS_IFD,
+    TAG_XP_TITLE,
+    TAG_XP_COMMENT,
+    0x9C9D, // XPAuthor
+    TAG_XP_KEYWORDS,
+    TAG_XP_SUBJECT,
+];
+const TIFF_TAG_XMP: u16 = 0x02BC;
+const TIFF_TAG_IPTC: u16 = 0x83BB;
+const TIFF_TAG_PHOTOSHOP: u16 = 0x8649;
+const TIFF_TAG_EXIF_IFD: u16 = 0x8769;
+const TIFF_TAG_GPS_IFD: u16 = 0x8825;
+const TIFF_TAG_INTEROP_IFD: u16 = 0xA005;
+
+/// Strip [`TIFF_METADATA_TAGS`] from every IFD of a TIFF, in place.
+///
+/// Each IFD is rewritten at its own offset with the remaining entries, and the
+/// values of the removed ones — including the whole EXIF, GPS and interop
+/// sub-IFDs — are zeroed so no metadata is left in unreferenced bytes. The file
+/// keeps its size and its pixel data.
+fn clear_tiff_metadata(data: &mut [u8]) -> Result<ClearResult> {
+    let big_endian = match data.get(..2) {
+        Some(b"MM") => true,
+        Some(b"II") => false,
+        _ => anyhow::bail!("Invalid TIFF byte order"),
+    };
+
+    let mut result = ClearResult::default();
+    let mut wipe = Vec::new();
+    for ifd in tiff_ifd_offsets(data) {
+        let count = tiff_u16(data, ifd, big_endian) as usize;
+        let next_ptr = ifd + 2 + count * 12;
+        if next_ptr + 4 > data.len() {
+            continue;
+        }
+
+        let mut kept = Vec::with_capacity(count * 12);
+        for entry in (ifd + 2..next_ptr).step_by(12) {
+            let tag = tiff_u16(data, entry, big_endian);
+            if !TIFF_METADATA_TAGS.contains(&tag) {
+                kept.extend_from_slice(&data[entry..entry + 12]);
+                continue;
+            }
+            match tag {
+                TIFF_TAG_XMP => result.xmp_removed = true,
+                TIFF_TAG_IPTC | TIFF_TAG_PHOTOSHOP => result.iptc_removed = true,
+                _ => result.exif_removed = true,
+            }
+            tiff_value_spans(data, entry, big_endian, 0, &mut wipe);
+        }
+
+        // Same offset, fewer entries: shift the next-IFD pointer up and zero the tail
+        let next = data[next_ptr..next_ptr + 4].to_vec();
+        let kept_count = (kept.len() / 12) as u16;
+        let count_bytes = if big_endian { kept_count.to_be_bytes() } else { kept_count.to_le_bytes() };
+        data[ifd..ifd + 2].copy_from_slice(&count_bytes);
+        let entries_end = ifd + 2 + kept.len();
+        data[ifd + 2..entries_end].copy_from_slice(&kept);
+        data[entries_end..entries_end + 4].copy_from_slice(&next);
+        data[entries_end + 4..next_ptr + 4].fill(0);
+    }
+
+    for span in wipe {
+        data[span].fill(0);
+    }
+    Ok(result)
+}
+
+/// Collect the byte ranges holding the value of the IFD entry at `entry`:
+/// its out-of-line data and, for sub-IFD pointers, the sub-IFD with all of
+/// its values.
+fn tiff_value_spans(
+    data: &[u8],
+    entry: usize,
+    big_endian: bool,
+    depth: usize,
+    spans: &mut Vec<std::ops::Range<usize>>,
+) {
+    const TYPE_SIZES: [usize; 13] = [0, 1, 1, 2, 4, 8, 1, 1, 2, 4, 8, 4, 8];
+
+    let tag = tiff_u16(data, entry, big_endian);
+    let format = tiff_u16(data, entry + 2, big_endian) as usize;
+    let count = tiff_u32(data, entry + 4, big_endian) as usize;
+    let offset = tiff_u32(data, entry + 8, big_endian) as usize;
+
+    let size = TYPE_SIZES.get(format).copied().unwrap_or(0).saturating_mul(count);
+    if size > 4 && offset.saturating_add(size) <= data.len() {
+        spans.push(offset..offset + size);
+    }
+
+    let is_sub_ifd = matches!(tag, TIFF_TAG_EXIF_IFD | TIFF_TAG_GPS_IFD | TIFF_TAG_INTEROP_IFD);
+    if !is_sub_ifd || depth >= 2 || offset + 2 > data.len() {
+        return;
+    }
+    let sub_count = tiff_u16(data, offset, big_endian) as usize;
+    let sub_end = offset + 2 + sub_count * 12 + 4;
+    if sub_end > data.len() {
+        return;
+    }
+    spans.push(offset..sub_end);
+    for sub_entry in (offset + 2..sub_end - 4).step_by(12) {
+        tiff_value_spans(data, sub_entry, big_endian, depth + 1, spans);
+    }
+}
+
+fn tiff_u16(data: &[u8], offset: usize, big_endian: bool) -> u16 {
+    let b = [data[offset], data[offset + 1]];
+    if big_endian { u16::from_be_bytes(b) } else { u16::from_le_bytes(b) }
+}
+
+fn tiff_u32(data: &[u8], offset: usize, big_endian: bool) -> u32 {
+    let b = [data[offset], data[offset + 1], data[offset + 2], data[offset + 3]];
+    if big_endian { u32::from_be_bytes(b) } else { u32::from_le_bytes(b) }
 }
 
 /// Write AI-generated metadata into an image file, preserving all existing data.
@@ -227,9 +655,17 @@ pub fn clear_exif(path: &Path, image_kind: ImageKind) -> Result<()> {
 /// | `Jpeg` | EXIF (APP1) + XMP (APP1) + IPTC (APP13) — all written in-place |
 /// | `Png` | XMP in iTXt chunk |
 /// | `WebP` | EXIF + XMP in RIFF chunks |
-/// | `Tiff` | EXIF via little_exif |
+/// | `Tiff` | EXIF via raw IFD injection |
 /// | `Sidecar` | Writes a `.xmp` sidecar file alongside the original (HEIC/RAW) |
 ///
+/// With `xmp_only`, EXIF and IPTC are left untouched: JPEG gets only its XMP
+/// APP1 segment, WebP only its `XMP ` chunk, and TIFF (which has no XMP
+/// writer) gets a `.xmp` sidecar. PNG and sidecar formats are XMP-only anyway.
+///
+/// With [`ExifFields::use_exiftool_if_available`], sidecar formats also get
+/// title, description, keywords and GPS embedded by `exiftool` when it is on
+/// `PATH`; the sidecar is still written, and is all there is without exiftool.
+///
 /// # Arguments
 ///
 /// * `path` — Path to the image file
@@ -238,6 +674,7 @@ pub fn clear_exif(path: &Path, image_kind: ImageKind) -> Result<()> {
 /// * `fields` — Which fields to write and whether to overwrite existing values
 /// * `dry_run` — If `true`, compute what would be written but don't modify any files
 /// * `image_kind` — The format of the image (determines write strategy)
+/// * `xmp_only` — If `true`, write XMP only and skip EXIF/IPTC
 ///
 /// # Returns
 ///
@@ -267,9 +704,10 @@ pub fn clear_exif(path: &Path, image_kind: ImageKind) -> Result<()> {
 ///     write_gps: false,
 ///     write_subject: false,
 ///     overwrite_existing: false,
+///     ..Default::default()
 /// };
 ///
-/// let result = write_exif(path, &ai, &existing, &fields, false, ImageKind::Jpeg)?;
+/// let result = write_exif(path, &ai, &existing, &fields, false, ImageKind::Jpeg, false)?;
 /// assert!(result.title_written);
 /// # Ok::<(), anyhow::Error>(())
 /// ```
@@ -280,88 +718,165 @@ pub fn write_exif(
     fields: &ExifFields,
     dry_run: bool,
     image_kind: ImageKind,
+    xmp_only: bool,
 ) -> Result<WriteResult> {
     let mut result = WriteResult::default();
 
+    // Keep an existing copyright notice or artist unless overwriting
+    let kept;
+    let fields = if !fields.overwrite_existing
+        && ((fields.write_copyright && existing.copyright.is_some()) || (fields.write_artist && existing.artist.is_some()))
+    {
+        kept = ExifFields {
+            write_copyright: fields.write_copyright && existing.copyright.is_none(),
+            write_artist: fields.write_artist && existing.artist.is_none(),
+            ..fields.clone()
+        };
+        log::debug!("  Keeping existing copyright/artist");
+        &kept
+    } else {
+        fields
+    };
+
+    // Never write (0, 0) or coordinates off the globe
+    let cleaned;
+    let ai_result = match &ai_result.gps {
+        Some(gps) if fields.write_gps && (gps.is_null_island() || !gps.validate()) => {
+            if !gps.is_null_island() {
+                log::warn!("  Ignoring invalid GPS from AI: {}, {}", gps.latitude, gps.longitude);
+                result.skipped_fields.push(SkippedField::new(Field::Gps, SkipReason::Invalid));
+            }
+            cleaned = AiResult { gps: None, ..ai_result.clone() };
+            &cleaned
+        }
+        _ => ai_result,
+    };
+
     // Collect which tags to write (used for both dry-run and real write)
     let mut new_tags: Vec<ExifTag> = Vec::new();
 
     // Title — ImageDescription (native) + XPTitle (custom)
-    if fields.write_title {
-        if let Some(ref title) = ai_result.title {
-            if existing.title.is_none() || fields.overwrite_existing {
-                new_tags.push(ExifTag::ImageDescription(title.clone()));
-                if let Some(xp_tag) = make_xp_tag(TAG_XP_TITLE, title) {
-                    new_tags.push(xp_tag);
-                }
-                result.title_written = true;
-                log::debug!("  Title: {title}");
-            } else {
-                result.skipped_fields.push("title (existing)".to_string());
+    if let Some(ref title) = ai_result.title {
+        if !fields.write_title {
+            result.skipped_fields.push(SkippedField::new(Field::Title, SkipReason::Disabled));
+        } else if existing.title.is_none() || fields.overwrite_existing {
+            new_tags.push(ExifTag::ImageDescription(title.clone()));
+            if let Some(xp_tag) = make_xp_tag(TAG_XP_TITLE, title) {
+                new_tags.push(xp_tag);
             }
+            result.title_written = true;
+            log::debug!("  Title: {title}");
+        } else {
+            result.skipped_fields.push(SkippedField::new(Field::Title, SkipReason::Existing));
         }
     }
 
     // Description — UserComment (native) + XPComment (custom)
-    if fields.write_description {
-        if let Some(ref desc) = ai_result.description {
-            if existing.description.is_none() || fields.overwrite_existing {
-                let mut comment_bytes = b"ASCII\0\0\0".to_vec();
-                comment_bytes.extend_from_slice(desc.as_bytes());
-                new_tags.push(ExifTag::UserComment(comment_bytes));
-                if let Some(xp_tag) = make_xp_tag(TAG_XP_COMMENT, desc) {
-                    new_tags.push(xp_tag);
-                }
-                result.description_written = true;
-                log::debug!("  Description: {desc}");
-            } else {
-                result.skipped_fields.push("description (existing)".to_string());
+    if let Some(ref desc) = ai_result.description {
+        if !fields.write_description {
+            result.skipped_fields.push(SkippedField::new(Field::Description, SkipReason::Disabled));
+        } else if existing.description.is_none() || fields.overwrite_existing {
+            let mut comment_bytes = b"ASCII\0\0\0".to_vec();
+            comment_bytes.extend_from_slice(desc.as_bytes());
+            new_tags.push(ExifTag::UserComment(comment_bytes));
+            if let Some(xp_tag) = make_xp_tag(TAG_XP_COMMENT, desc) {
+                new_tags.push(xp_tag);
             }
+            result.description_written = true;
+            log::debug!("  Description: {desc}");
+        } else {
+            result.skipped_fields.push(SkippedField::new(Field::Description, SkipReason::Existing));
         }
     }
 
     // Tags / Keywords — XPKeywords (custom)
-    if fields.write_tags {
-        if let Some(ref tags) = ai_result.tags {
-            if existing.keywords.is_none() || fields.overwrite_existing {
-                let keywords_str = tags.join("; ");
-                if let Some(xp_tag) = make_xp_tag(TAG_XP_KEYWORDS, &keywords_str) {
-                    new_tags.push(xp_tag);
-                }
-                result.tags_written = true;
-                log::debug!("  Tags: {}", tags.join(", "));
-            } else {
-                result.skipped_fields.push("tags (existing)".to_string());
+    if let Some(ref tags) = ai_result.tags {
+        if !fields.write_tags || !fields.keyword_targets.any() {
+            result.skipped_fields.push(SkippedField::new(Field::Tags, SkipReason::Disabled));
+        } else if existing.keywords.is_none() || fields.overwrite_existing {
+            let keywords_str = tags.join("; ");
+            if fields.keyword_targets.xp_keywords
+                && let Some(xp_tag) = make_xp_tag(TAG_XP_KEYWORDS, &keywords_str)
+            {
+                new_tags.push(xp_tag);
             }
+            result.tags_written = true;
+            log::debug!("  Tags: {}", tags.join(", "));
+        } else {
+            result.skipped_fields.push(SkippedField::new(Field::Tags, SkipReason::Existing));
         }
     }
 
     // Subject — XPSubject (custom)
-    if fields.write_subject {
-        if let Some(ref subjects) = ai_result.subject {
-            if !subjects.is_empty() && (existing.subject.is_none() || fields.overwrite_existing) {
-                let subject_str = subjects.join("; ");
-                if let Some(xp_tag) = make_xp_tag(TAG_XP_SUBJECT, &subject_str) {
-                    new_tags.push(xp_tag);
-                }
-                result.subject_written = true;
-                log::debug!("  Subject: {}", subjects.join(", "));
-            } else if !subjects.is_empty() {
-                result.skipped_fields.push("subject (existing)".to_string());
+    if let Some(ref subjects) = ai_result.subject {
+        if !fields.write_subject {
+            result.skipped_fields.push(SkippedField::new(Field::Subject, SkipReason::Disabled));
+        } else if subjects.is_empty() {
+            result.skipped_fields.push(SkippedField::new(Field::Subject, SkipReason::Empty));
+        } else if existing.subject.is_none() || fields.overwrite_existing {
+            let subject_str = subjects.join("; ");
+            if let Some(xp_tag) = make_xp_tag(TAG_XP_SUBJECT, &subject_str) {
+                new_tags.push(xp_tag);
             }
+            result.subject_written = true;
+            log::debug!("  Subject: {}", subjects.join(", "));
+        } else {
+            result.skipped_fields.push(SkippedField::new(Field::Subject, SkipReason::Existing));
         }
     }
 
     // GPS — only if no existing GPS AND AI identified a location
-    if fields.write_gps {
-        if let Some(ref gps) = ai_result.gps {
-            if !existing.has_gps {
-                collect_gps_tags(&mut new_tags, gps);
-                result.gps_written = true;
-                log::debug!("  GPS: {}, {}", gps.latitude, gps.longitude);
-            } else {
-                result.skipped_fields.push("gps (existing coordinates)".to_string());
-            }
+    if let Some(ref gps) = ai_result.gps {
+        if !fields.write_gps {
+            result.skipped_fields.push(SkippedField::new(Field::Gps, SkipReason::Disabled));
+        } else if !existing.has_gps {
+            collect_gps_tags(&mut new_tags, gps);
+            result.gps_written = true;
+            log::debug!("  GPS: {}, {}", gps.latitude, gps.longitude);
+        } else {
+            result.skipped_fields.push(SkippedField::new(Field::Gps, SkipReason::Existing));
+        }
+    }
+
+    // Date — DateTimeOriginal + CreateDate, only if the image has no capture date
+    if let Some(ref date) = ai_result.date_taken {
+        if !fields.write_date {
+            result.skipped_fields.push(SkippedField::new(Field::Date, SkipReason::Disabled));
+        } else if existing.date_time.is_some() {
+            result.skipped_fields.push(SkippedField::new(Field::Date, SkipReason::Existing));
+        } else if let Some(exif_date) = exif_date_time(date) {
+            new_tags.push(ExifTag::DateTimeOriginal(exif_date.clone()));
+            new_tags.push(ExifTag::CreateDate(exif_date.clone()));
+            result.date_written = true;
+            log::debug!("  Date: {exif_date}");
+        } else {
+            log::debug!("  Date not ISO 8601: {date:?}");
+            result.skipped_fields.push(SkippedField::new(Field::Date, SkipReason::Invalid));
+        }
+    }
+
+    // Copyright and Artist — fixed values from the config, written every time
+    let credits = Credits::new(fields);
+    if let Some(copyright) = credits.copyright {
+        new_tags.push(ExifTag::Copyright(copyright.to_string()));
+        result.copyright_written = true;
+        log::debug!("  Copyright: {copyright}");
+    }
+    if let Some(artist) = credits.artist {
+        new_tags.push(ExifTag::Artist(artist.to_string()));
+        result.artist_written = true;
+        log::debug!("  Artist: {artist}");
+    }
+
+    // exiftool can embed GPS in HEIC/RAW files, which sidecars can't carry
+    let exiftool = image_kind == ImageKind::Sidecar && fields.use_exiftool_if_available && exiftool_available();
+
+    // Fields the target format can't hold would otherwise be dropped silently
+    for field in ALL_FIELDS {
+        let written = result.written_flag(field);
+        if *written && !format_supports(image_kind, xmp_only, field, fields) && !(exiftool && field == Field::Gps) {
+            *written = false;
+            result.skipped_fields.push(SkippedField::new(field, SkipReason::FormatUnsupported));
         }
     }
 
@@ -370,37 +885,175 @@ pub fn write_exif(
     }
 
     // Route to the correct writer based on image format
-    match image_kind {
+    let xmp = Blocks { xmp: true, ..Default::default() };
+    let blocks = match image_kind {
+        ImageKind::Jpeg if xmp_only => {
+            write_xmp_to_jpeg(path, ai_result, existing, fields)
+                .context("Failed to write XMP metadata to JPEG")?
+        }
+        ImageKind::Tiff if xmp_only => {
+            let sidecar = write_sidecar_xmp(path, ai_result, existing, fields)
+                .context("Failed to write sidecar XMP")?;
+            result.sidecar_path = Some(sidecar);
+            xmp
+        }
         ImageKind::Jpeg => {
-            if !new_tags.is_empty() {
+            // Keywords can go to XMP/IPTC alone, without any EXIF tag
+            if !new_tags.is_empty() || result.tags_written {
                 write_tags_to_jpeg(path, &new_tags, ai_result, existing, fields)
-                    .context("Failed to write EXIF metadata to JPEG")?;
+                    .context("Failed to write EXIF metadata to JPEG")?
+            } else {
+                Blocks::default()
             }
         }
         ImageKind::Png => {
             write_xmp_to_png(path, ai_result, existing, fields)
                 .context("Failed to write XMP metadata to PNG")?;
+            xmp
         }
         ImageKind::WebP => {
-            write_metadata_to_webp(path, ai_result, existing, fields)
-                .context("Failed to write metadata to WebP")?;
+            write_metadata_to_webp(path, ai_result, existing, fields, xmp_only)
+                .context("Failed to write metadata to WebP")?
         }
         ImageKind::Tiff => {
             if !new_tags.is_empty() {
-                write_tags_to_tiff(path, &new_tags)
+                write_tags_to_tiff(path, ai_result, existing, fields)
                     .context("Failed to write EXIF metadata to TIFF")?;
+                Blocks { exif: true, ..Default::default() }
+            } else {
+                Blocks::default()
             }
         }
         ImageKind::Sidecar => {
             let sidecar = write_sidecar_xmp(path, ai_result, existing, fields)
                 .context("Failed to write sidecar XMP")?;
             result.sidecar_path = Some(sidecar);
+            let mut blocks = xmp;
+            if exiftool {
+                let args = exiftool_args(ai_result, &result, fields);
+                if !args.is_empty() {
+                    let run = run_exiftool(path, &args);
+                    if !run.success() {
+                        log::warn!("exiftool failed on {}: {}", path.display(), run.stderr);
+                    }
+                    // Embedded GPS stands in for an EXIF block
+                    blocks.exif = run.success();
+                    result.exiftool = Some(run);
+                }
+            }
+            blocks
+        }
+    };
+
+    // Only report what a stored block actually holds
+    for field in ALL_FIELDS {
+        let written = result.written_flag(field);
+        if *written && !blocks.hold(field, fields) {
+            *written = false;
+            log::debug!("  {field} not stored by the {image_kind:?} writer");
+            result.skipped_fields.push(SkippedField::new(field, SkipReason::FormatUnsupported));
         }
     }
+    if !blocks.any() {
+        result.copyright_written = false;
+        result.artist_written = false;
+    }
 
     Ok(result)
 }
 
+pub(crate) const ALL_FIELDS: [Field; 6] = [Field::Title, Field::Description, Field::Tags, Field::Subject, Field::Gps, Field::Date];
+
+/// Whether the writer for `image_kind` has anywhere to store `field`.
+///
+/// JPEG, WebP and TIFF carry every field in EXIF (keywords in TIFF only as
+/// XPKeywords, in WebP as XPKeywords or XMP). Everything written as XMP alone —
+/// PNG, sidecars and `xmp_only` — has no place for GPS, subject or date, and
+/// keywords need an XMP keyword target.
+fn format_supports(image_kind: ImageKind, xmp_only: bool, field: Field, fields: &ExifFields) -> bool {
+    let targets = fields.keyword_targets;
+    match (image_kind, field) {
+        (ImageKind::Jpeg, Field::Tags) if !xmp_only => targets.any(),
+        (ImageKind::WebP, Field::Tags) if !xmp_only => targets.xp_keywords || targets.any_xmp(),
+        (ImageKind::Tiff, Field::Tags) if !xmp_only => targets.xp_keywords,
+        (ImageKind::Jpeg | ImageKind::WebP | ImageKind::Tiff, _) if !xmp_only => true,
+        (_, Field::Title | Field::Description) => true,
+        (_, Field::Tags) => targets.any_xmp(),
+        (_, Field::Subject | Field::Gps | Field::Date) => false,
+    }
+}
+
+const EXIFTOOL: &str = "exiftool";
+
+/// Whether `exiftool` can be run from `PATH`. Checked once per process.
+fn exiftool_available() -> bool {
+    static AVAILABLE: std::sync::OnceLock<bool> = std::sync::OnceLock::new();
+    *AVAILABLE.get_or_init(|| {
+        let found = std::process::Command::new(EXIFTOOL)
+            .arg("-ver")
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .status()
+            .is_ok_and(|s| s.success());
+        log::debug!("exiftool {}", if found { "found" } else { "not found" });
+        found
+    })
+}
+
+/// exiftool tag assignments for the fields `result` marks as written; empty
+/// when there is nothing for exiftool to do.
+fn exiftool_args(ai_result: &AiResult, result: &WriteResult, fields: &ExifFields) -> Vec<String> {
+    let mut args = Vec::new();
+    if result.title_written
+        && let Some(title) = &ai_result.title
+    {
+        args.push(format!("-Title={title}"));
+    }
+    if result.description_written
+        && let Some(desc) = &ai_result.description
+    {
+        args.push(format!("-Description={desc}"));
+    }
+    if result.tags_written
+        && let Some(tags) = &ai_result.tags
+    {
+        // The first assignment replaces the list, the rest add to it
+        for tag in tags {
+            if fields.keyword_targets.iptc {
+                args.push(format!("-Keywords={tag}"));
+            }
+            if fields.keyword_targets.dc_subject {
+                args.push(format!("-XMP-dc:Subject={tag}"));
+            }
+        }
+    }
+    if result.gps_written
+        && let Some(gps) = &ai_result.gps
+    {
+        let lat_ref = if gps.latitude < 0.0 { "S" } else { "N" };
+        let lon_ref = if gps.longitude < 0.0 { "W" } else { "E" };
+        args.push(format!("-GPSLatitude={}", gps.latitude.abs()));
+        args.push(format!("-GPSLatitudeRef={lat_ref}"));
+        args.push(format!("-GPSLongitude={}", gps.longitude.abs()));
+        args.push(format!("-GPSLongitudeRef={lon_ref}"));
+    }
+    if !args.is_empty() {
+        args.insert(0, "-overwrite_original".into());
+    }
+    args
+}
+
+/// Run exiftool with `args` on `path`, capturing its exit code and stderr.
+fn run_exiftool(path: &Path, args: &[String]) -> ExiftoolRun {
+    match std::process::Command::new(EXIFTOOL).args(args).arg(path).output() {
+        Ok(output) => ExiftoolRun {
+            exit_code: output.status.code(),
+            stderr: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        },
+        Err(e) => ExiftoolRun { exit_code: None, stderr: format!("Failed to run {EXIFTOOL}: {e}") },
+    }
+}
+
 /// Write new EXIF tags into a JPEG file, preserving all existing data.
 fn write_tags_to_jpeg(
     path: &Path,
@@ -408,7 +1061,7 @@ fn write_tags_to_jpeg(
     ai_result: &AiResult,
     existing: &ExifData,
     fields: &ExifFields,
-) -> Result<()> {
+) -> Result<Blocks> {
     let file_bytes = std::fs::read(path).context("Failed to read image file")?;
 
     // Parse JPEG structure with img-parts (preserves all segments)
@@ -430,22 +1083,23 @@ fn write_tags_to_jpeg(
 
     // Try the little_exif round-trip first (works when it can parse the EXIF)
     // BUT skip it when GPS is involved to avoid losing GPS IFD.
-    if !gps_involved {
-        if let Some(mut metadata) = load_existing_metadata(path) {
-            log::debug!("little_exif parsed existing EXIF, using merge strategy");
-            for tag in new_tags {
-                metadata.set_tag(tag.clone());
-            }
-            let exif_bytes = metadata.as_u8_vec(FileExtension::JPEG);
-            if exif_bytes.len() > JPEG_EXIF_OVERHEAD {
-                new_tiff_data = Some(exif_bytes[JPEG_EXIF_OVERHEAD..].to_vec());
-            } else {
-                new_tiff_data = None;
-            }
+    if !new_tags.is_empty()
+        && !gps_involved
+        && let Some(mut metadata) = load_existing_metadata(path)
+    {
+        log::debug!("little_exif parsed existing EXIF, using merge strategy");
+        for tag in new_tags {
+            metadata.set_tag(tag.clone());
+        }
+        let exif_bytes = metadata.as_u8_vec(FileExtension::JPEG);
+        if exif_bytes.len() > JPEG_EXIF_OVERHEAD {
+            new_tiff_data = Some(exif_bytes[JPEG_EXIF_OVERHEAD..].to_vec());
+        } else {
+            new_tiff_data = None;
         }
     }
 
-    if new_tiff_data.is_none() {
+    if new_tiff_data.is_none() && !new_tags.is_empty() {
         log::info!("Using raw TIFF injection to preserve original EXIF");
         if original_exif.is_empty() {
             // No existing EXIF — build fresh
@@ -466,6 +1120,7 @@ fn write_tags_to_jpeg(
     }
 
     // Write the new EXIF via set_exif (removes old, inserts at pos 3)
+    let exif_written = new_tiff_data.is_some();
     if let Some(tiff_data) = new_tiff_data {
         jpeg.set_exif(Some(Bytes::from(tiff_data)));
 
@@ -484,15 +1139,34 @@ fn write_tags_to_jpeg(
     }
 
     // === Write XMP metadata (dc:title, dc:description, dc:subject) ===
-    update_xmp_metadata(&mut jpeg, ai_result, existing, fields);
+    let xmp_written = update_xmp_metadata(&mut jpeg, ai_result, existing, fields);
 
     // === Write IPTC metadata (caption, keywords) ===
-    update_iptc_metadata(&mut jpeg, ai_result, existing, fields);
+    let iptc_written = update_iptc_metadata(&mut jpeg, ai_result, existing, fields);
 
     let output = jpeg.encoder().bytes();
-    std::fs::write(path, &output).context("Failed to write JPEG file")?;
+    write_atomic(path, &output).context("Failed to write JPEG file")?;
 
-    Ok(())
+    Ok(Blocks { exif: exif_written, xmp: xmp_written, iptc: iptc_written })
+}
+
+/// Write only the XMP APP1 segment of a JPEG, leaving EXIF and IPTC untouched.
+fn write_xmp_to_jpeg(
+    path: &Path,
+    ai_result: &AiResult,
+    existing: &ExifData,
+    fields: &ExifFields,
+) -> Result<Blocks> {
+    let file_bytes = std::fs::read(path).context("Failed to read image file")?;
+    let mut jpeg = Jpeg::from_bytes(Bytes::from(file_bytes))
+        .map_err(|e| anyhow::anyhow!("Failed to parse JPEG: {e}"))?;
+
+    let xmp = update_xmp_metadata(&mut jpeg, ai_result, existing, fields);
+
+    let output = jpeg.encoder().bytes();
+    write_atomic(path, &output).context("Failed to write JPEG file")?;
+
+    Ok(Blocks { xmp, ..Default::default() })
 }
 
 /// Write XMP metadata into a PNG file using img-parts iTXt chunk.
@@ -512,6 +1186,9 @@ fn write_xmp_to_png(
         ai_result.title.as_deref().filter(|_| fields.write_title && (existing.title.is_none() || fields.overwrite_existing)),
         ai_result.description.as_deref().filter(|_| fields.write_description && (existing.description.is_none() || fields.overwrite_existing)),
         if fields.write_tags && (existing.keywords.is_none() || fields.overwrite_existing) { ai_result.tags.as_ref() } else { None },
+        ai_result.hierarchical_tags.as_ref(),
+        &LangAlternatives::new(ai_result, fields),
+        fields,
     );
 
     // Build iTXt chunk for XMP: keyword "XML:com.adobe.xmp" + null + compression flag + method + lang + translated keyword + text
@@ -541,7 +1218,7 @@ fn write_xmp_to_png(
     chunks.insert(insert_pos, xmp_chunk);
 
     let output = png.encoder().bytes();
-    std::fs::write(path, &output).context("Failed to write PNG file")?;
+    write_atomic(path, &output).context("Failed to write PNG file")?;
 
     Ok(())
 }
@@ -552,7 +1229,8 @@ fn write_metadata_to_webp(
     ai_result: &AiResult,
     existing: &ExifData,
     fields: &ExifFields,
-) -> Result<()> {
+    xmp_only: bool,
+) -> Result<Blocks> {
     use img_parts::riff::{RiffChunk, RiffContent};
     use img_parts::webp::WebP;
 
@@ -565,6 +1243,9 @@ fn write_metadata_to_webp(
         ai_result.title.as_deref().filter(|_| fields.write_title && (existing.title.is_none() || fields.overwrite_existing)),
         ai_result.description.as_deref().filter(|_| fields.write_description && (existing.description.is_none() || fields.overwrite_existing)),
         if fields.write_tags && (existing.keywords.is_none() || fields.overwrite_existing) { ai_result.tags.as_ref() } else { None },
+        ai_result.hierarchical_tags.as_ref(),
+        &LangAlternatives::new(ai_result, fields),
+        fields,
     );
 
     // Set XMP via RIFF chunk (WebP uses "XMP " chunk ID)
@@ -572,76 +1253,262 @@ fn write_metadata_to_webp(
     let xmp_chunk = RiffChunk::new(*b"XMP ", RiffContent::Data(Bytes::from(xmp_xml.into_bytes())));
     webp.chunks_mut().push(xmp_chunk);
 
-    // Build minimal EXIF TIFF for title (ImageDescription)
-    if fields.write_title {
-        if let Some(ref title) = ai_result.title {
-            if existing.title.is_none() || fields.overwrite_existing {
-                let mut metadata = Metadata::new();
-                metadata.set_tag(ExifTag::ImageDescription(title.clone()));
-                let exif_bytes = metadata.as_u8_vec(FileExtension::JPEG);
-                if exif_bytes.len() > JPEG_EXIF_OVERHEAD {
-                    webp.set_exif(Some(Bytes::from(exif_bytes[JPEG_EXIF_OVERHEAD..].to_vec())));
-                }
-            }
+    // EXIF chunk: the same raw TIFF injection as JPEG (XP* tags, UserComment,
+    // GPS, dates, credits), into the existing EXIF or a new empty one
+    let mut exif_written = false;
+    if !xmp_only {
+        const EXIF_PREFIX: &[u8] = b"Exif\0\0";
+        let original = match webp.exif() {
+            // Some writers keep the JPEG-style header in the chunk
+            Some(exif) if exif.len() > EXIF_PREFIX.len() => exif.strip_prefix(EXIF_PREFIX).unwrap_or(&exif).to_vec(),
+            _ => EMPTY_TIFF.to_vec(),
+        };
+        let merged = inject_ai_tags_into_tiff(&original, ai_result, existing, fields)?;
+        if merged != original {
+            webp.set_exif(Some(Bytes::from(merged)));
+            exif_written = true;
         }
     }
 
     let output = webp.encoder().bytes();
-    std::fs::write(path, &output).context("Failed to write WebP file")?;
+    write_atomic(path, &output).context("Failed to write WebP file")?;
 
+    Ok(Blocks { exif: exif_written, xmp: true, iptc: false })
+}
+
+/// Write EXIF tags into a TIFF file.
+///
+/// little_exif can't encode TIFF, so the new IFD0 is built in memory by raw
+/// TIFF injection and the result replaces the file atomically. Only page 0
+/// receives metadata: the rebuilt IFD0 keeps the original next-IFD pointer,
+/// so the remaining pages of multi-page TIFFs (scans, faxes) are left
+/// byte-for-byte intact.
+fn write_tags_to_tiff(
+    path: &Path,
+    ai_result: &AiResult,
+    existing: &ExifData,
+    fields: &ExifFields,
+) -> Result<()> {
+    let merged = build_tiff_with_tags(path, ai_result, existing, fields)?;
+    write_atomic(path, &merged).context("Failed to write TIFF file")?;
     Ok(())
 }
 
-/// Write EXIF tags into a TIFF file using little_exif.
-fn write_tags_to_tiff(path: &Path, new_tags: &[ExifTag]) -> Result<()> {
-    let mut metadata = load_existing_metadata(path)
-        .unwrap_or_else(Metadata::new);
+/// Read the TIFF at `path` and return its bytes with the AI tags injected.
+fn build_tiff_with_tags(
+    path: &Path,
+    ai_result: &AiResult,
+    existing: &ExifData,
+    fields: &ExifFields,
+) -> Result<Vec<u8>> {
+    let mut file_bytes = std::fs::read(path).context("Failed to read TIFF file")?;
+
+    let pages = tiff_ifd_offsets(&file_bytes).len();
+    if pages > 1 {
+        log::info!("Multi-page TIFF ({pages} pages) — writing metadata to page 0 only");
+    }
+    // Keep the appended IFDs word-aligned
+    if file_bytes.len() % 2 != 0 {
+        file_bytes.push(0);
+    }
+    inject_ai_tags_into_tiff(&file_bytes, ai_result, existing, fields)
+}
 
-    for tag in new_tags {
-        metadata.set_tag(tag.clone());
+/// Walk the IFD chain of a TIFF file and return the offset of each IFD (one per page).
+///
+/// Stops at the first out-of-bounds or repeated offset, so a malformed chain
+/// can never loop forever.
+fn tiff_ifd_offsets(data: &[u8]) -> Vec<usize> {
+    let mut offsets = Vec::new();
+    if data.len() < 8 {
+        return offsets;
     }
 
-    metadata.write_to_file(path)
-        .map_err(|e| anyhow::anyhow!("Failed to write TIFF EXIF: {e}"))?;
+    let big_endian = match &data[0..2] {
+        b"MM" => true,
+        b"II" => false,
+        _ => return offsets,
+    };
+    let read_u16 = |offset: usize| -> u16 {
+        let b = [data[offset], data[offset + 1]];
+        if big_endian { u16::from_be_bytes(b) } else { u16::from_le_bytes(b) }
+    };
+    let read_u32 = |offset: usize| -> u32 {
+        let b = [data[offset], data[offset + 1], data[offset + 2], data[offset + 3]];
+        if big_endian { u32::from_be_bytes(b) } else { u32::from_le_bytes(b) }
+    };
+
+    let mut next = read_u32(4) as usize;
+    while next != 0 && next + 2 <= data.len() && !offsets.contains(&next) {
+        offsets.push(next);
+        let next_ptr = next + 2 + read_u16(next) as usize * 12;
+        if next_ptr + 4 > data.len() {
+            break;
+        }
+        next = read_u32(next_ptr) as usize;
+    }
 
-    Ok(())
+    offsets
 }
 
 /// Write a sidecar .xmp file for formats that can't be written to directly (HEIC, RAW).
+///
+/// The sidecar uses `rdf:about=""`, which XMP defines as the whole associated file,
+/// so for multi-image HEIC (Live Photos, bursts) it describes the container as a whole.
+/// An existing sidecar is updated in place, keeping the properties exif-ai
+/// doesn't write (ratings, develop settings); one that isn't XMP is replaced.
+/// Like the native formats, the sidecar is written via [`write_atomic`], so an
+/// interrupted run leaves the previous sidecar intact.
 fn write_sidecar_xmp(
     path: &Path,
     ai_result: &AiResult,
     existing: &ExifData,
     fields: &ExifFields,
 ) -> Result<PathBuf> {
-    let sidecar_path = path.with_extension("xmp");
-
-    let xmp_xml = build_xmp(None,
-        ai_result.title.as_deref().filter(|_| fields.write_title && (existing.title.is_none() || fields.overwrite_existing)),
-        ai_result.description.as_deref().filter(|_| fields.write_description && (existing.description.is_none() || fields.overwrite_existing)),
-        if fields.write_tags && (existing.keywords.is_none() || fields.overwrite_existing) { ai_result.tags.as_ref() } else { None },
-    );
+    let (sidecar_path, previous) = read_sidecar(path);
+    let xmp_xml = sidecar_document(previous.as_deref(), ai_result, existing, fields);
 
-    std::fs::write(&sidecar_path, xmp_xml).context("Failed to write sidecar XMP file")?;
+    write_atomic(&sidecar_path, xmp_xml.as_bytes()).context("Failed to write sidecar XMP file")?;
     log::info!("  Sidecar XMP written: {}", sidecar_path.display());
 
     Ok(sidecar_path)
 }
 
-/// Find the position of the EXIF APP1 segment in a JPEG.
-/// EXIF segments have marker 0xE1 (APP1) and contents starting with "Exif\0\0".
-fn find_exif_segment_pos(jpeg: &Jpeg) -> Option<usize> {
-    const EXIF_PREFIX: &[u8] = b"Exif\0\0";
-    jpeg.segments().iter().position(|s| {
-        s.marker() == 0xE1 && s.contents().starts_with(EXIF_PREFIX)
-    })
+/// The sidecar path for `path` and its current contents, if it holds XMP.
+fn read_sidecar(path: &Path) -> (PathBuf, Option<String>) {
+    let sidecar_path = path.with_extension("xmp");
+    let previous = std::fs::read_to_string(&sidecar_path)
+        .ok()
+        .filter(|xmp| xmp.contains("<x:xmpmeta") && find_start_tag(xmp, "rdf:Description", 0).is_some());
+    (sidecar_path, previous)
 }
 
-// ============================================================================
+/// The sidecar XMP for `ai_result`, injected into `previous` when there is one.
+fn sidecar_document(previous: Option<&str>, ai_result: &AiResult, existing: &ExifData, fields: &ExifFields) -> String {
+    build_xmp(previous,
+        ai_result.title.as_deref().filter(|_| fields.write_title && (existing.title.is_none() || fields.overwrite_existing)),
+        ai_result.description.as_deref().filter(|_| fields.write_description && (existing.description.is_none() || fields.overwrite_existing)),
+        if fields.write_tags && (existing.keywords.is_none() || fields.overwrite_existing) { ai_result.tags.as_ref() } else { None },
+        ai_result.hierarchical_tags.as_ref(),
+        &LangAlternatives::new(ai_result, fields),
+        fields,
+    )
+}
+
+/// What [`write_exif`] would write to an image's `.xmp` sidecar.
+///
+/// Built by [`preview_sidecar_xmp`] for dry-run previews; nothing is written.
+#[derive(Debug, Clone)]
+pub struct SidecarPreview {
+    /// Path of the sidecar, e.g. `photo.xmp` for `photo.heic`.
+    pub path: PathBuf,
+    /// Whether a file already exists at `path`.
+    pub exists: bool,
+    /// The complete XMP document that would be written.
+    pub xmp: String,
+    previous: Option<String>,
+}
+
+impl SidecarPreview {
+    /// Whether the existing sidecar is XMP and would be updated in place.
+    /// `false` with [`exists`](Self::exists) means it would be replaced.
+    pub fn updates_existing(&self) -> bool {
+        self.previous.is_some()
+    }
+
+    /// The properties exif-ai writes whose value would change, as
+    /// `(name, value)` pairs; lists are joined with `"; "`.
+    pub fn changes(&self) -> Vec<(&'static str, String)> {
+        let value = |xmp: &str, name: &str| match name {
+            "dc:title" | "dc:description" | "dc:rights" => reader::xmp_lang_alt(xmp, name),
+            _ => {
+                let items: Vec<String> = reader::xmp_array_items(xmp, name).into_iter().map(|(_, text)| text).collect();
+                (!items.is_empty()).then(|| items.join("; "))
+            }
+        };
+        ["dc:title", "dc:description", "dc:subject", "lr:hierarchicalSubject", "dc:rights", "dc:creator"]
+            .into_iter()
+            .filter_map(|name| {
+                let new = value(&self.xmp, name)?;
+                let old = self.previous.as_deref().and_then(|xmp| value(xmp, name));
+                (old.as_ref() != Some(&new)).then_some((name, new))
+            })
+            .collect()
+    }
+}
+
+/// Render the sidecar XMP [`write_exif`] would write for a sidecar format,
+/// without touching the disk.
+///
+/// Uses the same rules as the writer: fields present in `existing` are left
+/// alone unless [`ExifFields::overwrite_existing`] is set, and an existing
+/// XMP sidecar is updated rather than replaced.
+pub fn preview_sidecar_xmp(path: &Path, ai_result: &AiResult, existing: &ExifData, fields: &ExifFields) -> SidecarPreview {
+    let (sidecar_path, previous) = read_sidecar(path);
+    let xmp = sidecar_document(previous.as_deref(), ai_result, existing, fields);
+    SidecarPreview { exists: sidecar_path.exists(), path: sidecar_path, xmp, previous }
+}
+
+/// Replace `path` with `contents` via a temp file in the same directory and a rename,
+/// so an interrupted write leaves the previous file intact. Every image and
+/// sidecar write goes through here. The new file keeps the permissions of the
+/// one it replaces.
+pub(crate) fn write_atomic(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    write_atomic_with(path, |file| file.write_all(contents))
+}
+
+fn write_atomic_with(
+    path: &Path,
+    write: impl FnOnce(&mut std::fs::File) -> std::io::Result<()>,
+) -> std::io::Result<()> {
+    // Write through a symlink like an in-place write would, instead of
+    // replacing the link with a regular file
+    let resolved;
+    let path = if path.symlink_metadata().is_ok_and(|m| m.file_type().is_symlink()) {
+        resolved = std::fs::canonicalize(path)?;
+        resolved.as_path()
+    } else {
+        path
+    };
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    let tmp_path = path.with_file_name(format!(".{file_name}.{}.tmp", std::process::id()));
+
+    let permissions = std::fs::metadata(path).ok().map(|m| m.permissions());
+    let result = std::fs::File::create(&tmp_path)
+        .and_then(|mut file| {
+            write(&mut file)?;
+            file.sync_all()?;
+            match permissions {
+                Some(permissions) => file.set_permissions(permissions),
+                None => Ok(()),
+            }
+        })
+        .and_then(|()| std::fs::rename(&tmp_path, path));
+    if result.is_err() {
+        let _ = std::fs::remove_file(&tmp_path);
+    }
+    result
+}
+
+/// Find the position of the EXIF APP1 segment in a JPEG.
+/// EXIF segments have marker 0xE1 (APP1) and contents starting with "Exif\0\0".
+fn find_exif_segment_pos(jpeg: &Jpeg) -> Option<usize> {
+    const EXIF_PREFIX: &[u8] = b"Exif\0\0";
+    jpeg.segments().iter().position(|s| {
+        s.marker() == 0xE1 && s.contents().starts_with(EXIF_PREFIX)
+    })
+}
+
+// ============================================================================
 // XMP Metadata Writing
 // ============================================================================
 
 const XMP_HEADER: &[u8] = b"http://ns.adobe.com/xap/1.0/\0";
+const XMP_EXTENSION_HEADER: &[u8] = b"http://ns.adobe.com/xmp/extension/\0";
+const XMP_NS_TIFF: &str = "http://ns.adobe.com/tiff/1.0/";
+const XMP_NS_EXIF: &str = "http://ns.adobe.com/exif/1.0/";
+const XMP_NS_MICROSOFT_PHOTO: &str = "http://ns.microsoft.com/photo/1.0/";
+const XMP_NS_LIGHTROOM: &str = "http://ns.adobe.com/lightroom/1.0/";
 
 /// Find the XMP APP1 segment position in a JPEG.
 fn find_xmp_segment_pos(jpeg: &Jpeg) -> Option<usize> {
@@ -652,12 +1519,14 @@ fn find_xmp_segment_pos(jpeg: &Jpeg) -> Option<usize> {
 
 /// Update or create XMP metadata in the JPEG with AI-generated fields.
 /// Writes dc:title, dc:description, dc:subject for macOS/Linux/Adobe compatibility.
+///
+/// Returns whether the XMP segment was written.
 fn update_xmp_metadata(
     jpeg: &mut Jpeg,
     ai_result: &AiResult,
     existing: &ExifData,
     fields: &ExifFields,
-) {
+) -> bool {
     // Collect what we need to write
     let title = if fields.write_title {
         ai_result.title.as_ref()
@@ -669,13 +1538,13 @@ fn update_xmp_metadata(
             .filter(|_| existing.description.is_none() || fields.overwrite_existing)
     } else { None };
 
-    let keywords: Option<&Vec<String>> = if fields.write_tags {
+    let keywords: Option<&Vec<String>> = if fields.write_tags && fields.keyword_targets.any_xmp() {
         ai_result.tags.as_ref()
             .filter(|_| existing.keywords.is_none() || fields.overwrite_existing)
     } else { None };
 
-    if title.is_none() && description.is_none() && keywords.is_none() {
-        return;
+    if title.is_none() && description.is_none() && keywords.is_none() && Credits::new(fields).is_empty() {
+        return false;
     }
 
     // Try to read existing XMP
@@ -687,7 +1556,16 @@ fn update_xmp_metadata(
     });
 
     // Build the new XMP
-    let new_xmp = build_xmp(existing_xmp.as_deref(), title.map(|s| s.as_str()), description.map(|s| s.as_str()), keywords);
+    let alternatives = LangAlternatives::new(ai_result, fields);
+    let new_xmp = build_xmp(
+        existing_xmp.as_deref(),
+        title.map(|s| s.as_str()),
+        description.map(|s| s.as_str()),
+        keywords,
+        ai_result.hierarchical_tags.as_ref(),
+        &alternatives,
+        fields,
+    );
 
     // Build the segment contents: XMP header + XMP data
     let mut contents = Vec::with_capacity(XMP_HEADER.len() + new_xmp.len());
@@ -707,6 +1585,7 @@ fn update_xmp_metadata(
         let insert_pos = std::cmp::min(insert_pos, segments.len());
         segments.insert(insert_pos, new_segment);
     }
+    true
 }
 
 /// Helper to find EXIF segment position from a segments slice.
@@ -717,18 +1596,106 @@ fn find_exif_segment_pos_from_segments(segments: &[JpegSegment]) -> Option<usize
     })
 }
 
+/// Translated titles/descriptions to emit next to `x-default` in XMP.
+///
+/// Only languages listed in [`ExifFields::languages`] are kept, so the default
+/// output stays a single `x-default` entry. With [`AiResult::language`], the
+/// title and description themselves come first, tagged with that language.
+#[derive(Default)]
+struct LangAlternatives<'a> {
+    titles: Vec<(&'a str, &'a str)>,
+    descriptions: Vec<(&'a str, &'a str)>,
+}
+
+impl<'a> LangAlternatives<'a> {
+    fn new(ai_result: &'a AiResult, fields: &'a ExifFields) -> Self {
+        let primary = ai_result.language.as_deref().filter(|lang| is_valid_lang_tag(lang));
+        let pick = |text: Option<&'a String>, map: Option<&'a std::collections::BTreeMap<String, String>>| {
+            let mut picked: Vec<(&'a str, &'a str)> = primary.zip(text).map(|(lang, t)| (lang, t.as_str())).into_iter().collect();
+            let Some(map) = map else { return picked };
+            picked.extend(fields.languages.iter()
+                .filter(|lang| is_valid_lang_tag(lang))
+                .filter(|lang| !primary.is_some_and(|p| p.eq_ignore_ascii_case(lang)))
+                .filter_map(|lang| {
+                    map.iter()
+                        .find(|(k, v)| k.eq_ignore_ascii_case(lang) && !v.trim().is_empty())
+                        .map(|(_, v)| (lang.as_str(), v.as_str()))
+                }));
+            picked
+        };
+        Self {
+            titles: pick(ai_result.title.as_ref(), ai_result.titles.as_ref()),
+            descriptions: pick(ai_result.description.as_ref(), ai_result.descriptions.as_ref()),
+        }
+    }
+}
+
+/// The configured copyright notice and artist, if `write_copyright` and
+/// `write_artist` are on. [`write_exif`] turns a flag off when the image
+/// already has that value and `overwrite_existing` is `false`.
+#[derive(Clone, Copy, Default)]
+struct Credits<'a> {
+    copyright: Option<&'a str>,
+    artist: Option<&'a str>,
+}
+
+impl<'a> Credits<'a> {
+    fn new(fields: &'a ExifFields) -> Self {
+        let pick = |enabled: bool, value: &'a Option<String>| {
+            value.as_deref().filter(|v| enabled && !v.trim().is_empty())
+        };
+        let defaults = &fields.metadata_defaults;
+        Self {
+            copyright: pick(fields.write_copyright, &defaults.copyright),
+            artist: pick(fields.write_artist, &defaults.artist),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.copyright.is_none() && self.artist.is_none()
+    }
+}
+
+/// RFC 3066-style language tag: alphanumeric subtags separated by `-`.
+pub(crate) fn is_valid_lang_tag(lang: &str) -> bool {
+    !lang.is_empty()
+        && !lang.eq_ignore_ascii_case("x-default")
+        && lang.split('-').all(|part| !part.is_empty() && part.len() <= 8 && part.bytes().all(|b| b.is_ascii_alphanumeric()))
+}
+
+/// Build a `<tag><rdf:Alt>…</rdf:Alt></tag>` line with `x-default` first.
+fn xmp_lang_alt(tag: &str, default: &str, alternatives: &[(&str, &str)]) -> String {
+    let mut out = format!("  <{tag}><rdf:Alt><rdf:li xml:lang=\"x-default\">{}</rdf:li>", xml_escape(default));
+    for (lang, text) in alternatives {
+        out.push_str(&format!("<rdf:li xml:lang=\"{lang}\">{}</rdf:li>", xml_escape(text)));
+    }
+    out.push_str(&format!("</rdf:Alt></{tag}>\n"));
+    out
+}
+
 /// Build XMP XML string, preserving existing XMP content and injecting new fields.
+///
+/// `hierarchy` is written as `lr:hierarchicalSubject` only alongside `keywords`
+/// and when that keyword target is enabled.
 fn build_xmp(
     existing: Option<&str>,
     title: Option<&str>,
     description: Option<&str>,
     keywords: Option<&Vec<String>>,
+    hierarchy: Option<&Vec<String>>,
+    alternatives: &LangAlternatives,
+    fields: &ExifFields,
 ) -> String {
+    let targets = fields.keyword_targets;
+    let hierarchy = hierarchy.filter(|h| keywords.is_some() && targets.lr_hierarchical_subject && !h.is_empty());
+
     // If we have existing XMP, try to inject into it
     if let Some(xmp) = existing {
-        return inject_into_existing_xmp(xmp, title, description, keywords);
+        return inject_into_existing_xmp(xmp, title, description, keywords, hierarchy, alternatives, fields);
     }
 
+    let mirror_exif = fields.xmp_exif_mirror;
+
     // Build fresh XMP
     let mut xmp = String::new();
     xmp.push_str("<?xpacket begin=\"\u{feff}\" id=\"W5M0MpCehiHzreSzNTczkc9d\"?>\n");
@@ -736,26 +1703,53 @@ fn build_xmp(
     xmp.push_str("<rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\">\n");
     xmp.push_str("<rdf:Description rdf:about=\"\"\n");
     xmp.push_str("  xmlns:dc=\"http://purl.org/dc/elements/1.1/\"\n");
+    if mirror_exif {
+        xmp.push_str(&format!("  xmlns:tiff=\"{XMP_NS_TIFF}\"\n"));
+        xmp.push_str(&format!("  xmlns:exif=\"{XMP_NS_EXIF}\"\n"));
+    }
+    if keywords.is_some() && targets.microsoft_photo {
+        xmp.push_str(&format!("  xmlns:MicrosoftPhoto=\"{XMP_NS_MICROSOFT_PHOTO}\"\n"));
+    }
+    if hierarchy.is_some() {
+        xmp.push_str(&format!("  xmlns:lr=\"{XMP_NS_LIGHTROOM}\"\n"));
+    }
     xmp.push_str("  xmlns:photoshop=\"http://ns.adobe.com/photoshop/1.0/\">\n");
 
     if let Some(t) = title {
-        let t_esc = xml_escape(t);
-        xmp.push_str(&format!("  <dc:title><rdf:Alt><rdf:li xml:lang=\"x-default\">{t_esc}</rdf:li></rdf:Alt></dc:title>\n"));
-        xmp.push_str(&format!("  <photoshop:Headline>{t_esc}</photoshop:Headline>\n"));
+        xmp.push_str(&xmp_lang_alt("dc:title", t, &alternatives.titles));
+        xmp.push_str(&format!("  <photoshop:Headline>{}</photoshop:Headline>\n", xml_escape(t)));
+        if mirror_exif {
+            xmp.push_str(&xmp_lang_alt("tiff:ImageDescription", t, &alternatives.titles));
+        }
     }
 
     if let Some(d) = description {
-        let d_esc = xml_escape(d);
-        xmp.push_str(&format!("  <dc:description><rdf:Alt><rdf:li xml:lang=\"x-default\">{d_esc}</rdf:li></rdf:Alt></dc:description>\n"));
-        xmp.push_str(&format!("  <photoshop:CaptionWriter>AI</photoshop:CaptionWriter>\n"));
+        xmp.push_str(&xmp_lang_alt("dc:description", d, &alternatives.descriptions));
+        xmp.push_str("  <photoshop:CaptionWriter>AI</photoshop:CaptionWriter>\n");
+        if mirror_exif {
+            xmp.push_str(&xmp_lang_alt("exif:UserComment", d, &alternatives.descriptions));
+        }
     }
 
     if let Some(kw) = keywords {
-        xmp.push_str("  <dc:subject><rdf:Bag>\n");
-        for k in kw {
-            xmp.push_str(&format!("    <rdf:li>{}</rdf:li>\n", xml_escape(k)));
+        if targets.dc_subject {
+            xmp.push_str(&xmp_bag("dc:subject", kw));
         }
-        xmp.push_str("  </rdf:Bag></dc:subject>\n");
+        if targets.microsoft_photo {
+            xmp.push_str(&xmp_bag("MicrosoftPhoto:LastKeywordXMP", kw));
+        }
+    }
+
+    if let Some(paths) = hierarchy {
+        xmp.push_str(&xmp_bag("lr:hierarchicalSubject", paths));
+    }
+
+    for (_, element) in xmp_credit_elements(Credits::new(fields)) {
+        xmp.push_str(&element);
+    }
+
+    for (tag, value) in xmp_workflow_properties(&fields.metadata_defaults) {
+        xmp.push_str(&format!("  <{tag}>{}</{tag}>\n", xml_escape(&value)));
     }
 
     xmp.push_str("</rdf:Description>\n");
@@ -771,83 +1765,113 @@ fn inject_into_existing_xmp(
     title: Option<&str>,
     description: Option<&str>,
     keywords: Option<&Vec<String>>,
+    hierarchy: Option<&Vec<String>>,
+    alternatives: &LangAlternatives,
+    fields: &ExifFields,
 ) -> String {
+    let mirror_exif = fields.xmp_exif_mirror;
+    let targets = fields.keyword_targets;
     let mut result = xmp.to_string();
 
     // Ensure dc namespace is declared
-    if !result.contains("xmlns:dc=") {
-        if let Some(pos) = result.find("rdf:about=\"\"") {
-            let insert_at = pos + "rdf:about=\"\"".len();
-            result.insert_str(insert_at, "\n  xmlns:dc=\"http://purl.org/dc/elements/1.1/\"");
-        }
+    if !result.contains("xmlns:dc=")
+        && let Some(pos) = result.find("rdf:about=\"\"")
+    {
+        let insert_at = pos + "rdf:about=\"\"".len();
+        result.insert_str(insert_at, "\n  xmlns:dc=\"http://purl.org/dc/elements/1.1/\"");
     }
 
     // Ensure photoshop namespace is declared
-    if !result.contains("xmlns:photoshop=") {
-        if let Some(pos) = result.find("rdf:about=\"\"") {
+    if !result.contains("xmlns:photoshop=")
+        && let Some(pos) = result.find("rdf:about=\"\"")
+    {
+        let insert_at = pos + "rdf:about=\"\"".len();
+        result.insert_str(insert_at, "\n  xmlns:photoshop=\"http://ns.adobe.com/photoshop/1.0/\"");
+    }
+
+    // Ensure tiff/exif namespaces are declared for the mirrors, and
+    // MicrosoftPhoto for its keyword list
+    let mut namespaces = Vec::new();
+    if mirror_exif {
+        namespaces.extend([("tiff", XMP_NS_TIFF), ("exif", XMP_NS_EXIF)]);
+    }
+    if keywords.is_some() && targets.microsoft_photo {
+        namespaces.push(("MicrosoftPhoto", XMP_NS_MICROSOFT_PHOTO));
+    }
+    if hierarchy.is_some() {
+        namespaces.push(("lr", XMP_NS_LIGHTROOM));
+    }
+    for (prefix, ns) in namespaces {
+        if !result.contains(&format!("xmlns:{prefix}="))
+            && let Some(pos) = result.find("rdf:about=\"\"")
+        {
             let insert_at = pos + "rdf:about=\"\"".len();
-            result.insert_str(insert_at, "\n  xmlns:photoshop=\"http://ns.adobe.com/photoshop/1.0/\"");
+            result.insert_str(insert_at, &format!("\n  xmlns:{prefix}=\"{ns}\""));
         }
     }
 
-    // Find insertion point: before </rdf:Description>
-    let insert_before = result.find("</rdf:Description>")
-        .or_else(|| result.find("/>").and_then(|p| {
-            // Check if this is the self-closing rdf:Description
-            if result[..p].rfind('<').map(|s| result[s..].starts_with("<rdf:Description")).unwrap_or(false) {
-                // Convert self-closing to open/close
-                None
-            } else {
-                None
-            }
-        }));
-
     // Handle self-closing rdf:Description: convert to open/close
-    if insert_before.is_none() {
-        // Find the self-closing rdf:Description
-        if let Some(desc_start) = result.find("<rdf:Description") {
-            if let Some(close_pos) = result[desc_start..].find("/>") {
-                let abs_close = desc_start + close_pos;
-                result.replace_range(abs_close..abs_close + 2, ">");
-                // Find </rdf:RDF> and insert </rdf:Description> before it
-                if let Some(rdf_end) = result.find("</rdf:RDF>") {
-                    result.insert_str(rdf_end, "</rdf:Description>\n");
-                }
-            }
-        }
+    if let Some((_, start_end)) = find_start_tag(&result, "rdf:Description", 0)
+        && result[..start_end].ends_with("/>")
+    {
+        result.replace_range(start_end - 2..start_end, ">\n</rdf:Description>");
     }
 
-    // Now find the insertion point
-    if result.find("</rdf:Description>").is_some() {
+    // Insert before the end of the top-level rdf:Description. Nested ones
+    // (e.g. mwg-rs:Regions structs) close earlier and must not be used.
+    if description_end(&result).is_some() {
         let mut new_elements = String::new();
 
         if let Some(t) = title {
-            let t_esc = xml_escape(t);
             // Remove existing dc:title if present
             remove_xml_element(&mut result, "dc:title");
-            new_elements.push_str(&format!("  <dc:title><rdf:Alt><rdf:li xml:lang=\"x-default\">{t_esc}</rdf:li></rdf:Alt></dc:title>\n"));
+            new_elements.push_str(&xmp_lang_alt("dc:title", t, &alternatives.titles));
             // Also set photoshop:Headline
             remove_xml_element(&mut result, "photoshop:Headline");
-            new_elements.push_str(&format!("  <photoshop:Headline>{t_esc}</photoshop:Headline>\n"));
+            new_elements.push_str(&format!("  <photoshop:Headline>{}</photoshop:Headline>\n", xml_escape(t)));
+            if mirror_exif {
+                remove_xml_element(&mut result, "tiff:ImageDescription");
+                new_elements.push_str(&xmp_lang_alt("tiff:ImageDescription", t, &alternatives.titles));
+            }
         }
 
         if let Some(d) = description {
-            let d_esc = xml_escape(d);
             remove_xml_element(&mut result, "dc:description");
-            new_elements.push_str(&format!("  <dc:description><rdf:Alt><rdf:li xml:lang=\"x-default\">{d_esc}</rdf:li></rdf:Alt></dc:description>\n"));
+            new_elements.push_str(&xmp_lang_alt("dc:description", d, &alternatives.descriptions));
+            if mirror_exif {
+                remove_xml_element(&mut result, "exif:UserComment");
+                new_elements.push_str(&xmp_lang_alt("exif:UserComment", d, &alternatives.descriptions));
+            }
         }
 
         if let Some(kw) = keywords {
-            remove_xml_element(&mut result, "dc:subject");
-            new_elements.push_str("  <dc:subject><rdf:Bag>\n");
-            for k in kw {
-                new_elements.push_str(&format!("    <rdf:li>{}</rdf:li>\n", xml_escape(k)));
+            if targets.dc_subject {
+                remove_xml_element(&mut result, "dc:subject");
+                new_elements.push_str(&xmp_bag("dc:subject", kw));
+            }
+            if targets.microsoft_photo {
+                remove_xml_element(&mut result, "MicrosoftPhoto:LastKeywordXMP");
+                new_elements.push_str(&xmp_bag("MicrosoftPhoto:LastKeywordXMP", kw));
             }
-            new_elements.push_str("  </rdf:Bag></dc:subject>\n");
+        }
+
+        if let Some(paths) = hierarchy {
+            remove_xml_element(&mut result, "lr:hierarchicalSubject");
+            new_elements.push_str(&xmp_bag("lr:hierarchicalSubject", paths));
+        }
+
+        for (tag, element) in xmp_credit_elements(Credits::new(fields)) {
+            remove_xml_element(&mut result, tag);
+            new_elements.push_str(&element);
+        }
+
+        for (tag, value) in xmp_workflow_properties(&fields.metadata_defaults) {
+            remove_xml_element(&mut result, tag);
+            new_elements.push_str(&format!("  <{tag}>{}</{tag}>\n", xml_escape(&value)));
         }
 
         // Re-find position after removals
-        if let Some(pos) = result.find("</rdf:Description>") {
+        if let Some(pos) = description_end(&result) {
             result.insert_str(pos, &new_elements);
         }
     }
@@ -855,21 +1879,107 @@ fn inject_into_existing_xmp(
     result
 }
 
+/// `dc:rights` and `dc:creator` elements for the configured credits, keyed by tag.
+fn xmp_credit_elements(credits: Credits) -> Vec<(&'static str, String)> {
+    let mut elements = Vec::new();
+    if let Some(copyright) = credits.copyright {
+        elements.push(("dc:rights", xmp_lang_alt("dc:rights", copyright, &[])));
+    }
+    if let Some(artist) = credits.artist {
+        let creator = format!("  <dc:creator><rdf:Seq><rdf:li>{}</rdf:li></rdf:Seq></dc:creator>\n", xml_escape(artist));
+        elements.push(("dc:creator", creator));
+    }
+    elements
+}
+
+/// The configured `photoshop:Instructions` and `photoshop:Urgency` values.
+fn xmp_workflow_properties(defaults: &MetadataDefaults) -> Vec<(&'static str, String)> {
+    let mut properties = Vec::new();
+    if let Some(instructions) = &defaults.instructions {
+        properties.push(("photoshop:Instructions", instructions.clone()));
+    }
+    if let Some(urgency) = defaults.urgency {
+        properties.push(("photoshop:Urgency", urgency.to_string()));
+    }
+    properties
+}
+
+/// Build a `<tag><rdf:Bag>…</rdf:Bag></tag>` block, one `rdf:li` per item.
+fn xmp_bag(tag: &str, items: &[String]) -> String {
+    let mut out = format!("  <{tag}><rdf:Bag>\n");
+    for item in items {
+        out.push_str(&format!("    <rdf:li>{}</rdf:li>\n", xml_escape(item)));
+    }
+    out.push_str(&format!("  </rdf:Bag></{tag}>\n"));
+    out
+}
+
+/// Find the first `<tag …>` start tag at or after `from`, matching the whole
+/// element name (so `dc:title` does not match `dc:titleAlt`).
+///
+/// Returns the offsets of its `<` and of the byte just past its `>`.
+fn find_start_tag(xml: &str, tag: &str, from: usize) -> Option<(usize, usize)> {
+    let open = format!("<{tag}");
+    let mut search = from;
+    while let Some(rel) = xml[search..].find(&open) {
+        let start = search + rel;
+        let after = start + open.len();
+        match xml.as_bytes().get(after) {
+            Some(b'>' | b'/' | b' ' | b'\t' | b'\r' | b'\n') => {
+                let end = after + xml[after..].find('>')? + 1;
+                return Some((start, end));
+            }
+            _ => search = after,
+        }
+    }
+    None
+}
+
+/// Offset of the `</rdf:Description>` that closes the first top-level
+/// `rdf:Description`, skipping any nested descriptions inside it.
+fn description_end(xml: &str) -> Option<usize> {
+    const CLOSE: &str = "</rdf:Description>";
+    let (_, mut pos) = find_start_tag(xml, "rdf:Description", 0)?;
+    let mut depth = 0usize;
+    loop {
+        let close = pos + xml[pos..].find(CLOSE)?;
+        match find_start_tag(xml, "rdf:Description", pos) {
+            Some((start, end)) if start < close => {
+                if !xml[..end].ends_with("/>") {
+                    depth += 1;
+                }
+                pos = end;
+            }
+            _ if depth == 0 => return Some(close),
+            _ => {
+                depth -= 1;
+                pos = close + CLOSE.len();
+            }
+        }
+    }
+}
+
 /// Remove an XML element and its contents from a string.
+///
+/// Only the element named exactly `tag` is removed (self-closing or with
+/// content); everything around it is left as-is.
 fn remove_xml_element(xml: &mut String, tag: &str) {
-    let open = format!("<{tag}");
     let close = format!("</{tag}>");
-    if let Some(start) = xml.find(&open) {
-        if let Some(end) = xml[start..].find(&close) {
-            let end_abs = start + end + close.len();
-            // Also remove trailing newline if present
-            let end_abs = if xml.as_bytes().get(end_abs) == Some(&b'\n') {
-                end_abs + 1
-            } else {
-                end_abs
-            };
-            xml.replace_range(start..end_abs, "");
-        }
+    if let Some((start, start_end)) = find_start_tag(xml, tag, 0) {
+        let end_abs = if xml[..start_end].ends_with("/>") {
+            start_end
+        } else if let Some(end) = xml[start_end..].find(&close) {
+            start_end + end + close.len()
+        } else {
+            return;
+        };
+        // Also remove trailing newline if present
+        let end_abs = if xml.as_bytes().get(end_abs) == Some(&b'\n') {
+            end_abs + 1
+        } else {
+            end_abs
+        };
+        xml.replace_range(start..end_abs, "");
     }
 }
 
@@ -888,15 +1998,29 @@ fn xml_escape(s: &str) -> String {
 
 const IPTC_HEADER: &[u8] = b"Photoshop 3.0\0";
 const IPTC_8BIM: &[u8] = b"8BIM";
+/// Maximum byte lengths of the IPTC-IIM records we write.
+const IPTC_OBJECT_NAME_MAX: usize = 64;
+const IPTC_KEYWORD_MAX: usize = 64;
+const IPTC_CAPTION_MAX: usize = 2000;
+const IPTC_INSTRUCTIONS_MAX: usize = 256;
+const IPTC_BYLINE_MAX: usize = 32;
+const IPTC_COPYRIGHT_MAX: usize = 128;
 
 /// Update or create IPTC metadata in the JPEG.
 /// Writes IPTC caption (2:120) and keywords (2:25) for broad tool compatibility.
+/// With `write_iptc_dates`, DateCreated (2:55) and TimeCreated (2:60) are added
+/// from `DateTimeOriginal`; they never trigger a rewrite on their own. The same
+/// holds for Urgency (2:10) and Special Instructions (2:40) from
+/// `metadata_defaults`. By-line (2:80) and Copyright Notice (2:116) are
+/// written on every run when `write_artist`/`write_copyright` are on.
+///
+/// Returns whether the IPTC segment was written.
 fn update_iptc_metadata(
     jpeg: &mut Jpeg,
     ai_result: &AiResult,
     existing: &ExifData,
     fields: &ExifFields,
-) {
+) -> bool {
     let title = if fields.write_title {
         ai_result.title.as_ref()
             .filter(|_| existing.title.is_none() || fields.overwrite_existing)
@@ -907,13 +2031,14 @@ fn update_iptc_metadata(
             .filter(|_| existing.description.is_none() || fields.overwrite_existing)
     } else { None };
 
-    let keywords: Option<&Vec<String>> = if fields.write_tags {
+    let keywords: Option<&Vec<String>> = if fields.write_tags && fields.keyword_targets.iptc {
         ai_result.tags.as_ref()
             .filter(|_| existing.keywords.is_none() || fields.overwrite_existing)
     } else { None };
 
-    if title.is_none() && description.is_none() && keywords.is_none() {
-        return;
+    let credits = Credits::new(fields);
+    if title.is_none() && description.is_none() && keywords.is_none() && credits.is_empty() {
+        return false;
     }
 
     // Find existing APP13 segment
@@ -926,12 +2051,19 @@ fn update_iptc_metadata(
         jpeg.segments()[pos].contents().to_vec()
     });
 
+    let created = if fields.write_iptc_dates {
+        existing.date_time_original.as_deref().and_then(iptc_date_time)
+    } else { None };
+
     // Build new IPTC APP13 contents
     let new_contents = build_iptc_contents(
         existing_iptc.as_deref(),
         title.map(|s| s.as_str()),
         description.map(|s| s.as_str()),
         keywords,
+        created.as_ref(),
+        &fields.metadata_defaults,
+        credits,
     );
 
     let new_segment = JpegSegment::new_with_contents(0xED, Bytes::from(new_contents));
@@ -944,6 +2076,7 @@ fn update_iptc_metadata(
         let insert_pos = segments.len().min(4);
         segments.insert(insert_pos, new_segment);
     }
+    true
 }
 
 /// Build IPTC APP13 segment contents.
@@ -953,6 +2086,9 @@ fn build_iptc_contents(
     title: Option<&str>,
     description: Option<&str>,
     keywords: Option<&Vec<String>>,
+    created: Option<&(String, String)>,
+    defaults: &MetadataDefaults,
+    credits: Credits,
 ) -> Vec<u8> {
     let mut result = Vec::new();
     result.extend_from_slice(IPTC_HEADER);
@@ -967,7 +2103,7 @@ fn build_iptc_contents(
             let resource_id = u16::from_be_bytes([data[pos + 4], data[pos + 5]]);
             // Skip pascal string (1 byte length + string + padding to even)
             let pascal_len = data[pos + 6] as usize;
-            let pascal_padded = if (pascal_len + 1) % 2 == 0 { pascal_len + 1 } else { pascal_len + 2 };
+            let pascal_padded = if (pascal_len + 1).is_multiple_of(2) { pascal_len + 1 } else { pascal_len + 2 };
             let data_start = pos + 6 + pascal_padded;
             if data_start + 4 > data.len() { break; }
             let data_len = u32::from_be_bytes([
@@ -975,7 +2111,7 @@ fn build_iptc_contents(
                 data[data_start + 2], data[data_start + 3],
             ]) as usize;
             let resource_end = data_start + 4 + data_len;
-            let resource_end_padded = if data_len % 2 == 0 { resource_end } else { resource_end + 1 };
+            let resource_end_padded = if data_len.is_multiple_of(2) { resource_end } else { resource_end + 1 };
 
             if resource_id != 0x0404 {
                 // Preserve this resource
@@ -995,31 +2131,45 @@ fn build_iptc_contents(
 
     // Object Name / Title (2:5)
     if let Some(t) = title {
-        let bytes = t.as_bytes();
-        let len = bytes.len().min(64) as u16;
-        iptc_data.extend_from_slice(&[0x1C, 0x02, 0x05]);
-        iptc_data.extend_from_slice(&len.to_be_bytes());
-        iptc_data.extend_from_slice(&bytes[..len as usize]);
+        push_iptc_record(&mut iptc_data, 0x05, truncate_utf8(t, IPTC_OBJECT_NAME_MAX));
+    }
+
+    // Urgency (2:10) — a single digit
+    if let Some(urgency) = defaults.urgency {
+        push_iptc_record(&mut iptc_data, 0x0A, &urgency.to_string());
     }
 
     // Keywords (2:25) — one record per keyword
     if let Some(kw) = keywords {
         for k in kw {
-            let bytes = k.as_bytes();
-            let len = bytes.len().min(64) as u16;
-            iptc_data.extend_from_slice(&[0x1C, 0x02, 0x19]);
-            iptc_data.extend_from_slice(&len.to_be_bytes());
-            iptc_data.extend_from_slice(&bytes[..len as usize]);
+            push_iptc_record(&mut iptc_data, 0x19, truncate_utf8(k, IPTC_KEYWORD_MAX));
         }
     }
 
+    // Special Instructions (2:40)
+    if let Some(instructions) = &defaults.instructions {
+        push_iptc_record(&mut iptc_data, 0x28, truncate_utf8(instructions, IPTC_INSTRUCTIONS_MAX));
+    }
+
+    // Date Created (2:55) and Time Created (2:60)
+    if let Some((date, time)) = created {
+        push_iptc_record(&mut iptc_data, 0x37, date);
+        push_iptc_record(&mut iptc_data, 0x3C, time);
+    }
+
+    // By-line (2:80)
+    if let Some(artist) = credits.artist {
+        push_iptc_record(&mut iptc_data, 0x50, truncate_utf8(artist, IPTC_BYLINE_MAX));
+    }
+
+    // Copyright Notice (2:116)
+    if let Some(copyright) = credits.copyright {
+        push_iptc_record(&mut iptc_data, 0x74, truncate_utf8(copyright, IPTC_COPYRIGHT_MAX));
+    }
+
     // Caption/Abstract (2:120)
     if let Some(d) = description {
-        let bytes = d.as_bytes();
-        let len = bytes.len().min(2000) as u16;
-        iptc_data.extend_from_slice(&[0x1C, 0x02, 0x78]);
-        iptc_data.extend_from_slice(&len.to_be_bytes());
-        iptc_data.extend_from_slice(&bytes[..len as usize]);
+        push_iptc_record(&mut iptc_data, 0x78, truncate_utf8(d, IPTC_CAPTION_MAX));
     }
 
     // Write the IPTC-IIM as 8BIM resource 0x0404
@@ -1039,6 +2189,135 @@ fn build_iptc_contents(
     result
 }
 
+/// Append an IPTC-IIM application record (2:`dataset`) to `buf`.
+fn push_iptc_record(buf: &mut Vec<u8>, dataset: u8, value: &str) {
+    buf.extend_from_slice(&[0x1C, 0x02, dataset]);
+    buf.extend_from_slice(&(value.len() as u16).to_be_bytes());
+    buf.extend_from_slice(value.as_bytes());
+}
+
+/// Shorten `s` to at most `max_bytes` bytes without splitting a character.
+///
+/// The cut backs off over combining marks, variation selectors and ZWJ so an
+/// accented letter or emoji sequence is not left half-written, and prefers the
+/// last whitespace in the second half of the text so words stay whole.
+fn truncate_utf8(s: &str, max_bytes: usize) -> &str {
+    if s.len() <= max_bytes {
+        return s;
+    }
+
+    let mut end = max_bytes;
+    while !s.is_char_boundary(end) {
+        end -= 1;
+    }
+    while end > 0 && s[end..].chars().next().is_some_and(is_grapheme_extend) {
+        end -= s[..end].chars().next_back().map_or(0, char::len_utf8);
+    }
+
+    let cut = s[..end].trim_end_matches('\u{200D}');
+    let at_word_break = s[end..].starts_with(char::is_whitespace);
+    match cut.rfind(char::is_whitespace) {
+        Some(pos) if !at_word_break && pos >= max_bytes / 2 => cut[..pos].trim_end(),
+        _ => cut.trim_end(),
+    }
+}
+
+/// Characters that attach to the preceding one and must not start a cut.
+fn is_grapheme_extend(c: char) -> bool {
+    matches!(c as u32,
+        0x0300..=0x036F   // combining diacritical marks
+        | 0x1AB0..=0x1AFF // combining diacritical marks extended
+        | 0x1DC0..=0x1DFF // combining diacritical marks supplement
+        | 0x200D          // zero width joiner
+        | 0x20D0..=0x20FF // combining marks for symbols
+        | 0xFE00..=0xFE0F // variation selectors
+        | 0xFE20..=0xFE2F // combining half marks
+        | 0x1F3FB..=0x1F3FF // emoji skin tone modifiers
+    )
+}
+
+/// Convert a capture timestamp into IPTC DateCreated (`CCYYMMDD`) and
+/// TimeCreated (`HHMMSS±HHMM`) values.
+///
+/// Accepts what [`read_exif`](super::read_exif) produces — `2003-12-14 12:01:44`
+/// or `2021-07-29T21:28:46+03:00` — as well as raw EXIF `2003:12:14 12:01:44`.
+/// Fractional seconds are dropped; a missing offset is written as `+0000`.
+fn iptc_date_time(date_time: &str) -> Option<(String, String)> {
+    let s = date_time.trim();
+    if !s.is_ascii() || s.len() < 19 || !matches!(s.as_bytes()[10], b' ' | b'T') {
+        return None;
+    }
+    let digits = |part: &str, seps: &[usize]| -> Option<String> {
+        let b = part.as_bytes();
+        let ok = b.iter().enumerate().all(|(i, c)| {
+            if seps.contains(&i) { matches!(c, b'-' | b':') } else { c.is_ascii_digit() }
+        });
+        ok.then(|| part.chars().filter(char::is_ascii_digit).collect())
+    };
+    let date = digits(&s[..10], &[4, 7])?;
+    let time = digits(&s[11..19], &[2, 5])?;
+
+    let rest = s[19..].trim_start_matches(|c: char| c == '.' || c.is_ascii_digit());
+    let offset = match rest {
+        "" | "Z" => "+0000".to_string(),
+        _ => {
+            let (sign, hm) = rest.split_at(1);
+            let hm = hm.replace(':', "");
+            if !matches!(sign, "+" | "-") || hm.len() != 4 || !hm.bytes().all(|c| c.is_ascii_digit()) {
+                return None;
+            }
+            format!("{sign}{hm}")
+        }
+    };
+
+    Some((date, format!("{time}{offset}")))
+}
+
+/// Convert an ISO 8601 date from the AI (`1969-07-21`, `1969-07-21T20:17`,
+/// `1969-07-21T20:17:40Z`) into the EXIF `YYYY:MM:DD HH:MM:SS` format.
+///
+/// A missing time is written as midnight. Fractional seconds and UTC offsets
+/// are dropped, since EXIF dates are local time. Partial dates (`1969`,
+/// `1969-07`) and out-of-range values are rejected.
+fn exif_date_time(date: &str) -> Option<String> {
+    let s = date.trim();
+    if !s.is_ascii() || s.len() < 10 {
+        return None;
+    }
+    let number = |part: &str, max: u32| -> Option<u32> {
+        if part.is_empty() || !part.bytes().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        part.parse().ok().filter(|&n| n <= max)
+    };
+
+    let (date, time) = s.split_at(10);
+    let b = date.as_bytes();
+    if b[4] != b'-' || b[7] != b'-' {
+        return None;
+    }
+    let year = number(&date[..4], 9999)?;
+    let month = number(&date[5..7], 12).filter(|&m| m >= 1)?;
+    let day = number(&date[8..10], 31).filter(|&d| d >= 1)?;
+
+    let (mut hour, mut minute, mut second) = (0, 0, 0);
+    if !time.is_empty() {
+        let time = time.strip_prefix(['T', ' '])?;
+        let end = time.find(['Z', '+', '-', '.']).unwrap_or(time.len());
+        let mut parts = time[..end].split(':');
+        hour = number(parts.next()?, 23)?;
+        minute = number(parts.next()?, 59)?;
+        if let Some(sec) = parts.next() {
+            second = number(sec, 60)?;
+        }
+        if parts.next().is_some() {
+            return None;
+        }
+    }
+
+    Some(format!("{year:04}:{month:02}:{day:02} {hour:02}:{minute:02}:{second:02}"))
+}
+
 /// A raw IFD entry to inject into a TIFF, built in the correct endianness.
 struct RawIfdEntry {
     tag_id: u16,
@@ -1096,6 +2375,9 @@ fn make_user_comment_entry(tag_id: u16, value: &str) -> RawIfdEntry {
     }
 }
 
+/// A little-endian TIFF header with an empty IFD0, for images without EXIF.
+const EMPTY_TIFF: [u8; 14] = [b'I', b'I', 42, 0, 8, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+
 /// Inject AI-generated tags directly into the original raw TIFF data,
 /// building IFD entries in the correct endianness.
 /// Writes IFD0 tags (ImageDescription, XP*) and ExifIFD tags (UserComment)
@@ -1120,50 +2402,61 @@ fn inject_ai_tags_into_tiff(
     let mut ifd0_entries: Vec<RawIfdEntry> = Vec::new();
     let mut exif_ifd_entries: Vec<RawIfdEntry> = Vec::new();
 
-    if fields.write_title {
-        if let Some(ref title) = ai_result.title {
-            if existing.title.is_none() || fields.overwrite_existing {
-                ifd0_entries.push(make_string_entry(0x010E, title, big_endian)); // ImageDescription
-                ifd0_entries.push(make_xp_entry(TAG_XP_TITLE, title));
-            }
-        }
+    if fields.write_title
+        && let Some(ref title) = ai_result.title
+        && (existing.title.is_none() || fields.overwrite_existing)
+    {
+        ifd0_entries.push(make_string_entry(0x010E, title, big_endian)); // ImageDescription
+        ifd0_entries.push(make_xp_entry(TAG_XP_TITLE, title));
     }
 
-    if fields.write_description {
-        if let Some(ref desc) = ai_result.description {
-            if existing.description.is_none() || fields.overwrite_existing {
-                exif_ifd_entries.push(make_user_comment_entry(0x9286, desc)); // UserComment → ExifIFD
-                ifd0_entries.push(make_xp_entry(TAG_XP_COMMENT, desc));
-            }
-        }
+    if fields.write_description
+        && let Some(ref desc) = ai_result.description
+        && (existing.description.is_none() || fields.overwrite_existing)
+    {
+        exif_ifd_entries.push(make_user_comment_entry(0x9286, desc)); // UserComment → ExifIFD
+        ifd0_entries.push(make_xp_entry(TAG_XP_COMMENT, desc));
     }
 
-    if fields.write_tags {
-        if let Some(ref tags) = ai_result.tags {
-            if existing.keywords.is_none() || fields.overwrite_existing {
-                let kw = tags.join("; ");
-                ifd0_entries.push(make_xp_entry(TAG_XP_KEYWORDS, &kw));
-            }
-        }
+    if fields.write_tags && fields.keyword_targets.xp_keywords
+        && let Some(ref tags) = ai_result.tags
+        && (existing.keywords.is_none() || fields.overwrite_existing)
+    {
+        let kw = tags.join("; ");
+        ifd0_entries.push(make_xp_entry(TAG_XP_KEYWORDS, &kw));
     }
 
-    if fields.write_subject {
-        if let Some(ref subjects) = ai_result.subject {
-            if !subjects.is_empty() && (existing.subject.is_none() || fields.overwrite_existing) {
-                let subj = subjects.join("; ");
-                ifd0_entries.push(make_xp_entry(TAG_XP_SUBJECT, &subj));
-            }
-        }
+    if fields.write_subject
+        && let Some(ref subjects) = ai_result.subject
+        && !subjects.is_empty() && (existing.subject.is_none() || fields.overwrite_existing)
+    {
+        let subj = subjects.join("; ");
+        ifd0_entries.push(make_xp_entry(TAG_XP_SUBJECT, &subj));
+    }
+
+    if fields.write_date
+        && existing.date_time.is_none()
+        && let Some(date) = ai_result.date_taken.as_deref().and_then(exif_date_time)
+    {
+        exif_ifd_entries.push(make_string_entry(TAG_DATE_TIME_ORIGINAL, &date, big_endian));
+        exif_ifd_entries.push(make_string_entry(TAG_CREATE_DATE, &date, big_endian));
+    }
+
+    let credits = Credits::new(fields);
+    if let Some(copyright) = credits.copyright {
+        ifd0_entries.push(make_string_entry(TAG_COPYRIGHT, copyright, big_endian));
+    }
+    if let Some(artist) = credits.artist {
+        ifd0_entries.push(make_string_entry(TAG_ARTIST, artist, big_endian));
     }
 
     // Build GPS IFD entries for new GPS coordinates
     let mut gps_ifd_entries: Vec<RawIfdEntry> = Vec::new();
-    if fields.write_gps {
-        if let Some(ref gps) = ai_result.gps {
-            if !existing.has_gps {
-                gps_ifd_entries.extend(make_raw_gps_entries(gps));
-            }
-        }
+    if fields.write_gps
+        && let Some(ref gps) = ai_result.gps
+        && !existing.has_gps
+    {
+        gps_ifd_entries.extend(make_raw_gps_entries(gps, big_endian));
     }
 
     if ifd0_entries.is_empty() && exif_ifd_entries.is_empty() && gps_ifd_entries.is_empty() {
@@ -1269,7 +2562,8 @@ fn inject_ai_tags_into_tiff(
     let mut result = original.to_vec();
 
     // === Rebuild ExifIFD at the end (if we have ExifIFD entries to add) ===
-    let new_exif_ifd_start: Option<u32> = if !exif_ifd_entries.is_empty() && exif_ifd_offset.is_some() {
+    // Images without an ExifIFD (common for scans) get a new one.
+    let new_exif_ifd_start: Option<u32> = if !exif_ifd_entries.is_empty() {
         let exif_append_count = exif_ifd_entries.iter()
             .filter(|e| !exif_tag_ids.contains(&e.tag_id)).count();
         let total = exif_count + exif_append_count;
@@ -1331,6 +2625,11 @@ fn inject_ai_tags_into_tiff(
         None
     };
 
+    // Keep tags the original GPS IFD already has (e.g. its GPSVersionID)
+    gps_ifd_entries.retain(|entry| {
+        !(0..gps_count).any(|i| read_u16(original, gps_start + i * 12) == entry.tag_id)
+    });
+
     // === Rebuild GPS IFD at the end ===
     let new_gps_ifd_start: Option<u32> = if !gps_ifd_entries.is_empty() || gps_ifd_offset.is_some() {
         let start = result.len() as u32;
@@ -1389,11 +2688,14 @@ fn inject_ai_tags_into_tiff(
 
     // If we're writing new GPS and IFD0 doesn't have a GPS pointer yet, we need to add one
     let need_gps_pointer = new_gps_ifd_start.is_some() && !ifd0_tag_ids.contains(&0x8825);
+    // Likewise for a newly created ExifIFD
+    let need_exif_pointer = new_exif_ifd_start.is_some() && exif_ifd_offset.is_none();
 
     // === Rebuild IFD0 at the end ===
     let ifd0_append_count = ifd0_entries.iter()
         .filter(|e| !ifd0_tag_ids.contains(&e.tag_id)).count()
-        + if need_gps_pointer { 1 } else { 0 };
+        + if need_gps_pointer { 1 } else { 0 }
+        + if need_exif_pointer { 1 } else { 0 };
     let ifd0_total = ifd0_count + ifd0_append_count;
 
     let new_ifd0_start = result.len() as u32;
@@ -1458,6 +2760,18 @@ fn inject_ai_tags_into_tiff(
         // Offset will be filled in below when we update GPS IFD pointer
         ib[8..12].copy_from_slice(&encode_u32(0));
         result[off..off + 12].copy_from_slice(&ib);
+        slot += 1;
+    }
+
+    // Same for a new ExifIFD pointer, filled in with the ExifIFD offset below
+    if need_exif_pointer {
+        let off = ifd0_append_start + slot * 12;
+        let mut ib = [0u8; 12];
+        ib[0..2].copy_from_slice(&encode_u16(0x8769)); // ExifIFD tag
+        ib[2..4].copy_from_slice(&encode_u16(4));      // LONG format
+        ib[4..8].copy_from_slice(&encode_u32(1));      // 1 component
+        ib[8..12].copy_from_slice(&encode_u32(0));
+        result[off..off + 12].copy_from_slice(&ib);
     }
 
     // If we rebuilt ExifIFD, update the ExifIFD pointer in the new IFD0
@@ -1510,9 +2824,17 @@ fn inject_ai_tags_into_tiff(
     Ok(result)
 }
 
-/// Build raw IFD entries for GPS coordinates (for inject_ai_tags_into_tiff).
-fn make_raw_gps_entries(gps: &GpsCoords) -> Vec<RawIfdEntry> {
+/// Build raw IFD entries for GPS coordinates (for inject_ai_tags_into_tiff),
+/// with rationals in the TIFF's byte order.
+fn make_raw_gps_entries(gps: &GpsCoords, big_endian: bool) -> Vec<RawIfdEntry> {
     let mut entries = Vec::new();
+    // The encoders produce little-endian u32 words
+    let file_order = |mut bytes: Vec<u8>| {
+        if big_endian {
+            bytes.chunks_exact_mut(4).for_each(|word| word.reverse());
+        }
+        bytes
+    };
 
     let lat = gps.latitude;
     let lon = gps.longitude;
@@ -1520,16 +2842,14 @@ fn make_raw_gps_entries(gps: &GpsCoords) -> Vec<RawIfdEntry> {
     let lat_ref = if lat >= 0.0 { "N" } else { "S" };
     let lon_ref = if lon >= 0.0 { "E" } else { "W" };
 
-    let lat_abs = lat.abs();
-    let lon_abs = lon.abs();
-
-    let lat_deg = lat_abs.floor() as u32;
-    let lat_min = ((lat_abs - lat_deg as f64) * 60.0).floor() as u32;
-    let lat_sec = ((lat_abs - lat_deg as f64 - lat_min as f64 / 60.0) * 3600.0 * 10000.0) as u32;
-
-    let lon_deg = lon_abs.floor() as u32;
-    let lon_min = ((lon_abs - lon_deg as f64) * 60.0).floor() as u32;
-    let lon_sec = ((lon_abs - lon_deg as f64 - lon_min as f64 / 60.0) * 3600.0 * 10000.0) as u32;
+    // GPSVersionID (tag 0x0000, BYTE, 4 bytes: 2.3.0.0)
+    entries.push(RawIfdEntry {
+        tag_id: TAG_GPS_VERSION_ID,
+        data_format: 1, // BYTE
+        count: 4,
+        inline_value: GPS_VERSION,
+        extra_data: None,
+    });
 
     // GPSLatitudeRef (tag 0x0001, ASCII, 2 bytes: "N\0" or "S\0")
     let lat_ref_data = format!("{lat_ref}\0");
@@ -1552,7 +2872,7 @@ fn make_raw_gps_entries(gps: &GpsCoords) -> Vec<RawIfdEntry> {
         data_format: 5, // RATIONAL (unsigned)
         count: 3,
         inline_value: [0u8; 4],
-        extra_data: Some(encode_gps_rational(lat_deg, lat_min, lat_sec, 10000)),
+        extra_data: Some(file_order(encode_gps_rational(lat))),
     });
 
     // GPSLongitudeRef (tag 0x0003, ASCII, 2 bytes)
@@ -1576,9 +2896,31 @@ fn make_raw_gps_entries(gps: &GpsCoords) -> Vec<RawIfdEntry> {
         data_format: 5, // RATIONAL (unsigned)
         count: 3,
         inline_value: [0u8; 4],
-        extra_data: Some(encode_gps_rational(lon_deg, lon_min, lon_sec, 10000)),
+        extra_data: Some(file_order(encode_gps_rational(lon))),
     });
 
+    // GPSAltitudeRef (tag 0x0005, BYTE) and GPSAltitude (tag 0x0006, 1 RATIONAL)
+    if let Some(altitude) = gps.altitude {
+        let (reference, altitude_bytes) = encode_gps_altitude(altitude);
+        entries.push(RawIfdEntry {
+            tag_id: TAG_GPS_ALTITUDE_REF,
+            data_format: 1, // BYTE
+            count: 1,
+            inline_value: [reference, 0, 0, 0],
+            extra_data: None,
+        });
+        entries.push(RawIfdEntry {
+            tag_id: TAG_GPS_ALTITUDE,
+            data_format: 5, // RATIONAL (unsigned)
+            count: 1,
+            inline_value: [0u8; 4],
+            extra_data: Some(file_order(altitude_bytes)),
+        });
+    }
+
+    // GPSMapDatum (tag 0x0012, ASCII, "WGS-84\0")
+    entries.push(make_string_entry(TAG_GPS_MAP_DATUM, GPS_MAP_DATUM, false));
+
     entries
 }
 
@@ -1590,16 +2932,15 @@ fn collect_gps_tags(tags: &mut Vec<ExifTag>, gps: &GpsCoords) {
     let lat_ref = if lat >= 0.0 { "N" } else { "S" };
     let lon_ref = if lon >= 0.0 { "E" } else { "W" };
 
-    let lat_abs = lat.abs();
-    let lon_abs = lon.abs();
-
-    let lat_deg = lat_abs.floor() as u32;
-    let lat_min = ((lat_abs - lat_deg as f64) * 60.0).floor() as u32;
-    let lat_sec = ((lat_abs - lat_deg as f64 - lat_min as f64 / 60.0) * 3600.0 * 10000.0) as u32;
-
-    let lon_deg = lon_abs.floor() as u32;
-    let lon_min = ((lon_abs - lon_deg as f64) * 60.0).floor() as u32;
-    let lon_sec = ((lon_abs - lon_deg as f64 - lon_min as f64 / 60.0) * 3600.0 * 10000.0) as u32;
+    if let Ok(tag) = ExifTag::from_u16_with_data(
+        TAG_GPS_VERSION_ID,
+        &ExifTagFormat::INT8U,
+        &GPS_VERSION.to_vec(),
+        &Endian::Little,
+        &ExifTagGroup::GPSIFD,
+    ) {
+        tags.push(tag);
+    }
 
     if let Ok(tag) = ExifTag::from_u16_with_data(
         TAG_GPS_LATITUDE_REF,
@@ -1611,7 +2952,7 @@ fn collect_gps_tags(tags: &mut Vec<ExifTag>, gps: &GpsCoords) {
         tags.push(tag);
     }
 
-    let lat_bytes = encode_gps_rational(lat_deg, lat_min, lat_sec, 10000);
+    let lat_bytes = encode_gps_rational(lat);
     if let Ok(tag) = ExifTag::from_u16_with_data(
         TAG_GPS_LATITUDE,
         &ExifTagFormat::RATIONAL64U,
@@ -1632,7 +2973,7 @@ fn collect_gps_tags(tags: &mut Vec<ExifTag>, gps: &GpsCoords) {
         tags.push(tag);
     }
 
-    let lon_bytes = encode_gps_rational(lon_deg, lon_min, lon_sec, 10000);
+    let lon_bytes = encode_gps_rational(lon);
     if let Ok(tag) = ExifTag::from_u16_with_data(
         TAG_GPS_LONGITUDE,
         &ExifTagFormat::RATIONAL64U,
@@ -1642,6 +2983,39 @@ fn collect_gps_tags(tags: &mut Vec<ExifTag>, gps: &GpsCoords) {
     ) {
         tags.push(tag);
     }
+
+    if let Some(altitude) = gps.altitude {
+        let (reference, altitude_bytes) = encode_gps_altitude(altitude);
+        if let Ok(tag) = ExifTag::from_u16_with_data(
+            TAG_GPS_ALTITUDE_REF,
+            &ExifTagFormat::INT8U,
+            &vec![reference],
+            &Endian::Little,
+            &ExifTagGroup::GPSIFD,
+        ) {
+            tags.push(tag);
+        }
+
+        if let Ok(tag) = ExifTag::from_u16_with_data(
+            TAG_GPS_ALTITUDE,
+            &ExifTagFormat::RATIONAL64U,
+            &altitude_bytes,
+            &Endian::Little,
+            &ExifTagGroup::GPSIFD,
+        ) {
+            tags.push(tag);
+        }
+    }
+
+    if let Ok(tag) = ExifTag::from_u16_with_data(
+        TAG_GPS_MAP_DATUM,
+        &ExifTagFormat::STRING,
+        &format!("{GPS_MAP_DATUM}\0").into_bytes(),
+        &Endian::Little,
+        &ExifTagGroup::GPSIFD,
+    ) {
+        tags.push(tag);
+    }
 }
 
 #[cfg(test)]
@@ -1660,6 +3034,7 @@ mod tests {
             write_gps: true,
             write_subject: true,
             overwrite_existing: false,
+            ..Default::default()
         }
     }
 
@@ -1670,6 +3045,7 @@ mod tests {
             tags: Some(vec!["tag1".into(), "tag2".into(), "tag3".into()]),
             gps: None,
             subject: Some(vec!["Test Subject".into()]),
+            ..Default::default()
         }
     }
 
@@ -1700,7 +3076,7 @@ mod tests {
         let ai = test_ai_result();
         let fields = test_fields();
 
-        let result = write_exif(&path, &ai, &existing, &fields, true, ImageKind::Jpeg).unwrap();
+        let result = write_exif(&path, &ai, &existing, &fields, true, ImageKind::Jpeg, false).unwrap();
 
         // Dry run should report what would be written
         assert!(result.title_written);
@@ -1721,15 +3097,17 @@ mod tests {
         let path = dir.path().join("test.jpg");
         std::fs::write(&path, b"fake").unwrap();
 
-        let mut existing = ExifData::default();
-        existing.title = Some("Existing Title".into());
+        let existing = ExifData {
+            title: Some("Existing Title".into()),
+            ..Default::default()
+        };
 
         let ai = test_ai_result();
         let fields = test_fields(); // overwrite_existing = false
 
-        let result = write_exif(&path, &ai, &existing, &fields, true, ImageKind::Jpeg).unwrap();
+        let result = write_exif(&path, &ai, &existing, &fields, true, ImageKind::Jpeg, false).unwrap();
         assert!(!result.title_written);
-        assert!(result.skipped_fields.iter().any(|s| s.contains("title")));
+        assert!(result.skipped_fields.contains(&SkippedField::new(Field::Title, SkipReason::Existing)));
     }
 
     #[test]
@@ -1738,15 +3116,17 @@ mod tests {
         let path = dir.path().join("test.jpg");
         std::fs::write(&path, b"fake").unwrap();
 
-        let mut existing = ExifData::default();
-        existing.description = Some("Existing Desc".into());
+        let existing = ExifData {
+            description: Some("Existing Desc".into()),
+            ..Default::default()
+        };
 
         let ai = test_ai_result();
         let fields = test_fields();
 
-        let result = write_exif(&path, &ai, &existing, &fields, true, ImageKind::Jpeg).unwrap();
+        let result = write_exif(&path, &ai, &existing, &fields, true, ImageKind::Jpeg, false).unwrap();
         assert!(!result.description_written);
-        assert!(result.skipped_fields.iter().any(|s| s.contains("description")));
+        assert!(result.skipped_fields.contains(&SkippedField::new(Field::Description, SkipReason::Existing)));
     }
 
     #[test]
@@ -1755,15 +3135,17 @@ mod tests {
         let path = dir.path().join("test.jpg");
         std::fs::write(&path, b"fake").unwrap();
 
-        let mut existing = ExifData::default();
-        existing.keywords = Some("existing; keywords".into());
+        let existing = ExifData {
+            keywords: Some("existing; keywords".into()),
+            ..Default::default()
+        };
 
         let ai = test_ai_result();
         let fields = test_fields();
 
-        let result = write_exif(&path, &ai, &existing, &fields, true, ImageKind::Jpeg).unwrap();
+        let result = write_exif(&path, &ai, &existing, &fields, true, ImageKind::Jpeg, false).unwrap();
         assert!(!result.tags_written);
-        assert!(result.skipped_fields.iter().any(|s| s.contains("tags")));
+        assert!(result.skipped_fields.contains(&SkippedField::new(Field::Tags, SkipReason::Existing)));
     }
 
     #[test]
@@ -1772,17 +3154,19 @@ mod tests {
         let path = dir.path().join("test.jpg");
         std::fs::write(&path, b"fake").unwrap();
 
-        let mut existing = ExifData::default();
-        existing.has_gps = true;
+        let existing = ExifData {
+            has_gps: true,
+            ..Default::default()
+        };
 
         let mut ai = test_ai_result();
-        ai.gps = Some(crate::ai::GpsCoords { latitude: 48.8, longitude: 2.3 });
+        ai.gps = Some(crate::ai::GpsCoords { latitude: 48.8, longitude: 2.3, altitude: None });
 
         let fields = test_fields();
 
-        let result = write_exif(&path, &ai, &existing, &fields, true, ImageKind::Jpeg).unwrap();
+        let result = write_exif(&path, &ai, &existing, &fields, true, ImageKind::Jpeg, false).unwrap();
         assert!(!result.gps_written);
-        assert!(result.skipped_fields.iter().any(|s| s.contains("gps")));
+        assert!(result.skipped_fields.contains(&SkippedField::new(Field::Gps, SkipReason::Existing)));
     }
 
     // ── write_exif: overwrite_existing ───────────────────────────────
@@ -1793,16 +3177,18 @@ mod tests {
         let path = dir.path().join("test.jpg");
         std::fs::write(&path, b"fake").unwrap();
 
-        let mut existing = ExifData::default();
-        existing.title = Some("Old Title".into());
-        existing.description = Some("Old Desc".into());
-        existing.keywords = Some("old".into());
+        let existing = ExifData {
+            title: Some("Old Title".into()),
+            description: Some("Old Desc".into()),
+            keywords: Some("old".into()),
+            ..Default::default()
+        };
 
         let ai = test_ai_result();
         let mut fields = test_fields();
         fields.overwrite_existing = true;
 
-        let result = write_exif(&path, &ai, &existing, &fields, true, ImageKind::Jpeg).unwrap();
+        let result = write_exif(&path, &ai, &existing, &fields, true, ImageKind::Jpeg, false).unwrap();
         assert!(result.title_written);
         assert!(result.description_written);
         assert!(result.tags_written);
@@ -1826,14 +3212,212 @@ mod tests {
             write_gps: false,
             write_subject: false,
             overwrite_existing: false,
+            ..Default::default()
         };
 
-        let result = write_exif(&path, &ai, &existing, &fields, true, ImageKind::Jpeg).unwrap();
+        let result = write_exif(&path, &ai, &existing, &fields, true, ImageKind::Jpeg, false).unwrap();
         assert!(!result.title_written);
         assert!(!result.description_written);
         assert!(!result.tags_written);
         assert!(!result.gps_written);
         assert!(!result.subject_written);
+        assert!(result.skipped_fields.iter().all(|s| s.reason == SkipReason::Disabled));
+        assert_eq!(result.skipped_fields.len(), 4); // no GPS in the AI result
+    }
+
+    #[test]
+    fn empty_subject_is_skipped_as_empty() {
+        let existing = ExifData::default();
+        let mut ai = test_ai_result();
+        ai.subject = Some(Vec::new());
+
+        let result = write_exif(Path::new("unused.jpg"), &ai, &existing, &test_fields(), true, ImageKind::Jpeg, false).unwrap();
+        assert!(!result.subject_written);
+        assert_eq!(result.skipped_fields, vec![SkippedField::new(Field::Subject, SkipReason::Empty)]);
+    }
+
+    // ── SkippedField ─────────────────────────────────────────────────
+
+    #[test]
+    fn skipped_field_display_matches_legacy_text() {
+        assert_eq!(SkippedField::new(Field::Title, SkipReason::Existing).to_string(), "title (existing)");
+        assert_eq!(SkippedField::new(Field::Gps, SkipReason::Existing).to_string(), "gps (existing coordinates)");
+        assert_eq!(SkippedField::new(Field::Tags, SkipReason::Disabled).to_string(), "tags (disabled)");
+    }
+
+    #[test]
+    fn skipped_field_explanations() {
+        let explain = |field, reason| SkippedField::new(field, reason).explanation();
+        assert_eq!(explain(Field::Title, SkipReason::Existing), "title skipped: existing value present and overwrite=false");
+        assert_eq!(explain(Field::Gps, SkipReason::Existing), "gps skipped: image already geotagged");
+        assert_eq!(explain(Field::Description, SkipReason::Disabled), "description skipped: write_description=false");
+        assert_eq!(explain(Field::Tags, SkipReason::Disabled), "tags skipped: write_tags=false or no keyword_targets enabled");
+        assert_eq!(explain(Field::Subject, SkipReason::Empty), "subject skipped: AI returned an empty value");
+        assert_eq!(explain(Field::Gps, SkipReason::FormatUnsupported), "gps skipped: the image format has nowhere to store it");
+    }
+
+    #[test]
+    fn skipped_field_serializes_as_text() {
+        let skipped = vec![SkippedField::new(Field::Description, SkipReason::Existing)];
+        assert_eq!(serde_json::to_string(&skipped).unwrap(), r#"["description (existing)"]"#);
+        assert_eq!(serde_json::to_string(&SkipReason::Disabled).unwrap(), r#""disabled""#);
+        assert_eq!(serde_json::to_string(&SkipReason::FormatUnsupported).unwrap(), r#""format-unsupported""#);
+        assert_eq!(serde_json::to_string(&Field::Gps).unwrap(), r#""gps""#);
+    }
+
+    // ── write_exif: format-unsupported fields ────────────────────────
+
+    /// Read a WebP's EXIF chunk, which nom-exif can't find on its own, by
+    /// saving it as a bare TIFF.
+    fn webp_exif(path: &Path) -> ExifData {
+        let webp = img_parts::webp::WebP::from_bytes(std::fs::read(path).unwrap().into()).unwrap();
+        let tiff = path.with_extension("exif.tif");
+        std::fs::write(&tiff, webp.exif().expect("EXIF chunk")).unwrap();
+        crate::exif::read_exif(&tiff).unwrap()
+    }
+
+    #[test]
+    fn webp_gets_gps_subject_and_keywords_in_exif() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("photo.webp");
+        image::RgbImage::new(8, 8).save(&path).unwrap();
+
+        let mut ai = test_ai_result();
+        ai.gps = Some(GpsCoords { latitude: 48.8566, longitude: 2.3522, altitude: None });
+
+        let result = write_exif(&path, &ai, &ExifData::default(), &test_fields(), false, ImageKind::WebP, false).unwrap();
+        assert!(result.title_written);
+        assert!(result.description_written);
+        assert!(result.tags_written);
+        assert!(result.gps_written);
+        assert!(result.subject_written);
+        assert!(result.skipped_fields.is_empty());
+
+        let webp = img_parts::webp::WebP::from_bytes(std::fs::read(&path).unwrap().into()).unwrap();
+        let xmp = webp.chunk_by_id(*b"XMP ").unwrap().content().data().unwrap().clone();
+        let xmp = String::from_utf8_lossy(&xmp);
+        assert!(xmp.contains("Test Title"));
+        assert!(xmp.contains("<rdf:li>tag1</rdf:li>"));
+
+        let data = webp_exif(&path);
+        assert_eq!(data.title.as_deref(), Some("Test Title"));
+        assert_eq!(data.description.as_deref(), Some("A test description"));
+        assert_eq!(data.keywords.as_deref(), Some("tag1; tag2; tag3"));
+        assert_eq!(data.subject.as_deref(), Some("Test Subject"));
+        assert!(data.has_gps);
+        assert!((data.gps_latitude.unwrap() - 48.8566).abs() < 1e-4);
+
+        // A second write merges into the EXIF chunk instead of replacing it
+        let fields = ExifFields { overwrite_existing: true, ..credit_fields() };
+        write_exif(&path, &AiResult::default(), &data, &fields, false, ImageKind::WebP, false).unwrap();
+        let data = webp_exif(&path);
+        assert_eq!(data.artist.as_deref(), Some("Jane Doe"));
+        assert_eq!(data.subject.as_deref(), Some("Test Subject"));
+        assert!(data.has_gps);
+    }
+
+    #[test]
+    fn written_flags_follow_the_stored_blocks() {
+        let fields = test_fields();
+        let xmp = Blocks { xmp: true, ..Default::default() };
+        assert!(xmp.hold(Field::Title, &fields));
+        assert!(xmp.hold(Field::Tags, &fields));
+        assert!(!xmp.hold(Field::Gps, &fields));
+        assert!(!xmp.hold(Field::Subject, &fields));
+        let exif = Blocks { exif: true, ..Default::default() };
+        assert!(exif.hold(Field::Gps, &fields));
+        let no_xp_keywords = ExifFields {
+            keyword_targets: crate::config::KeywordTargets { xp_keywords: false, ..Default::default() },
+            ..test_fields()
+        };
+        assert!(!exif.hold(Field::Tags, &no_xp_keywords));
+        assert!(Blocks { iptc: true, ..Default::default() }.hold(Field::Tags, &no_xp_keywords));
+        assert!(!Blocks::default().any());
+    }
+
+    #[test]
+    fn jpeg_keywords_without_exif_target_are_still_written() {
+        // Only keywords, and none of them bound for EXIF: no EXIF tag changes,
+        // but XMP and IPTC still have to be written
+        let (_dir, path) = copy_to_temp("test_canon_powershot.jpg");
+        let ai = AiResult { tags: Some(vec!["barn".into(), "snow".into()]), ..Default::default() };
+        let fields = ExifFields {
+            keyword_targets: crate::config::KeywordTargets { xp_keywords: false, ..Default::default() },
+            ..test_fields()
+        };
+        let before = std::fs::read(&path).unwrap();
+        let existing = crate::exif::read_exif(&path).unwrap();
+
+        let result = write_exif(&path, &ai, &existing, &fields, false, ImageKind::Jpeg, false).unwrap();
+        assert!(result.tags_written);
+        let after = std::fs::read(&path).unwrap();
+        assert!(contains_bytes(&after, "<rdf:li>barn</rdf:li>"));
+        assert_eq!(iptc_record(&after, 25), Some(&b"barn"[..]));
+        verify_pixels_unchanged(&before, &after);
+    }
+
+    #[test]
+    fn webp_xmp_only_reports_gps_not_written() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("photo.webp");
+        image::RgbImage::new(8, 8).save(&path).unwrap();
+        let mut ai = test_ai_result();
+        ai.gps = Some(GpsCoords { latitude: 48.8566, longitude: 2.3522, altitude: None });
+
+        let result = write_exif(&path, &ai, &ExifData::default(), &test_fields(), false, ImageKind::WebP, true).unwrap();
+        assert!(result.title_written);
+        assert!(!result.gps_written);
+        assert!(result.skipped_fields.contains(&SkippedField::new(Field::Gps, SkipReason::FormatUnsupported)));
+        let webp = img_parts::webp::WebP::from_bytes(std::fs::read(&path).unwrap().into()).unwrap();
+        assert!(webp.exif().is_none());
+    }
+
+    #[test]
+    fn webp_xmp_only_skips_gps_and_subject() {
+        let mut ai = test_ai_result();
+        ai.gps = Some(GpsCoords { latitude: 48.8566, longitude: 2.3522, altitude: None });
+
+        let result = write_exif(Path::new("unused.webp"), &ai, &ExifData::default(), &test_fields(), true, ImageKind::WebP, true).unwrap();
+        assert!(result.tags_written);
+        assert!(!result.gps_written);
+        assert!(!result.subject_written);
+        assert!(result.skipped_fields.contains(&SkippedField::new(Field::Gps, SkipReason::FormatUnsupported)));
+        assert!(result.skipped_fields.contains(&SkippedField::new(Field::Subject, SkipReason::FormatUnsupported)));
+    }
+
+    #[test]
+    fn jpeg_xmp_only_skips_gps_and_subject() {
+        let mut ai = test_ai_result();
+        ai.gps = Some(GpsCoords { latitude: 48.8566, longitude: 2.3522, altitude: None });
+
+        let result = write_exif(Path::new("unused.jpg"), &ai, &ExifData::default(), &test_fields(), true, ImageKind::Jpeg, true).unwrap();
+        assert!(result.title_written);
+        assert!(!result.gps_written);
+        assert!(!result.subject_written);
+        assert_eq!(result.skipped_fields, vec![
+            SkippedField::new(Field::Subject, SkipReason::FormatUnsupported),
+            SkippedField::new(Field::Gps, SkipReason::FormatUnsupported),
+        ]);
+    }
+
+    #[test]
+    fn png_tags_need_an_xmp_keyword_target() {
+        let mut fields = test_fields();
+        fields.keyword_targets = crate::config::KeywordTargets {
+            xp_keywords: true,
+            dc_subject: false,
+            microsoft_photo: false,
+            iptc: true,
+            lr_hierarchical_subject: false,
+        };
+
+        let png = write_exif(Path::new("unused.png"), &test_ai_result(), &ExifData::default(), &fields, true, ImageKind::Png, false).unwrap();
+        assert!(!png.tags_written);
+        assert!(png.skipped_fields.contains(&SkippedField::new(Field::Tags, SkipReason::FormatUnsupported)));
+
+        let jpeg = write_exif(Path::new("unused.jpg"), &test_ai_result(), &ExifData::default(), &fields, true, ImageKind::Jpeg, false).unwrap();
+        assert!(jpeg.tags_written);
+        assert!(jpeg.skipped_fields.is_empty());
     }
 
     // ── write_exif: sidecar XMP ──────────────────────────────────────
@@ -1848,113 +3432,1086 @@ mod tests {
         let ai = test_ai_result();
         let fields = test_fields();
 
-        let result = write_exif(&path, &ai, &existing, &fields, false, ImageKind::Sidecar).unwrap();
+        let result = write_exif(&path, &ai, &existing, &fields, false, ImageKind::Sidecar, false).unwrap();
 
         assert!(result.sidecar_path.is_some());
         let sidecar = result.sidecar_path.unwrap();
         assert_eq!(sidecar.extension().unwrap(), "xmp");
         assert!(sidecar.exists());
 
-        // Verify XMP content
-        let content = std::fs::read_to_string(&sidecar).unwrap();
-        assert!(content.contains("Test Title"));
-        assert!(content.contains("A test description"));
-        assert!(content.contains("tag1"));
+        // Verify XMP content
+        let content = std::fs::read_to_string(&sidecar).unwrap();
+        assert!(content.contains("Test Title"));
+        assert!(content.contains("A test description"));
+        assert!(content.contains("tag1"));
+    }
+
+    #[test]
+    fn exiftool_args_follow_written_fields() {
+        let mut ai = test_ai_result();
+        ai.gps = Some(GpsCoords { latitude: -33.86, longitude: 151.21, altitude: None });
+        let mut result = WriteResult { title_written: true, gps_written: true, ..Default::default() };
+        assert_eq!(exiftool_args(&ai, &result, &test_fields()), [
+            "-overwrite_original",
+            "-Title=Test Title",
+            "-GPSLatitude=33.86",
+            "-GPSLatitudeRef=S",
+            "-GPSLongitude=151.21",
+            "-GPSLongitudeRef=E",
+        ]);
+
+        result = WriteResult { tags_written: true, ..Default::default() };
+        let mut fields = test_fields();
+        fields.keyword_targets.iptc = false;
+        let args = exiftool_args(&ai, &result, &fields);
+        assert_eq!(args[1..], ["-XMP-dc:Subject=tag1", "-XMP-dc:Subject=tag2", "-XMP-dc:Subject=tag3"]);
+
+        assert!(exiftool_args(&ai, &WriteResult::default(), &test_fields()).is_empty());
+    }
+
+    #[test]
+    fn sidecar_with_exiftool_flag_still_writes_sidecar() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("photo.heic");
+        std::fs::write(&path, b"fake heic").unwrap();
+        let mut ai = test_ai_result();
+        ai.gps = Some(GpsCoords { latitude: 48.8566, longitude: 2.3522, altitude: None });
+        let fields = ExifFields { use_exiftool_if_available: true, ..test_fields() };
+
+        let result = write_exif(&path, &ai, &ExifData::default(), &fields, false, ImageKind::Sidecar, false).unwrap();
+        assert!(result.sidecar_path.unwrap().exists());
+        assert!(result.title_written);
+        if exiftool_available() {
+            // Not a real HEIC, so exiftool refuses it and GPS is dropped
+            let run = result.exiftool.unwrap();
+            assert!(!run.success());
+            assert!(!run.stderr.is_empty());
+        } else {
+            assert!(result.exiftool.is_none());
+        }
+        assert!(!result.gps_written);
+        assert!(result.skipped_fields.contains(&SkippedField::new(Field::Gps, SkipReason::FormatUnsupported)));
+    }
+
+    #[test]
+    fn sidecar_dry_run_no_file() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("photo.cr3");
+        std::fs::write(&path, b"fake raw").unwrap();
+
+        let existing = ExifData::default();
+        let ai = test_ai_result();
+        let fields = test_fields();
+
+        let result = write_exif(&path, &ai, &existing, &fields, true, ImageKind::Sidecar, false).unwrap();
+
+        // Dry run should not create sidecar
+        assert!(result.sidecar_path.is_none());
+        let xmp_path = path.with_extension("xmp");
+        assert!(!xmp_path.exists());
+    }
+
+    #[test]
+    fn sidecar_interrupted_write_keeps_previous() {
+        let dir = TempDir::new().unwrap();
+        let sidecar = dir.path().join("photo.xmp");
+        std::fs::write(&sidecar, "<x:xmpmeta>previous</x:xmpmeta>").unwrap();
+
+        // Simulate the process failing halfway through the new sidecar
+        let err = write_atomic_with(&sidecar, |file| {
+            file.write_all(b"<x:xmpmeta>trunc")?;
+            Err(std::io::Error::other("interrupted"))
+        })
+        .unwrap_err();
+        assert_eq!(err.to_string(), "interrupted");
+
+        assert_eq!(std::fs::read_to_string(&sidecar).unwrap(), "<x:xmpmeta>previous</x:xmpmeta>");
+        let leftovers: Vec<_> = std::fs::read_dir(dir.path()).unwrap().collect();
+        assert_eq!(leftovers.len(), 1, "temp file should be cleaned up");
+    }
+
+    #[test]
+    fn sidecar_reads_back_and_keeps_existing_properties() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("photo.heic");
+        std::fs::copy(data_path("test.heic"), &path).unwrap();
+        assert_eq!(crate::exif::read_exif(&path).unwrap().title, None);
+
+        write_exif(&path, &test_ai_result(), &ExifData::default(), &test_fields(), false, ImageKind::Sidecar, false).unwrap();
+        let existing = crate::exif::read_exif(&path).unwrap();
+        assert_eq!(existing.title.as_deref(), Some("Test Title"));
+        assert!(existing.description.is_some());
+        assert!(existing.keywords.is_some());
+
+        // A second run without overwrite skips the title and keeps it in the sidecar
+        let sidecar = path.with_extension("xmp");
+        let rated = std::fs::read_to_string(&sidecar).unwrap().replacen("rdf:about=\"\"", "rdf:about=\"\" xmlns:xmp=\"http://ns.adobe.com/xap/1.0/\" xmp:Rating=\"4\"", 1);
+        std::fs::write(&sidecar, rated).unwrap();
+        let next = AiResult { title: Some("Other Title".into()), ..test_ai_result() };
+        let result = write_exif(&path, &next, &existing, &test_fields(), false, ImageKind::Sidecar, false).unwrap();
+        assert!(!result.title_written);
+        let reread = crate::exif::read_exif(&path).unwrap();
+        assert_eq!(reread.title.as_deref(), Some("Test Title"));
+        assert_eq!(reread.rating, Some(4));
+    }
+
+    #[test]
+    fn sidecar_preview_matches_write_and_lists_changes() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("photo.heic");
+        std::fs::write(&path, b"fake heic").unwrap();
+
+        let preview = preview_sidecar_xmp(&path, &test_ai_result(), &ExifData::default(), &test_fields());
+        assert!(!preview.exists && !preview.updates_existing());
+        assert!(!preview.path.exists(), "preview must not write");
+        let changes = preview.changes();
+        assert!(changes.contains(&("dc:title", "Test Title".to_string())), "{changes:?}");
+        assert!(changes.iter().any(|(name, _)| *name == "dc:subject"));
+
+        let result = write_exif(&path, &test_ai_result(), &ExifData::default(), &test_fields(), false, ImageKind::Sidecar, false).unwrap();
+        assert_eq!(std::fs::read_to_string(result.sidecar_path.unwrap()).unwrap(), preview.xmp);
+
+        // Writing the same values again changes nothing
+        let again = preview_sidecar_xmp(&path, &test_ai_result(), &ExifData::default(), &test_fields());
+        assert!(again.exists && again.updates_existing());
+        assert_eq!(again.changes(), []);
+    }
+
+    #[test]
+    fn sidecar_replaces_existing_file() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("photo.heic");
+        std::fs::write(&path, b"fake heic").unwrap();
+        std::fs::write(path.with_extension("xmp"), "old sidecar").unwrap();
+
+        let result = write_exif(&path, &test_ai_result(), &ExifData::default(), &test_fields(), false, ImageKind::Sidecar, false).unwrap();
+
+        let content = std::fs::read_to_string(result.sidecar_path.unwrap()).unwrap();
+        assert!(content.contains("Test Title"));
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 2);
+    }
+
+    fn hierarchical_fields() -> ExifFields {
+        let mut fields = test_fields();
+        fields.keyword_targets.lr_hierarchical_subject = true;
+        fields
+    }
+
+    fn hierarchical_ai_result() -> AiResult {
+        AiResult {
+            tags: Some(vec!["owl".into(), "forest".into()]),
+            hierarchical_tags: Some(vec!["Nature|Birds|Owl".into(), "Places|Forest".into()]),
+            ..test_ai_result()
+        }
+    }
+
+    #[test]
+    fn sidecar_writes_flat_and_hierarchical_keywords() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("photo.heic");
+        std::fs::write(&path, b"fake heic").unwrap();
+
+        let result = write_exif(&path, &hierarchical_ai_result(), &ExifData::default(), &hierarchical_fields(), false, ImageKind::Sidecar, false).unwrap();
+        assert!(result.tags_written);
+
+        let content = std::fs::read_to_string(result.sidecar_path.unwrap()).unwrap();
+        assert!(content.contains("xmlns:lr=\"http://ns.adobe.com/lightroom/1.0/\""), "{content}");
+        assert!(content.contains(&xmp_bag("dc:subject", &["owl".into(), "forest".into()])), "{content}");
+        assert!(content.contains(&xmp_bag("lr:hierarchicalSubject", &["Nature|Birds|Owl".into(), "Places|Forest".into()])), "{content}");
+    }
+
+    #[test]
+    fn hierarchical_keywords_need_their_target_and_flat_keywords() {
+        let ai = hierarchical_ai_result();
+        let hierarchy = ai.hierarchical_tags.as_ref();
+        let tags = ai.tags.as_ref();
+        let fields = hierarchical_fields();
+        let alternatives = LangAlternatives::new(&ai, &fields);
+
+        let off = build_xmp(None, None, None, tags, hierarchy, &alternatives, &test_fields());
+        assert!(!off.contains("lr:") && off.contains("<dc:subject>"));
+        // Keywords skipped (e.g. existing ones kept) leave the hierarchy alone too
+        let skipped = build_xmp(None, Some("Owl"), None, None, hierarchy, &alternatives, &fields);
+        assert!(!skipped.contains("lr:"));
+    }
+
+    #[test]
+    fn inject_hierarchical_keywords_replaces_existing_ones() {
+        let ai = hierarchical_ai_result();
+        let fields = hierarchical_fields();
+        let existing = concat!(
+            "<x:xmpmeta xmlns:x=\"adobe:ns:meta/\"><rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\">",
+            "<rdf:Description rdf:about=\"\">\n",
+            "  <lr:hierarchicalSubject><rdf:Bag><rdf:li>Old|Path</rdf:li></rdf:Bag></lr:hierarchicalSubject>\n",
+            "</rdf:Description></rdf:RDF></x:xmpmeta>",
+        );
+
+        let xmp = build_xmp(Some(existing), None, None, ai.tags.as_ref(), ai.hierarchical_tags.as_ref(), &LangAlternatives::new(&ai, &fields), &fields);
+        assert_eq!(xmp.matches("<lr:hierarchicalSubject>").count(), 1, "{xmp}");
+        assert!(xmp.contains("Nature|Birds|Owl") && !xmp.contains("Old|Path"), "{xmp}");
+        assert!(xmp.contains("xmlns:lr=\"http://ns.adobe.com/lightroom/1.0/\""), "{xmp}");
+        assert!(xmp.contains("<dc:subject>"), "{xmp}");
+    }
+
+    // ── encode_utf16le ───────────────────────────────────────────────
+
+    #[test]
+    fn encode_utf16le_ascii() {
+        let bytes = encode_utf16le("ABC");
+        // A=0x41, B=0x42, C=0x43 in UTF-16LE + null terminator
+        assert_eq!(bytes, vec![0x41, 0x00, 0x42, 0x00, 0x43, 0x00, 0x00, 0x00]);
+    }
+
+    #[test]
+    fn encode_utf16le_empty() {
+        let bytes = encode_utf16le("");
+        // Just null terminator
+        assert_eq!(bytes, vec![0x00, 0x00]);
+    }
+
+    // ── collect_gps_tags ─────────────────────────────────────────────
+
+    fn from_dms_rationals(dms: [(u32, u32); 3]) -> f64 {
+        let [d, m, s] = dms.map(|(n, den)| n as f64 / den as f64);
+        d + m / 60.0 + s / 3600.0
+    }
+
+    #[test]
+    fn dms_rationals_round_trip() {
+        for coord in [48.858093, -48.858093, 2.294694, 179.999999, 0.0] {
+            let decoded = from_dms_rationals(to_dms_rationals(coord));
+            assert!((decoded - coord.abs()).abs() < 1e-5, "{coord}: {decoded}");
+        }
+    }
+
+    #[test]
+    fn dms_rationals_carry_instead_of_sixty_seconds() {
+        // 10°59'59.99999" rounds up to 11°00'00"
+        let coord = 10.0 + 59.0 / 60.0 + 59.99999 / 3600.0;
+        assert_eq!(to_dms_rationals(coord), [(11, 1), (0, 1), (0, GPS_SECONDS_DENOMINATOR)]);
+    }
+
+    #[test]
+    fn gps_coordinates_round_trip_precisely() {
+        for (name, kind) in [("test_exif.jpg", ImageKind::Jpeg), ("test.tiff", ImageKind::Tiff)] {
+            let (_dir, path) = copy_to_temp(name);
+            let existing = crate::exif::read_exif(&path).unwrap();
+            let mut ai = test_ai_result();
+            ai.gps = Some(GpsCoords { latitude: 48.858093, longitude: -2.294694, altitude: None });
+
+            write_exif(&path, &ai, &existing, &test_fields(), false, kind, false).unwrap();
+
+            let after = crate::exif::read_exif(&path).unwrap();
+            let lat = after.gps_latitude.unwrap();
+            let lon = after.gps_longitude.unwrap();
+            assert!((lat - 48.858093).abs() < 1e-5, "{name}: lat={lat}");
+            assert!((lon + 2.294694).abs() < 1e-5, "{name}: lon={lon}");
+        }
+    }
+
+    #[test]
+    fn collect_gps_tags_positive_coords() {
+        let mut tags = Vec::new();
+        let gps = GpsCoords { latitude: 48.8566, longitude: 2.3522, altitude: None };
+        collect_gps_tags(&mut tags, &gps);
+        // Should produce 6 tags: version, lat_ref, lat, lon_ref, lon, map datum
+        assert_eq!(tags.len(), 6);
+    }
+
+    #[test]
+    fn collect_gps_tags_negative_coords() {
+        let mut tags = Vec::new();
+        let gps = GpsCoords { latitude: -33.8688, longitude: -118.2426, altitude: None };
+        collect_gps_tags(&mut tags, &gps);
+        assert_eq!(tags.len(), 6);
+    }
+
+    // ── Write round-trip tests (real files from data/) ───────────────
+
+    fn data_path(name: &str) -> std::path::PathBuf {
+        std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("data").join(name)
+    }
+
+    fn copy_to_temp(name: &str) -> (TempDir, std::path::PathBuf) {
+        let dir = TempDir::new().unwrap();
+        let src = data_path(name);
+        let dst = dir.path().join(name);
+        std::fs::copy(&src, &dst).unwrap();
+        (dir, dst)
+    }
+
+    #[test]
+    fn write_jpeg_round_trip_canon() {
+        let (_dir, path) = copy_to_temp("test_canon_powershot.jpg");
+
+        let existing = crate::exif::read_exif(&path).unwrap();
+        assert!(existing.title.is_none()); // no title yet
+        // Canon file has a non-empty description ("Undefined[0x00...]"), so writer skips it
+        assert!(existing.description.is_some());
+
+        let ai = test_ai_result();
+        let fields = test_fields();
+
+        let result = write_exif(&path, &ai, &existing, &fields, false, ImageKind::Jpeg, false).unwrap();
+        assert!(result.title_written);
+        assert!(!result.description_written); // skipped — existing description present
+        assert!(result.tags_written);
+        assert!(result.subject_written);
+        assert!(!result.gps_written); // no GPS in ai_result
+
+        // Read back and verify AI metadata was written
+        let after = crate::exif::read_exif(&path).unwrap();
+        // Original camera data should be preserved
+        assert_eq!(after.make.as_deref(), Some("Canon"));
+        assert_eq!(after.model.as_deref(), Some("Canon PowerShot S40"));
+        // AI-written title should be present
+        assert!(after.title.is_some(), "title should be written");
+        verify_pixels_unchanged(&std::fs::read(data_path("test_canon_powershot.jpg")).unwrap(), &std::fs::read(&path).unwrap());
+    }
+
+    /// Split a JPEG into its `(marker, segment bytes)` headers and everything
+    /// from the first SOS (start of scan) on: the entropy-coded image data,
+    /// any further scans and EOI.
+    fn jpeg_layout(bytes: &[u8]) -> (Vec<(u8, &[u8])>, &[u8]) {
+        assert_eq!(bytes[..2], [0xFF, 0xD8], "not a JPEG");
+        let mut segments = Vec::new();
+        let mut pos = 2;
+        loop {
+            assert_eq!(bytes[pos], 0xFF, "bad marker at {pos}");
+            let marker = bytes[pos + 1];
+            if marker == 0xFF {
+                // Fill byte before a marker
+                pos += 1;
+                continue;
+            }
+            if marker == 0xDA {
+                return (segments, &bytes[pos..]);
+            }
+            let len = u16::from_be_bytes([bytes[pos + 2], bytes[pos + 3]]) as usize;
+            segments.push((marker, &bytes[pos..pos + 2 + len]));
+            pos += 2 + len;
+        }
+    }
+
+    /// Assert that a JPEG write only touched metadata: the image data from the
+    /// first SOS on is byte-identical, and every segment besides APP1 (EXIF,
+    /// XMP) and APP13 (IPTC) is unchanged and in the same order.
+    fn verify_pixels_unchanged(before: &[u8], after: &[u8]) {
+        let (segments_before, scan_before) = jpeg_layout(before);
+        let (segments_after, scan_after) = jpeg_layout(after);
+        assert!(scan_before == scan_after, "entropy-coded image data changed");
+        let non_metadata = |segments: Vec<(u8, &[u8])>| -> Vec<(u8, Vec<u8>)> {
+            segments.into_iter()
+                .filter(|(marker, _)| !matches!(marker, 0xE1 | 0xED))
+                .map(|(marker, bytes)| (marker, bytes.to_vec()))
+                .collect()
+        };
+        assert_eq!(non_metadata(segments_before), non_metadata(segments_after), "non-metadata segments changed");
+    }
+
+    #[test]
+    fn jpeg_writes_leave_image_data_untouched() {
+        let samples = ["test_canon_powershot.jpg", "test_exif.jpg", "test_gps.jpg", "test_mobile_exif.jpg", "test_serial.jpg"];
+        let mut ai = test_ai_result();
+        ai.gps = Some(GpsCoords { latitude: 46.5, longitude: 7.9, altitude: Some(1200.0) });
+        let fields = ExifFields { overwrite_existing: true, ..credit_fields() };
+        for name in samples {
+            for xmp_only in [false, true] {
+                let (_dir, path) = copy_to_temp(name);
+                let before = std::fs::read(&path).unwrap();
+                let existing = crate::exif::read_exif(&path).unwrap();
+                let result = write_exif(&path, &ai, &existing, &fields, false, ImageKind::Jpeg, xmp_only).unwrap();
+                assert!(result.title_written, "{name}");
+                let after = std::fs::read(&path).unwrap();
+                assert_ne!(before, after, "{name}: nothing written");
+                verify_pixels_unchanged(&before, &after);
+            }
+        }
+    }
+
+    /// Extract the payload of the first IPTC dataset `2:<dataset>` in `data`.
+    fn iptc_record(data: &[u8], dataset: u8) -> Option<&[u8]> {
+        let start = data.windows(3).position(|w| w == [0x1C, 0x02, dataset])?;
+        let len = u16::from_be_bytes([data[start + 3], data[start + 4]]) as usize;
+        data.get(start + 5..start + 5 + len)
+    }
+
+    #[test]
+    fn keywords_written_to_every_enabled_destination() {
+        let (_dir, path) = copy_to_temp("test_canon_powershot.jpg");
+        let ai = test_ai_result();
+        let fields = ExifFields { overwrite_existing: true, ..test_fields() };
+
+        let result = write_exif(&path, &ai, &ExifData::default(), &fields, false, ImageKind::Jpeg, false).unwrap();
+        assert!(result.tags_written);
+
+        let bytes = std::fs::read(&path).unwrap();
+        let text = String::from_utf8_lossy(&bytes);
+        assert_eq!(crate::exif::read_exif(&path).unwrap().keywords.as_deref(), Some("tag1; tag2; tag3"));
+        assert!(text.contains("<dc:subject><rdf:Bag>\n    <rdf:li>tag1</rdf:li>"));
+        assert!(text.contains("<MicrosoftPhoto:LastKeywordXMP><rdf:Bag>\n    <rdf:li>tag1</rdf:li>"));
+        assert!(text.contains("xmlns:MicrosoftPhoto=\"http://ns.microsoft.com/photo/1.0/\""));
+        assert_eq!(iptc_record(&bytes, 25), Some(&b"tag1"[..]));
+    }
+
+    #[test]
+    fn keyword_destinations_can_be_disabled() {
+        let (_dir, path) = copy_to_temp("test_canon_powershot.jpg");
+        let ai = test_ai_result();
+        let fields = ExifFields {
+            overwrite_existing: true,
+            keyword_targets: crate::config::KeywordTargets {
+                xp_keywords: false,
+                dc_subject: true,
+                microsoft_photo: false,
+                iptc: false,
+                lr_hierarchical_subject: false,
+            },
+            ..test_fields()
+        };
+
+        write_exif(&path, &ai, &ExifData::default(), &fields, false, ImageKind::Jpeg, false).unwrap();
+
+        let bytes = std::fs::read(&path).unwrap();
+        let text = String::from_utf8_lossy(&bytes);
+        // No XPKeywords; the keywords read back come from dc:subject
+        let xp_keywords = encode_utf16le("tag1");
+        assert!(!bytes.windows(xp_keywords.len()).any(|w| w == xp_keywords));
+        assert_eq!(crate::exif::read_exif(&path).unwrap().keywords.as_deref(), Some("tag1; tag2; tag3"));
+        assert!(text.contains("<dc:subject><rdf:Bag>"));
+        assert!(!text.contains("MicrosoftPhoto"));
+        assert!(iptc_record(&bytes, 25).is_none());
+        // Title still reaches IPTC
+        assert_eq!(iptc_record(&bytes, 5), Some(&b"Test Title"[..]));
+    }
+
+    #[test]
+    fn no_keyword_destinations_skips_tags() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("photo.heic");
+        std::fs::write(&path, b"fake heic").unwrap();
+        let fields = ExifFields {
+            keyword_targets: crate::config::KeywordTargets {
+                xp_keywords: false,
+                dc_subject: false,
+                microsoft_photo: false,
+                iptc: false,
+                lr_hierarchical_subject: false,
+            },
+            ..test_fields()
+        };
+
+        let result = write_exif(&path, &test_ai_result(), &ExifData::default(), &fields, false, ImageKind::Sidecar, false).unwrap();
+        assert!(!result.tags_written);
+        assert!(result.skipped_fields.contains(&SkippedField::new(Field::Tags, SkipReason::Disabled)));
+        let xmp = std::fs::read_to_string(result.sidecar_path.unwrap()).unwrap();
+        assert!(!xmp.contains("tag1"));
+    }
+
+    #[test]
+    fn iptc_date_time_formats() {
+        assert_eq!(
+            iptc_date_time("2003-12-14 12:01:44"),
+            Some(("20031214".into(), "120144+0000".into()))
+        );
+        assert_eq!(
+            iptc_date_time("2021-07-29T21:28:46+03:00"),
+            Some(("20210729".into(), "212846+0300".into()))
+        );
+        assert_eq!(
+            iptc_date_time("2003:12:14 12:01:44.250-0500"),
+            Some(("20031214".into(), "120144-0500".into()))
+        );
+        assert_eq!(iptc_date_time("2003-12-14"), None);
+        assert_eq!(iptc_date_time("not a date at all!!"), None);
+        assert_eq!(iptc_date_time("2003-12-14 12:01:44 PST"), None);
+    }
+
+    #[test]
+    fn build_iptc_contents_writes_dates_in_order() {
+        let created = ("20031214".to_string(), "120144+0000".to_string());
+        let keywords = vec!["tree".to_string()];
+        let data = build_iptc_contents(None, Some("t"), Some("d"), Some(&keywords), Some(&created), &MetadataDefaults::default(), Credits::default());
+
+        assert_eq!(iptc_record(&data, 0x37), Some(&b"20031214"[..]));
+        assert_eq!(iptc_record(&data, 0x3C), Some(&b"120144+0000"[..]));
+        let pos = |ds: u8| data.windows(3).position(|w| w == [0x1C, 0x02, ds]).unwrap();
+        assert!(pos(0x19) < pos(0x37) && pos(0x37) < pos(0x3C) && pos(0x3C) < pos(0x78));
+    }
+
+    fn newsroom_defaults() -> MetadataDefaults {
+        MetadataDefaults { instructions: Some("Embargoed until 18:00 <CET>".into()), urgency: Some(2), ..Default::default() }
+    }
+
+    #[test]
+    fn build_iptc_contents_writes_workflow_defaults() {
+        let keywords = vec!["tree".to_string()];
+        let data = build_iptc_contents(None, Some("t"), None, Some(&keywords), None, &newsroom_defaults(), Credits::default());
+
+        assert_eq!(iptc_record(&data, 0x0A), Some(&b"2"[..]));
+        assert_eq!(iptc_record(&data, 0x28), Some(&b"Embargoed until 18:00 <CET>"[..]));
+        let pos = |ds: u8| data.windows(3).position(|w| w == [0x1C, 0x02, ds]).unwrap();
+        assert!(pos(0x05) < pos(0x0A) && pos(0x0A) < pos(0x19) && pos(0x19) < pos(0x28));
+
+        let plain = build_iptc_contents(None, Some("t"), None, None, None, &MetadataDefaults::default(), Credits::default());
+        assert!(iptc_record(&plain, 0x0A).is_none());
+        assert!(iptc_record(&plain, 0x28).is_none());
+    }
+
+    #[test]
+    fn build_xmp_writes_workflow_defaults() {
+        let ai = test_ai_result();
+        let fields = ExifFields { metadata_defaults: newsroom_defaults(), ..test_fields() };
+        let alternatives = LangAlternatives::new(&ai, &fields);
+
+        let fresh = build_xmp(None, Some("Test Title"), None, None, None, &alternatives, &fields);
+        assert!(fresh.contains("<photoshop:Instructions>Embargoed until 18:00 &lt;CET&gt;</photoshop:Instructions>"));
+        assert!(fresh.contains("<photoshop:Urgency>2</photoshop:Urgency>"));
+
+        let existing = concat!(
+            "<x:xmpmeta xmlns:x=\"adobe:ns:meta/\"><rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\">",
+            "<rdf:Description rdf:about=\"\" xmlns:photoshop=\"http://ns.adobe.com/photoshop/1.0/\">\n",
+            "  <photoshop:Urgency>5</photoshop:Urgency>\n",
+            "</rdf:Description></rdf:RDF></x:xmpmeta>",
+        );
+        let injected = build_xmp(Some(existing), Some("Test Title"), None, None, None, &alternatives, &fields);
+        assert_eq!(injected.matches("<photoshop:Urgency>").count(), 1, "{injected}");
+        assert!(injected.contains("<photoshop:Urgency>2</photoshop:Urgency>"));
+        assert!(injected.contains("<photoshop:Instructions>"));
+
+        let plain = build_xmp(None, Some("Test Title"), None, None, None, &alternatives, &test_fields());
+        assert!(!plain.contains("photoshop:Instructions") && !plain.contains("photoshop:Urgency"));
+    }
+
+    #[test]
+    fn write_jpeg_workflow_defaults() {
+        let (_dir, path) = copy_to_temp("test_canon_powershot.jpg");
+        let existing = crate::exif::read_exif(&path).unwrap();
+        let fields = ExifFields { metadata_defaults: newsroom_defaults(), ..test_fields() };
+
+        write_exif(&path, &test_ai_result(), &existing, &fields, false, ImageKind::Jpeg, false).unwrap();
+
+        let jpeg = Jpeg::from_bytes(std::fs::read(&path).unwrap().into()).unwrap();
+        let iptc = jpeg_segment(&jpeg, 0xED, IPTC_HEADER).expect("APP13 segment should be written");
+        assert_eq!(iptc_record(&iptc, 0x0A), Some(&b"2"[..]));
+        assert_eq!(iptc_record(&iptc, 0x28), Some(&b"Embargoed until 18:00 <CET>"[..]));
+        let xmp = jpeg_segment(&jpeg, 0xE1, XMP_HEADER).expect("XMP segment should be written");
+        let xmp = String::from_utf8_lossy(&xmp[XMP_HEADER.len()..]).to_string();
+        assert!(xmp.contains("<photoshop:Urgency>2</photoshop:Urgency>"));
+    }
+
+    fn credit_fields() -> ExifFields {
+        let metadata_defaults = MetadataDefaults {
+            copyright: Some("(c) 2024 Jane Doe".into()),
+            artist: Some("Jane Doe".into()),
+            ..Default::default()
+        };
+        ExifFields { write_copyright: true, write_artist: true, metadata_defaults, ..test_fields() }
+    }
+
+    fn contains_bytes(haystack: &[u8], needle: &str) -> bool {
+        haystack.windows(needle.len()).any(|w| w == needle.as_bytes())
+    }
+
+    #[test]
+    fn credits_need_their_flag_and_a_value() {
+        let mut fields = credit_fields();
+        fields.write_artist = false;
+        fields.metadata_defaults.copyright = Some(" ".into());
+        assert!(Credits::new(&fields).is_empty());
+
+        let fields = credit_fields();
+        let credits = Credits::new(&fields);
+        assert_eq!(credits.copyright, Some("(c) 2024 Jane Doe"));
+        assert_eq!(credits.artist, Some("Jane Doe"));
+    }
+
+    #[test]
+    fn build_iptc_contents_writes_credits() {
+        let fields = credit_fields();
+        let data = build_iptc_contents(None, Some("t"), Some("d"), None, None, &MetadataDefaults::default(), Credits::new(&fields));
+
+        assert_eq!(iptc_record(&data, 0x50), Some(&b"Jane Doe"[..]));
+        assert_eq!(iptc_record(&data, 0x74), Some(&b"(c) 2024 Jane Doe"[..]));
+        let pos = |ds: u8| data.windows(3).position(|w| w == [0x1C, 0x02, ds]).unwrap();
+        assert!(pos(0x05) < pos(0x50) && pos(0x50) < pos(0x74) && pos(0x74) < pos(0x78));
+
+        let long = ExifFields { metadata_defaults: MetadataDefaults { artist: Some("x".repeat(40)), ..Default::default() }, ..credit_fields() };
+        let data = build_iptc_contents(None, None, None, None, None, &MetadataDefaults::default(), Credits::new(&long));
+        assert_eq!(iptc_record(&data, 0x50).map(<[u8]>::len), Some(IPTC_BYLINE_MAX));
+    }
+
+    #[test]
+    fn build_xmp_writes_credits() {
+        let ai = test_ai_result();
+        let fields = credit_fields();
+        let alternatives = LangAlternatives::new(&ai, &fields);
+
+        let fresh = build_xmp(None, None, None, None, None, &alternatives, &fields);
+        assert!(fresh.contains("<dc:rights><rdf:Alt><rdf:li xml:lang=\"x-default\">(c) 2024 Jane Doe</rdf:li></rdf:Alt></dc:rights>"), "{fresh}");
+        assert!(fresh.contains("<dc:creator><rdf:Seq><rdf:li>Jane Doe</rdf:li></rdf:Seq></dc:creator>"), "{fresh}");
+
+        let existing = concat!(
+            "<x:xmpmeta xmlns:x=\"adobe:ns:meta/\"><rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\">",
+            "<rdf:Description rdf:about=\"\" xmlns:dc=\"http://purl.org/dc/elements/1.1/\">\n",
+            "  <dc:creator><rdf:Seq><rdf:li>Someone Else</rdf:li></rdf:Seq></dc:creator>\n",
+            "</rdf:Description></rdf:RDF></x:xmpmeta>",
+        );
+        let injected = build_xmp(Some(existing), None, None, None, None, &alternatives, &fields);
+        assert_eq!(injected.matches("<dc:creator>").count(), 1, "{injected}");
+        assert!(injected.contains("<rdf:li>Jane Doe</rdf:li>") && !injected.contains("Someone Else"));
+        assert!(injected.contains("<dc:rights>"));
+
+        let plain = build_xmp(None, Some("Test Title"), None, None, None, &alternatives, &test_fields());
+        assert!(!plain.contains("dc:rights") && !plain.contains("dc:creator"));
+    }
+
+    #[test]
+    fn write_jpeg_credits_without_ai_output() {
+        for name in ["test_canon_powershot.jpg", "test_gps.jpg"] {
+            let (_dir, path) = copy_to_temp(name);
+            let existing = crate::exif::read_exif(&path).unwrap();
+
+            let result = write_exif(&path, &AiResult::default(), &existing, &credit_fields(), false, ImageKind::Jpeg, false).unwrap();
+            assert!(result.copyright_written && result.artist_written, "{name}");
+
+            let jpeg = Jpeg::from_bytes(std::fs::read(&path).unwrap().into()).unwrap();
+            let exif = jpeg.exif().expect("EXIF should be kept");
+            assert!(contains_bytes(&exif, "(c) 2024 Jane Doe"), "{name}: EXIF Copyright");
+            assert!(contains_bytes(&exif, "Jane Doe\0"), "{name}: EXIF Artist");
+            let iptc = jpeg_segment(&jpeg, 0xED, IPTC_HEADER).expect("APP13 segment should be written");
+            assert_eq!(iptc_record(&iptc, 0x74), Some(&b"(c) 2024 Jane Doe"[..]));
+            assert_eq!(iptc_record(&iptc, 0x50), Some(&b"Jane Doe"[..]));
+            let xmp = jpeg_segment(&jpeg, 0xE1, XMP_HEADER).expect("XMP segment should be written");
+            let xmp = String::from_utf8_lossy(&xmp[XMP_HEADER.len()..]).to_string();
+            assert!(xmp.contains("<dc:rights>") && xmp.contains("<dc:creator>"), "{name}");
+
+            let after = crate::exif::read_exif(&path).unwrap();
+            assert_eq!(after.make, existing.make, "{name}: camera info preserved");
+            assert_eq!(after.has_gps, existing.has_gps, "{name}: GPS preserved");
+            assert_eq!(after.copyright.as_deref(), Some("(c) 2024 Jane Doe"), "{name}");
+            assert_eq!(after.artist.as_deref(), Some("Jane Doe"), "{name}");
+        }
+    }
+
+    #[test]
+    fn existing_credits_are_kept_unless_overwriting() {
+        let (_dir, path) = copy_to_temp("test_canon_powershot.jpg");
+        let existing = crate::exif::read_exif(&path).unwrap();
+        write_exif(&path, &AiResult::default(), &existing, &credit_fields(), false, ImageKind::Jpeg, false).unwrap();
+
+        let mut fields = credit_fields();
+        fields.metadata_defaults.artist = Some("John Roe".into());
+        let existing = crate::exif::read_exif(&path).unwrap();
+        let result = write_exif(&path, &AiResult::default(), &existing, &fields, false, ImageKind::Jpeg, false).unwrap();
+        assert!(!result.artist_written && !result.copyright_written);
+        assert_eq!(crate::exif::read_exif(&path).unwrap().artist.as_deref(), Some("Jane Doe"));
+
+        fields.overwrite_existing = true;
+        let result = write_exif(&path, &AiResult::default(), &existing, &fields, false, ImageKind::Jpeg, false).unwrap();
+        assert!(result.artist_written);
+        assert_eq!(crate::exif::read_exif(&path).unwrap().artist.as_deref(), Some("John Roe"));
+    }
+
+    #[test]
+    fn write_tiff_credits_replace_existing_values() {
+        let (_dir, path) = copy_to_temp("test.tiff");
+        let mut fields = credit_fields();
+        write_exif(&path, &AiResult::default(), &ExifData::default(), &fields, false, ImageKind::Tiff, false).unwrap();
+
+        fields.metadata_defaults.artist = Some("John Roe".into());
+        let result = write_exif(&path, &AiResult::default(), &ExifData::default(), &fields, false, ImageKind::Tiff, false).unwrap();
+        assert!(result.artist_written);
+
+        let bytes = std::fs::read(&path).unwrap();
+        let ifd0 = tiff_ifd_offsets(&bytes)[0];
+        let big_endian = &bytes[..2] == b"MM";
+        let tags: Vec<u16> = (0..tiff_u16(&bytes, ifd0, big_endian) as usize)
+            .map(|i| tiff_u16(&bytes, ifd0 + 2 + i * 12, big_endian))
+            .collect();
+        assert_eq!(tags.iter().filter(|&&t| t == TAG_ARTIST).count(), 1, "Artist replaced, not duplicated");
+        assert_eq!(tags.iter().filter(|&&t| t == TAG_COPYRIGHT).count(), 1);
+        let artist = tags.iter().position(|&t| t == TAG_ARTIST).unwrap();
+        let offset = tiff_u32(&bytes, ifd0 + 2 + artist * 12 + 8, big_endian) as usize;
+        assert_eq!(&bytes[offset..offset + 9], b"John Roe\0");
+    }
+
+    #[test]
+    fn write_png_credits() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("photo.png");
+        image::RgbImage::new(8, 8).save(&path).unwrap();
+
+        let result = write_exif(&path, &AiResult::default(), &ExifData::default(), &credit_fields(), false, ImageKind::Png, false).unwrap();
+        assert!(result.copyright_written && result.artist_written);
+        let bytes = std::fs::read(&path).unwrap();
+        assert!(contains_bytes(&bytes, "<dc:rights>") && contains_bytes(&bytes, "<rdf:li>Jane Doe</rdf:li>"));
+    }
+
+    fn multilingual_ai_result() -> AiResult {
+        let titles = [("fr", "Tour Eiffel"), ("de", "Eiffelturm"), ("es", "Torre Eiffel")];
+        AiResult {
+            title: Some("Eiffel Tower".into()),
+            description: Some("The tower at dusk".into()),
+            titles: Some(titles.iter().map(|(l, t)| (l.to_string(), t.to_string())).collect()),
+            descriptions: Some([("fr".to_string(), "La tour & le ciel".to_string())].into()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn build_xmp_writes_language_alternatives() {
+        let ai = multilingual_ai_result();
+        let fields = ExifFields { languages: vec!["fr".into(), "DE".into(), "it".into()], ..test_fields() };
+        let alternatives = LangAlternatives::new(&ai, &fields);
+        let xmp = build_xmp(None, Some("Eiffel Tower"), Some("The tower at dusk"), None, None, &alternatives, &fields);
+
+        assert!(xmp.contains(concat!(
+            "<dc:title><rdf:Alt>",
+            "<rdf:li xml:lang=\"x-default\">Eiffel Tower</rdf:li>",
+            "<rdf:li xml:lang=\"fr\">Tour Eiffel</rdf:li>",
+            "<rdf:li xml:lang=\"DE\">Eiffelturm</rdf:li>",
+            "</rdf:Alt></dc:title>",
+        )));
+        assert!(xmp.contains("<rdf:li xml:lang=\"fr\">La tour &amp; le ciel</rdf:li></rdf:Alt></dc:description>"));
+        // Not configured, or not returned by the AI
+        assert!(!xmp.contains("Torre Eiffel"));
+        assert!(!xmp.contains("xml:lang=\"it\""));
+    }
+
+    #[test]
+    fn build_xmp_tags_title_and_description_with_language() {
+        let ai = AiResult { language: Some("de".into()), ..multilingual_ai_result() };
+        let fields = ExifFields { languages: vec!["fr".into(), "de".into()], ..test_fields() };
+        let alternatives = LangAlternatives::new(&ai, &fields);
+        let xmp = build_xmp(None, ai.title.as_deref(), ai.description.as_deref(), None, None, &alternatives, &fields);
+
+        assert!(xmp.contains(concat!(
+            "<dc:title><rdf:Alt>",
+            "<rdf:li xml:lang=\"x-default\">Eiffel Tower</rdf:li>",
+            "<rdf:li xml:lang=\"de\">Eiffel Tower</rdf:li>",
+            "<rdf:li xml:lang=\"fr\">Tour Eiffel</rdf:li>",
+            "</rdf:Alt></dc:title>",
+        )), "{xmp}");
+        assert!(xmp.contains("<rdf:li xml:lang=\"de\">The tower at dusk</rdf:li><rdf:li xml:lang=\"fr\">"));
+        // The translation for the primary language is not repeated
+        assert!(!xmp.contains("Eiffelturm"));
+    }
+
+    #[test]
+    fn build_xmp_exif_mirror_writes_both_namespaces() {
+        let ai = test_ai_result();
+        let fields = test_fields();
+        let mirrored = ExifFields { xmp_exif_mirror: true, ..test_fields() };
+        let alternatives = LangAlternatives::new(&ai, &fields);
+        let xmp = build_xmp(None, Some("Test Title"), Some("A test description"), None, None, &alternatives, &mirrored);
+
+        assert!(xmp.contains("<dc:title><rdf:Alt><rdf:li xml:lang=\"x-default\">Test Title</rdf:li></rdf:Alt></dc:title>"));
+        assert!(xmp.contains("<tiff:ImageDescription><rdf:Alt><rdf:li xml:lang=\"x-default\">Test Title</rdf:li></rdf:Alt></tiff:ImageDescription>"));
+        assert!(xmp.contains("<dc:description><rdf:Alt><rdf:li xml:lang=\"x-default\">A test description</rdf:li></rdf:Alt></dc:description>"));
+        assert!(xmp.contains("<exif:UserComment><rdf:Alt><rdf:li xml:lang=\"x-default\">A test description</rdf:li></rdf:Alt></exif:UserComment>"));
+        assert!(xmp.contains("xmlns:tiff=\"http://ns.adobe.com/tiff/1.0/\""));
+        assert!(xmp.contains("xmlns:exif=\"http://ns.adobe.com/exif/1.0/\""));
+
+        let plain = build_xmp(None, Some("Test Title"), Some("A test description"), None, None, &alternatives, &fields);
+        assert!(!plain.contains("tiff:"));
+        assert!(!plain.contains("exif:"));
+    }
+
+    #[test]
+    fn inject_exif_mirror_replaces_existing() {
+        let existing = concat!(
+            "<x:xmpmeta xmlns:x=\"adobe:ns:meta/\"><rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\">",
+            "<rdf:Description rdf:about=\"\" xmlns:exif=\"http://ns.adobe.com/exif/1.0/\">",
+            "<exif:UserComment><rdf:Alt><rdf:li xml:lang=\"x-default\">old comment</rdf:li></rdf:Alt></exif:UserComment>",
+            "</rdf:Description></rdf:RDF></x:xmpmeta>",
+        );
+        let ai = test_ai_result();
+        let fields = test_fields();
+        let mirrored = ExifFields { xmp_exif_mirror: true, ..test_fields() };
+        let alternatives = LangAlternatives::new(&ai, &fields);
+        let xmp = build_xmp(Some(existing), Some("Test Title"), Some("A test description"), None, None, &alternatives, &mirrored);
+
+        assert!(!xmp.contains("old comment"));
+        assert_eq!(xmp.matches("<exif:UserComment>").count(), 1);
+        assert!(xmp.contains(">A test description</rdf:li></rdf:Alt></exif:UserComment>"));
+        assert!(xmp.contains(">Test Title</rdf:li></rdf:Alt></tiff:ImageDescription>"));
+        assert_eq!(xmp.matches("xmlns:exif=").count(), 1);
+        assert!(xmp.contains("xmlns:tiff=\"http://ns.adobe.com/tiff/1.0/\""));
+    }
+
+    #[test]
+    fn build_xmp_default_is_single_x_default() {
+        let ai = multilingual_ai_result();
+        let fields = test_fields();
+        let xmp = build_xmp(None, Some("Eiffel Tower"), None, None, None, &LangAlternatives::new(&ai, &fields), &fields);
+        assert!(xmp.contains("<dc:title><rdf:Alt><rdf:li xml:lang=\"x-default\">Eiffel Tower</rdf:li></rdf:Alt></dc:title>"));
+        assert_eq!(xmp.matches("xml:lang").count(), 1);
+    }
+
+    #[test]
+    fn inject_existing_xmp_replaces_title_with_alternatives() {
+        let existing = "<x:xmpmeta><rdf:RDF><rdf:Description rdf:about=\"\">\n  <dc:title><rdf:Alt><rdf:li xml:lang=\"x-default\">Old</rdf:li></rdf:Alt></dc:title>\n</rdf:Description></rdf:RDF></x:xmpmeta>";
+        let ai = multilingual_ai_result();
+        let fields = ExifFields { languages: vec!["de".into()], ..test_fields() };
+        let xmp = build_xmp(Some(existing), Some("Eiffel Tower"), None, None, None, &LangAlternatives::new(&ai, &fields), &fields);
+
+        assert!(!xmp.contains(">Old<"));
+        assert_eq!(xmp.matches("<dc:title>").count(), 1);
+        assert!(xmp.contains("<rdf:li xml:lang=\"de\">Eiffelturm</rdf:li>"));
     }
 
+    /// XMP as written by Lightroom: a rating, a face region (with its own
+    /// nested rdf:Description) and a collection, plus the fields we replace.
+    const XMP_WITH_REGIONS: &str = r#"<x:xmpmeta xmlns:x="adobe:ns:meta/"><rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+<rdf:Description rdf:about=""
+  xmlns:xmp="http://ns.adobe.com/xap/1.0/"
+  xmlns:dc="http://purl.org/dc/elements/1.1/"
+  xmlns:lr="http://ns.adobe.com/lightroom/1.0/"
+  xmlns:mwg-rs="http://www.metadataworkinggroup.com/schemas/regions/"
+  xmlns:stArea="http://ns.adobe.com/xmp/sType/Area#"
+  xmp:Rating="4">
+  <dc:title><rdf:Alt><rdf:li xml:lang="x-default">Old</rdf:li></rdf:Alt></dc:title>
+  <dc:titleNote>keep me</dc:titleNote>
+  <mwg-rs:Regions rdf:parseType="Resource">
+   <mwg-rs:RegionList><rdf:Bag><rdf:li>
+    <rdf:Description mwg-rs:Name="Alice" mwg-rs:Type="Face">
+     <mwg-rs:Area stArea:x="0.5" stArea:y="0.4" stArea:w="0.2" stArea:h="0.3"/>
+    </rdf:Description>
+   </rdf:li></rdf:Bag></mwg-rs:RegionList>
+  </mwg-rs:Regions>
+  <lr:hierarchicalSubject><rdf:Bag><rdf:li>Trips|Paris</rdf:li></rdf:Bag></lr:hierarchicalSubject>
+</rdf:Description>
+</rdf:RDF></x:xmpmeta>"#;
+
     #[test]
-    fn sidecar_dry_run_no_file() {
-        let dir = TempDir::new().unwrap();
-        let path = dir.path().join("photo.cr3");
-        std::fs::write(&path, b"fake raw").unwrap();
+    fn inject_existing_xmp_keeps_rating_regions_and_collections() {
+        let ai = test_ai_result();
+        let fields = test_fields();
+        let xmp = build_xmp(Some(XMP_WITH_REGIONS), Some("New Title"), None, None, None, &LangAlternatives::new(&ai, &fields), &fields);
+
+        assert!(!xmp.contains(">Old<"));
+        assert_eq!(xmp.matches("<dc:title>").count(), 1);
+        assert!(xmp.contains("xmp:Rating=\"4\""));
+        assert!(xmp.contains("<dc:titleNote>keep me</dc:titleNote>"));
+        assert!(xmp.contains("<lr:hierarchicalSubject><rdf:Bag><rdf:li>Trips|Paris</rdf:li></rdf:Bag></lr:hierarchicalSubject>"));
+        let region_start = xmp.find("<mwg-rs:Regions").unwrap();
+        let region_end = xmp.find("</mwg-rs:Regions>").unwrap();
+        assert!(xmp[region_start..region_end].contains("mwg-rs:Name=\"Alice\""));
+        assert!(!xmp[region_start..region_end].contains("New Title"), "title must not land inside the region struct");
+        assert!(xmp.find(">New Title</rdf:li>").unwrap() > region_end);
+    }
 
-        let existing = ExifData::default();
+    #[test]
+    fn inject_existing_xmp_expands_self_closing_description() {
+        let existing = r#"<x:xmpmeta><rdf:RDF><rdf:Description rdf:about="" xmlns:xmp="http://ns.adobe.com/xap/1.0/" xmp:Rating="5"/></rdf:RDF></x:xmpmeta>"#;
         let ai = test_ai_result();
         let fields = test_fields();
+        let xmp = build_xmp(Some(existing), Some("New Title"), None, None, None, &LangAlternatives::new(&ai, &fields), &fields);
 
-        let result = write_exif(&path, &ai, &existing, &fields, true, ImageKind::Sidecar).unwrap();
+        assert!(xmp.contains("xmp:Rating=\"5\""));
+        let title = xmp.find(">New Title</rdf:li>").unwrap();
+        assert!(title < xmp.find("</rdf:Description>").unwrap());
+    }
 
-        // Dry run should not create sidecar
-        assert!(result.sidecar_path.is_none());
-        let xmp_path = path.with_extension("xmp");
-        assert!(!xmp_path.exists());
+    #[test]
+    fn remove_xml_element_matches_whole_name() {
+        let mut xml = "<dc:titleNote>a</dc:titleNote>\n<dc:title>b</dc:title>\n".to_string();
+        remove_xml_element(&mut xml, "dc:title");
+        assert_eq!(xml, "<dc:titleNote>a</dc:titleNote>\n");
+
+        let mut xml = "<dc:subject/>\n<other/>\n".to_string();
+        remove_xml_element(&mut xml, "dc:subject");
+        assert_eq!(xml, "<other/>\n");
     }
 
-    // ── encode_utf16le ───────────────────────────────────────────────
+    #[test]
+    fn lang_tag_validation() {
+        assert!(is_valid_lang_tag("fr"));
+        assert!(is_valid_lang_tag("pt-BR"));
+        assert!(!is_valid_lang_tag("x-default"));
+        assert!(!is_valid_lang_tag("fr\"><evil"));
+        assert!(!is_valid_lang_tag(""));
+    }
 
     #[test]
-    fn encode_utf16le_ascii() {
-        let bytes = encode_utf16le("ABC");
-        // A=0x41, B=0x42, C=0x43 in UTF-16LE + null terminator
-        assert_eq!(bytes, vec![0x41, 0x00, 0x42, 0x00, 0x43, 0x00, 0x00, 0x00]);
+    fn truncate_utf8_short_text_unchanged() {
+        assert_eq!(truncate_utf8("Sunset over the bay", 64), "Sunset over the bay");
     }
 
     #[test]
-    fn encode_utf16le_empty() {
-        let bytes = encode_utf16le("");
-        // Just null terminator
-        assert_eq!(bytes, vec![0x00, 0x00]);
+    fn truncate_utf8_prefers_word_boundary() {
+        assert_eq!(truncate_utf8("Sunset over the harbour", 19), "Sunset over the");
+        assert_eq!(truncate_utf8("Sunset over the harbour", 15), "Sunset over the");
     }
 
-    // ── collect_gps_tags ─────────────────────────────────────────────
+    #[test]
+    fn truncate_utf8_never_splits_characters() {
+        // "日" is 3 bytes; a 7-byte cut must back off to 6
+        assert_eq!(truncate_utf8("日本語", 7), "日本");
+        // "e" + combining acute must not lose its accent
+        assert_eq!(truncate_utf8("cafe\u{301}", 5), "caf");
+        // No dangling zero width joiner
+        assert_eq!(truncate_utf8("👨\u{200D}👩", 8), "👨");
+    }
 
     #[test]
-    fn collect_gps_tags_positive_coords() {
-        let mut tags = Vec::new();
-        let gps = GpsCoords { latitude: 48.8566, longitude: 2.3522 };
-        collect_gps_tags(&mut tags, &gps);
-        // Should produce 4 tags: lat_ref, lat, lon_ref, lon
-        assert_eq!(tags.len(), 4);
+    fn iptc_caption_multibyte_truncated_under_limit() {
+        // 2100 bytes: 300 × "日本 " (7 bytes each)
+        let description = "日本 ".repeat(300);
+        assert_eq!(description.len(), 2100);
+
+        let data = build_iptc_contents(None, None, Some(&description), None, None, &MetadataDefaults::default(), Credits::default());
+        let caption = iptc_record(&data, 0x78).unwrap();
+        assert!(caption.len() <= IPTC_CAPTION_MAX);
+        let caption = std::str::from_utf8(caption).expect("caption must be valid UTF-8");
+        assert!(caption.ends_with("日本"));
     }
 
     #[test]
-    fn collect_gps_tags_negative_coords() {
-        let mut tags = Vec::new();
-        let gps = GpsCoords { latitude: -33.8688, longitude: -118.2426 };
-        collect_gps_tags(&mut tags, &gps);
-        assert_eq!(tags.len(), 4);
+    fn iptc_title_and_keywords_truncated_on_char_boundary() {
+        let title = "é".repeat(40); // 80 bytes
+        let keywords = vec!["ü".repeat(33)]; // 66 bytes
+        let data = build_iptc_contents(None, Some(&title), None, Some(&keywords), None, &MetadataDefaults::default(), Credits::default());
+
+        let title = std::str::from_utf8(iptc_record(&data, 0x05).unwrap()).unwrap();
+        assert_eq!(title, "é".repeat(32));
+        let keyword = std::str::from_utf8(iptc_record(&data, 0x19).unwrap()).unwrap();
+        assert_eq!(keyword, "ü".repeat(32));
     }
 
-    // ── Write round-trip tests (real files from data/) ───────────────
+    #[test]
+    fn write_jpeg_iptc_dates() {
+        let (_dir, path) = copy_to_temp("test_canon_powershot.jpg");
+        let existing = crate::exif::read_exif(&path).unwrap();
+        let fields = ExifFields { write_iptc_dates: true, ..test_fields() };
 
-    fn data_path(name: &str) -> std::path::PathBuf {
-        std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("data").join(name)
+        write_exif(&path, &test_ai_result(), &existing, &fields, false, ImageKind::Jpeg, false).unwrap();
+
+        let jpeg = Jpeg::from_bytes(std::fs::read(&path).unwrap().into()).unwrap();
+        let app13 = jpeg.segments().iter()
+            .find(|s| s.marker() == 0xED && s.contents().starts_with(IPTC_HEADER))
+            .expect("APP13 segment should be written");
+        assert_eq!(iptc_record(app13.contents(), 0x37), Some(&b"20031214"[..]));
+        assert_eq!(iptc_record(app13.contents(), 0x3C), Some(&b"120144+0000"[..]));
     }
 
-    fn copy_to_temp(name: &str) -> (TempDir, std::path::PathBuf) {
-        let dir = TempDir::new().unwrap();
-        let src = data_path(name);
-        let dst = dir.path().join(name);
-        std::fs::copy(&src, &dst).unwrap();
-        (dir, dst)
+    #[test]
+    fn write_jpeg_iptc_dates_disabled_by_default() {
+        let (_dir, path) = copy_to_temp("test_canon_powershot.jpg");
+        let existing = crate::exif::read_exif(&path).unwrap();
+
+        write_exif(&path, &test_ai_result(), &existing, &test_fields(), false, ImageKind::Jpeg, false).unwrap();
+
+        let jpeg = Jpeg::from_bytes(std::fs::read(&path).unwrap().into()).unwrap();
+        let app13 = jpeg.segments().iter()
+            .find(|s| s.marker() == 0xED && s.contents().starts_with(IPTC_HEADER))
+            .unwrap();
+        assert!(iptc_record(app13.contents(), 0x37).is_none());
+    }
+
+    fn jpeg_segment(jpeg: &Jpeg, marker: u8, prefix: &[u8]) -> Option<Vec<u8>> {
+        jpeg.segments().iter()
+            .find(|s| s.marker() == marker && s.contents().starts_with(prefix))
+            .map(|s| s.contents().to_vec())
     }
 
     #[test]
-    fn write_jpeg_round_trip_canon() {
+    fn write_jpeg_xmp_only_leaves_exif_and_iptc() {
         let (_dir, path) = copy_to_temp("test_canon_powershot.jpg");
+        let before = Jpeg::from_bytes(std::fs::read(&path).unwrap().into()).unwrap();
+        let exif_before = jpeg_segment(&before, 0xE1, b"Exif\0\0").unwrap();
+        let iptc_before = jpeg_segment(&before, 0xED, IPTC_HEADER);
 
         let existing = crate::exif::read_exif(&path).unwrap();
-        assert!(existing.title.is_none()); // no title yet
-        // Canon file has a non-empty description ("Undefined[0x00...]"), so writer skips it
-        assert!(existing.description.is_some());
+        let result = write_exif(&path, &test_ai_result(), &existing, &test_fields(), false, ImageKind::Jpeg, true).unwrap();
+        assert!(result.title_written);
 
-        let ai = test_ai_result();
-        let fields = test_fields();
+        let after = Jpeg::from_bytes(std::fs::read(&path).unwrap().into()).unwrap();
+        assert_eq!(jpeg_segment(&after, 0xE1, b"Exif\0\0").unwrap(), exif_before);
+        assert_eq!(jpeg_segment(&after, 0xED, IPTC_HEADER), iptc_before);
+
+        let xmp = jpeg_segment(&after, 0xE1, XMP_HEADER).expect("XMP segment should be written");
+        let xmp = String::from_utf8_lossy(&xmp[XMP_HEADER.len()..]).to_string();
+        assert!(xmp.contains(">Test Title</rdf:li>"));
+        assert!(xmp.contains("<rdf:li>tag1</rdf:li>"));
+
+        // EXIF has no title, so the XMP one reads back
+        let reread = crate::exif::read_exif(&path).unwrap();
+        assert_eq!(existing.title, None);
+        assert_eq!(reread.title.as_deref(), Some("Test Title"));
+        assert_eq!(reread.make, existing.make);
+    }
 
-        let result = write_exif(&path, &ai, &existing, &fields, false, ImageKind::Jpeg).unwrap();
+    #[test]
+    fn write_jpeg_overwrite_keeps_unrelated_xmp() {
+        let (_dir, path) = copy_to_temp("test_canon_powershot.jpg");
+        let mut jpeg = Jpeg::from_bytes(std::fs::read(&path).unwrap().into()).unwrap();
+        let mut contents = XMP_HEADER.to_vec();
+        contents.extend_from_slice(XMP_WITH_REGIONS.as_bytes());
+        jpeg.segments_mut().insert(2, JpegSegment::new_with_contents(0xE1, Bytes::from(contents)));
+        std::fs::write(&path, jpeg.encoder().bytes()).unwrap();
+
+        let existing = crate::exif::read_exif(&path).unwrap();
+        let mut fields = test_fields();
+        fields.overwrite_existing = true;
+        let result = write_exif(&path, &test_ai_result(), &existing, &fields, false, ImageKind::Jpeg, false).unwrap();
         assert!(result.title_written);
-        assert!(!result.description_written); // skipped — existing description present
-        assert!(result.tags_written);
-        assert!(result.subject_written);
-        assert!(!result.gps_written); // no GPS in ai_result
 
-        // Read back and verify AI metadata was written
-        let after = crate::exif::read_exif(&path).unwrap();
-        // Original camera data should be preserved
-        assert_eq!(after.make.as_deref(), Some("Canon"));
-        assert_eq!(after.model.as_deref(), Some("Canon PowerShot S40"));
-        // AI-written title should be present
-        assert!(after.title.is_some(), "title should be written");
+        let after = Jpeg::from_bytes(std::fs::read(&path).unwrap().into()).unwrap();
+        let xmp = jpeg_segment(&after, 0xE1, XMP_HEADER).unwrap();
+        let xmp = String::from_utf8_lossy(&xmp[XMP_HEADER.len()..]).to_string();
+        assert!(xmp.contains(">Test Title</rdf:li>"));
+        assert!(!xmp.contains(">Old<"));
+        assert!(xmp.contains("xmp:Rating=\"4\""));
+        assert!(xmp.contains("mwg-rs:Name=\"Alice\""));
+        assert!(xmp.contains("<mwg-rs:Area stArea:x=\"0.5\""));
+        assert!(xmp.contains("Trips|Paris"));
+    }
+
+    #[test]
+    fn write_tiff_xmp_only_uses_sidecar() {
+        let (_dir, path) = copy_to_temp("test.tiff");
+        let original = std::fs::read(&path).unwrap();
+        let existing = crate::exif::read_exif(&path).unwrap();
+
+        let result = write_exif(&path, &test_ai_result(), &existing, &test_fields(), false, ImageKind::Tiff, true).unwrap();
+
+        assert_eq!(std::fs::read(&path).unwrap(), original);
+        let sidecar = result.sidecar_path.expect("TIFF XMP goes to a sidecar");
+        assert!(std::fs::read_to_string(sidecar).unwrap().contains("Test Title"));
+    }
+
+    #[test]
+    fn out_of_range_gps_is_skipped_as_invalid() {
+        let (_dir, path) = copy_to_temp("test_canon_powershot.jpg");
+        let existing = crate::exif::read_exif(&path).unwrap();
+        assert!(!existing.has_gps);
+
+        for (latitude, longitude) in [(200.0, 2.35), (48.85, -181.0), (f64::NAN, 2.35)] {
+            let mut ai = test_ai_result();
+            ai.gps = Some(GpsCoords { latitude, longitude, altitude: None });
+            let result = write_exif(&path, &ai, &existing, &test_fields(), false, ImageKind::Jpeg, false).unwrap();
+            assert!(result.title_written);
+            assert!(!result.gps_written);
+            let skipped = SkippedField::new(Field::Gps, SkipReason::Invalid);
+            assert!(result.skipped_fields.contains(&skipped), "{:?}", result.skipped_fields);
+            assert_eq!(skipped.to_string(), "gps (invalid coordinates)");
+        }
+        assert!(!crate::exif::read_exif(&path).unwrap().has_gps);
+    }
+
+    #[test]
+    fn null_island_gps_is_treated_as_unset() {
+        let (_dir, path) = copy_to_temp("test_canon_powershot.jpg");
+        let existing = crate::exif::read_exif(&path).unwrap();
+
+        let mut ai = test_ai_result();
+        ai.gps = Some(GpsCoords { latitude: 0.0, longitude: 0.0, altitude: Some(10.0) });
+        let result = write_exif(&path, &ai, &existing, &test_fields(), false, ImageKind::Jpeg, false).unwrap();
+        assert!(!result.gps_written);
+        assert!(!result.skipped_fields.iter().any(|s| s.field == Field::Gps));
+        assert!(!crate::exif::read_exif(&path).unwrap().has_gps);
     }
 
     #[test]
@@ -1965,11 +4522,11 @@ mod tests {
         assert!(existing.has_gps); // already has GPS
 
         let mut ai = test_ai_result();
-        ai.gps = Some(GpsCoords { latitude: 0.0, longitude: 0.0 });
+        ai.gps = Some(GpsCoords { latitude: 48.8566, longitude: 2.3522, altitude: None });
 
         let fields = test_fields();
 
-        let result = write_exif(&path, &ai, &existing, &fields, false, ImageKind::Jpeg).unwrap();
+        let result = write_exif(&path, &ai, &existing, &fields, false, ImageKind::Jpeg, false).unwrap();
         assert!(result.title_written);
         assert!(!result.gps_written); // should skip — existing GPS
 
@@ -1992,11 +4549,11 @@ mod tests {
         assert!(!existing.has_gps); // Jolla has no GPS
 
         let mut ai = test_ai_result();
-        ai.gps = Some(GpsCoords { latitude: 48.8566, longitude: 2.3522 });
+        ai.gps = Some(GpsCoords { latitude: 48.8566, longitude: 2.3522, altitude: None });
 
         let fields = test_fields();
 
-        let result = write_exif(&path, &ai, &existing, &fields, false, ImageKind::Jpeg).unwrap();
+        let result = write_exif(&path, &ai, &existing, &fields, false, ImageKind::Jpeg, false).unwrap();
         assert!(result.title_written);
         assert!(result.gps_written);
 
@@ -2007,8 +4564,91 @@ mod tests {
         assert!(after.has_gps, "GPS should be written");
         let lat = after.gps_latitude.unwrap();
         let lon = after.gps_longitude.unwrap();
-        assert!((lat - 48.8566).abs() < 0.01, "lat={lat}");
-        assert!((lon - 2.3522).abs() < 0.01, "lon={lon}");
+        assert!((lat - 48.8566).abs() < 1e-5, "lat={lat}");
+        assert!((lon - 2.3522).abs() < 1e-5, "lon={lon}");
+    }
+
+    #[test]
+    fn gps_altitude_round_trips() {
+        for (name, kind) in [("test_exif.jpg", ImageKind::Jpeg), ("test.tiff", ImageKind::Tiff)] {
+            for altitude in [8848.86, -430.5] {
+                let (_dir, path) = copy_to_temp(name);
+                let existing = crate::exif::read_exif(&path).unwrap();
+                let mut ai = test_ai_result();
+                ai.gps = Some(GpsCoords { latitude: 27.988, longitude: 86.925, altitude: Some(altitude) });
+
+                let result = write_exif(&path, &ai, &existing, &test_fields(), false, kind, false).unwrap();
+                assert!(result.gps_written);
+
+                let after = crate::exif::read_exif(&path).unwrap();
+                let read = after.gps_altitude.unwrap_or_else(|| panic!("{name}: no altitude"));
+                assert!((read - altitude).abs() < 0.01, "{name}: {read} != {altitude}");
+                assert_eq!(after.gps_altitude_ref, Some(if altitude < 0.0 { 1 } else { 0 }));
+                assert!((after.gps_latitude.unwrap() - 27.988).abs() < 0.001);
+            }
+        }
+    }
+
+    #[test]
+    fn gps_without_altitude_writes_no_altitude() {
+        let (_dir, path) = copy_to_temp("test_exif.jpg");
+        let existing = crate::exif::read_exif(&path).unwrap();
+        let mut ai = test_ai_result();
+        ai.gps = Some(GpsCoords { latitude: 48.8566, longitude: 2.3522, altitude: None });
+
+        write_exif(&path, &ai, &existing, &test_fields(), false, ImageKind::Jpeg, false).unwrap();
+        let after = crate::exif::read_exif(&path).unwrap();
+        assert!(after.has_gps);
+        assert_eq!((after.gps_altitude, after.gps_altitude_ref), (None, None));
+    }
+
+    #[test]
+    fn collect_gps_tags_with_altitude() {
+        let mut tags = Vec::new();
+        let gps = GpsCoords { latitude: 27.988, longitude: 86.925, altitude: Some(8848.86) };
+        collect_gps_tags(&mut tags, &gps);
+        assert_eq!(tags.len(), 8);
+        assert_eq!(encode_gps_altitude(-430.5), (1, [43050u32.to_le_bytes(), 100u32.to_le_bytes()].concat()));
+    }
+
+    /// The `(tag, value bytes)` of each BYTE/ASCII entry in the GPS IFD of a JPEG's EXIF.
+    fn gps_ifd_entries(path: &Path) -> Vec<(u16, Vec<u8>)> {
+        let jpeg = Jpeg::from_bytes(std::fs::read(path).unwrap().into()).unwrap();
+        let tiff = jpeg.exif().expect("EXIF segment");
+        let be = tiff.starts_with(b"MM");
+        let u16_at = |o: usize| if be { u16::from_be_bytes([tiff[o], tiff[o + 1]]) } else { u16::from_le_bytes([tiff[o], tiff[o + 1]]) };
+        let u32_at = |o: usize| {
+            let b = [tiff[o], tiff[o + 1], tiff[o + 2], tiff[o + 3]];
+            (if be { u32::from_be_bytes(b) } else { u32::from_le_bytes(b) }) as usize
+        };
+        let entries = |ifd: usize| (0..u16_at(ifd) as usize).map(move |i| ifd + 2 + i * 12);
+
+        let ifd0 = u32_at(4);
+        let gps = entries(ifd0).find(|&e| u16_at(e) == 0x8825).map(|e| u32_at(e + 8)).expect("GPS IFD pointer");
+        entries(gps)
+            .filter(|&e| matches!(u16_at(e + 2), 1 | 2))
+            .map(|e| {
+                let count = u32_at(e + 4);
+                let at = if count <= 4 { e + 8 } else { u32_at(e + 8) };
+                (u16_at(e), tiff[at..at + count].to_vec())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn write_jpeg_gps_includes_version_and_datum() {
+        let (_dir, path) = copy_to_temp("test_exif.jpg");
+        let existing = crate::exif::read_exif(&path).unwrap();
+        let mut ai = test_ai_result();
+        ai.gps = Some(GpsCoords { latitude: 48.8566, longitude: 2.3522, altitude: None });
+
+        write_exif(&path, &ai, &existing, &test_fields(), false, ImageKind::Jpeg, false).unwrap();
+
+        let entries = gps_ifd_entries(&path);
+        assert!(entries.contains(&(TAG_GPS_VERSION_ID, vec![2, 3, 0, 0])), "{entries:?}");
+        assert!(entries.contains(&(TAG_GPS_MAP_DATUM, b"WGS-84\0".to_vec())), "{entries:?}");
+        let tags: Vec<u16> = entries.iter().map(|(tag, _)| *tag).collect();
+        assert!(tags.is_sorted(), "GPS IFD entries must be in tag order: {tags:?}");
     }
 
     #[test]
@@ -2022,7 +4662,7 @@ mod tests {
         let ai = test_ai_result(); // no GPS in AI result
         let fields = test_fields();
 
-        let result = write_exif(&path, &ai, &existing, &fields, false, ImageKind::Jpeg).unwrap();
+        let result = write_exif(&path, &ai, &existing, &fields, false, ImageKind::Jpeg, false).unwrap();
         assert!(result.title_written);
         assert!(!result.gps_written); // skipped — existing GPS
 
@@ -2046,7 +4686,7 @@ mod tests {
         let existing = crate::exif::read_exif(&path).unwrap();
         let ai = test_ai_result();
         let fields = test_fields();
-        write_exif(&path, &ai, &existing, &fields, false, ImageKind::Jpeg).unwrap();
+        write_exif(&path, &ai, &existing, &fields, false, ImageKind::Jpeg, false).unwrap();
 
         // Second write with different AI data — should skip because overwrite=false
         let existing2 = crate::exif::read_exif(&path).unwrap();
@@ -2058,9 +4698,10 @@ mod tests {
             tags: Some(vec!["overwritten".into()]),
             gps: None,
             subject: Some(vec!["overwritten".into()]),
+            ..Default::default()
         };
 
-        let result2 = write_exif(&path, &ai2, &existing2, &fields, false, ImageKind::Jpeg).unwrap();
+        let result2 = write_exif(&path, &ai2, &existing2, &fields, false, ImageKind::Jpeg, false).unwrap();
         assert!(!result2.title_written);
         assert!(!result2.description_written);
 
@@ -2070,21 +4711,202 @@ mod tests {
     }
 
     #[test]
-    fn write_tiff_unsupported_variant_errors() {
-        // This TIFF variant is not supported by little_exif for writing
+    fn write_tiff_single_page() {
         let (_dir, path) = copy_to_temp("test.tiff");
 
         let existing = crate::exif::read_exif(&path).unwrap();
         let ai = test_ai_result();
         let fields = test_fields();
 
-        let result = write_exif(&path, &ai, &existing, &fields, false, ImageKind::Tiff);
-        assert!(result.is_err(), "little_exif should fail on this TIFF variant");
+        let result = write_exif(&path, &ai, &existing, &fields, false, ImageKind::Tiff, false).unwrap();
+        assert!(result.title_written);
 
-        // Original file should be unchanged (write failed before modifying)
         let after = crate::exif::read_exif(&path).unwrap();
+        assert_eq!(after.title.as_deref(), Some("Test Title"));
         assert_eq!(after.image_width.as_deref(), Some("635"));
         assert_eq!(after.image_height.as_deref(), Some("348"));
+        let img = image::open(&path).expect("TIFF should still decode");
+        assert_eq!((img.width(), img.height()), (635, 348));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn write_tiff_through_symlink() {
+        let (dir, target) = copy_to_temp("test.tiff");
+        let link = dir.path().join("link.tiff");
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+
+        let existing = crate::exif::read_exif(&link).unwrap();
+        write_exif(&link, &test_ai_result(), &existing, &test_fields(), false, ImageKind::Tiff, false).unwrap();
+
+        assert!(std::fs::symlink_metadata(&link).unwrap().file_type().is_symlink());
+        assert_eq!(crate::exif::read_exif(&target).unwrap().title.as_deref(), Some("Test Title"));
+    }
+
+    #[test]
+    fn tiff_interrupted_write_keeps_original() {
+        let (dir, path) = copy_to_temp("test.tiff");
+        let original = std::fs::read(&path).unwrap();
+
+        let existing = crate::exif::read_exif(&path).unwrap();
+        let merged = build_tiff_with_tags(&path, &test_ai_result(), &existing, &test_fields()).unwrap();
+
+        // Simulate the process failing halfway through the new TIFF
+        let err = write_atomic_with(&path, |file| {
+            file.write_all(&merged[..merged.len() / 2])?;
+            Err(std::io::Error::other("interrupted"))
+        })
+        .unwrap_err();
+        assert_eq!(err.to_string(), "interrupted");
+
+        assert_eq!(std::fs::read(&path).unwrap(), original);
+        let leftovers: Vec<_> = std::fs::read_dir(dir.path()).unwrap().collect();
+        assert_eq!(leftovers.len(), 1, "temp file should be cleaned up");
+    }
+
+    #[test]
+    fn jpeg_interrupted_write_keeps_original() {
+        let (dir, path) = copy_to_temp("test_canon_powershot.jpg");
+        let original = std::fs::read(&path).unwrap();
+
+        // Simulate the process failing halfway through the new JPEG
+        let err = write_atomic_with(&path, |file| {
+            file.write_all(&original[..original.len() / 2])?;
+            Err(std::io::Error::other("interrupted"))
+        })
+        .unwrap_err();
+        assert_eq!(err.to_string(), "interrupted");
+
+        assert_eq!(std::fs::read(&path).unwrap(), original);
+        let leftovers: Vec<_> = std::fs::read_dir(dir.path()).unwrap().collect();
+        assert_eq!(leftovers.len(), 1, "temp file should be cleaned up");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn native_writes_replace_the_file_and_keep_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        for (name, kind) in [("test_canon_powershot.jpg", ImageKind::Jpeg), ("test.png", ImageKind::Png), ("test.webp", ImageKind::WebP)] {
+            let (dir, path) = match name {
+                "test_canon_powershot.jpg" => copy_to_temp(name),
+                _ => {
+                    let dir = tempfile::TempDir::new().unwrap();
+                    let path = dir.path().join(name);
+                    let img = image::RgbImage::new(2, 2);
+                    img.save(&path).unwrap();
+                    (dir, path)
+                }
+            };
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o640)).unwrap();
+            let existing = crate::exif::read_exif(&path).unwrap_or_default();
+            write_exif(&path, &test_ai_result(), &existing, &test_fields(), false, kind, false).unwrap();
+
+            assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o640, "{name}");
+            let entries: Vec<_> = std::fs::read_dir(dir.path()).unwrap().collect();
+            assert_eq!(entries.len(), 1, "{name}: no temp file left behind");
+        }
+    }
+
+    /// Build a minimal little-endian TIFF with `pages` 1×1 grayscale pages.
+    /// Page `i` holds the single pixel value `0x10 * (i + 1)`.
+    fn make_multipage_tiff(pages: usize) -> Vec<u8> {
+        const ENTRIES: usize = 8;
+        const PAGE_LEN: usize = 2 + 2 + ENTRIES * 12 + 4; // pixel + pad + IFD
+
+        let mut data = b"II*\0".to_vec();
+        data.extend_from_slice(&(8u32 + 2).to_le_bytes());
+
+        for i in 0..pages {
+            let pixel_off = 8 + i * PAGE_LEN;
+            data.push(0x10 * (i as u8 + 1));
+            data.push(0);
+
+            let short = |tag: u16, val: u16| {
+                let mut e = Vec::with_capacity(12);
+                e.extend_from_slice(&tag.to_le_bytes());
+                e.extend_from_slice(&3u16.to_le_bytes());
+                e.extend_from_slice(&1u32.to_le_bytes());
+                e.extend_from_slice(&(val as u32).to_le_bytes());
+                e
+            };
+            let long = |tag: u16, val: u32| {
+                let mut e = Vec::with_capacity(12);
+                e.extend_from_slice(&tag.to_le_bytes());
+                e.extend_from_slice(&4u16.to_le_bytes());
+                e.extend_from_slice(&1u32.to_le_bytes());
+                e.extend_from_slice(&val.to_le_bytes());
+                e
+            };
+
+            data.extend_from_slice(&(ENTRIES as u16).to_le_bytes());
+            data.extend(short(0x0100, 1)); // ImageWidth
+            data.extend(short(0x0101, 1)); // ImageLength
+            data.extend(short(0x0102, 8)); // BitsPerSample
+            data.extend(short(0x0103, 1)); // Compression: none
+            data.extend(short(0x0106, 1)); // PhotometricInterpretation: BlackIsZero
+            data.extend(long(0x0111, pixel_off as u32)); // StripOffsets
+            data.extend(short(0x0116, 1)); // RowsPerStrip
+            data.extend(long(0x0117, 1)); // StripByteCounts
+
+            let next = if i + 1 < pages { (pixel_off + PAGE_LEN + 2) as u32 } else { 0 };
+            data.extend_from_slice(&next.to_le_bytes());
+        }
+
+        data
+    }
+
+    /// Read the single strip byte of the page whose IFD starts at `ifd`.
+    fn tiff_page_pixel(data: &[u8], ifd: usize) -> u8 {
+        let count = u16::from_le_bytes([data[ifd], data[ifd + 1]]) as usize;
+        for i in 0..count {
+            let e = ifd + 2 + i * 12;
+            if u16::from_le_bytes([data[e], data[e + 1]]) == 0x0111 {
+                let off = u32::from_le_bytes([data[e + 8], data[e + 9], data[e + 10], data[e + 11]]);
+                return data[off as usize];
+            }
+        }
+        panic!("page at {ifd} has no StripOffsets");
+    }
+
+    #[test]
+    fn tiff_ifd_offsets_counts_pages() {
+        assert_eq!(tiff_ifd_offsets(&make_multipage_tiff(1)).len(), 1);
+        assert_eq!(tiff_ifd_offsets(&make_multipage_tiff(3)).len(), 3);
+        assert!(tiff_ifd_offsets(b"not a tiff").is_empty());
+    }
+
+    #[test]
+    fn write_multipage_tiff_preserves_other_pages() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("scan.tiff");
+        let original = make_multipage_tiff(2);
+        std::fs::write(&path, &original).unwrap();
+        let orig_offsets = tiff_ifd_offsets(&original);
+
+        let existing = crate::exif::read_exif(&path).unwrap();
+        let ai = test_ai_result();
+        let fields = test_fields();
+
+        let result = write_exif(&path, &ai, &existing, &fields, false, ImageKind::Tiff, false).unwrap();
+        assert!(result.title_written);
+
+        let after = std::fs::read(&path).unwrap();
+        let offsets = tiff_ifd_offsets(&after);
+        assert_eq!(offsets.len(), 2, "both pages must still be chained");
+
+        // Page 1 is untouched: same IFD, same entries, same pixel data
+        assert_eq!(offsets[1], orig_offsets[1]);
+        let ifd1 = orig_offsets[1];
+        assert_eq!(after[ifd1..ifd1 + 102], original[ifd1..ifd1 + 102]);
+        assert_eq!(tiff_page_pixel(&after, offsets[1]), 0x20);
+
+        // Page 0 still decodes and now carries the metadata
+        assert_eq!(tiff_page_pixel(&after, offsets[0]), 0x10);
+        let img = image::load_from_memory(&after).expect("page 0 should still decode");
+        assert_eq!((img.width(), img.height()), (1, 1));
+        let data = crate::exif::read_exif(&path).unwrap();
+        assert_eq!(data.title.as_deref(), Some("Test Title"));
     }
 
     #[test]
@@ -2096,7 +4918,7 @@ mod tests {
         let fields = test_fields();
 
         // Dry run should succeed even for unsupported TIFF
-        let result = write_exif(&path, &ai, &existing, &fields, true, ImageKind::Tiff).unwrap();
+        let result = write_exif(&path, &ai, &existing, &fields, true, ImageKind::Tiff, false).unwrap();
         assert!(result.title_written); // dry run reports what would be written
     }
 
@@ -2110,7 +4932,7 @@ mod tests {
         let ai = test_ai_result();
         let fields = test_fields();
 
-        let result = write_exif(&path, &ai, &existing, &fields, false, ImageKind::Sidecar).unwrap();
+        let result = write_exif(&path, &ai, &existing, &fields, false, ImageKind::Sidecar, false).unwrap();
         assert!(result.sidecar_path.is_some());
 
         let sidecar = result.sidecar_path.unwrap();
@@ -2141,7 +4963,9 @@ mod tests {
         assert!(before.has_gps);
 
         // Clear it
-        clear_exif(&path, ImageKind::Jpeg).unwrap();
+        let result = clear_exif(&path, ImageKind::Jpeg).unwrap();
+        assert!(result.exif_removed);
+        assert_eq!(result.sidecar_removed, None);
 
         // Read back — all metadata should be gone
         let after = crate::exif::read_exif(&path).unwrap();
@@ -2160,8 +4984,10 @@ mod tests {
         let (_dir, path) = copy_to_temp("test_canon_powershot.jpg");
 
         let size_before = std::fs::metadata(&path).unwrap().len();
+        let pixels_before = image::open(&path).unwrap().to_rgb8();
 
         clear_exif(&path, ImageKind::Jpeg).unwrap();
+        assert_eq!(image::open(&path).unwrap().to_rgb8(), pixels_before, "pixels must be untouched");
 
         // File should be smaller (metadata removed) but still valid
         let size_after = std::fs::metadata(&path).unwrap().len();
@@ -2188,14 +5014,105 @@ mod tests {
     }
 
     #[test]
-    fn clear_exif_tiff_returns_error() {
+    fn clear_exif_jpeg_removes_xmp_and_iptc() {
+        let (_dir, path) = copy_to_temp("test_exif.jpg");
+        write_exif(&path, &test_ai_result(), &ExifData::default(), &test_fields(), false, ImageKind::Jpeg, false).unwrap();
+
+        let result = clear_exif(&path, ImageKind::Jpeg).unwrap();
+        assert_eq!(result.removed_blocks(), vec!["EXIF", "XMP", "IPTC"]);
+
+        let jpeg = Jpeg::from_bytes(std::fs::read(&path).unwrap().into()).unwrap();
+        assert!(jpeg.segments().iter().all(|seg| !matches!(seg.marker(), 0xE1 | 0xED)));
+        let after = crate::exif::read_exif(&path).unwrap();
+        assert!(after.title.is_none());
+        assert!(after.keywords.is_none());
+    }
+
+    #[test]
+    fn clear_exif_png_removes_exif_xmp_and_text() {
+        use img_parts::png::{Png, PngChunk};
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("photo.png");
+        image::RgbImage::from_fn(8, 8, |x, y| image::Rgb([x as u8 * 30, y as u8 * 30, 7])).save(&path).unwrap();
+        let pixels_before = image::open(&path).unwrap().to_rgb8();
+        write_exif(&path, &test_ai_result(), &ExifData::default(), &test_fields(), false, ImageKind::Png, false).unwrap();
+
+        let mut png = Png::from_bytes(std::fs::read(&path).unwrap().into()).unwrap();
+        png.set_exif(Some(Bytes::from_static(b"II*\0\x08\0\0\0\0\0\0\0\0\0")));
+        let idat = png.chunks().iter().position(|c| c.kind() == *b"IDAT").unwrap();
+        png.chunks_mut().insert(idat, PngChunk::new(*b"tEXt", Bytes::from_static(b"Author\0Someone")));
+        std::fs::write(&path, png.encoder().bytes()).unwrap();
+
+        let result = clear_exif(&path, ImageKind::Png).unwrap();
+        assert_eq!(result.removed_blocks(), vec!["EXIF", "XMP", "text"]);
+
+        let png = Png::from_bytes(std::fs::read(&path).unwrap().into()).unwrap();
+        assert!(png.chunks().iter().all(|c| !matches!(&c.kind(), b"eXIf" | b"tEXt" | b"zTXt" | b"iTXt")));
+        assert_eq!(image::open(&path).unwrap().to_rgb8(), pixels_before, "pixels must be untouched");
+    }
+
+    #[test]
+    fn clear_exif_webp_removes_exif_and_xmp() {
+        use img_parts::webp::WebP;
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("photo.webp");
+        image::RgbImage::from_fn(8, 8, |x, y| image::Rgb([x as u8 * 30, y as u8 * 30, 7])).save(&path).unwrap();
+        let pixels_before = image::open(&path).unwrap().to_rgb8();
+        write_exif(&path, &test_ai_result(), &ExifData::default(), &test_fields(), false, ImageKind::WebP, false).unwrap();
+
+        let result = clear_exif(&path, ImageKind::WebP).unwrap();
+        assert_eq!(result.removed_blocks(), vec!["EXIF", "XMP"]);
+
+        let webp = WebP::from_bytes(std::fs::read(&path).unwrap().into()).unwrap();
+        assert!(webp.exif().is_none());
+        assert!(webp.chunk_by_id(*b"XMP ").is_none());
+        assert_eq!(image::open(&path).unwrap().to_rgb8(), pixels_before, "pixels must be untouched");
+    }
+
+    #[test]
+    fn clear_exif_tiff_strips_metadata_tags() {
         let (_dir, path) = copy_to_temp("test.tiff");
-        let result = clear_exif(&path, ImageKind::Tiff);
-        assert!(result.is_err(), "clearing TIFF EXIF should error");
-        assert!(
-            result.unwrap_err().to_string().contains("not supported"),
-            "error should mention not supported"
-        );
+        let mut ai = dated("2021-05-04T10:30:00");
+        ai.gps = Some(GpsCoords { latitude: 48.8566, longitude: 2.3522, altitude: Some(35.0) });
+        write_exif(&path, &ai, &ExifData::default(), &date_fields(), false, ImageKind::Tiff, false).unwrap();
+
+        let before = crate::exif::read_exif(&path).unwrap();
+        assert!(before.title.is_some() && before.has_gps && before.date_time_original.is_some());
+        let bytes_before = std::fs::read(&path).unwrap();
+
+        let result = clear_exif(&path, ImageKind::Tiff).unwrap();
+        assert!(result.exif_removed);
+
+        let after = crate::exif::read_exif(&path).unwrap();
+        assert!(after.title.is_none(), "title should be cleared");
+        assert!(after.description.is_none(), "description should be cleared");
+        assert!(after.keywords.is_none(), "keywords should be cleared");
+        assert!(after.make.is_none() && after.model.is_none() && after.software.is_none());
+        assert!(after.date_time.is_none() && after.date_time_original.is_none());
+        assert!(!after.has_gps, "GPS should be cleared");
+
+        // Structure and pixel data survive; only metadata bytes changed
+        let bytes_after = std::fs::read(&path).unwrap();
+        assert_eq!(bytes_after.len(), bytes_before.len());
+        assert_eq!(after.image_width, before.image_width);
+        assert_eq!(after.image_height, before.image_height);
+        assert_eq!(tiff_ifd_offsets(&bytes_after), tiff_ifd_offsets(&bytes_before));
+        let leaked = |needle: &[u8]| bytes_after.windows(needle.len()).any(|w| w == needle);
+        assert!(!leaked(b"Test Title"), "cleared values must not linger in the file");
+        assert!(!leaked(b"2021:05:04 10:30:00"));
+    }
+
+    #[test]
+    fn clear_exif_without_metadata_leaves_file_untouched() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("photo.png");
+        image::RgbImage::new(8, 8).save(&path).unwrap();
+        let bytes_before = std::fs::read(&path).unwrap();
+
+        let result = clear_exif(&path, ImageKind::Png).unwrap();
+        assert_eq!(result, ClearResult::default());
+        assert!(result.removed_blocks().is_empty());
+        assert_eq!(std::fs::read(&path).unwrap(), bytes_before);
     }
 
     #[test]
@@ -2206,13 +5123,15 @@ mod tests {
         let existing = crate::exif::read_exif(&path).unwrap();
         let ai = test_ai_result();
         let fields = test_fields();
-        let result = write_exif(&path, &ai, &existing, &fields, false, ImageKind::Sidecar).unwrap();
+        let result = write_exif(&path, &ai, &existing, &fields, false, ImageKind::Sidecar, false).unwrap();
         let sidecar = result.sidecar_path.unwrap();
         assert!(sidecar.exists(), "sidecar should exist after write");
 
         // Now clear it
-        clear_exif(&path, ImageKind::Sidecar).unwrap();
+        let cleared = clear_exif(&path, ImageKind::Sidecar).unwrap();
         assert!(!sidecar.exists(), "sidecar should be removed after clear");
+        assert_eq!(cleared.sidecar_removed, Some(sidecar));
+        assert_eq!(cleared.removed_blocks(), vec!["sidecar"]);
     }
 
     #[test]
@@ -2222,7 +5141,8 @@ mod tests {
         // No sidecar exists — should succeed without error
         let xmp_path = path.with_extension("xmp");
         assert!(!xmp_path.exists());
-        clear_exif(&path, ImageKind::Sidecar).unwrap();
+        let cleared = clear_exif(&path, ImageKind::Sidecar).unwrap();
+        assert_eq!(cleared, ClearResult::default());
     }
 
     #[test]
@@ -2238,7 +5158,7 @@ mod tests {
         // Write new metadata to the cleared file
         let ai = test_ai_result();
         let fields = test_fields();
-        let result = write_exif(&path, &ai, &cleared, &fields, false, ImageKind::Jpeg).unwrap();
+        let result = write_exif(&path, &ai, &cleared, &fields, false, ImageKind::Jpeg, false).unwrap();
         assert!(result.title_written);
 
         // Read back — new metadata should be present
@@ -2254,12 +5174,106 @@ mod tests {
         assert!(existing.has_gps); // iPhone has GPS
 
         let mut ai = test_ai_result();
-        ai.gps = Some(GpsCoords { latitude: 0.0, longitude: 0.0 });
+        ai.gps = Some(GpsCoords { latitude: 48.8566, longitude: 2.3522, altitude: None });
 
         let fields = test_fields();
 
-        let result = write_exif(&path, &ai, &existing, &fields, false, ImageKind::Sidecar).unwrap();
+        let result = write_exif(&path, &ai, &existing, &fields, false, ImageKind::Sidecar, false).unwrap();
         assert!(!result.gps_written); // GPS already exists
-        assert!(result.skipped_fields.iter().any(|s| s.contains("gps")));
+        assert!(result.skipped_fields.contains(&SkippedField::new(Field::Gps, SkipReason::Existing)));
+    }
+
+    // ── Capture date ─────────────────────────────────────────────────
+
+    fn date_fields() -> ExifFields {
+        ExifFields { write_date: true, ..test_fields() }
+    }
+
+    fn dated(date: &str) -> AiResult {
+        AiResult { date_taken: Some(date.into()), ..test_ai_result() }
+    }
+
+    #[test]
+    fn exif_date_time_converts_iso_8601() {
+        assert_eq!(exif_date_time("1969-07-21").as_deref(), Some("1969:07:21 00:00:00"));
+        assert_eq!(exif_date_time("1969-07-21T20:17").as_deref(), Some("1969:07:21 20:17:00"));
+        assert_eq!(exif_date_time(" 1969-07-21 20:17:40 ").as_deref(), Some("1969:07:21 20:17:40"));
+        assert_eq!(exif_date_time("1969-07-21T20:17:40.5-05:00").as_deref(), Some("1969:07:21 20:17:40"));
+        assert_eq!(exif_date_time("1969-07-21T20:17:40Z").as_deref(), Some("1969:07:21 20:17:40"));
+
+        for bad in ["1969", "1969-07", "1969-13-01", "1969-07-00", "21/07/1969", "1969-07-21T25:00", "July 1969"] {
+            assert_eq!(exif_date_time(bad), None, "{bad}");
+        }
+    }
+
+    #[test]
+    fn write_date_to_jpeg_without_date() {
+        let (_dir, path) = copy_to_temp("test_serial.jpg");
+        let existing = crate::exif::read_exif(&path).unwrap();
+        assert!(existing.date_time.is_none());
+
+        let result = write_exif(&path, &dated("1969-07-21T20:17:40"), &existing, &date_fields(), false, ImageKind::Jpeg, false).unwrap();
+        assert!(result.date_written);
+
+        let after = crate::exif::read_exif(&path).unwrap();
+        assert_eq!(after.date_time_original.as_deref(), Some("1969-07-21 20:17:40"));
+        assert_eq!(after.title.as_deref(), Some("Test Title"));
+    }
+
+    #[test]
+    fn write_date_to_tiff_creates_exif_ifd() {
+        // Like most scans, test.tiff has no ExifIFD at all
+        let (_dir, path) = copy_to_temp("test.tiff");
+        let existing = crate::exif::read_exif(&path).unwrap();
+
+        let result = write_exif(&path, &dated("1969-07-21"), &existing, &date_fields(), false, ImageKind::Tiff, false).unwrap();
+        assert!(result.date_written && result.description_written);
+
+        let after = crate::exif::read_exif(&path).unwrap();
+        assert_eq!(after.date_time_original.as_deref(), Some("1969-07-21 00:00:00"));
+        assert_eq!(after.description.as_deref(), Some("A test description"));
+        assert_eq!(after.title.as_deref(), Some("Test Title"));
+        let img = image::open(&path).expect("TIFF should still decode");
+        assert_eq!((img.width(), img.height()), (635, 348));
+    }
+
+    #[test]
+    fn write_date_with_gps_uses_raw_injection() {
+        let (_dir, path) = copy_to_temp("test_gps.jpg");
+        let before = crate::exif::read_exif(&path).unwrap();
+        // Pretend the date is missing to exercise the raw path's ExifIFD rewrite
+        let existing = ExifData { date_time: None, ..before.clone() };
+
+        let result = write_exif(&path, &dated("1969-07-21T20:17:40"), &existing, &date_fields(), false, ImageKind::Jpeg, false).unwrap();
+        assert!(result.date_written);
+
+        let after = crate::exif::read_exif(&path).unwrap();
+        assert_eq!(after.date_time_original.as_deref(), Some("1969-07-21 20:17:40"));
+        assert_eq!((after.gps_latitude, after.gps_longitude), (before.gps_latitude, before.gps_longitude));
+    }
+
+    #[test]
+    fn write_date_skips_dated_disabled_and_invalid() {
+        let (_dir, path) = copy_to_temp("test_canon_powershot.jpg");
+        let existing = crate::exif::read_exif(&path).unwrap();
+        let skipped = |ai: &AiResult, fields: &ExifFields, kind: ImageKind| {
+            let result = write_exif(&path, ai, &existing, fields, true, kind, false).unwrap();
+            assert!(!result.date_written);
+            result.skipped_fields.iter().find(|s| s.field == Field::Date).unwrap().reason
+        };
+
+        assert_eq!(skipped(&dated("1969-07-21"), &date_fields(), ImageKind::Jpeg), SkipReason::Existing);
+        assert_eq!(skipped(&dated("1969-07-21"), &test_fields(), ImageKind::Jpeg), SkipReason::Disabled);
+
+        let undated = ExifData { date_time: None, ..existing.clone() };
+        let result = write_exif(&path, &dated("the sixties"), &undated, &date_fields(), true, ImageKind::Jpeg, false).unwrap();
+        assert!(result.skipped_fields.contains(&SkippedField::new(Field::Date, SkipReason::Invalid)));
+        let result = write_exif(&path, &dated("1969-07-21"), &undated, &date_fields(), true, ImageKind::Png, false).unwrap();
+        assert!(result.skipped_fields.contains(&SkippedField::new(Field::Date, SkipReason::FormatUnsupported)));
+
+        assert_eq!(
+            SkippedField::new(Field::Date, SkipReason::Existing).explanation(),
+            "date skipped: image already has a capture date"
+        );
     }
 }
@@ -6,9 +6,10 @@ use std::sync::mpsc;
 use eframe::egui;
 
 use exif_ai::ai::local::download_model;
+use exif_ai::cache::{self, AnalysisCache};
 use exif_ai::config::Config;
 use exif_ai::exif::{self, ExifData};
-use exif_ai::pipeline::{collect_images, ImageKind, Pipeline, ProcessResult};
+use exif_ai::pipeline::{collect_images, ImageKind, Pipeline, ProcessResult, WriteMode};
 
 fn load_icon() -> Option<egui::IconData> {
     let png_bytes = include_bytes!("../../assets/icon_256.png");
@@ -51,7 +52,7 @@ fn main() -> eframe::Result<()> {
 
 enum BgMessage {
     /// AI processing finished for one image.
-    ProcessResult(ProcessResult),
+    ProcessResult(Box<ProcessResult>),
     /// All images in the batch are done.
     BatchDone,
     /// Model download progress / completion.
@@ -141,9 +142,10 @@ impl App {
     fn open_files(&mut self) {
         if let Some(paths) = rfd::FileDialog::new()
             .add_filter("Images", &[
-                "jpg", "jpeg", "png", "webp", "tif", "tiff",
-                "heic", "heif", "avif",
+                "jpg", "jpeg", "jpe", "jfif", "png", "webp", "tif", "tiff",
+                "heic", "heif", "avif", "jxl",
                 "cr3", "cr2", "dng", "nef", "arw", "raf", "orf", "rw2", "pef", "srw",
+                "gif", "bmp",
             ])
             .pick_files()
         {
@@ -173,12 +175,21 @@ impl App {
         let config = self.config.clone();
         let dry_run = self.dry_run;
         let tx = self.tx.clone();
+        let cache_path = if config.output.enable_cache && !config.output.no_ai {
+            self.config_path.clone().or_else(|| Config::config_path().ok()).map(|p| cache::cache_path(&p))
+        } else {
+            None
+        };
 
         self.rt.spawn(async move {
-            let pipeline = Pipeline::builder()
-                .from_config(&config)
-                .dry_run(dry_run)
-                .build();
+            let mut builder = Pipeline::builder().from_config(&config).dry_run(dry_run);
+            if let Some(path) = cache_path {
+                match AnalysisCache::load(&path) {
+                    Ok(cache) => builder = builder.analysis_cache(cache),
+                    Err(e) => log::warn!("Analysis cache unavailable: {e:#}"),
+                }
+            }
+            let pipeline = builder.build();
 
             let pipeline = match pipeline {
                 Ok(p) => p,
@@ -191,7 +202,7 @@ impl App {
 
             for path in &paths {
                 let result = pipeline.process_image(path).await;
-                let _ = tx.send(BgMessage::ProcessResult(result));
+                let _ = tx.send(BgMessage::ProcessResult(Box::new(result)));
             }
             let _ = tx.send(BgMessage::BatchDone);
         });
@@ -238,7 +249,7 @@ impl App {
                         .find(|e| e.path == result.path)
                     {
                         entry.existing_exif = Some(result.existing_exif.clone());
-                        entry.result = Some(result);
+                        entry.result = Some(*result);
                     }
                 }
                 BgMessage::BatchDone => {
@@ -476,31 +487,8 @@ impl App {
             return;
         }
 
-        // Try loading directly with the image crate (JPEG, PNG, WebP, TIFF)
-        let decoded = std::fs::read(&entry.path).ok().and_then(|bytes| {
-            image::load_from_memory(&bytes).ok()
-        }).or_else(|| {
-            // Fallback: use macOS `sips` to convert HEIC/RAW/AVIF to JPEG for preview
-            #[cfg(target_os = "macos")]
-            {
-                let tmp = std::env::temp_dir().join("exif_ai_preview.jpg");
-                let status = std::process::Command::new("sips")
-                    .args(["-s", "format", "jpeg", "-s", "formatOptions", "70"])
-                    .arg(&entry.path)
-                    .arg("--out")
-                    .arg(&tmp)
-                    .stdout(std::process::Stdio::null())
-                    .stderr(std::process::Stdio::null())
-                    .status();
-                if status.is_ok_and(|s| s.success()) {
-                    if let Ok(bytes) = std::fs::read(&tmp) {
-                        let _ = std::fs::remove_file(&tmp);
-                        return image::load_from_memory(&bytes).ok();
-                    }
-                }
-            }
-            None
-        });
+        // The image crate for JPEG, PNG, WebP, TIFF; `sips` on macOS for HEIC/RAW/AVIF
+        let decoded = exif_ai::decode::Decoders::new().decode(&entry.path);
 
         if let Some(img) = decoded {
             let img = img.thumbnail(400, 400);
@@ -546,12 +534,18 @@ impl App {
                                 section(ui, "CAMERA", &[
                                     ("Make", data.make.as_deref()),
                                     ("Model", data.model.as_deref()),
+                                    ("Lens Make", data.lens_make.as_deref()),
                                     ("Lens", data.lens_model.as_deref()),
+                                    ("Lens Spec", data.lens_specification.as_deref()),
+                                    ("Body Serial", data.body_serial_number.as_deref()),
+                                    ("Lens Serial", data.lens_serial_number.as_deref()),
+                                    ("Unique ID", data.image_unique_id.as_deref()),
                                 ]);
 
                                 // Exposure
                                 section(ui, "EXPOSURE", &[
                                     ("Date", data.date_time.as_deref()),
+                                    ("Program", data.exposure_program.as_deref()),
                                     ("Exposure", data.exposure_time.as_deref()),
                                     ("F-Number", data.f_number.as_deref()),
                                     ("ISO", data.iso.as_deref()),
@@ -570,22 +564,29 @@ impl App {
                                 ]);
 
                                 // Metadata (IPTC / XMP fields)
+                                let rating = data.rating.map(|r| match r {
+                                    ..0 => "Rejected".to_string(),
+                                    _ => "★".repeat(r.min(5) as usize) + &"☆".repeat(5 - r.min(5) as usize),
+                                });
                                 section(ui, "METADATA", &[
                                     ("Title", data.title.as_deref()),
                                     ("Description", data.description.as_deref()),
                                     ("Keywords", data.keywords.as_deref()),
                                     ("Subject", data.subject.as_deref()),
+                                    ("Artist", data.artist.as_deref()),
+                                    ("Copyright", data.copyright.as_deref()),
+                                    ("Rating", rating.as_deref()),
                                 ]);
 
                                 // GPS
-                                if data.has_gps {
-                                    if let (Some(lat), Some(lon)) = (data.gps_latitude, data.gps_longitude) {
-                                        ui.label(egui::RichText::new("GPS").small().color(egui::Color32::GRAY));
-                                        ui.end_row();
-                                        ui.label(egui::RichText::new("Coordinates").strong());
-                                        ui.label(format!("{lat:.6}, {lon:.6}"));
-                                        ui.end_row();
-                                    }
+                                if data.has_gps
+                                    && let (Some(lat), Some(lon)) = (data.gps_latitude, data.gps_longitude)
+                                {
+                                    ui.label(egui::RichText::new("GPS").small().color(egui::Color32::GRAY));
+                                    ui.end_row();
+                                    ui.label(egui::RichText::new("Coordinates").strong());
+                                    ui.label(format!("{lat:.6}, {lon:.6}"));
+                                    ui.end_row();
                                 }
                             });
                     });
@@ -673,31 +674,54 @@ impl App {
                         ui.end_row();
                     }
 
-                    if let Some(ref subjects) = ai.subject {
-                        if !subjects.is_empty() {
-                            ui.label(egui::RichText::new("Subject").strong());
-                            ui.horizontal_wrapped(|ui| {
-                                ui.label(subjects.join(", "));
-                                status_label(ui, result.subject_written);
-                            });
-                            ui.end_row();
-                        }
+                    if let Some(ref subjects) = ai.subject
+                        && !subjects.is_empty()
+                    {
+                        ui.label(egui::RichText::new("Subject").strong());
+                        ui.horizontal_wrapped(|ui| {
+                            ui.label(subjects.join(", "));
+                            status_label(ui, result.subject_written);
+                        });
+                        ui.end_row();
+                    }
+
+                    if let Some(ref date) = ai.date_taken {
+                        ui.label(egui::RichText::new("Date").strong());
+                        ui.horizontal(|ui| {
+                            ui.label(date);
+                            status_label(ui, result.date_written);
+                        });
+                        ui.end_row();
                     }
                 });
 
             // Skipped fields
             if !result.skipped_fields.is_empty() {
                 ui.add_space(8.0);
-                ui.colored_label(
-                    egui::Color32::from_rgb(180, 180, 50),
-                    format!("Skipped: {}", result.skipped_fields.join(", ")),
-                );
+                ui.colored_label(egui::Color32::from_rgb(180, 180, 50), result.summary_line());
             }
 
-            // Sidecar
-            if let Some(ref sidecar) = result.sidecar_path {
-                ui.add_space(4.0);
-                ui.label(format!("Sidecar XMP: {}", sidecar.display()));
+            // Where the metadata went
+            ui.add_space(4.0);
+            match result.write_mode {
+                WriteMode::Embedded => {
+                    ui.label("Metadata embedded in the image file");
+                }
+                WriteMode::Sidecar => {
+                    let sidecar = result.sidecar_path.as_ref()
+                        .map(|p| p.display().to_string())
+                        .unwrap_or_else(|| "alongside the image".to_string());
+                    ui.label(format!("Sidecar XMP: {sidecar} (original not modified)"));
+                }
+                WriteMode::Json => {
+                    let json = result.json_sidecar_path.as_ref()
+                        .map(|p| p.display().to_string())
+                        .unwrap_or_else(|| "alongside the image".to_string());
+                    ui.label(format!("JSON: {json} (image not modified)"));
+                }
+                WriteMode::Skipped => {
+                    ui.weak("No metadata written");
+                }
             }
         }
     }
@@ -720,20 +744,19 @@ impl App {
                     } else {
                         ui.label("(default)");
                     }
-                    if ui.button("Load...").clicked() {
-                        if let Some(path) = rfd::FileDialog::new()
+                    if ui.button("Load...").clicked()
+                        && let Some(path) = rfd::FileDialog::new()
                             .add_filter("JSON", &["json"])
                             .pick_file()
-                        {
-                            match Config::load(Some(&path)) {
-                                Ok(c) => {
-                                    self.config = c;
-                                    self.config_path = Some(path);
-                                    self.status = "Config loaded".into();
-                                }
-                                Err(e) => {
-                                    self.status = format!("Failed to load config: {e}");
-                                }
+                    {
+                        match Config::load(Some(&path)) {
+                            Ok(c) => {
+                                self.config = c;
+                                self.config_path = Some(path);
+                                self.status = "Config loaded".into();
+                            }
+                            Err(e) => {
+                                self.status = format!("Failed to load config: {e}");
                             }
                         }
                     }
@@ -805,6 +828,21 @@ impl App {
                         });
                     });
 
+                // Claude
+                egui::CollapsingHeader::new(egui::RichText::new("Claude").strong())
+                    .default_open(self.config.ai_services.claude.enabled)
+                    .show(ui, |ui| {
+                        ui.checkbox(&mut self.config.ai_services.claude.enabled, "Enabled");
+                        ui.horizontal(|ui| {
+                            ui.label("API Key:");
+                            ui.add(egui::TextEdit::singleline(&mut self.config.ai_services.claude.api_key).password(true));
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Model:");
+                            ui.text_edit_singleline(&mut self.config.ai_services.claude.model);
+                        });
+                    });
+
                 // Cloudflare
                 egui::CollapsingHeader::new(egui::RichText::new("Cloudflare").strong())
                     .default_open(self.config.ai_services.cloudflare.enabled)
@@ -824,6 +862,21 @@ impl App {
                         });
                     });
 
+                // Ollama
+                egui::CollapsingHeader::new(egui::RichText::new("Ollama").strong())
+                    .default_open(self.config.ai_services.ollama.enabled)
+                    .show(ui, |ui| {
+                        ui.checkbox(&mut self.config.ai_services.ollama.enabled, "Enabled");
+                        ui.horizontal(|ui| {
+                            ui.label("Server URL:");
+                            ui.text_edit_singleline(&mut self.config.ai_services.ollama.base_url);
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Model:");
+                            ui.text_edit_singleline(&mut self.config.ai_services.ollama.model);
+                        });
+                    });
+
                 ui.add_space(16.0);
                 ui.separator();
 
@@ -837,6 +890,7 @@ impl App {
                 ui.checkbox(&mut self.config.exif_fields.write_tags, "Write tags (XPKeywords)");
                 ui.checkbox(&mut self.config.exif_fields.write_gps, "Write GPS coordinates");
                 ui.checkbox(&mut self.config.exif_fields.write_subject, "Write subject (XPSubject)");
+                ui.checkbox(&mut self.config.exif_fields.write_date, "Write date if visible (DateTimeOriginal)");
                 ui.add_space(4.0);
                 ui.checkbox(&mut self.config.exif_fields.overwrite_existing, "Overwrite existing values");
 
@@ -850,6 +904,8 @@ impl App {
 
                 ui.checkbox(&mut self.config.output.dry_run, "Dry run (preview only)");
                 ui.checkbox(&mut self.config.output.backup_originals, "Backup originals (.bak)");
+                ui.checkbox(&mut self.config.output.backup_metadata_only, "Backup metadata only (.metabak)");
+                ui.checkbox(&mut self.config.output.augment_existing, "Augment existing title/description");
             });
         });
     }
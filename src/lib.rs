@@ -1,7 +1,7 @@
 //! # exif-ai
 //!
 //! AI-powered EXIF metadata writer — generate SEO titles, descriptions, tags, GPS coordinates,
-//! and subject data for images using AI vision models (OpenAI, Google Gemini, Cloudflare Workers AI).
+//! and subject data for images using AI vision models (OpenAI, Google Gemini, Anthropic Claude, Cloudflare Workers AI).
 //!
 //! This crate provides:
 //! - **Library** — [`Pipeline`](pipeline::Pipeline) builder API for programmatic use
@@ -70,10 +70,7 @@
 //!     // 2. Analyze with AI
 //!     let service = OpenAiService::new("sk-...".into(), "gpt-4o-mini".into());
 //!     let bytes = std::fs::read(path)?;
-//!     let b64 = base64::Engine::encode(
-//!         &base64::engine::general_purpose::STANDARD, &bytes,
-//!     );
-//!     let ai_result = service.analyze(&b64, &build_prompt(), "image/jpeg").await?;
+//!     let ai_result = service.analyze_bytes(&bytes, &build_prompt(), "image/jpeg").await?;
 //!     println!("AI title: {:?}", ai_result.title);
 //!
 //!     // 3. Write metadata back (format-aware)
@@ -84,8 +81,9 @@
 //!         write_gps: true,
 //!         write_subject: true,
 //!         overwrite_existing: false,
+//!         ..Default::default()
 //!     };
-//!     let result = write_exif(path, &ai_result, &existing, &fields, false, ImageKind::Jpeg)?;
+//!     let result = write_exif(path, &ai_result, &existing, &fields, false, ImageKind::Jpeg, false)?;
 //!     println!("Title written: {}", result.title_written);
 //!
 //!     Ok(())
@@ -96,22 +94,30 @@
 //!
 //! | Format | Write Strategy |
 //! |--------|---------------|
-//! | JPEG (`.jpg`, `.jpeg`) | Native — EXIF + XMP + IPTC |
+//! | JPEG (`.jpg`, `.jpeg`, `.jpe`, `.jfif`) | Native — EXIF + XMP + IPTC |
 //! | PNG (`.png`) | Native — XMP in iTXt chunk |
 //! | WebP (`.webp`) | Native — EXIF + XMP in RIFF |
-//! | TIFF (`.tif`, `.tiff`) | Native — EXIF |
+//! | TIFF (`.tif`, `.tiff`) | Native — EXIF (page 0 only for multi-page files) |
 //! | HEIC/HEIF (`.heic`, `.heif`) | Sidecar `.xmp` file |
 //! | AVIF (`.avif`) | Sidecar `.xmp` file |
+//! | JPEG XL (`.jxl`) | Sidecar `.xmp` file |
 //! | RAW (`.cr2`, `.cr3`, `.dng`, `.nef`, `.arw`, `.raf`, `.orf`, `.rw2`, `.pef`, `.srw`) | Sidecar `.xmp` file |
+//! | GIF (`.gif`), BMP (`.bmp`) | Sidecar `.xmp` file |
 //!
 //! ## Modules
 //!
-//! - [`ai`] — AI service trait and implementations (OpenAI, Gemini, Cloudflare)
+//! - [`ai`] — AI service trait and implementations (OpenAI, Gemini, Claude, Cloudflare, Ollama)
+//! - [`cache`] — On-disk cache of AI results keyed by image content
 //! - [`config`] — Configuration types and loading/saving
+//! - [`decode`] — Pluggable image decoding for previews and local model input
+//! - [`error`] — Typed pipeline failures ([`ExifAiError`](error::ExifAiError))
 //! - [`exif`] — EXIF/XMP/IPTC reading and writing
 //! - [`pipeline`] — High-level processing pipeline, image collection, and format detection
 
 pub mod ai;
+pub mod cache;
 pub mod config;
+pub mod decode;
+pub mod error;
 pub mod exif;
 pub mod pipeline;
@@ -1,16 +1,22 @@
 use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 use walkdir::WalkDir;
 
-use crate::ai::{self, AiResult, AiService};
-use crate::config::{Config, ExifFields};
+use crate::ai::{self, AiError, AiResult, AiService, DescriptionFormat, TagStyle};
+use crate::cache::{self, AnalysisCache};
+use crate::config::{Config, ExifFields, Limits};
+use crate::error::ExifAiError;
 use crate::exif::{self, ExifData};
-use crate::exif::write_exif;
+use crate::exif::{backup_metadata, write_exif, Field, SkipReason, SkippedField};
 
 /// Supported image extensions.
 const IMAGE_EXTENSIONS: &[&str] = &[
     // Native write support (EXIF+XMP+IPTC)
-    "jpg", "jpeg",
+    "jpg", "jpeg", "jpe", "jfif",
     // Native write support (XMP)
     "png", "webp",
     // Native write support (EXIF)
@@ -19,15 +25,19 @@ const IMAGE_EXTENSIONS: &[&str] = &[
     "heic", "heif",
     // AVIF — read EXIF, sidecar XMP write
     "avif",
+    // JPEG XL — read EXIF, sidecar XMP write
+    "jxl",
     // RAW formats — read EXIF, sidecar XMP write
     "cr3", "cr2", "dng", "nef", "arw", "raf", "orf", "rw2", "pef", "srw",
+    // GIF/BMP — no metadata to read, sidecar XMP write
+    "gif", "bmp",
 ];
 
 /// The write strategy for a given image file, determined by its format.
 ///
 /// Different image formats support different metadata embedding approaches:
 /// - **Native** formats (JPEG, PNG, WebP, TIFF) have metadata written directly into the file.
-/// - **Sidecar** formats (HEIC, AVIF, RAW) get a `.xmp` sidecar file written alongside the original.
+/// - **Sidecar** formats (HEIC, AVIF, JPEG XL, RAW, GIF, BMP) get a `.xmp` sidecar file written alongside the original.
 ///
 /// Use [`ImageKind::from_path`] to detect the format from a file extension.
 ///
@@ -51,9 +61,9 @@ pub enum ImageKind {
     Png,
     /// WebP — EXIF+XMP in RIFF chunks
     WebP,
-    /// TIFF — EXIF write via little_exif
+    /// TIFF — EXIF write via raw IFD injection
     Tiff,
-    /// HEIC/HEIF/AVIF/RAW — read EXIF from original, write sidecar .xmp
+    /// HEIC/HEIF/AVIF/JXL/RAW/GIF/BMP — read EXIF from original, write sidecar .xmp
     Sidecar,
 }
 
@@ -62,17 +72,48 @@ impl ImageKind {
     pub fn from_path(path: &Path) -> Option<Self> {
         let ext = path.extension()?.to_str()?.to_lowercase();
         match ext.as_str() {
-            "jpg" | "jpeg" => Some(Self::Jpeg),
+            "jpg" | "jpeg" | "jpe" | "jfif" => Some(Self::Jpeg),
             "png" => Some(Self::Png),
             "webp" => Some(Self::WebP),
             "tif" | "tiff" => Some(Self::Tiff),
-            "heic" | "heif" | "avif"
+            "heic" | "heif" | "avif" | "jxl"
             | "cr3" | "cr2" | "dng" | "nef" | "arw" | "raf" | "orf" | "rw2" | "pef" | "srw"
+            | "gif" | "bmp"
                 => Some(Self::Sidecar),
             _ => None,
         }
     }
 
+    /// Determine the image kind from the file's leading bytes (magic numbers).
+    ///
+    /// TIFF-based RAW files (CR2, NEF, ARW, DNG, ...) sniff as [`Tiff`](Self::Tiff);
+    /// ISO-BMFF containers (HEIC, AVIF, CR3), JPEG XL, RAF, ORF, RW2, GIF and BMP as
+    /// [`Sidecar`](Self::Sidecar).
+    ///
+    /// ```rust
+    /// use exif_ai::pipeline::ImageKind;
+    ///
+    /// assert_eq!(ImageKind::from_bytes(&[0xFF, 0xD8, 0xFF, 0xE1]), Some(ImageKind::Jpeg));
+    /// assert_eq!(ImageKind::from_bytes(b"hello"), None);
+    /// ```
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        match bytes {
+            [0xFF, 0xD8, 0xFF, ..] => Some(Self::Jpeg),
+            [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A, ..] => Some(Self::Png),
+            [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => Some(Self::WebP),
+            [b'I', b'I', b'*', 0, ..] | [b'M', b'M', 0, b'*', ..] => Some(Self::Tiff),
+            [_, _, _, _, b'f', b't', b'y', b'p', ..]
+            | [b'F', b'U', b'J', b'I', b'F', b'I', b'L', b'M', ..]
+            | [b'I', b'I', b'R', b'O', ..]
+            | [b'I', b'I', b'U', 0, ..]
+            | [0xFF, 0x0A, ..]
+            | [0, 0, 0, 0x0C, b'J', b'X', b'L', b' ', ..]
+            | [b'G', b'I', b'F', b'8', b'7' | b'9', b'a', ..]
+            | [b'B', b'M', ..] => Some(Self::Sidecar),
+            _ => None,
+        }
+    }
+
     /// Get the MIME type for sending to AI services.
     pub fn mime_type(&self, path: &Path) -> &'static str {
         let ext = path.extension()
@@ -80,13 +121,14 @@ impl ImageKind {
             .map(|e| e.to_lowercase())
             .unwrap_or_default();
         match ext.as_str() {
-            "jpg" | "jpeg" => "image/jpeg",
+            "jpg" | "jpeg" | "jpe" | "jfif" => "image/jpeg",
             "png" => "image/png",
             "webp" => "image/webp",
             "tif" | "tiff" => "image/tiff",
             "heic" => "image/heic",
             "heif" => "image/heif",
             "avif" => "image/avif",
+            "jxl" => "image/jxl",
             "cr2" => "image/x-canon-cr2",
             "cr3" => "image/x-canon-cr3",
             "dng" => "image/x-adobe-dng",
@@ -97,16 +139,131 @@ impl ImageKind {
             "rw2" => "image/x-panasonic-rw2",
             "pef" => "image/x-pentax-pef",
             "srw" => "image/x-samsung-srw",
+            "gif" => "image/gif",
+            "bmp" => "image/bmp",
             _ => "image/jpeg",
         }
     }
 }
 
+/// Where AI metadata ended up for a processed image.
+///
+/// Serializes as `"embedded"`, `"sidecar"`, `"json"` or `"skipped"`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WriteMode {
+    /// Written into the image file itself (JPEG, PNG, WebP, TIFF).
+    Embedded,
+    /// Written to a `.xmp` sidecar; the original file was not modified (HEIC, AVIF, RAW).
+    Sidecar,
+    /// Written only to the `<image>.json` file (see [`PipelineBuilder::sidecar_json`]).
+    Json,
+    /// Nothing was written (error, or every field was skipped).
+    #[default]
+    Skipped,
+}
+
+impl WriteMode {
+    /// The mode used for an image kind when at least one field is written.
+    pub fn for_kind(kind: ImageKind) -> Self {
+        match kind {
+            ImageKind::Sidecar => WriteMode::Sidecar,
+            _ => WriteMode::Embedded,
+        }
+    }
+}
+
+impl fmt::Display for WriteMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            WriteMode::Embedded => "embedded",
+            WriteMode::Sidecar => "sidecar",
+            WriteMode::Json => "json",
+            WriteMode::Skipped => "skipped",
+        })
+    }
+}
+
+/// What to do with an image that is a symbolic link.
+///
+/// Serializes as `"follow"`, `"skip"` or `"copy"`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SymlinkPolicy {
+    /// Write through the link, modifying the file it points to.
+    #[default]
+    Follow,
+    /// Leave symlinked images alone; they are logged and not analyzed.
+    Skip,
+    /// Replace the link with a copy of its target and write to the copy.
+    /// The target is left untouched.
+    Copy,
+}
+
+/// Orientation class of an image, for layout-driven publishing (grids, collages).
+///
+/// Serializes as `"landscape"`, `"portrait"` or `"square"`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AspectClass {
+    Landscape,
+    Portrait,
+    Square,
+}
+
+impl AspectClass {
+    /// How far the long side may exceed the short side for an image to still
+    /// count as square (5%, so 1050×1000 is square and 1051×1000 isn't).
+    pub const SQUARE_TOLERANCE: f64 = 0.05;
+
+    /// Classify `width`×`height`. `None` if either side is zero.
+    ///
+    /// ```
+    /// use exif_ai::pipeline::AspectClass;
+    ///
+    /// assert_eq!(AspectClass::from_dimensions(6000, 4000), Some(AspectClass::Landscape));
+    /// assert_eq!(AspectClass::from_dimensions(1020, 1000), Some(AspectClass::Square));
+    /// ```
+    pub fn from_dimensions(width: u32, height: u32) -> Option<Self> {
+        if width == 0 || height == 0 {
+            return None;
+        }
+        let (long, short) = (width.max(height) as f64, width.min(height) as f64);
+        Some(if long <= short * (1.0 + Self::SQUARE_TOLERANCE) {
+            AspectClass::Square
+        } else if width > height {
+            AspectClass::Landscape
+        } else {
+            AspectClass::Portrait
+        })
+    }
+
+    /// The keyword written for this class.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AspectClass::Landscape => "landscape",
+            AspectClass::Portrait => "portrait",
+            AspectClass::Square => "square",
+        }
+    }
+}
+
+impl fmt::Display for AspectClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 /// The AI processing pipeline.
 ///
 /// Owns the AI service chain and configuration. Built via [`Pipeline::builder()`]
 /// which provides a fluent API for construction.
 ///
+/// The service type `S` defaults to `Box<dyn AiService>`, the dynamic failover
+/// chain used by the CLI and GUI. Single-service users can start from
+/// [`Pipeline::with_service`] to get a `Pipeline<S>` over a concrete service
+/// (or their own enum implementing [`AiService`]) with no trait-object boxing.
+///
 /// # Example
 ///
 /// ```rust,no_run
@@ -125,11 +282,39 @@ impl ImageKind {
 /// # Ok(())
 /// # }
 /// ```
-pub struct Pipeline {
-    services: Vec<Box<dyn AiService>>,
+pub struct Pipeline<S: AiService = Box<dyn AiService>> {
+    services: Vec<S>,
     exif_fields: ExifFields,
     dry_run: bool,
     backup_originals: bool,
+    backup_metadata_only: bool,
+    augment_existing: bool,
+    required_fields: Vec<Field>,
+    allow_partial: bool,
+    xmp_only: bool,
+    always_sidecar: bool,
+    write_json_sidecar: bool,
+    sidecar_json: bool,
+    filename_fallback_title: bool,
+    folder_keyword: bool,
+    write_aspect_class: bool,
+    no_ai: bool,
+    per_image_timeout: Option<Duration>,
+    max_upload_dimension: Option<u32>,
+    reprompt_on_parse_error: bool,
+    merge_services: bool,
+    warn_unsupported_fields: bool,
+    tag_style: Option<TagStyle>,
+    language: Option<String>,
+    write_provenance: bool,
+    description_format: DescriptionFormat,
+    limits: Limits,
+    symlink_policy: SymlinkPolicy,
+    prompt_context_fields: Vec<String>,
+    prompt_override: Option<String>,
+    retry: ai::RetryPolicy,
+    openai_batch: Option<ai::OpenAiBatch>,
+    analysis_cache: Option<AnalysisCache>,
 }
 
 impl Pipeline {
@@ -137,6 +322,29 @@ impl Pipeline {
     pub fn builder() -> PipelineBuilder {
         PipelineBuilder::new()
     }
+}
+
+impl<S: AiService> Pipeline<S> {
+    /// Create a [`PipelineBuilder`] around a concrete service.
+    ///
+    /// More services of the same type can be chained with
+    /// [`add_service`](PipelineBuilder::add_service).
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use exif_ai::ai::OpenAiService;
+    /// use exif_ai::pipeline::Pipeline;
+    ///
+    /// let pipeline: Pipeline<OpenAiService> = Pipeline::with_service(
+    ///     OpenAiService::new("sk-...".into(), "gpt-4o-mini".into()),
+    /// )
+    /// .build()
+    /// .unwrap();
+    /// ```
+    pub fn with_service(service: S) -> PipelineBuilder<S> {
+        PipelineBuilder::new().add_service(service)
+    }
 
     /// Return the number of configured AI services.
     pub fn service_count(&self) -> usize {
@@ -178,6 +386,16 @@ impl Pipeline {
     /// # }
     /// ```
     pub async fn process_image(&self, path: &Path) -> ProcessResult {
+        self.process_image_with(path, None).await
+    }
+
+    /// Like [`process_image`](Self::process_image), but with the AI result
+    /// already known, e.g. from [`analyze_batch`](Self::analyze_batch).
+    ///
+    /// With `analyzed` set, no service is called; the result is
+    /// post-processed and written as if the batch service had returned it.
+    /// With `None`, the image goes through the service chain as usual.
+    pub async fn process_image_with(&self, path: &Path, analyzed: Option<AiResult>) -> ProcessResult {
         let kind = ImageKind::from_path(path);
 
         let mut result = ProcessResult {
@@ -189,13 +407,37 @@ impl Pipeline {
             tags_written: false,
             gps_written: false,
             subject_written: false,
+            date_written: false,
+            copyright_written: false,
+            artist_written: false,
             skipped_fields: Vec::new(),
             error: None,
             ai_service_used: None,
+            field_sources: BTreeMap::new(),
             sidecar_path: None,
             image_kind: kind,
+            write_mode: WriteMode::Skipped,
+            missing_fields: Vec::new(),
+            json_sidecar_path: None,
+            title_from_filename: false,
+            provenance: None,
         };
 
+        let is_symlink = path.symlink_metadata().is_ok_and(|m| m.file_type().is_symlink());
+        match self.preflight(path) {
+            Ok(true) => {}
+            Ok(false) => {
+                log::info!("Skipping symlink: {}", path.display());
+                return result;
+            }
+            Err(e) => {
+                result.error = Some(ExifAiError::io_or(path, &e, e.to_string(), |path, message| {
+                    ExifAiError::Read { path, message }
+                }));
+                return result;
+            }
+        }
+
         // Read existing EXIF
         match exif::read_exif(path) {
             Ok(data) => result.existing_exif = data,
@@ -204,81 +446,184 @@ impl Pipeline {
             }
         }
 
-        // Read and encode image
-        let image_bytes = match std::fs::read(path) {
-            Ok(bytes) => bytes,
-            Err(e) => {
-                result.error = Some(format!("Failed to read file: {e}"));
-                return result;
+        let (prompt, augmenting) = self.prompt_for(&result.existing_exif);
+        let (cache_key, cached) = self.cache_lookup(path);
+
+        // Without AI, start from an empty result and fill in only derived fields
+        let analysis = if self.no_ai {
+            Ok((AiResult::default(), None, BTreeMap::new()))
+        } else if let Some(ai_data) = analyzed {
+            let service = self.openai_batch.as_ref().map_or("OpenAI Batch", |b| b.name());
+            let sources = field_sources(&ai_data, service);
+            Ok((ai_data, Some(service), sources))
+        } else if let Some(ai_data) = cached {
+            log::info!("  Using cached analysis");
+            let sources = field_sources(&ai_data, cache::CACHE_SERVICE_NAME);
+            Ok((ai_data, Some(cache::CACHE_SERVICE_NAME), sources))
+        } else {
+            match self.per_image_timeout {
+                Some(limit) => tokio::time::timeout(limit, self.analyze_with_prompt(path, &prompt))
+                    .await
+                    .unwrap_or(Err(ExifAiError::Timeout(limit))),
+                None => self.analyze_with_prompt(path, &prompt).await,
             }
+            .map(|(ai_data, service, sources)| (ai_data, Some(service), sources))
         };
-        let image_base64 = base64::Engine::encode(&base64::engine::general_purpose::STANDARD, &image_bytes);
 
-        // Determine MIME type for AI service
-        let mime_type = kind
-            .map(|k| k.mime_type(path))
-            .unwrap_or("image/jpeg");
-
-        // Build prompt
-        let prompt = ai::build_prompt();
-
-        // Try each AI service in order (failover chain)
-        let mut errors = Vec::new();
-        for service in &self.services {
-            log::info!("  Trying {}...", service.name());
+        match analysis {
+            Ok((mut ai_data, service, sources)) => {
+                result.field_sources = sources;
+                // Cache what the AI returned, before this run's post-processing
+                if let (Some(cache), Some(key)) = (&self.analysis_cache, &cache_key)
+                    && service.is_some_and(|s| s != cache::CACHE_SERVICE_NAME)
+                {
+                    cache.insert(key.clone(), ai_data.clone());
+                }
+                ai_data.language = self.language.clone();
+                if let (Some(style), Some(tags)) = (self.tag_style, &ai_data.tags) {
+                    ai_data.tags = Some(ai::normalize_tags(tags, style)).filter(|t| !t.is_empty());
+                }
+                if self.description_format != DescriptionFormat::AsIs
+                    && let Some(ref desc) = ai_data.description
+                {
+                    ai_data.description = Some(ai::format_description(desc, self.description_format));
+                }
+                if self.filename_fallback_title
+                    && ai_data.title.as_deref().is_none_or(|t| t.trim().is_empty())
+                    && let Some(title) = title_from_filename(path)
+                {
+                    log::info!("  No AI title; using filename: {title}");
+                    ai_data.title = Some(title);
+                    result.title_from_filename = true;
+                }
+                if ai_data.clamp(self.limits.max_title_chars, self.limits.max_description_chars) {
+                    log::info!(
+                        "  Shortened title/description to {}/{} characters",
+                        self.limits.max_title_chars,
+                        self.limits.max_description_chars
+                    );
+                }
+                if self.folder_keyword
+                    && let Some(keyword) = keyword_from_folder(path)
+                {
+                    let tags = ai_data.tags.get_or_insert_with(Vec::new);
+                    if !tags.iter().any(|t| t.trim().eq_ignore_ascii_case(&keyword)) {
+                        tags.push(keyword);
+                    }
+                }
+                if self.write_aspect_class
+                    && let Some(class) = image_dimensions(path, &result.existing_exif)
+                        .and_then(|(w, h)| AspectClass::from_dimensions(w, h))
+                {
+                    let tags = ai_data.tags.get_or_insert_with(Vec::new);
+                    if !tags.iter().any(|t| t.trim().eq_ignore_ascii_case(class.as_str())) {
+                        tags.push(class.to_string());
+                    }
+                }
+                if self.write_provenance
+                    && let Some(service) = service
+                {
+                    let model = match &self.openai_batch {
+                        Some(batch) if batch.name() == service => batch.model(),
+                        _ => self.services.iter().find(|s| s.name() == service).and_then(|s| s.model()),
+                    };
+                    result.provenance = Some(Provenance::new(service, model, &prompt));
+                }
+                result.ai_result = Some(ai_data);
+                result.ai_service_used = service.map(str::to_string);
+            }
+            Err(e) => {
+                result.error = Some(e);
+                return result;
+            }
+        }
 
-            // Use file-based analysis for services that support it (e.g. local BLIP),
-            // otherwise fall back to base64 analysis.
-            let ai_response = if service.supports_file_analysis() {
-                service.analyze_file(path)
-            } else {
-                service.analyze(&image_base64, &prompt, mime_type).await
-            };
+        // Enforce completeness: the model omitted fields that are required.
+        // Without AI there is no model to hold to them.
+        if !self.no_ai {
+            result.missing_fields = missing_fields(result.ai_result.as_ref().unwrap(), &self.required_fields);
+        }
+        if !result.missing_fields.is_empty() {
+            let error = ExifAiError::MissingFields(result.missing_fields.clone());
+            if !self.allow_partial {
+                result.error = Some(error);
+                return result;
+            }
+            log::warn!("  {error}");
+        }
 
-            match ai_response {
-                Ok(ai_data) => {
-                    if ai_data.title.is_some() || ai_data.description.is_some() {
-                        result.ai_result = Some(ai_data);
-                        result.ai_service_used = Some(service.name().to_string());
-                        log::info!("  {} succeeded", service.name());
-                        break;
-                    } else {
-                        errors.push(format!("{}: returned empty result", service.name()));
+        // JSON only: record the result and leave the image untouched
+        if self.sidecar_json {
+            result.write_mode = WriteMode::Json;
+            if !self.dry_run {
+                match write_json_file(&result, &sidecar_json_path(path)) {
+                    Ok(json_path) => result.json_sidecar_path = Some(json_path),
+                    Err(e) => {
+                        result.write_mode = WriteMode::Skipped;
+                        result.error = Some(ExifAiError::io_or(
+                            path,
+                            &e,
+                            format!("Failed to write JSON sidecar: {e}"),
+                            |path, message| ExifAiError::Write { path, message },
+                        ));
                     }
                 }
-                Err(e) => {
-                    errors.push(format!("{}: {e}", service.name()));
-                    log::warn!("  {} failed: {e}", service.name());
-                }
             }
+            return result;
         }
 
-        if result.ai_result.is_none() {
-            result.error = Some(format!(
-                "All AI services failed: {}",
-                errors.join("; ")
+        // Write metadata based on image kind
+        let ai_data = result.ai_result.as_ref().unwrap();
+        let image_kind = self.write_kind(kind.unwrap_or(ImageKind::Jpeg));
+
+        // Give the link its own file so the target stays untouched. Sidecar
+        // formats never modify the image, so the link can stay.
+        if is_symlink
+            && self.symlink_policy == SymlinkPolicy::Copy
+            && image_kind != ImageKind::Sidecar
+            && !self.dry_run
+            && let Err(e) = replace_symlink_with_copy(path)
+        {
+            result.error = Some(ExifAiError::io_or(
+                path,
+                &e,
+                format!("Failed to copy symlink target: {e}"),
+                |path, message| ExifAiError::Write { path, message },
             ));
             return result;
         }
 
-        // Backup original if configured
-        if self.backup_originals && !self.dry_run {
-            if let Err(e) = backup_file(path) {
+        // Backup original if configured. TIFF metadata can't be split from
+        // the pixel data, so it always gets a full copy.
+        if !self.dry_run {
+            let backup = if self.backup_metadata_only && image_kind != ImageKind::Tiff {
+                Some(backup_metadata(path, image_kind))
+            } else if self.backup_originals || self.backup_metadata_only {
+                Some(backup_file(path))
+            } else {
+                None
+            };
+            if let Some(Err(e)) = backup {
                 log::warn!("Failed to backup {}: {e}", path.display());
             }
         }
 
-        // Write metadata based on image kind
-        let ai_data = result.ai_result.as_ref().unwrap();
-        let image_kind = kind.unwrap_or(ImageKind::Jpeg);
+        // An augmented title/description already blends the existing text,
+        // so it replaces the original instead of being skipped as existing.
+        let write_existing = if augmenting {
+            ExifData { title: None, description: None, ..result.existing_exif.clone() }
+        } else {
+            result.existing_exif.clone()
+        };
 
         match write_exif(
             path,
             ai_data,
-            &result.existing_exif,
+            &write_existing,
             &self.exif_fields,
             self.dry_run,
             image_kind,
+            self.xmp_only,
         ) {
             Ok(write_result) => {
                 result.title_written = write_result.title_written;
@@ -286,11 +631,66 @@ impl Pipeline {
                 result.tags_written = write_result.tags_written;
                 result.gps_written = write_result.gps_written;
                 result.subject_written = write_result.subject_written;
+                result.date_written = write_result.date_written;
+                result.copyright_written = write_result.copyright_written;
+                result.artist_written = write_result.artist_written;
                 result.skipped_fields = write_result.skipped_fields;
+                if self.warn_unsupported_fields {
+                    for skipped in &result.skipped_fields {
+                        if skipped.reason == SkipReason::FormatUnsupported {
+                            log::warn!("  {} can't hold {} — not written", path.display(), skipped.field);
+                        }
+                    }
+                }
                 result.sidecar_path = write_result.sidecar_path;
+                // exiftool also changed the HEIC/RAW file itself
+                let embedded = match &write_result.exiftool {
+                    Some(run) if run.success() => {
+                        log::info!("  Embedded with exiftool: {}", path.display());
+                        true
+                    }
+                    // Already logged by the writer
+                    _ => false,
+                };
+                let any_written = result.title_written
+                    || result.description_written
+                    || result.tags_written
+                    || result.gps_written
+                    || result.subject_written
+                    || result.date_written
+                    || result.copyright_written
+                    || result.artist_written;
+                if result.sidecar_path.is_some() {
+                    result.write_mode = WriteMode::Sidecar;
+                } else if any_written {
+                    result.write_mode = WriteMode::for_kind(image_kind);
+                }
+                // The written file has new bytes; file the result under them too
+                if (embedded || (result.sidecar_path.is_none() && any_written))
+                    && !self.dry_run
+                    && let (Some(cache), Some(key)) = (&self.analysis_cache, &cache_key)
+                    && let Some(analyzed) = cache.get(key)
+                {
+                    match cache::hash_file(path) {
+                        Ok(written_key) => cache.insert(written_key, analyzed),
+                        Err(e) => log::warn!("  Not caching written {}: {e:#}", path.display()),
+                    }
+                }
+
+                if (self.write_json_sidecar || self.write_provenance) && !self.dry_run {
+                    match write_json_sidecar(&result) {
+                        Ok(json_path) => result.json_sidecar_path = Some(json_path),
+                        Err(e) => log::warn!("Failed to write JSON sidecar for {}: {e}", path.display()),
+                    }
+                }
             }
             Err(e) => {
-                result.error = Some(format!("Failed to write metadata: {e}"));
+                result.error = Some(ExifAiError::io_or(
+                    path,
+                    &e,
+                    format!("Failed to write metadata: {e}"),
+                    |path, message| ExifAiError::Write { path, message },
+                ));
             }
         }
 
@@ -326,254 +726,1632 @@ impl Pipeline {
         }
         results
     }
-}
 
-/// Builder for constructing a [`Pipeline`].
-///
-/// # Example
-///
-/// ```rust,no_run
-/// use exif_ai::pipeline::Pipeline;
-/// use exif_ai::config::Config;
-///
-/// let config = Config::load(Some("config.json".as_ref())).unwrap();
-///
-/// // From config (most common)
-/// let pipeline = Pipeline::builder()
-///     .from_config(&config)
-///     .build()
-///     .unwrap();
-///
-/// // Manual construction
-/// use exif_ai::ai::OpenAiService;
-/// use exif_ai::config::ExifFields;
-///
-/// let pipeline = Pipeline::builder()
-///     .add_service(Box::new(OpenAiService::new("sk-...".into(), "gpt-4o-mini".into())))
-///     .fields(ExifFields {
-///         write_title: true,
-///         write_description: true,
-///         write_tags: true,
-///         write_gps: false,
-///         write_subject: false,
-///         overwrite_existing: false,
-///     })
-///     .dry_run(true)
-///     .build()
-///     .unwrap();
-/// ```
-pub struct PipelineBuilder {
-    services: Vec<Box<dyn AiService>>,
-    exif_fields: ExifFields,
-    dry_run: bool,
-    backup_originals: bool,
-}
+    /// Process images with up to `concurrency` of them in flight at once,
+    /// returning the results in input order.
+    ///
+    /// Worth raising when the time goes into waiting for a remote service.
+    /// See [`process_images_with`](Self::process_images_with).
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # use exif_ai::pipeline::Pipeline;
+    /// # use exif_ai::config::Config;
+    /// # use std::path::PathBuf;
+    /// # async fn example() -> anyhow::Result<()> {
+    /// let config = Config::load(Some("config.json".as_ref()))?;
+    /// let pipeline = Pipeline::builder().from_config(&config).build()?;
+    ///
+    /// let images = exif_ai::pipeline::collect_images(&[PathBuf::from("photos")]);
+    /// let results = pipeline.process_images(&images, config.output.max_concurrency).await;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn process_images(&self, paths: &[PathBuf], concurrency: usize) -> Vec<ProcessResult> {
+        self.process_images_with(paths, HashMap::new(), concurrency, |_, _| {}).await
+    }
 
-impl PipelineBuilder {
-    /// Create a new builder with default settings.
-    fn new() -> Self {
-        Self {
-            services: Vec::new(),
-            exif_fields: ExifFields::default(),
-            dry_run: false,
-            backup_originals: false,
+    /// Like [`process_images`](Self::process_images), but analyze the images
+    /// in one OpenAI batch first when [batch mode](Self::batch_enabled) is on.
+    ///
+    /// Batch results arrive in minutes to hours rather than seconds, so
+    /// nothing is written until the batch finishes. Images the batch leaves
+    /// out, and every image if the batch endpoint errors, are analyzed one at
+    /// a time through the regular service chain. Without batch mode this is
+    /// the same as `process_images`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # use exif_ai::pipeline::Pipeline;
+    /// # use exif_ai::config::Config;
+    /// # use std::path::PathBuf;
+    /// # async fn example() -> anyhow::Result<()> {
+    /// let config = Config::load(Some("config.json".as_ref()))?;
+    /// let pipeline = Pipeline::builder().from_config(&config).build()?;
+    ///
+    /// let images = exif_ai::pipeline::collect_images(&[PathBuf::from("photos")]);
+    /// let results = pipeline.process_images_batched(&images, config.output.max_concurrency).await;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn process_images_batched(&self, paths: &[PathBuf], concurrency: usize) -> Vec<ProcessResult> {
+        let analyzed = self.analyze_batch(paths).await;
+        self.process_images_with(paths, analyzed, concurrency, |_, _| {}).await
+    }
+
+    /// Like [`process_images`](Self::process_images), calling `on_progress`
+    /// each time an image is done with the number of images done so far, the
+    /// total and the image's path — enough to drive a progress bar.
+    ///
+    /// The callback runs in the processing loop, never for two images at
+    /// once, but with `concurrency` > 1 images finish out of input order, so
+    /// `path` is not necessarily the `done`th path. It should return quickly,
+    /// as no further results are collected while it runs.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # use exif_ai::pipeline::Pipeline;
+    /// # use std::path::PathBuf;
+    /// # async fn example(pipeline: Pipeline, images: Vec<PathBuf>) {
+    /// let results = pipeline
+    ///     .process_images_with_progress(&images, 4, |done, total, path| {
+    ///         eprintln!("[{done}/{total}] {}", path.display());
+    ///     })
+    ///     .await;
+    /// # }
+    /// ```
+    pub async fn process_images_with_progress(
+        &self,
+        paths: &[PathBuf],
+        concurrency: usize,
+        on_progress: impl Fn(usize, usize, &Path),
+    ) -> Vec<ProcessResult> {
+        let total = paths.len();
+        let mut done = 0;
+        self.process_images_with(paths, HashMap::new(), concurrency, |i, _| {
+            done += 1;
+            on_progress(done, total, &paths[i]);
+        })
+        .await
+    }
+
+    /// Like [`process_images`](Self::process_images), handing each result to
+    /// `on_result` with its index in `paths` as soon as it is done.
+    ///
+    /// Images found in `analyzed` (e.g. from [`analyze_batch`](Self::analyze_batch))
+    /// skip the service chain, as in [`process_image_with`](Self::process_image_with).
+    ///
+    /// Images that write to the same files never run at the same time: paths
+    /// resolving to the same file (e.g. a symlink and its target) or sharing
+    /// an `.xmp` sidecar (`IMG_1.CR2` and `IMG_1.NEF`) are processed one after
+    /// the other, so backups and sidecars stay consistent. A `concurrency` of
+    /// 0 is treated as 1.
+    pub async fn process_images_with<F>(
+        &self,
+        paths: &[PathBuf],
+        analyzed: HashMap<PathBuf, AiResult>,
+        concurrency: usize,
+        mut on_result: F,
+    ) -> Vec<ProcessResult>
+    where
+        F: FnMut(usize, &ProcessResult),
+    {
+        use futures::StreamExt;
+
+        let mut results: Vec<Option<ProcessResult>> = std::iter::repeat_with(|| None).take(paths.len()).collect();
+        let jobs = self.jobs(paths, analyzed);
+        let mut done = futures::stream::iter(jobs).buffer_unordered(concurrency.max(1));
+        while let Some((i, result)) = done.next().await {
+            on_result(i, &result);
+            results[i] = Some(result);
         }
+        if let Err(e) = self.save_cache() {
+            log::warn!("{e:#}");
+        }
+        results.into_iter().map(|r| r.expect("every image yields a result")).collect()
     }
 
-    /// Populate the builder from a [`Config`].
+    /// Process images with up to `concurrency` of them in flight, yielding
+    /// each [`ProcessResult`] as soon as its image is done.
     ///
-    /// Loads AI services from the config's `service_order` and enabled flags,
-    /// and copies EXIF field settings and output options.
-    pub fn from_config(mut self, config: &Config) -> Self {
-        self.exif_fields = config.exif_fields.clone();
-        self.dry_run = config.output.dry_run;
-        self.backup_originals = config.output.backup_originals;
+    /// With `concurrency` > 1 results arrive in completion order, not input
+    /// order; use [`ProcessResult::path`] to match them up. Images that write
+    /// to the same files are serialized as in
+    /// [`process_images_with`](Self::process_images_with). New analysis cache
+    /// entries are not saved when the stream ends; call
+    /// [`save_cache`](Self::save_cache) afterwards (the pipeline also saves
+    /// them when dropped).
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # use exif_ai::pipeline::Pipeline;
+    /// # use futures::StreamExt;
+    /// # use std::path::PathBuf;
+    /// # async fn example(pipeline: &Pipeline, images: Vec<PathBuf>) {
+    /// let mut results = std::pin::pin!(pipeline.process_stream(&images, 4));
+    /// while let Some(result) = results.next().await {
+    ///     match &result.error {
+    ///         Some(e) => eprintln!("{}: {e}", result.path.display()),
+    ///         None => println!("{}: done", result.path.display()),
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    pub fn process_stream<'a>(
+        &'a self,
+        paths: &'a [PathBuf],
+        concurrency: usize,
+    ) -> impl futures::Stream<Item = ProcessResult> + 'a {
+        use futures::StreamExt;
 
-        for name in &config.service_order {
-            match name.as_str() {
-                "openai" if config.ai_services.openai.enabled => {
-                    if config.ai_services.openai.api_key.is_empty() {
-                        log::warn!("OpenAI enabled but no API key configured");
-                        continue;
-                    }
-                    self.services.push(Box::new(ai::OpenAiService::new(
-                        config.ai_services.openai.api_key.clone(),
-                        config.ai_services.openai.model.clone(),
-                    )));
-                }
-                "gemini" if config.ai_services.gemini.enabled => {
-                    if config.ai_services.gemini.api_key.is_empty() {
-                        log::warn!("Gemini enabled but no API key configured");
-                        continue;
-                    }
-                    self.services.push(Box::new(ai::GeminiService::new(
-                        config.ai_services.gemini.api_key.clone(),
-                        config.ai_services.gemini.model.clone(),
-                    )));
+        futures::stream::iter(self.jobs(paths, HashMap::new()))
+            .buffer_unordered(concurrency.max(1))
+            .map(|(_, result)| result)
+    }
+
+    /// One future per image, tagged with its index in `paths`. Futures for
+    /// images sharing a [`write_key`] hold the same lock while they run.
+    fn jobs<'a>(
+        &'a self,
+        paths: &'a [PathBuf],
+        mut analyzed: HashMap<PathBuf, AiResult>,
+    ) -> Vec<impl std::future::Future<Output = (usize, ProcessResult)> + 'a> {
+        let total = paths.len();
+        let mut locks: HashMap<PathBuf, std::sync::Arc<tokio::sync::Mutex<()>>> = HashMap::new();
+        paths
+            .iter()
+            .enumerate()
+            .map(|(i, path)| {
+                let lock = locks.entry(write_key(path)).or_default().clone();
+                let analyzed = analyzed.remove(path);
+                async move {
+                    let _guard = lock.lock().await;
+                    log::info!("[{}/{}] Processing: {}", i + 1, total, path.display());
+                    (i, self.process_image_with(path, analyzed).await)
                 }
-                "cloudflare" if config.ai_services.cloudflare.enabled => {
-                    if config.ai_services.cloudflare.account_id.is_empty()
-                        || config.ai_services.cloudflare.api_token.is_empty()
-                    {
-                        log::warn!("Cloudflare enabled but account ID or API token not configured");
-                        continue;
-                    }
-                    self.services.push(Box::new(ai::CloudflareService::new(
-                        config.ai_services.cloudflare.account_id.clone(),
-                        config.ai_services.cloudflare.api_token.clone(),
-                        config.ai_services.cloudflare.model.clone(),
-                    )));
-                }
-                "local" if config.ai_services.local.enabled => {
-                    let local_service = if config.ai_services.local.model_path.is_empty() {
-                        match ai::LocalService::from_default_dir() {
-                            Ok(s) => s,
-                            Err(e) => {
-                                log::warn!("Local service: failed to resolve model directory: {e}");
-                                continue;
-                            }
-                        }
-                    } else {
-                        let dir = std::path::PathBuf::from(&config.ai_services.local.model_path);
-                        ai::LocalService::new(
-                            dir.join("model.safetensors"),
-                            dir.join("tokenizer.json"),
-                        )
-                    };
-                    if !local_service.model_exists() {
-                        log::warn!(
-                            "Local model not found. Run `exif-ai-cli --download-model` to download it. Skipping local service."
-                        );
-                        continue;
-                    }
-                    self.services.push(Box::new(local_service));
-                }
-                _ => {}
+            })
+            .collect()
+    }
+
+    /// Analyze a single image through the service chain without writing anything.
+    ///
+    /// Returns the first non-empty result together with the name of the service
+    /// that produced it. Existing EXIF is not read and no [`ProcessResult`] is built.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # use exif_ai::pipeline::Pipeline;
+    /// # use exif_ai::config::Config;
+    /// # use std::path::Path;
+    /// # async fn example() -> anyhow::Result<()> {
+    /// let config = Config::load(Some("config.json".as_ref()))?;
+    /// let pipeline = Pipeline::builder().from_config(&config).build()?;
+    ///
+    /// let (ai, service) = pipeline.analyze(Path::new("photo.jpg")).await?;
+    /// println!("{service}: {:?}", ai.title);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn analyze(&self, path: &Path) -> Result<(AiResult, &str)> {
+        let (ai_data, service, _) = self.analyze_with_prompt(path, &self.base_prompt()).await?;
+        Ok((ai_data, service))
+    }
+
+    /// The prompt [`process_image`](Self::process_image) would send for an
+    /// image, without calling any service.
+    ///
+    /// With a `path`, the image's existing metadata is read and injected as
+    /// `process_image` would (e.g. in augment mode). Without one, the prompt
+    /// for an image without metadata is returned.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # use exif_ai::pipeline::Pipeline;
+    /// # use exif_ai::config::Config;
+    /// # use std::path::Path;
+    /// # fn example() -> anyhow::Result<()> {
+    /// # let config = Config::load(None)?;
+    /// let pipeline = Pipeline::builder().from_config(&config).build()?;
+    /// println!("{}", pipeline.effective_prompt(Some(Path::new("photo.jpg"))));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn effective_prompt(&self, path: Option<&Path>) -> String {
+        let existing = match path.map(exif::read_exif) {
+            Some(Ok(data)) => data,
+            Some(Err(e)) => {
+                log::warn!("Failed to read EXIF from {}: {e}", path.unwrap().display());
+                ExifData::default()
             }
+            None => ExifData::default(),
+        };
+        self.prompt_for(&existing).0
+    }
+
+    /// The request `service` would send for an image: its
+    /// [`effective_prompt`](Self::effective_prompt) wrapped in the service's
+    /// request body, with [`ai::IMAGE_PLACEHOLDER`] in place of the image.
+    ///
+    /// `service` is matched against the configured services' names, ignoring
+    /// case and anything after the first word (`"local"` matches
+    /// `"Local (BLIP)"`). Fails if no such service is configured, or if it
+    /// doesn't send a prompt.
+    pub fn request_preview(&self, service: &str, path: Option<&Path>) -> Result<serde_json::Value> {
+        let found = self.services.iter().find(|s| {
+            s.name().eq_ignore_ascii_case(service)
+                || s.name().split(' ').next().is_some_and(|first| first.eq_ignore_ascii_case(service))
+        });
+        let Some(found) = found else {
+            anyhow::bail!("Service \"{service}\" is not configured (configured: {})", self.service_names().join(", "));
+        };
+        let mime_type = path.and_then(|p| ImageKind::from_path(p).map(|k| k.mime_type(p))).unwrap_or("image/jpeg");
+        found
+            .request_preview(&self.effective_prompt(path), mime_type)
+            .with_context(|| format!("{} does not send a prompt", found.name()))
+    }
+
+    /// The custom prompt if one is set, otherwise the built-in one asking
+    /// for the [requested](Self::requested) fields.
+    fn base_prompt(&self) -> String {
+        self.prompt_override.clone().unwrap_or_else(|| ai::build_prompt_requesting(|field| self.requested(field)))
+    }
+
+    /// Whether the AI is asked for `field`: it is written or required.
+    ///
+    /// Every field is asked for with a custom prompt, when the JSON catalog
+    /// (`sidecar_json`, `write_json_sidecar`) records the full result, or when
+    /// no field would be written at all.
+    fn requested(&self, field: Field) -> bool {
+        let used = |f: &Field| self.exif_fields.writes(*f) || self.required_fields.contains(f);
+        self.prompt_override.is_some()
+            || self.sidecar_json
+            || self.write_json_sidecar
+            || !exif::ALL_FIELDS.iter().any(used)
+            || used(&field)
+    }
+
+    /// Whether an AI result counts as a success: it has a title or
+    /// description, or, if neither was asked for, any requested field.
+    fn usable(&self, ai: &AiResult) -> bool {
+        if self.requested(Field::Title) || self.requested(Field::Description) {
+            return ai.title.is_some() || ai.description.is_some();
         }
+        let requested: Vec<Field> = exif::ALL_FIELDS.iter().copied().filter(|&f| self.requested(f)).collect();
+        missing_fields(ai, &requested).len() < requested.len()
+    }
 
-        self
+    /// Assemble the prompt for an image with `existing` metadata, and whether
+    /// it asks the AI to refine that metadata (augment mode).
+    fn prompt_for(&self, existing: &ExifData) -> (String, bool) {
+        // In augment mode, ask the AI to refine the existing title/description
+        let augmenting = self.augment_existing && (existing.title.is_some() || existing.description.is_some());
+        let mut prompt = self.base_prompt();
+        if augmenting {
+            ai::append_existing_metadata(&mut prompt, existing.title.as_deref(), existing.description.as_deref());
+        }
+        ai::append_exif_context(&mut prompt, &existing.prompt_context(&self.prompt_context_fields));
+        if let Some(lang) = &self.language {
+            ai::append_output_language(&mut prompt, lang);
+        }
+        ai::append_language_request(&mut prompt, &self.exif_fields.languages);
+        if let Some(style) = self.tag_style
+            && self.requested(Field::Tags)
+        {
+            ai::append_tag_style(&mut prompt, style);
+        }
+        if self.exif_fields.write_date && existing.date_time.is_none() {
+            ai::append_date_request(&mut prompt);
+        }
+        if self.exif_fields.write_tags && self.exif_fields.keyword_targets.lr_hierarchical_subject {
+            ai::append_hierarchy_request(&mut prompt);
+        }
+        (prompt, augmenting)
     }
 
-    /// Add a custom AI service to the pipeline.
-    pub fn add_service(mut self, service: Box<dyn AiService>) -> Self {
-        self.services.push(service);
-        self
+    /// How an image of `kind` is written: as a sidecar for every format with
+    /// [`always_sidecar`](PipelineBuilder::always_sidecar).
+    fn write_kind(&self, kind: ImageKind) -> ImageKind {
+        if self.always_sidecar { ImageKind::Sidecar } else { kind }
     }
 
-    /// Set which EXIF fields to write.
-    pub fn fields(mut self, fields: ExifFields) -> Self {
-        self.exif_fields = fields;
-        self
+    /// Checks run before an image is sent to the AI. `Ok(false)` means the
+    /// image is a symlink the [`SymlinkPolicy`] skips.
+    fn preflight(&self, path: &Path) -> Result<bool> {
+        let kind = ImageKind::from_path(path).unwrap_or(ImageKind::Jpeg);
+        let write_kind = self.write_kind(kind);
+        if self.symlink_policy == SymlinkPolicy::Skip
+            && path.symlink_metadata().is_ok_and(|m| m.file_type().is_symlink())
+        {
+            return Ok(false);
+        }
+        // Make sure the result can be written before paying for analysis
+        if !self.dry_run {
+            check_writable(path, write_kind)?;
+        }
+        // Don't send empty or broken files to the AI
+        check_image_data(path, kind)?;
+        Ok(true)
     }
 
-    /// Enable or disable dry-run mode.
-    pub fn dry_run(mut self, dry_run: bool) -> Self {
-        self.dry_run = dry_run;
-        self
+    /// Write new [analysis cache](PipelineBuilder::analysis_cache) entries to
+    /// disk. [`process_images_with`](Self::process_images_with) does this when
+    /// it finishes, and the cache when the pipeline is dropped.
+    pub fn save_cache(&self) -> Result<()> {
+        self.analysis_cache.as_ref().map_or(Ok(()), AnalysisCache::save)
     }
 
-    /// Enable or disable backup of original files before writing.
-    pub fn backup(mut self, backup: bool) -> Self {
-        self.backup_originals = backup;
-        self
+    /// The analysis cache key for `path` and, unless overwriting existing
+    /// metadata, the result cached under it.
+    fn cache_lookup(&self, path: &Path) -> (Option<String>, Option<AiResult>) {
+        let Some(cache) = self.analysis_cache.as_ref().filter(|_| !self.no_ai) else {
+            return (None, None);
+        };
+        match cache::hash_file(path) {
+            Ok(key) => {
+                let cached = if self.exif_fields.overwrite_existing { None } else { cache.get(&key) };
+                (Some(key), cached)
+            }
+            Err(e) => {
+                log::warn!("  Not caching {}: {e:#}", path.display());
+                (None, None)
+            }
+        }
     }
 
-    /// Build the [`Pipeline`].
+    /// Whether [`analyze_batch`](Self::analyze_batch) will use the OpenAI
+    /// Batch API (see [`OpenAiConfig::batch`](crate::config::OpenAiConfig::batch)).
+    pub fn batch_enabled(&self) -> bool {
+        self.openai_batch.is_some() && !self.no_ai
+    }
+
+    /// Analyze images in one OpenAI batch, returning the results by path.
     ///
-    /// Returns an error if no AI services are configured.
-    pub fn build(self) -> Result<Pipeline> {
-        if self.services.is_empty() {
-            anyhow::bail!(
-                "No AI services configured. Add services via from_config() or add_service()."
-            );
+    /// This waits until the batch finishes, which can take up to 24 hours.
+    /// Each image gets the prompt [`process_image`](Self::process_image)
+    /// would send. Images that fail the usual pre-checks, have a result in
+    /// the analysis cache, have a format the batch can't read, or come back
+    /// failed or empty are left out; pass the results to
+    /// [`process_image_with`](Self::process_image_with), which serves the
+    /// missing images from the cache or the regular service chain.
+    ///
+    /// Returns an empty map when batch mode is not enabled.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # use exif_ai::pipeline::Pipeline;
+    /// # use exif_ai::config::Config;
+    /// # use std::path::PathBuf;
+    /// # async fn example() -> anyhow::Result<()> {
+    /// let config = Config::load(Some("config.json".as_ref()))?;
+    /// let pipeline = Pipeline::builder().from_config(&config).build()?;
+    ///
+    /// let images = vec![PathBuf::from("a.jpg"), PathBuf::from("b.png")];
+    /// let mut analyzed = pipeline.analyze_batch(&images).await;
+    /// for path in &images {
+    ///     let result = pipeline.process_image_with(path, analyzed.remove(path)).await;
+    ///     println!("{}", result.summary_line());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn analyze_batch(&self, paths: &[PathBuf]) -> HashMap<PathBuf, AiResult> {
+        let Some(batch) = self.openai_batch.as_ref().filter(|_| !self.no_ai) else {
+            return HashMap::new();
+        };
+
+        let mut requests = Vec::new();
+        for (index, path) in paths.iter().enumerate() {
+            // Failures are reported again when the image is processed
+            if !self.preflight(path).unwrap_or(false) {
+                continue;
+            }
+            // Served from the cache when the image is processed
+            if self.cache_lookup(path).1.is_some() {
+                continue;
+            }
+            let Ok((image_base64, mime_type)) = upload_image(path, self.max_upload_dimension) else {
+                continue;
+            };
+            if !batch.supports_mime_type(mime_type) {
+                continue;
+            }
+            let existing = exif::read_exif(path).unwrap_or_default();
+            requests.push(ai::BatchRequest {
+                custom_id: index.to_string(),
+                image_base64,
+                prompt: self.prompt_for(&existing).0,
+                mime_type: mime_type.to_string(),
+            });
+        }
+        if requests.is_empty() {
+            return HashMap::new();
         }
 
-        Ok(Pipeline {
-            services: self.services,
-            exif_fields: self.exif_fields,
-            dry_run: self.dry_run,
-            backup_originals: self.backup_originals,
-        })
+        log::info!("Submitting {} image(s) to {}", requests.len(), batch.name());
+        let results = match batch.run(&requests).await {
+            Ok(results) => results,
+            Err(e) => {
+                log::warn!("{} failed: {e}", batch.name());
+                return HashMap::new();
+            }
+        };
+
+        results
+            .into_iter()
+            .filter_map(|(id, result)| {
+                let path = id.parse::<usize>().ok().and_then(|i| paths.get(i))?;
+                match result {
+                    Ok(ai_data) if self.usable(&ai_data) => {
+                        Some((path.clone(), ai_data))
+                    }
+                    Ok(_) => {
+                        log::warn!("  {}: {} returned empty result", path.display(), batch.name());
+                        None
+                    }
+                    Err(e) => {
+                        log::warn!("  {}: {} failed: {e}", path.display(), batch.name());
+                        None
+                    }
+                }
+            })
+            .collect()
+    }
+
+    /// Run the failover chain with a specific prompt, returning the result,
+    /// the service credited with it and the service behind each field.
+    async fn analyze_with_prompt(
+        &self,
+        path: &Path,
+        prompt: &str,
+    ) -> Result<(AiResult, &str, BTreeMap<Field, String>), ExifAiError> {
+        let (image_base64, mime_type) = upload_image(path, self.max_upload_dimension).map_err(|e| {
+            ExifAiError::io_or(path, &e, e.to_string(), |path, message| ExifAiError::Read { path, message })
+        })?;
+
+        // Try each AI service in order (failover chain), skipping those that can't read this format
+        let mut errors = Vec::new();
+        let mut merged: Option<(AiResult, &str, BTreeMap<Field, String>)> = None;
+        for service in &self.services {
+            if !service.supports_mime_type(mime_type) {
+                log::debug!("  Skipping {}: {mime_type} not supported", service.name());
+                continue;
+            }
+            log::info!("  Trying {}...", service.name());
+
+            // Rate limited or a server error: back off and retry before failing over
+            let mut ai_response =
+                ai::retry_analyze(self.retry, || call_service(service, path, &image_base64, prompt, mime_type)).await;
+
+            // Output cut off at the token limit: retry once with twice the room
+            if let Err(ref e) = ai_response
+                && let Some(AiError::InvalidJson { raw }) = e.downcast_ref::<AiError>()
+                && ai::is_truncated_json(raw)
+                && !service.supports_file_analysis()
+                && let Some(limit) = service.max_tokens()
+            {
+                let raised = limit.saturating_mul(2);
+                log::warn!("  {} response was truncated, retrying with max_tokens {raised}", service.name());
+                ai_response = service.analyze_with_max_tokens(&image_base64, prompt, mime_type, raised).await;
+            }
+
+            // Unparseable output: ask once more, showing the model what it returned
+            if self.reprompt_on_parse_error
+                && let Err(ref e) = ai_response
+                && let Some(AiError::InvalidJson { raw }) = e.downcast_ref::<AiError>()
+            {
+                log::warn!("  {} returned invalid JSON, re-prompting", service.name());
+                let reprompt = ai::build_reprompt(prompt, raw);
+                ai_response = call_service(service, path, &image_base64, &reprompt, mime_type).await;
+            }
+
+            match ai_response {
+                Ok(ai_data) if self.merge_services => {
+                    let (combined, first, sources) =
+                        merged.get_or_insert_with(|| (AiResult::default(), "", BTreeMap::new()));
+                    let filled = fill_missing(combined, ai_data, service.name(), sources);
+                    if filled.is_empty() {
+                        errors.push(format!("{}: returned no new fields", service.name()));
+                        continue;
+                    }
+                    if first.is_empty() {
+                        *first = service.name();
+                    }
+                    let names: Vec<&str> = filled.iter().map(Field::as_str).collect();
+                    log::info!("  {} contributed {}", service.name(), names.join(", "));
+                    let wanted: Vec<Field> = [Field::Title, Field::Description, Field::Tags]
+                        .into_iter()
+                        .filter(|&f| self.requested(f))
+                        .chain(self.required_fields.iter().copied())
+                        .collect();
+                    if missing_fields(combined, &wanted).is_empty() {
+                        break;
+                    }
+                }
+                Ok(ai_data) => {
+                    if self.usable(&ai_data) {
+                        log::info!("  {} succeeded", service.name());
+                        let sources = field_sources(&ai_data, service.name());
+                        return Ok((ai_data, service.name(), sources));
+                    } else {
+                        errors.push(format!("{}: returned empty result", service.name()));
+                    }
+                }
+                Err(e) => {
+                    errors.push(format!("{}: {e}", service.name()));
+                    log::warn!("  {} failed: {e}", service.name());
+                }
+            }
+        }
+
+        // A combined result must be usable, like a single one
+        if let Some(merged) = merged.filter(|(ai, ..)| self.usable(ai)) {
+            return Ok(merged);
+        }
+        if errors.is_empty() {
+            return Err(ExifAiError::Unsupported { path: path.to_path_buf(), mime_type: mime_type.to_string() });
+        }
+        Err(ExifAiError::AllServicesFailed(errors))
+    }
+
+    /// Analyze multiple images without writing, handing each result to `on_result`.
+    ///
+    /// Results are delivered as they complete instead of being collected, which
+    /// keeps memory flat for long-running or high-throughput callers.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # use exif_ai::pipeline::Pipeline;
+    /// # use exif_ai::config::Config;
+    /// # use std::path::PathBuf;
+    /// # async fn example() -> anyhow::Result<()> {
+    /// let config = Config::load(Some("config.json".as_ref()))?;
+    /// let pipeline = Pipeline::builder().from_config(&config).build()?;
+    ///
+    /// let images = vec![PathBuf::from("a.jpg"), PathBuf::from("b.png")];
+    /// pipeline.analyze_all(&images, |path, result| match result {
+    ///     Ok((ai, service)) => println!("{} [{service}]: {:?}", path.display(), ai.title),
+    ///     Err(e) => eprintln!("{}: {e}", path.display()),
+    /// }).await;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn analyze_all<F>(&self, paths: &[PathBuf], mut on_result: F)
+    where
+        F: FnMut(&Path, Result<(AiResult, &str)>),
+    {
+        for path in paths {
+            on_result(path, self.analyze(path).await);
+        }
     }
 }
 
-/// The result of processing a single image through the AI pipeline.
+/// Run one service on an already-encoded image.
 ///
-/// Contains the AI analysis output, which metadata fields were written,
-/// any errors encountered, and the sidecar path for HEIC/RAW formats.
+/// Uses file-based analysis for services that support it (e.g. local BLIP),
+/// otherwise falls back to base64 analysis.
+async fn call_service<S: AiService + ?Sized>(
+    service: &S,
+    path: &Path,
+    image_base64: &str,
+    prompt: &str,
+    mime_type: &str,
+) -> Result<AiResult> {
+    if service.supports_file_analysis() {
+        service.analyze_file(path)
+    } else {
+        service.analyze(image_base64, prompt, mime_type).await
+    }
+}
+
+/// Analyze one image with a single service, without building a [`Pipeline`].
+///
+/// Services that support file analysis (e.g. local BLIP) read the file
+/// themselves; otherwise the image is base64-encoded and sent with the default
+/// prompt. Generic over `S`, so concrete services are called without boxing.
+/// Unlike [`Pipeline::analyze`], an empty result is returned as-is.
 ///
 /// # Example
 ///
 /// ```rust,no_run
-/// # use exif_ai::pipeline::Pipeline;
-/// # use exif_ai::config::Config;
-/// # async fn example() -> anyhow::Result<()> {
-/// # let config = Config::load(Some("config.json".as_ref()))?;
-/// # let pipeline = Pipeline::builder().from_config(&config).build()?;
-/// let result = pipeline.process_image("photo.jpg".as_ref()).await;
+/// use exif_ai::ai::OpenAiService;
+/// use exif_ai::pipeline::analyze_with;
+/// use std::path::Path;
 ///
-/// if result.error.is_none() {
-///     println!("Title written: {}", result.title_written);
-///     println!("AI service: {:?}", result.ai_service_used);
-///     if let Some(ref sidecar) = result.sidecar_path {
-///         println!("Sidecar: {}", sidecar.display());
-///     }
-/// }
+/// # async fn example() -> anyhow::Result<()> {
+/// let service = OpenAiService::new("sk-...".into(), "gpt-4o-mini".into());
+/// let ai = analyze_with(&service, Path::new("photo.jpg")).await?;
+/// println!("Title: {:?}", ai.title);
 /// # Ok(())
 /// # }
 /// ```
-#[derive(Debug)]
-pub struct ProcessResult {
-    pub path: PathBuf,
-    pub ai_result: Option<AiResult>,
-    pub existing_exif: ExifData,
-    pub title_written: bool,
-    pub description_written: bool,
-    pub tags_written: bool,
-    pub gps_written: bool,
-    pub subject_written: bool,
-    pub skipped_fields: Vec<String>,
-    pub error: Option<String>,
-    pub ai_service_used: Option<String>,
-    /// If a sidecar XMP file was written (for HEIC/RAW), this is the path.
-    pub sidecar_path: Option<PathBuf>,
-    /// The image kind detected for this file.
-    pub image_kind: Option<ImageKind>,
+pub async fn analyze_with<S: AiService + ?Sized>(service: &S, path: &Path) -> Result<AiResult> {
+    if service.supports_file_analysis() {
+        return service.analyze_file(path);
+    }
+
+    let image_bytes = std::fs::read(path)
+        .with_context(|| format!("Failed to read file: {}", path.display()))?;
+    let image_base64 = base64::Engine::encode(&base64::engine::general_purpose::STANDARD, &image_bytes);
+    let mime_type = ImageKind::from_path(path)
+        .map(|k| k.mime_type(path))
+        .unwrap_or("image/jpeg");
+
+    service.analyze(&image_base64, &ai::build_prompt(), mime_type).await
 }
 
-/// Collect supported image files from the given paths.
-///
-/// Accepts a mix of file paths and directory paths. Directories are walked
-/// recursively (following symlinks). Only files with supported image extensions
-/// are included (see [`ImageKind`] for the full list).
+/// JPEG quality for images downscaled before upload.
+const UPLOAD_JPEG_QUALITY: u8 = 85;
+
+/// The size an image of `width`×`height` is sent to the AI at: scaled down,
+/// keeping its aspect ratio, so its longest edge is at most `max_dimension`.
 ///
 /// # Example
 ///
-/// ```rust,no_run
-/// use exif_ai::pipeline::collect_images;
-/// use std::path::PathBuf;
+/// ```rust
+/// use exif_ai::pipeline::upload_dimensions;
 ///
-/// let images = collect_images(&[
-///     PathBuf::from("photo.jpg"),       // single file
-///     PathBuf::from("./photos/"),        // entire directory
-/// ]);
-/// println!("Found {} images", images.len());
+/// assert_eq!(upload_dimensions(6000, 4000, Some(1500)), (1500, 1000));
+/// assert_eq!(upload_dimensions(800, 600, Some(1500)), (800, 600));
+/// assert_eq!(upload_dimensions(6000, 4000, None), (6000, 4000));
 /// ```
-pub fn collect_images(paths: &[PathBuf]) -> Vec<PathBuf> {
-    let mut images = Vec::new();
+pub fn upload_dimensions(width: u32, height: u32, max_dimension: Option<u32>) -> (u32, u32) {
+    let longest = width.max(height);
+    match max_dimension {
+        Some(max) if longest > max => {
+            let scale = f64::from(max) / f64::from(longest);
+            let fit = |side: u32| ((f64::from(side) * scale).round() as u32).clamp(1, max);
+            (fit(width), fit(height))
+        }
+        _ => (width, height),
+    }
+}
 
-    for path in paths {
-        if path.is_file() {
-            if is_supported_image(path) {
-                images.push(path.clone());
-            } else {
-                log::warn!("Skipping unsupported file: {}", path.display());
-            }
-        } else if path.is_dir() {
-            for entry in WalkDir::new(path)
-                .follow_links(true)
-                .into_iter()
+/// Read an image for the AI: base64-encoded, with its MIME type.
+///
+/// With a `max_dimension`, larger images are downscaled and re-encoded as
+/// JPEG. Images that already fit, or that the `image` crate can't decode
+/// (RAW, HEIC, TIFF), are sent unchanged. GIF and BMP, which few services
+/// accept, are sent as PNG (the first frame of an animated GIF).
+pub(crate) fn upload_image(path: &Path, max_dimension: Option<u32>) -> Result<(String, &'static str)> {
+    let image_bytes = std::fs::read(path)
+        .map_err(|e| anyhow::anyhow!("Failed to read file: {e}"))?;
+    let mime_type = ImageKind::from_path(path)
+        .map(|k| k.mime_type(path))
+        .unwrap_or("image/jpeg");
+
+    let (bytes, mime_type) = match max_dimension.and_then(|max| downscale(path, &image_bytes, max)) {
+        Some(jpeg) => (jpeg, "image/jpeg"),
+        None if matches!(mime_type, "image/gif" | "image/bmp") => match to_png(path, &image_bytes) {
+            Some(png) => (png, "image/png"),
+            None => (image_bytes, mime_type),
+        },
+        None => (image_bytes, mime_type),
+    };
+    Ok((base64::Engine::encode(&base64::engine::general_purpose::STANDARD, &bytes), mime_type))
+}
+
+/// Downscale an encoded image to fit `max_dimension`, as a JPEG.
+///
+/// Returns `None` when it already fits or can't be decoded. The EXIF
+/// orientation is applied first, since the JPEG carries no metadata.
+fn downscale(path: &Path, bytes: &[u8], max_dimension: u32) -> Option<Vec<u8>> {
+    use image::ImageDecoder;
+
+    let decoder = image::ImageReader::new(std::io::Cursor::new(bytes))
+        .with_guessed_format()
+        .ok()
+        .and_then(|reader| reader.into_decoder().ok());
+    let Some(mut decoder) = decoder else {
+        log::debug!("  Sending {} at full size: format can't be decoded", path.display());
+        return None;
+    };
+    let (width, height) = decoder.dimensions();
+    let (target_width, target_height) = upload_dimensions(width, height, Some(max_dimension));
+    if (target_width, target_height) == (width, height) {
+        return None;
+    }
+    let orientation = decoder.orientation().unwrap_or(image::metadata::Orientation::NoTransforms);
+
+    let mut img = match image::DynamicImage::from_decoder(decoder) {
+        Ok(img) => img,
+        Err(e) => {
+            log::debug!("  Sending {} at full size: {e}", path.display());
+            return None;
+        }
+    };
+    img = img.resize_exact(target_width, target_height, image::imageops::FilterType::Triangle);
+    img.apply_orientation(orientation);
+
+    let mut jpeg = Vec::new();
+    let encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(&mut jpeg, UPLOAD_JPEG_QUALITY);
+    if let Err(e) = img.into_rgb8().write_with_encoder(encoder) {
+        log::debug!("  Sending {} at full size: {e}", path.display());
+        return None;
+    }
+    log::debug!(
+        "  Downscaled {} from {width}×{height} to {target_width}×{target_height} for upload",
+        path.display()
+    );
+    Some(jpeg)
+}
+
+/// Re-encode an image as PNG, or `None` if it can't be decoded.
+fn to_png(path: &Path, bytes: &[u8]) -> Option<Vec<u8>> {
+    let mut png = Vec::new();
+    let encoded = image::load_from_memory(bytes)
+        .and_then(|img| img.write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png));
+    if let Err(e) = encoded {
+        log::debug!("  Sending {} as it is: {e}", path.display());
+        return None;
+    }
+    Some(png)
+}
+
+/// Builder for constructing a [`Pipeline`].
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use exif_ai::pipeline::Pipeline;
+/// use exif_ai::config::Config;
+///
+/// let config = Config::load(Some("config.json".as_ref())).unwrap();
+///
+/// // From config (most common)
+/// let pipeline = Pipeline::builder()
+///     .from_config(&config)
+///     .build()
+///     .unwrap();
+///
+/// // Manual construction
+/// use exif_ai::ai::OpenAiService;
+/// use exif_ai::config::ExifFields;
+///
+/// let pipeline = Pipeline::builder()
+///     .add_service(Box::new(OpenAiService::new("sk-...".into(), "gpt-4o-mini".into())))
+///     .fields(ExifFields {
+///         write_title: true,
+///         write_description: true,
+///         write_tags: true,
+///         write_gps: false,
+///         write_subject: false,
+///         overwrite_existing: false,
+///         ..Default::default()
+///     })
+///     .dry_run(true)
+///     .build()
+///     .unwrap();
+/// ```
+pub struct PipelineBuilder<S: AiService = Box<dyn AiService>> {
+    services: Vec<S>,
+    exif_fields: ExifFields,
+    dry_run: bool,
+    backup_originals: bool,
+    backup_metadata_only: bool,
+    augment_existing: bool,
+    required_fields: Vec<Field>,
+    allow_partial: bool,
+    xmp_only: bool,
+    always_sidecar: bool,
+    write_json_sidecar: bool,
+    sidecar_json: bool,
+    filename_fallback_title: bool,
+    folder_keyword: bool,
+    write_aspect_class: bool,
+    no_ai: bool,
+    per_image_timeout: Option<Duration>,
+    max_upload_dimension: Option<u32>,
+    reprompt_on_parse_error: bool,
+    merge_services: bool,
+    warn_unsupported_fields: bool,
+    tag_style: Option<TagStyle>,
+    language: Option<String>,
+    write_provenance: bool,
+    description_format: DescriptionFormat,
+    limits: Limits,
+    symlink_policy: SymlinkPolicy,
+    prompt_context_fields: Vec<String>,
+    prompt_override: Option<String>,
+    retry: ai::RetryPolicy,
+    openai_batch: Option<ai::OpenAiBatch>,
+    analysis_cache: Option<AnalysisCache>,
+}
+
+impl<S: AiService> PipelineBuilder<S> {
+    /// Create a new builder with default settings.
+    fn new() -> Self {
+        Self {
+            services: Vec::new(),
+            exif_fields: ExifFields::default(),
+            dry_run: false,
+            backup_originals: false,
+            backup_metadata_only: false,
+            augment_existing: false,
+            required_fields: Vec::new(),
+            allow_partial: false,
+            xmp_only: false,
+            always_sidecar: false,
+            write_json_sidecar: false,
+            sidecar_json: false,
+            filename_fallback_title: false,
+            folder_keyword: false,
+            write_aspect_class: false,
+            no_ai: false,
+            per_image_timeout: None,
+            max_upload_dimension: None,
+            reprompt_on_parse_error: false,
+            merge_services: false,
+            warn_unsupported_fields: false,
+            tag_style: None,
+            language: None,
+            write_provenance: false,
+            description_format: DescriptionFormat::AsIs,
+            limits: Limits::default(),
+            symlink_policy: SymlinkPolicy::Follow,
+            prompt_context_fields: crate::config::default_prompt_context_fields(),
+            prompt_override: None,
+            retry: ai::RetryPolicy::default(),
+            openai_batch: None,
+            analysis_cache: None,
+        }
+    }
+}
+
+impl PipelineBuilder {
+
+    /// Populate the builder from a [`Config`].
+    ///
+    /// Loads AI services from the config's `service_order` and enabled flags,
+    /// and copies EXIF field settings and output options.
+    pub fn from_config(mut self, config: &Config) -> Self {
+        self.exif_fields = config.exif_fields.clone();
+        self.dry_run = config.output.dry_run;
+        self.backup_originals = config.output.backup_originals;
+        self.backup_metadata_only = config.output.backup_metadata_only;
+        self.augment_existing = config.output.augment_existing;
+        self.required_fields = config.output.required_ai_fields.clone();
+        self.allow_partial = config.output.allow_partial;
+        self.xmp_only = config.output.xmp_only;
+        self.always_sidecar = config.output.always_sidecar;
+        self.write_json_sidecar = config.output.write_json_sidecar;
+        self.sidecar_json = config.output.sidecar_json;
+        self.filename_fallback_title = config.output.filename_fallback_title;
+        self.folder_keyword = config.output.folder_keyword;
+        self.write_aspect_class = config.output.write_aspect_class;
+        self.no_ai = config.output.no_ai;
+        self.per_image_timeout = config.output.per_image_timeout_secs.map(Duration::from_secs);
+        self.max_upload_dimension = config.output.max_upload_dimension;
+        self.reprompt_on_parse_error = config.output.reprompt_on_parse_error;
+        self.merge_services = config.output.merge_services;
+        self.warn_unsupported_fields = config.output.warn_unsupported_fields;
+        self.tag_style = config.output.tag_style;
+        self.language = config.language.clone();
+        self.write_provenance = config.output.write_provenance;
+        self.description_format = config.output.description_format;
+        self.limits = config.limits;
+        self.symlink_policy = config.output.symlink_policy;
+        self.prompt_context_fields = config.output.prompt_context_fields.clone();
+        self.prompt_override = config.output.prompt.clone();
+        self.retry = ai::RetryPolicy {
+            max_retries: config.output.max_retries,
+            base_backoff: Duration::from_millis(config.output.base_backoff_ms),
+        };
+
+        if config.output.no_ai {
+            return self;
+        }
+
+        let max_tokens = config.output.max_tokens.unwrap_or(ai::DEFAULT_MAX_TOKENS);
+        for (i, name) in config.service_order.iter().enumerate() {
+            if config.service_order[..i].contains(name) {
+                continue;
+            }
+            match name.as_str() {
+                "openai" if config.ai_services.openai.enabled => {
+                    let openai = &config.ai_services.openai;
+                    if openai.api_key.is_empty() && openai.base_url.is_none() {
+                        log::warn!("OpenAI enabled but no API key configured");
+                        continue;
+                    }
+                    let service = || {
+                        let mut service = ai::OpenAiService::new(openai.api_key.clone(), openai.model.clone())
+                            .with_headers(openai.headers.clone().unwrap_or_default())
+                            .with_max_tokens(max_tokens)
+                            .with_timeouts(
+                                Duration::from_secs(openai.timeout_secs),
+                                Duration::from_secs(openai.connect_timeout_secs),
+                            );
+                        if let Some(base_url) = &openai.base_url {
+                            service = service.with_base_url(base_url.clone());
+                        }
+                        if let Some(api_version) = &openai.api_version {
+                            service = service.with_api_version(api_version.clone());
+                        }
+                        service
+                    };
+                    self.services.push(Box::new(service()));
+                    if openai.batch {
+                        self.openai_batch = Some(ai::OpenAiBatch::new(service()));
+                    }
+                }
+                "gemini" if config.ai_services.gemini.enabled => {
+                    if config.ai_services.gemini.api_key.is_empty() {
+                        log::warn!("Gemini enabled but no API key configured");
+                        continue;
+                    }
+                    self.services.push(Box::new(
+                        ai::GeminiService::new(
+                            config.ai_services.gemini.api_key.clone(),
+                            config.ai_services.gemini.model.clone(),
+                        )
+                        .with_headers(config.ai_services.gemini.headers.clone().unwrap_or_default())
+                        .with_max_tokens(max_tokens)
+                        .with_timeouts(
+                            Duration::from_secs(config.ai_services.gemini.timeout_secs),
+                            Duration::from_secs(config.ai_services.gemini.connect_timeout_secs),
+                        ),
+                    ));
+                }
+                "claude" if config.ai_services.claude.enabled => {
+                    if config.ai_services.claude.api_key.is_empty() {
+                        log::warn!("Claude enabled but no API key configured");
+                        continue;
+                    }
+                    self.services.push(Box::new(
+                        ai::ClaudeService::new(
+                            config.ai_services.claude.api_key.clone(),
+                            config.ai_services.claude.model.clone(),
+                        )
+                        .with_headers(config.ai_services.claude.headers.clone().unwrap_or_default())
+                        .with_max_tokens(max_tokens)
+                        .with_timeouts(
+                            Duration::from_secs(config.ai_services.claude.timeout_secs),
+                            Duration::from_secs(config.ai_services.claude.connect_timeout_secs),
+                        ),
+                    ));
+                }
+                "cloudflare" if config.ai_services.cloudflare.enabled => {
+                    if config.ai_services.cloudflare.account_id.is_empty()
+                        || config.ai_services.cloudflare.api_token.is_empty()
+                    {
+                        log::warn!("Cloudflare enabled but account ID or API token not configured");
+                        continue;
+                    }
+                    self.services.push(Box::new(
+                        ai::CloudflareService::new(
+                            config.ai_services.cloudflare.account_id.clone(),
+                            config.ai_services.cloudflare.api_token.clone(),
+                            config.ai_services.cloudflare.model.clone(),
+                        )
+                        .with_headers(config.ai_services.cloudflare.headers.clone().unwrap_or_default())
+                        .with_max_tokens(max_tokens)
+                        .with_timeouts(
+                            Duration::from_secs(config.ai_services.cloudflare.timeout_secs),
+                            Duration::from_secs(config.ai_services.cloudflare.connect_timeout_secs),
+                        ),
+                    ));
+                }
+                "ollama" if config.ai_services.ollama.enabled => {
+                    self.services.push(Box::new(
+                        ai::OllamaService::new(
+                            config.ai_services.ollama.base_url.clone(),
+                            config.ai_services.ollama.model.clone(),
+                        )
+                        .with_headers(config.ai_services.ollama.headers.clone().unwrap_or_default())
+                        .with_max_tokens(max_tokens)
+                        .with_timeouts(
+                            Duration::from_secs(config.ai_services.ollama.timeout_secs),
+                            Duration::from_secs(config.ai_services.ollama.connect_timeout_secs),
+                        ),
+                    ));
+                }
+                "local" if config.ai_services.local.enabled => {
+                    let local_service = match ai::LocalService::from_config(&config.ai_services.local) {
+                        Ok(s) => s,
+                        Err(e) => {
+                            log::warn!("Local service: failed to resolve model directory: {e}");
+                            continue;
+                        }
+                    };
+                    if !local_service.model_exists() {
+                        log::warn!(
+                            "Local model not found. Run `exif-ai-cli --download-model` to download it. Skipping local service."
+                        );
+                        continue;
+                    }
+                    self.services.push(Box::new(local_service));
+                }
+                _ => log::debug!("Skipping {name}: not enabled"),
+            }
+        }
+
+        self
+    }
+
+}
+
+impl<S: AiService> PipelineBuilder<S> {
+    /// Add a custom AI service to the pipeline.
+    pub fn add_service(mut self, service: S) -> Self {
+        self.services.push(service);
+        self
+    }
+
+    /// Set which EXIF fields to write.
+    pub fn fields(mut self, fields: ExifFields) -> Self {
+        self.exif_fields = fields;
+        self
+    }
+
+    /// Enable or disable dry-run mode.
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Enable or disable backup of original files before writing.
+    pub fn backup(mut self, backup: bool) -> Self {
+        self.backup_originals = backup;
+        self
+    }
+
+    /// Back up only the metadata blocks (`.metabak`) instead of the whole file.
+    ///
+    /// See [`restore_metadata`](crate::exif::restore_metadata).
+    pub fn backup_metadata_only(mut self, metadata_only: bool) -> Self {
+        self.backup_metadata_only = metadata_only;
+        self
+    }
+
+    /// Enable or disable augmenting existing titles/descriptions.
+    ///
+    /// See [`OutputConfig::augment_existing`](crate::config::OutputConfig::augment_existing).
+    pub fn augment(mut self, augment: bool) -> Self {
+        self.augment_existing = augment;
+        self
+    }
+
+    /// Set the fields the AI must return for an image to be written.
+    pub fn required_fields(mut self, fields: Vec<Field>) -> Self {
+        self.required_fields = fields;
+        self
+    }
+
+    /// Write images that are missing required fields instead of flagging them as errors.
+    pub fn allow_partial(mut self, allow: bool) -> Self {
+        self.allow_partial = allow;
+        self
+    }
+
+    /// Write only XMP for native formats, leaving EXIF and IPTC untouched.
+    pub fn xmp_only(mut self, xmp_only: bool) -> Self {
+        self.xmp_only = xmp_only;
+        self
+    }
+
+    /// Write a `.xmp` sidecar for every format and never modify the image.
+    ///
+    /// See [`OutputConfig::always_sidecar`](crate::config::OutputConfig::always_sidecar).
+    pub fn always_sidecar(mut self, always: bool) -> Self {
+        self.always_sidecar = always;
+        self
+    }
+
+    /// Write a `<image>.exif-ai.json` catalog file next to each processed image.
+    pub fn write_json_sidecar(mut self, write: bool) -> Self {
+        self.write_json_sidecar = write;
+        self
+    }
+
+    /// Write the AI result only to a `<image>.json` file (see
+    /// [`sidecar_json_path`]), leaving the image untouched. Takes precedence
+    /// over [`xmp_only`](Self::xmp_only).
+    pub fn sidecar_json(mut self, sidecar_json: bool) -> Self {
+        self.sidecar_json = sidecar_json;
+        self
+    }
+
+    /// Former name of [`sidecar_json`](Self::sidecar_json).
+    pub fn json_only(self, json_only: bool) -> Self {
+        self.sidecar_json(json_only)
+    }
+
+    /// Derive a title from the filename when the AI returns none.
+    pub fn filename_fallback_title(mut self, fallback: bool) -> Self {
+        self.filename_fallback_title = fallback;
+        self
+    }
+
+    /// Add a keyword derived from each image's folder name to its tags.
+    pub fn folder_keyword(mut self, add: bool) -> Self {
+        self.folder_keyword = add;
+        self
+    }
+
+    /// Add the image's [`AspectClass`] (`landscape`, `portrait`, `square`) to its tags.
+    pub fn write_aspect_class(mut self, write: bool) -> Self {
+        self.write_aspect_class = write;
+        self
+    }
+
+    /// Skip the AI and write only metadata derived from the config and the
+    /// file: filename title, folder keyword, aspect class, copyright, artist
+    /// and IPTC dates from `DateTimeOriginal` (see [`ExifFields`]). Existing
+    /// GPS is kept as-is; no place names are looked up from it. The pipeline
+    /// may then have no services, and required AI fields are not enforced.
+    pub fn no_ai(mut self, no_ai: bool) -> Self {
+        self.no_ai = no_ai;
+        self
+    }
+
+    /// Limit the time the whole service chain may spend on one image.
+    ///
+    /// Blocking local inference is not interrupted; the deadline applies at
+    /// the next await point.
+    pub fn per_image_timeout(mut self, timeout: Duration) -> Self {
+        self.per_image_timeout = Some(timeout);
+        self
+    }
+
+    /// Downscale images to fit `max_dimension` pixels before sending them to the AI.
+    ///
+    /// See [`OutputConfig::max_upload_dimension`](crate::config::OutputConfig::max_upload_dimension).
+    pub fn max_upload_dimension(mut self, max_dimension: u32) -> Self {
+        self.max_upload_dimension = Some(max_dimension);
+        self
+    }
+
+    /// Re-prompt a service once with a JSON fix-up instruction when its response can't be parsed.
+    pub fn reprompt_on_parse_error(mut self, reprompt: bool) -> Self {
+        self.reprompt_on_parse_error = reprompt;
+        self
+    }
+
+    /// Fill each field from the first service in the chain that returns it.
+    ///
+    /// See [`OutputConfig::merge_services`](crate::config::OutputConfig::merge_services).
+    pub fn merge_services(mut self, merge: bool) -> Self {
+        self.merge_services = merge;
+        self
+    }
+
+    /// Log a warning for each field the image's format can't hold.
+    pub fn warn_unsupported_fields(mut self, warn: bool) -> Self {
+        self.warn_unsupported_fields = warn;
+        self
+    }
+
+    /// Request tags as single keywords or phrases, and normalize them to match.
+    ///
+    /// See [`OutputConfig::tag_style`](crate::config::OutputConfig::tag_style).
+    pub fn tag_style(mut self, style: TagStyle) -> Self {
+        self.tag_style = Some(style);
+        self
+    }
+
+    /// Have the AI write in `lang` (a BCP-47 tag), and tag the title and
+    /// description with it in XMP.
+    ///
+    /// See [`Config::language`](crate::config::Config::language).
+    pub fn language(mut self, lang: impl Into<String>) -> Self {
+        self.language = Some(lang.into());
+        self
+    }
+
+    /// Record a [`Provenance`] block in each result and its `.exif-ai.json` file.
+    pub fn write_provenance(mut self, write: bool) -> Self {
+        self.write_provenance = write;
+        self
+    }
+
+    /// Post-process AI descriptions, e.g. to drop the trailing period.
+    ///
+    /// See [`OutputConfig::description_format`](crate::config::OutputConfig::description_format).
+    pub fn description_format(mut self, format: DescriptionFormat) -> Self {
+        self.description_format = format;
+        self
+    }
+
+    /// Cut longer titles and descriptions at a word boundary.
+    ///
+    /// See [`Limits`].
+    pub fn limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// How to treat images that are symbolic links.
+    ///
+    /// See [`OutputConfig::symlink_policy`](crate::config::OutputConfig::symlink_policy).
+    pub fn symlink_policy(mut self, policy: SymlinkPolicy) -> Self {
+        self.symlink_policy = policy;
+        self
+    }
+
+    /// Existing EXIF fields the prompt may include as context.
+    ///
+    /// See [`OutputConfig::prompt_context_fields`](crate::config::OutputConfig::prompt_context_fields).
+    pub fn prompt_context_fields(mut self, fields: Vec<String>) -> Self {
+        self.prompt_context_fields = fields;
+        self
+    }
+
+    /// Use `prompt` instead of the built-in [`build_prompt`](ai::build_prompt),
+    /// e.g. to ask for accessibility alt-text rather than SEO copy.
+    ///
+    /// See [`OutputConfig::prompt`](crate::config::OutputConfig::prompt).
+    pub fn prompt(mut self, prompt: impl Into<String>) -> Self {
+        self.prompt_override = Some(prompt.into());
+        self
+    }
+
+    /// Retry rate-limited and failing (5xx) services with backoff before
+    /// failing over.
+    ///
+    /// See [`OutputConfig::max_retries`](crate::config::OutputConfig::max_retries).
+    pub fn retry_policy(mut self, policy: ai::RetryPolicy) -> Self {
+        self.retry = policy;
+        self
+    }
+
+    /// Analyze images through the OpenAI Batch API with
+    /// [`Pipeline::analyze_batch`].
+    ///
+    /// See [`OpenAiConfig::batch`](crate::config::OpenAiConfig::batch).
+    pub fn openai_batch(mut self, batch: ai::OpenAiBatch) -> Self {
+        self.openai_batch = Some(batch);
+        self
+    }
+
+    /// Reuse AI results for images analyzed before, and remember new ones.
+    ///
+    /// An image is looked up by the hash of its bytes; with
+    /// [`overwrite_existing`](crate::config::ExifFields::overwrite_existing)
+    /// on, it is analyzed again and the entry replaced. See
+    /// [`OutputConfig::enable_cache`](crate::config::OutputConfig::enable_cache).
+    pub fn analysis_cache(mut self, cache: AnalysisCache) -> Self {
+        self.analysis_cache = Some(cache);
+        self
+    }
+
+    /// Build the [`Pipeline`].
+    ///
+    /// Returns an error if no AI services are configured.
+    pub fn build(self) -> Result<Pipeline<S>> {
+        if self.services.is_empty() && !self.no_ai {
+            anyhow::bail!(
+                "No AI services configured. Add services via from_config() or add_service()."
+            );
+        }
+
+        let mut exif_fields = self.exif_fields;
+        // exiftool would embed into the image the sidecar is meant to spare
+        if self.always_sidecar {
+            exif_fields.use_exiftool_if_available = false;
+        }
+
+        Ok(Pipeline {
+            services: self.services,
+            exif_fields,
+            dry_run: self.dry_run,
+            backup_originals: self.backup_originals,
+            backup_metadata_only: self.backup_metadata_only,
+            augment_existing: self.augment_existing,
+            required_fields: self.required_fields,
+            allow_partial: self.allow_partial,
+            xmp_only: self.xmp_only,
+            always_sidecar: self.always_sidecar,
+            write_json_sidecar: self.write_json_sidecar,
+            sidecar_json: self.sidecar_json,
+            filename_fallback_title: self.filename_fallback_title,
+            folder_keyword: self.folder_keyword,
+            write_aspect_class: self.write_aspect_class,
+            no_ai: self.no_ai,
+            per_image_timeout: self.per_image_timeout,
+            max_upload_dimension: self.max_upload_dimension,
+            reprompt_on_parse_error: self.reprompt_on_parse_error,
+            merge_services: self.merge_services,
+            warn_unsupported_fields: self.warn_unsupported_fields,
+            tag_style: self.tag_style,
+            language: self.language,
+            write_provenance: self.write_provenance,
+            description_format: self.description_format,
+            limits: self.limits,
+            symlink_policy: self.symlink_policy,
+            prompt_context_fields: self.prompt_context_fields,
+            prompt_override: self.prompt_override,
+            retry: self.retry,
+            openai_batch: self.openai_batch,
+            analysis_cache: self.analysis_cache,
+        })
+    }
+}
+
+/// The result of processing a single image through the AI pipeline.
+///
+/// Contains the AI analysis output, which metadata fields were written,
+/// any errors encountered, and the sidecar path for HEIC/RAW formats.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// # use exif_ai::pipeline::Pipeline;
+/// # use exif_ai::config::Config;
+/// # async fn example() -> anyhow::Result<()> {
+/// # let config = Config::load(Some("config.json".as_ref()))?;
+/// # let pipeline = Pipeline::builder().from_config(&config).build()?;
+/// let result = pipeline.process_image("photo.jpg".as_ref()).await;
+///
+/// if result.error.is_none() {
+///     println!("Title written: {}", result.title_written);
+///     println!("AI service: {:?}", result.ai_service_used);
+///     if let Some(ref sidecar) = result.sidecar_path {
+///         println!("Sidecar: {}", sidecar.display());
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct ProcessResult {
+    pub path: PathBuf,
+    /// Everything the AI returned, whether or not each field was written —
+    /// the `write_*` flags in [`ExifFields`] only gate writing.
+    pub ai_result: Option<AiResult>,
+    pub existing_exif: ExifData,
+    pub title_written: bool,
+    pub description_written: bool,
+    pub tags_written: bool,
+    pub gps_written: bool,
+    pub subject_written: bool,
+    pub date_written: bool,
+    pub copyright_written: bool,
+    pub artist_written: bool,
+    pub skipped_fields: Vec<SkippedField>,
+    /// Why the image could not be processed; `None` on success.
+    pub error: Option<ExifAiError>,
+    /// The service that answered; with
+    /// [`merge_services`](PipelineBuilder::merge_services), the first one
+    /// that contributed a field.
+    pub ai_service_used: Option<String>,
+    /// The service that returned each field of [`ai_result`](Self::ai_result),
+    /// before post-processing such as the filename title. Differs from
+    /// [`ai_service_used`](Self::ai_service_used) only when services are merged.
+    pub field_sources: BTreeMap<Field, String>,
+    /// If a sidecar XMP file was written (for HEIC/RAW), this is the path.
+    pub sidecar_path: Option<PathBuf>,
+    /// The image kind detected for this file.
+    pub image_kind: Option<ImageKind>,
+    /// Whether metadata was embedded, written to a sidecar, or not written.
+    /// In dry-run mode this reports where it would have gone.
+    pub write_mode: WriteMode,
+    /// Required fields (see [`OutputConfig::required_ai_fields`](crate::config::OutputConfig::required_ai_fields))
+    /// that the AI did not return. Unlike [`skipped_fields`](Self::skipped_fields),
+    /// this is about the model's output, not about what the writer chose to skip.
+    pub missing_fields: Vec<Field>,
+    /// If a JSON file was written (the `.exif-ai.json` catalog, or the
+    /// `<image>.json` file of [`sidecar_json`](PipelineBuilder::sidecar_json)), this is the path.
+    pub json_sidecar_path: Option<PathBuf>,
+    /// `true` if the title was derived from the filename rather than returned
+    /// by the AI (see [`OutputConfig::filename_fallback_title`](crate::config::OutputConfig::filename_fallback_title)).
+    pub title_from_filename: bool,
+    /// How the metadata was generated, if
+    /// [`OutputConfig::write_provenance`](crate::config::OutputConfig::write_provenance) is set.
+    pub provenance: Option<Provenance>,
+}
+
+impl ProcessResult {
+    /// Names of the fields that were written, in the order title,
+    /// description, tags, gps, subject, date.
+    ///
+    /// In dry-run mode these are the fields that would be written.
+    pub fn written_fields(&self) -> Vec<&'static str> {
+        [
+            (Field::Title, self.title_written),
+            (Field::Description, self.description_written),
+            (Field::Tags, self.tags_written),
+            (Field::Gps, self.gps_written),
+            (Field::Subject, self.subject_written),
+            (Field::Date, self.date_written),
+        ]
+        .into_iter()
+        .filter(|&(_, written)| written)
+        .map(|(field, _)| field.as_str())
+        .collect()
+    }
+
+    /// A one-line summary for front-ends, e.g.
+    /// `"Wrote: title, gps. Skipped: description (existing)"`.
+    pub fn summary_line(&self) -> String {
+        if let Some(ref error) = self.error {
+            return format!("Nothing written: {error}");
+        }
+        if self.write_mode == WriteMode::Json {
+            return match &self.json_sidecar_path {
+                Some(path) => format!("Wrote JSON: {}", path.display()),
+                None => "Would write JSON".to_string(),
+            };
+        }
+
+        let mut parts = Vec::new();
+        let written = self.written_fields();
+        if !written.is_empty() {
+            parts.push(format!("Wrote: {}", written.join(", ")));
+        }
+        if !self.skipped_fields.is_empty() {
+            let skipped: Vec<String> = self.skipped_fields.iter().map(|s| s.to_string()).collect();
+            parts.push(format!("Skipped: {}", skipped.join(", ")));
+        }
+        if parts.is_empty() {
+            "Nothing written".to_string()
+        } else {
+            parts.join(". ")
+        }
+    }
+
+    /// One line per field saying whether it was written and, if not, why —
+    /// e.g. `"title written"` or `"gps skipped: image already geotagged"`.
+    ///
+    /// In dry-run mode "written" means it would be written.
+    pub fn explain(&self) -> Vec<String> {
+        if let Some(ref error) = self.error {
+            return vec![format!("nothing written: {error}")];
+        }
+
+        [
+            (Field::Title, self.title_written),
+            (Field::Description, self.description_written),
+            (Field::Tags, self.tags_written),
+            (Field::Subject, self.subject_written),
+            (Field::Gps, self.gps_written),
+            (Field::Date, self.date_written),
+        ]
+        .into_iter()
+        .map(|(field, written)| {
+            if written {
+                format!("{field} written")
+            } else if let Some(skipped) = self.skipped_fields.iter().find(|s| s.field == field) {
+                skipped.explanation()
+            } else if self.missing_fields.contains(&field) {
+                format!("{field} skipped: required but not returned by the AI")
+            } else {
+                format!("{field} skipped: not returned by the AI")
+            }
+        })
+        .collect()
+    }
+}
+
+/// Which tool, model and prompt produced an image's metadata.
+///
+/// The prompt is stored as a SHA-256 hash, so re-running with the same hash,
+/// service and model reproduces the request; a different hash means the prompt
+/// changed (e.g. through `languages`, `augment_existing` or `tag_style`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Provenance {
+    /// This crate and its version, e.g. `exif-ai 0.2.1`.
+    pub generator: String,
+    /// The AI service that answered.
+    pub service: String,
+    /// The model the service ran, if it reports one.
+    pub model: Option<String>,
+    /// Lowercase hex SHA-256 of the prompt sent to the service.
+    pub prompt_sha256: String,
+}
+
+impl Provenance {
+    /// Record that `service` (running `model`) answered `prompt`.
+    pub fn new(service: &str, model: Option<&str>, prompt: &str) -> Self {
+        use sha2::{Digest, Sha256};
+        let hash = Sha256::digest(prompt.as_bytes());
+        Self {
+            generator: concat!(env!("CARGO_PKG_NAME"), " ", env!("CARGO_PKG_VERSION")).to_string(),
+            service: service.to_string(),
+            model: model.map(String::from),
+            prompt_sha256: hash.iter().map(|b| format!("{b:02x}")).collect(),
+        }
+    }
+}
+
+/// Options for [`collect_images_with`].
+///
+/// The default walks directories without a depth limit, skips hidden entries
+/// and excludes nothing — the behaviour of [`collect_images`].
+#[derive(Debug, Clone, Default)]
+pub struct CollectOptions {
+    /// How many levels of subdirectories to descend into; `Some(0)` reads only
+    /// the files directly inside each given directory. `None` means no limit.
+    pub max_depth: Option<usize>,
+    /// Also collect files and walk directories whose name starts with `.`.
+    /// Paths given explicitly are always used, hidden or not.
+    pub include_hidden: bool,
+    /// Glob patterns (`*` and `?`) matched against file and directory names,
+    /// e.g. `*-thumbnail.jpg`. A matching directory is not walked at all.
+    pub exclude_patterns: Vec<String>,
+}
+
+impl CollectOptions {
+    /// Whether an entry with this file name is skipped.
+    fn skips(&self, name: &str, hidden_allowed: bool) -> bool {
+        (!hidden_allowed && name.starts_with('.'))
+            || self.exclude_patterns.iter().any(|pattern| glob_match(pattern, name))
+    }
+}
+
+/// Collect supported image files from the given paths.
+///
+/// Accepts a mix of file paths and directory paths. Directories are walked
+/// recursively (following symlinks), skipping hidden files and directories.
+/// Only files with supported image extensions are included (see [`ImageKind`]
+/// for the full list). Use [`collect_images_with`] to limit the depth, include
+/// hidden entries or exclude names by pattern.
+///
+/// Files appear in argument order; each directory's contents are sorted by
+/// file name, so the order is the same on every run.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use exif_ai::pipeline::collect_images;
+/// use std::path::PathBuf;
+///
+/// let images = collect_images(&[
+///     PathBuf::from("photo.jpg"),       // single file
+///     PathBuf::from("./photos/"),        // entire directory
+/// ]);
+/// println!("Found {} images", images.len());
+/// ```
+pub fn collect_images(paths: &[PathBuf]) -> Vec<PathBuf> {
+    collect_images_with(paths, &CollectOptions::default())
+}
+
+/// [`collect_images`] with explicit [`CollectOptions`].
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use exif_ai::pipeline::{collect_images_with, CollectOptions};
+/// use std::path::PathBuf;
+///
+/// let options = CollectOptions {
+///     max_depth: Some(1),
+///     exclude_patterns: vec!["*-thumbnail.jpg".to_string()],
+///     ..Default::default()
+/// };
+/// let images = collect_images_with(&[PathBuf::from("./photos/")], &options);
+/// println!("Found {} images", images.len());
+/// ```
+pub fn collect_images_with(paths: &[PathBuf], options: &CollectOptions) -> Vec<PathBuf> {
+    let mut images = Vec::new();
+
+    for path in paths {
+        if path.is_file() {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            if options.skips(&name, true) {
+                log::debug!("Skipping excluded file: {}", path.display());
+            } else if is_supported_image(path) {
+                images.push(path.clone());
+            } else {
+                log::warn!("Skipping unsupported file: {}", path.display());
+            }
+        } else if path.is_dir() {
+            let mut walker = WalkDir::new(path).follow_links(true).sort_by_file_name();
+            if let Some(depth) = options.max_depth {
+                walker = walker.max_depth(depth.saturating_add(1));
+            }
+            for entry in walker
+                .into_iter()
+                .filter_entry(|e| {
+                    e.depth() == 0 || !options.skips(&e.file_name().to_string_lossy(), options.include_hidden)
+                })
                 .filter_map(|e| e.ok())
             {
                 let p = entry.path();
@@ -581,274 +2359,2655 @@ pub fn collect_images(paths: &[PathBuf]) -> Vec<PathBuf> {
                     images.push(p.to_path_buf());
                 }
             }
-        } else {
-            log::warn!("Path does not exist: {}", path.display());
+        } else {
+            log::warn!("Path does not exist: {}", path.display());
+        }
+    }
+
+    images
+}
+
+/// Match `name` against a glob `pattern` where `*` is any run of characters
+/// and `?` is exactly one.
+fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // Position of the last `*` and the name index it currently covers up to
+    let mut star: Option<(usize, usize)> = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((sp, sn)) => {
+                    p = sp + 1;
+                    n = sn + 1;
+                    star = Some((sp, sn + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Pick `n` of `images` at random, keeping their collected order.
+///
+/// The same `seed` always picks the same images. With `n` at or above
+/// `images.len()`, every image is returned.
+pub fn sample_images(images: &[PathBuf], n: usize, seed: u64) -> Vec<PathBuf> {
+    let mut rng = fastrand::Rng::with_seed(seed);
+    let mut picked = rng.choose_multiple(0..images.len(), n);
+    picked.sort_unstable();
+    picked.into_iter().map(|i| images[i].clone()).collect()
+}
+
+/// Check if a file has a supported image extension.
+fn is_supported_image(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| IMAGE_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+/// Replace the symlink at `path` with a regular file holding its target's contents.
+fn replace_symlink_with_copy(path: &Path) -> Result<()> {
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    let tmp_path = path.with_file_name(format!(".{file_name}.{}.tmp", std::process::id()));
+    let copied = std::fs::copy(path, &tmp_path).and_then(|_| std::fs::rename(&tmp_path, path));
+    if copied.is_err() {
+        let _ = std::fs::remove_file(&tmp_path);
+    }
+    copied.with_context(|| format!("Failed to replace symlink {}", path.display()))
+}
+
+/// The files an image's metadata is written to, as one key: its resolved
+/// path without extension, which also covers a shared `.xmp` sidecar.
+fn write_key(path: &Path) -> PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf()).with_extension("")
+}
+
+/// Create a backup of the original file.
+fn backup_file(path: &Path) -> Result<PathBuf> {
+    let backup_path = path.with_extension(format!(
+        "{}.bak",
+        path.extension().unwrap_or_default().to_string_lossy()
+    ));
+
+    if !backup_path.exists() {
+        std::fs::copy(path, &backup_path).context("Failed to create backup")?;
+        log::debug!("Backup created: {}", backup_path.display());
+    }
+
+    Ok(backup_path)
+}
+
+/// Path of the `.exif-ai.json` catalog file for `path` (e.g. `photo.jpg.exif-ai.json`).
+pub fn json_sidecar_path(path: &Path) -> PathBuf {
+    path.with_extension(format!(
+        "{}.exif-ai.json",
+        path.extension().unwrap_or_default().to_string_lossy()
+    ))
+}
+
+/// Path of the [`sidecar_json`](PipelineBuilder::sidecar_json) file for
+/// `path`: the image's file name plus `.json` (e.g. `photo.jpg.json`).
+pub fn sidecar_json_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".json");
+    path.with_file_name(name)
+}
+
+/// Write the AI result and what was done with it next to the image.
+fn write_json_sidecar(result: &ProcessResult) -> Result<PathBuf> {
+    write_json_file(result, &json_sidecar_path(&result.path))
+}
+
+/// Write the AI result and what was done with it to `json_path`.
+fn write_json_file(result: &ProcessResult, json_path: &Path) -> Result<PathBuf> {
+    let json_path = json_path.to_path_buf();
+    let catalog = serde_json::json!({
+        "path": result.path.display().to_string(),
+        "ai_service": result.ai_service_used,
+        "field_sources": result.field_sources,
+        "ai_result": result.ai_result,
+        "title_written": result.title_written,
+        "description_written": result.description_written,
+        "tags_written": result.tags_written,
+        "gps_written": result.gps_written,
+        "subject_written": result.subject_written,
+        "date_written": result.date_written,
+        "copyright_written": result.copyright_written,
+        "artist_written": result.artist_written,
+        "skipped_fields": result.skipped_fields,
+        "sidecar_path": result.sidecar_path.as_ref().map(|p| p.display().to_string()),
+        "write_mode": result.write_mode,
+        "missing_fields": result.missing_fields,
+        "title_from_filename": result.title_from_filename,
+        "provenance": result.provenance,
+    });
+    let contents = serde_json::to_string_pretty(&catalog).context("Failed to serialize JSON sidecar")?;
+    std::fs::write(&json_path, contents).context("Failed to write JSON sidecar")?;
+    log::debug!("JSON sidecar written: {}", json_path.display());
+    Ok(json_path)
+}
+
+/// Column headers of [`write_csv_report`].
+const CSV_COLUMNS: [&str; 17] = [
+    "path",
+    "ai_service",
+    "title",
+    "description",
+    "tags",
+    "gps_lat",
+    "gps_lon",
+    "subjects",
+    "title_written",
+    "description_written",
+    "tags_written",
+    "gps_written",
+    "subject_written",
+    "date_written",
+    "copyright_written",
+    "artist_written",
+    "error",
+];
+
+/// Write a spreadsheet of a run's results to `path`, one row per image.
+///
+/// Columns: path, AI service, title, description, tags and subjects (joined
+/// with `;`), GPS latitude and longitude, a `true`/`false` column per written
+/// field, and the error. Missing values are empty cells. The file is
+/// comma-separated, or tab-separated if `path` ends in `.tsv`; values holding
+/// the separator, quotes or line breaks are quoted as in RFC 4180.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// # use exif_ai::pipeline::{Pipeline, write_csv_report};
+/// # use std::path::{Path, PathBuf};
+/// # async fn example(pipeline: Pipeline, images: Vec<PathBuf>) -> anyhow::Result<()> {
+/// let results = pipeline.process_images(&images, 4).await;
+/// write_csv_report(&results, Path::new("catalog.csv"))?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn write_csv_report(results: &[ProcessResult], path: &Path) -> Result<()> {
+    let separator = if path.extension().is_some_and(|e| e.eq_ignore_ascii_case("tsv")) { '\t' } else { ',' };
+    let mut out = String::new();
+    let mut push_row = |cells: &[String]| {
+        let row: Vec<String> = cells.iter().map(|c| csv_cell(c, separator)).collect();
+        out.push_str(&row.join(&separator.to_string()));
+        out.push_str("\r\n");
+    };
+
+    push_row(&CSV_COLUMNS.map(String::from));
+    for result in results {
+        let ai = result.ai_result.as_ref();
+        let text = |value: Option<&String>| value.cloned().unwrap_or_default();
+        let list = |values: Option<&Vec<String>>| values.map(|v| v.join(";")).unwrap_or_default();
+        let gps = ai.and_then(|a| a.gps.as_ref());
+        push_row(&[
+            result.path.display().to_string(),
+            text(result.ai_service_used.as_ref()),
+            text(ai.and_then(|a| a.title.as_ref())),
+            text(ai.and_then(|a| a.description.as_ref())),
+            list(ai.and_then(|a| a.tags.as_ref())),
+            gps.map(|g| g.latitude.to_string()).unwrap_or_default(),
+            gps.map(|g| g.longitude.to_string()).unwrap_or_default(),
+            list(ai.and_then(|a| a.subject.as_ref())),
+            result.title_written.to_string(),
+            result.description_written.to_string(),
+            result.tags_written.to_string(),
+            result.gps_written.to_string(),
+            result.subject_written.to_string(),
+            result.date_written.to_string(),
+            result.copyright_written.to_string(),
+            result.artist_written.to_string(),
+            result.error.as_ref().map(ToString::to_string).unwrap_or_default(),
+        ]);
+    }
+
+    std::fs::write(path, out).with_context(|| format!("Failed to write CSV report {}", path.display()))
+}
+
+/// Quote a CSV value if it holds the separator, a quote or a line break.
+fn csv_cell(value: &str, separator: char) -> String {
+    if value.contains([separator, '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Turn a filename slug into a Title Case title (`sunset-over_the.bay.jpg` → `Sunset Over The Bay`).
+fn title_from_filename(path: &Path) -> Option<String> {
+    let stem = path.file_stem()?.to_string_lossy();
+    let words: Vec<String> = stem
+        .split(|c: char| c == '-' || c == '_' || c == '.' || c.is_whitespace())
+        .filter(|w| !w.is_empty())
+        .map(|w| {
+            let mut chars = w.chars();
+            let first = chars.next().unwrap();
+            first.to_uppercase().chain(chars.flat_map(char::to_lowercase)).collect()
+        })
+        .collect();
+    if words.is_empty() { None } else { Some(words.join(" ")) }
+}
+
+/// Turn the parent folder name into a keyword (`2024-06-paris` → `paris 2024`).
+///
+/// Words are lowercased and a four-digit year moves to the end; other numbers
+/// such as months and days are dropped.
+fn keyword_from_folder(path: &Path) -> Option<String> {
+    let folder = path.parent()?.file_name()?.to_string_lossy();
+    let mut words = Vec::new();
+    let mut year = None;
+    for part in folder.split(|c: char| !c.is_alphanumeric()).filter(|p| !p.is_empty()) {
+        if part.chars().all(|c| c.is_ascii_digit()) {
+            if part.len() == 4 && year.is_none() {
+                year = Some(part.to_string());
+            }
+        } else {
+            words.push(part.to_lowercase());
+        }
+    }
+    words.extend(year);
+    if words.is_empty() { None } else { Some(words.join(" ")) }
+}
+
+/// Displayed width and height, swapped when the EXIF orientation rotates the
+/// image by 90°.
+fn image_dimensions(path: &Path, existing: &ExifData) -> Option<(u32, u32)> {
+    let (width, height) = ai::cost::image_dimensions(path, existing)?;
+    let rotated = matches!(existing.orientation.as_deref().map(str::trim), Some("5" | "6" | "7" | "8"));
+    Some(if rotated { (height, width) } else { (width, height) })
+}
+
+/// Credit every field present in `ai` to `service`.
+fn field_sources(ai: &AiResult, service: &str) -> BTreeMap<Field, String> {
+    let missing = missing_fields(ai, &exif::ALL_FIELDS);
+    exif::ALL_FIELDS.iter().filter(|f| !missing.contains(f)).map(|&f| (f, service.to_string())).collect()
+}
+
+/// Fill the fields `combined` lacks from `from`, crediting them to
+/// `service` in `sources`; returns the fields filled.
+fn fill_missing(
+    combined: &mut AiResult,
+    mut from: AiResult,
+    service: &str,
+    sources: &mut BTreeMap<Field, String>,
+) -> Vec<Field> {
+    let filled: Vec<Field> = missing_fields(combined, &exif::ALL_FIELDS)
+        .into_iter()
+        .filter(|f| !missing_fields(&from, &[*f]).contains(f))
+        .collect();
+    for field in &filled {
+        match field {
+            Field::Title => {
+                combined.title = from.title.take();
+                if combined.titles.is_none() {
+                    combined.titles = from.titles.take();
+                }
+            }
+            Field::Description => {
+                combined.description = from.description.take();
+                if combined.descriptions.is_none() {
+                    combined.descriptions = from.descriptions.take();
+                }
+            }
+            Field::Tags => {
+                combined.tags = from.tags.take();
+                if combined.hierarchical_tags.is_none() {
+                    combined.hierarchical_tags = from.hierarchical_tags.take();
+                }
+            }
+            Field::Gps => combined.gps = from.gps.take(),
+            Field::Subject => combined.subject = from.subject.take(),
+            Field::Date => combined.date_taken = from.date_taken.take(),
+        }
+        sources.insert(*field, service.to_string());
+    }
+    filled
+}
+
+/// Required fields that `ai` leaves absent or empty.
+fn missing_fields(ai: &AiResult, required: &[Field]) -> Vec<Field> {
+    let has_text = |s: &Option<String>| s.as_deref().is_some_and(|s| !s.trim().is_empty());
+    let has_list = |v: &Option<Vec<String>>| v.as_ref().is_some_and(|v| !v.is_empty());
+    required.iter()
+        .copied()
+        .filter(|field| match field {
+            Field::Title => !has_text(&ai.title),
+            Field::Description => !has_text(&ai.description),
+            Field::Tags => !has_list(&ai.tags),
+            Field::Gps => ai.gps.as_ref().is_none_or(ai::GpsCoords::is_null_island),
+            Field::Subject => !has_list(&ai.subject),
+            Field::Date => !has_text(&ai.date_taken),
+        })
+        .collect()
+}
+
+/// Pre-flight check that `path` holds a plausible image: non-empty, with a
+/// known magic number and, for natively written formats, a readable header.
+fn check_image_data(path: &Path, kind: ImageKind) -> Result<()> {
+    use std::io::Read;
+
+    let mut header = Vec::with_capacity(16);
+    std::fs::File::open(path)
+        .and_then(|file| file.take(16).read_to_end(&mut header))
+        .with_context(|| format!("Cannot read {}", path.display()))?;
+    if header.is_empty() {
+        anyhow::bail!("{} is empty (0 bytes)", path.display());
+    }
+    if ImageKind::from_bytes(&header).is_none() {
+        anyhow::bail!("{} is not a valid image: unrecognized file header", path.display());
+    }
+    if kind != ImageKind::Sidecar {
+        let dimensions = image::ImageReader::open(path)
+            .and_then(|reader| reader.with_guessed_format())
+            .map_err(image::ImageError::from)
+            .and_then(|reader| reader.into_dimensions());
+        if let Err(e) = dimensions {
+            anyhow::bail!("{} looks truncated or corrupt: {e}", path.display());
+        }
+    }
+    Ok(())
+}
+
+/// Pre-flight check that the write target for `path` is writable.
+///
+/// Native formats need the image itself to be writable; sidecar formats need
+/// a writable `.xmp` if one already exists. Both are written via a temp file
+/// and a rename, so the directory must be writable too. That is probed by
+/// creating a temp file, since permission bits don't tell (Windows ignores
+/// the read-only attribute on directories; on Unix it depends on the owner).
+/// Failures are [`std::io::Error`]s, so they surface as [`ExifAiError::Io`].
+fn check_writable(path: &Path, kind: ImageKind) -> Result<()> {
+    use std::io::{Error, ErrorKind};
+
+    let read_only = |p: &Path| std::fs::metadata(p).map(|m| m.permissions().readonly());
+    let probe_dir = |path: &Path, what: &str| -> Result<()> {
+        let dir = match path.parent() {
+            Some(d) if !d.as_os_str().is_empty() => d,
+            _ => Path::new("."),
+        };
+        if !dir.is_dir() {
+            let message = format!("Cannot write {what}: directory {} does not exist", dir.display());
+            return Err(Error::new(ErrorKind::NotFound, message).into());
+        }
+        // Dropping the probe deletes it
+        tempfile::NamedTempFile::new_in(dir).map_err(|e| {
+            Error::new(e.kind(), format!("Cannot write {what}: directory {} is not writable: {e}", dir.display()))
+        })?;
+        Ok(())
+    };
+
+    if kind == ImageKind::Sidecar {
+        probe_dir(path, "sidecar")?;
+        let xmp = path.with_extension("xmp");
+        if xmp.exists() && read_only(&xmp).unwrap_or(false) {
+            let message = format!("Cannot write sidecar: {} is read-only", xmp.display());
+            return Err(Error::new(ErrorKind::PermissionDenied, message).into());
+        }
+        return Ok(());
+    }
+
+    if read_only(path).with_context(|| format!("Cannot access {}", path.display()))? {
+        let message = format!("Cannot write to {}: file is read-only", path.display());
+        return Err(Error::new(ErrorKind::PermissionDenied, message).into());
+    }
+    std::fs::OpenOptions::new()
+        .write(true)
+        .open(path)
+        .with_context(|| format!("Cannot write to {}", path.display()))?;
+    // A symlink's target is replaced in the target's directory
+    probe_dir(&std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf()), "image")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::MockAiService;
+    use std::fs;
+    use tempfile::TempDir;
+
+    // ── ImageKind::from_path ──────────────────────────────────────────
+
+    #[test]
+    fn image_kind_jpeg() {
+        assert_eq!(ImageKind::from_path(Path::new("photo.jpg")), Some(ImageKind::Jpeg));
+        assert_eq!(ImageKind::from_path(Path::new("photo.jpeg")), Some(ImageKind::Jpeg));
+        assert_eq!(ImageKind::from_path(Path::new("PHOTO.JPG")), Some(ImageKind::Jpeg));
+    }
+
+    #[test]
+    fn image_kind_jpe_and_jfif() {
+        for name in ["photo.jpe", "PHOTO.JPE", "photo.Jpe", "photo.jfif", "PHOTO.JFIF"] {
+            assert_eq!(ImageKind::from_path(Path::new(name)), Some(ImageKind::Jpeg), "{name}");
+            assert!(is_supported_image(Path::new(name)), "{name}");
+            assert_eq!(ImageKind::Jpeg.mime_type(Path::new(name)), "image/jpeg", "{name}");
+        }
+    }
+
+    #[tokio::test]
+    async fn jpe_file_round_trips_metadata() {
+        let dir = TempDir::new().unwrap();
+        let jpe = dir.path().join("photo.JPE");
+        fs::copy(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("data/test_canon_powershot.jpg"), &jpe).unwrap();
+
+        assert_eq!(collect_images(&[dir.path().to_path_buf()]), vec![jpe.clone()]);
+
+        let pipeline = Pipeline::with_service(PromptProbe).build().unwrap();
+        let result = pipeline.process_image(&jpe).await;
+        assert!(result.error.is_none(), "{:?}", result.error);
+        assert_eq!(result.write_mode, WriteMode::Embedded);
+        assert_eq!(exif::read_exif(&jpe).unwrap().title.as_deref(), Some("Probe"));
+    }
+
+    #[test]
+    fn image_kind_png() {
+        assert_eq!(ImageKind::from_path(Path::new("image.png")), Some(ImageKind::Png));
+        assert_eq!(ImageKind::from_path(Path::new("IMAGE.PNG")), Some(ImageKind::Png));
+    }
+
+    #[test]
+    fn image_kind_webp() {
+        assert_eq!(ImageKind::from_path(Path::new("image.webp")), Some(ImageKind::WebP));
+    }
+
+    #[test]
+    fn image_kind_tiff() {
+        assert_eq!(ImageKind::from_path(Path::new("scan.tif")), Some(ImageKind::Tiff));
+        assert_eq!(ImageKind::from_path(Path::new("scan.tiff")), Some(ImageKind::Tiff));
+    }
+
+    #[test]
+    fn image_kind_sidecar_heic() {
+        assert_eq!(ImageKind::from_path(Path::new("photo.heic")), Some(ImageKind::Sidecar));
+        assert_eq!(ImageKind::from_path(Path::new("photo.heif")), Some(ImageKind::Sidecar));
+        assert_eq!(ImageKind::from_path(Path::new("photo.HEIC")), Some(ImageKind::Sidecar));
+    }
+
+    #[test]
+    fn image_kind_sidecar_avif() {
+        assert_eq!(ImageKind::from_path(Path::new("photo.avif")), Some(ImageKind::Sidecar));
+    }
+
+    #[test]
+    fn image_kind_sidecar_raw() {
+        for ext in &["cr3", "cr2", "dng", "nef", "arw", "raf", "orf", "rw2", "pef", "srw"] {
+            let path = format!("photo.{ext}");
+            assert_eq!(
+                ImageKind::from_path(Path::new(&path)),
+                Some(ImageKind::Sidecar),
+                "Expected Sidecar for .{ext}"
+            );
+        }
+    }
+
+    #[test]
+    fn image_kind_unsupported() {
+        assert_eq!(ImageKind::from_path(Path::new("doc.pdf")), None);
+        assert_eq!(ImageKind::from_path(Path::new("video.mp4")), None);
+        assert_eq!(ImageKind::from_path(Path::new("noext")), None);
+    }
+
+    // ── ImageKind::mime_type ──────────────────────────────────────────
+
+    #[test]
+    fn mime_type_jpeg() {
+        let kind = ImageKind::Jpeg;
+        assert_eq!(kind.mime_type(Path::new("a.jpg")), "image/jpeg");
+        assert_eq!(kind.mime_type(Path::new("a.jpeg")), "image/jpeg");
+    }
+
+    #[test]
+    fn mime_type_png() {
+        assert_eq!(ImageKind::Png.mime_type(Path::new("a.png")), "image/png");
+    }
+
+    #[test]
+    fn mime_type_webp() {
+        assert_eq!(ImageKind::WebP.mime_type(Path::new("a.webp")), "image/webp");
+    }
+
+    #[test]
+    fn mime_type_heic() {
+        assert_eq!(ImageKind::Sidecar.mime_type(Path::new("a.heic")), "image/heic");
+        assert_eq!(ImageKind::Sidecar.mime_type(Path::new("a.heif")), "image/heif");
+    }
+
+    #[test]
+    fn mime_type_raw_formats() {
+        assert_eq!(ImageKind::Sidecar.mime_type(Path::new("a.cr3")), "image/x-canon-cr3");
+        assert_eq!(ImageKind::Sidecar.mime_type(Path::new("a.nef")), "image/x-nikon-nef");
+        assert_eq!(ImageKind::Sidecar.mime_type(Path::new("a.arw")), "image/x-sony-arw");
+        assert_eq!(ImageKind::Sidecar.mime_type(Path::new("a.dng")), "image/x-adobe-dng");
+    }
+
+    #[test]
+    fn gif_and_bmp_use_the_sidecar_strategy() {
+        assert_eq!(ImageKind::from_path(Path::new("anim.gif")), Some(ImageKind::Sidecar));
+        assert_eq!(ImageKind::from_path(Path::new("scan.BMP")), Some(ImageKind::Sidecar));
+        assert_eq!(ImageKind::from_bytes(b"GIF89a\x08\x00"), Some(ImageKind::Sidecar));
+        assert_eq!(ImageKind::from_bytes(b"GIF87a\x08\x00"), Some(ImageKind::Sidecar));
+        assert_eq!(ImageKind::from_bytes(b"BM\x36\x00\x00\x00"), Some(ImageKind::Sidecar));
+        assert_eq!(ImageKind::Sidecar.mime_type(Path::new("a.gif")), "image/gif");
+        assert_eq!(ImageKind::Sidecar.mime_type(Path::new("a.bmp")), "image/bmp");
+    }
+
+    #[test]
+    fn jxl_uses_the_sidecar_strategy() {
+        assert_eq!(ImageKind::from_path(Path::new("photo.jxl")), Some(ImageKind::Sidecar));
+        assert_eq!(ImageKind::from_path(Path::new("photo.JXL")), Some(ImageKind::Sidecar));
+        // Bare codestream and ISO-BMFF container
+        assert_eq!(ImageKind::from_bytes(&[0xFF, 0x0A, 0xFA, 0x1F]), Some(ImageKind::Sidecar));
+        assert_eq!(ImageKind::from_bytes(b"\0\0\0\x0cJXL \r\n\x87\n"), Some(ImageKind::Sidecar));
+        assert_eq!(ImageKind::Sidecar.mime_type(Path::new("a.jxl")), "image/jxl");
+        assert!(is_supported_image(Path::new("photo.jxl")));
+
+        // nom-exif can't parse JPEG XL yet; reading yields no metadata, not an error
+        let dir = TempDir::new().unwrap();
+        let jxl = dir.path().join("photo.jxl");
+        fs::write(&jxl, [0xFF, 0x0A, 0xFA, 0x1F, 0x00, 0x00]).unwrap();
+        assert!(crate::exif::read_exif(&jxl).unwrap().make.is_none());
+    }
+
+    #[test]
+    fn mime_type_fallback() {
+        assert_eq!(ImageKind::Jpeg.mime_type(Path::new("noext")), "image/jpeg");
+    }
+
+    // ── is_supported_image ───────────────────────────────────────────
+
+    #[test]
+    fn supported_image_extensions() {
+        assert!(is_supported_image(Path::new("photo.jpg")));
+        assert!(is_supported_image(Path::new("photo.JPEG")));
+        assert!(is_supported_image(Path::new("photo.png")));
+        assert!(is_supported_image(Path::new("photo.webp")));
+        assert!(is_supported_image(Path::new("photo.tif")));
+        assert!(is_supported_image(Path::new("photo.heic")));
+        assert!(is_supported_image(Path::new("photo.cr3")));
+        assert!(is_supported_image(Path::new("photo.dng")));
+        assert!(is_supported_image(Path::new("anim.GIF")));
+        assert!(is_supported_image(Path::new("scan.bmp")));
+    }
+
+    #[test]
+    fn unsupported_image_extensions() {
+        assert!(!is_supported_image(Path::new("doc.pdf")));
+        assert!(!is_supported_image(Path::new("video.mp4")));
+        assert!(!is_supported_image(Path::new("readme.txt")));
+        assert!(!is_supported_image(Path::new("noext")));
+    }
+
+    // ── collect_images ───────────────────────────────────────────────
+
+    #[test]
+    fn collect_images_single_file() {
+        let dir = TempDir::new().unwrap();
+        let jpg = dir.path().join("test.jpg");
+        fs::write(&jpg, b"fake").unwrap();
+
+        let images = collect_images(std::slice::from_ref(&jpg));
+        assert_eq!(images.len(), 1);
+        assert_eq!(images[0], jpg);
+    }
+
+    #[test]
+    fn collect_images_skips_unsupported() {
+        let dir = TempDir::new().unwrap();
+        let txt = dir.path().join("readme.txt");
+        fs::write(&txt, b"hello").unwrap();
+
+        let images = collect_images(&[txt]);
+        assert!(images.is_empty());
+    }
+
+    #[test]
+    fn collect_images_directory_recursive() {
+        let dir = TempDir::new().unwrap();
+        let sub = dir.path().join("sub");
+        fs::create_dir(&sub).unwrap();
+
+        fs::write(dir.path().join("a.jpg"), b"fake").unwrap();
+        fs::write(sub.join("b.png"), b"fake").unwrap();
+        fs::write(sub.join("c.txt"), b"fake").unwrap();
+
+        let images = collect_images(&[dir.path().to_path_buf()]);
+        assert_eq!(images.len(), 2);
+    }
+
+    #[test]
+    fn collect_images_empty_dir() {
+        let dir = TempDir::new().unwrap();
+        let images = collect_images(&[dir.path().to_path_buf()]);
+        assert!(images.is_empty());
+    }
+
+    #[test]
+    fn collect_images_nonexistent_path() {
+        let images = collect_images(&[PathBuf::from("/nonexistent/path")]);
+        assert!(images.is_empty());
+    }
+
+    #[test]
+    fn collect_images_mixed_files_and_dirs() {
+        let dir = TempDir::new().unwrap();
+        let jpg = dir.path().join("photo.jpg");
+        let sub = dir.path().join("folder");
+        fs::create_dir(&sub).unwrap();
+        fs::write(&jpg, b"fake").unwrap();
+        fs::write(sub.join("deep.heic"), b"fake").unwrap();
+
+        let images = collect_images(&[jpg.clone(), sub]);
+        assert_eq!(images.len(), 2);
+    }
+
+    #[test]
+    fn collect_images_sorts_directory_entries() {
+        let dir = TempDir::new().unwrap();
+        for name in ["c.jpg", "a.jpg", "b.png"] {
+            fs::write(dir.path().join(name), b"fake").unwrap();
+        }
+
+        let names: Vec<_> = collect_images(&[dir.path().to_path_buf()])
+            .iter()
+            .map(|p| p.file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(names, ["a.jpg", "b.png", "c.jpg"]);
+    }
+
+    #[test]
+    fn collect_images_skips_hidden_unless_asked() {
+        let dir = TempDir::new().unwrap();
+        let hidden_dir = dir.path().join(".thumbnails");
+        fs::create_dir(&hidden_dir).unwrap();
+        fs::write(dir.path().join("a.jpg"), b"fake").unwrap();
+        fs::write(dir.path().join(".b.jpg"), b"fake").unwrap();
+        fs::write(hidden_dir.join("c.jpg"), b"fake").unwrap();
+
+        assert_eq!(collect_images(&[dir.path().to_path_buf()]), vec![dir.path().join("a.jpg")]);
+        // An explicitly named hidden file is still collected
+        assert_eq!(collect_images(&[dir.path().join(".b.jpg")]).len(), 1);
+
+        let options = CollectOptions { include_hidden: true, ..Default::default() };
+        assert_eq!(collect_images_with(&[dir.path().to_path_buf()], &options).len(), 3);
+    }
+
+    #[test]
+    fn collect_images_respects_max_depth() {
+        let dir = TempDir::new().unwrap();
+        let deep = dir.path().join("one").join("two");
+        fs::create_dir_all(&deep).unwrap();
+        fs::write(dir.path().join("a.jpg"), b"fake").unwrap();
+        fs::write(dir.path().join("one").join("b.jpg"), b"fake").unwrap();
+        fs::write(deep.join("c.jpg"), b"fake").unwrap();
+
+        let count = |max_depth| {
+            let options = CollectOptions { max_depth, ..Default::default() };
+            collect_images_with(&[dir.path().to_path_buf()], &options).len()
+        };
+        assert_eq!(count(Some(0)), 1);
+        assert_eq!(count(Some(1)), 2);
+        assert_eq!(count(None), 3);
+    }
+
+    #[test]
+    fn collect_images_applies_exclude_patterns() {
+        let dir = TempDir::new().unwrap();
+        let drafts = dir.path().join("drafts");
+        fs::create_dir(&drafts).unwrap();
+        fs::write(dir.path().join("a.jpg"), b"fake").unwrap();
+        fs::write(dir.path().join("a-thumbnail.jpg"), b"fake").unwrap();
+        fs::write(drafts.join("b.jpg"), b"fake").unwrap();
+
+        let options = CollectOptions {
+            exclude_patterns: vec!["*-thumbnail.jpg".to_string(), "draft?".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(
+            collect_images_with(&[dir.path().to_path_buf()], &options),
+            vec![dir.path().join("a.jpg")]
+        );
+        assert!(collect_images_with(&[dir.path().join("a-thumbnail.jpg")], &options).is_empty());
+    }
+
+    #[test]
+    fn glob_match_handles_wildcards() {
+        assert!(glob_match("*-thumbnail.jpg", "IMG_1-thumbnail.jpg"));
+        assert!(glob_match("*", ""));
+        assert!(glob_match("a?c", "abc"));
+        assert!(glob_match("*.j*g", "x.jpeg"));
+        assert!(!glob_match("*-thumbnail.jpg", "IMG_1.jpg"));
+        assert!(!glob_match("a?c", "ac"));
+    }
+
+    #[test]
+    fn sample_images_picks_n_in_collected_order() {
+        let images: Vec<PathBuf> = (0..20).map(|i| PathBuf::from(format!("{i:02}.jpg"))).collect();
+
+        let sample = sample_images(&images, 5, 42);
+        assert_eq!(sample.len(), 5);
+        assert!(sample.windows(2).all(|w| w[0] < w[1]), "keeps collected order: {sample:?}");
+        assert!(sample.iter().all(|p| images.contains(p)));
+        assert_eq!(sample, sample_images(&images, 5, 42), "same seed, same sample");
+        assert_ne!(sample, images[..5], "not simply the first N");
+
+        assert_eq!(sample_images(&images, 50, 1), images);
+    }
+
+    // ── Pipeline::builder ────────────────────────────────────────────
+
+    #[test]
+    fn builder_fails_with_no_services() {
+        let mut config = Config::default();
+        config.ai_services.openai.enabled = false;
+        config.ai_services.gemini.enabled = false;
+        config.ai_services.cloudflare.enabled = false;
+
+        let result = Pipeline::builder().from_config(&config).build();
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn builder_skips_empty_keys() {
+        let config = Config::default(); // openai enabled but key is empty
+        let result = Pipeline::builder().from_config(&config).build();
+        assert!(result.is_err()); // no valid services → error
+    }
+
+    #[test]
+    fn builder_with_key() {
+        let mut config = Config::default();
+        config.ai_services.openai.api_key = "sk-test".to_string();
+
+        let pipeline = Pipeline::builder().from_config(&config).build().unwrap();
+        assert_eq!(pipeline.service_count(), 1);
+        assert_eq!(pipeline.service_names(), vec!["OpenAI"]);
+    }
+
+    #[test]
+    fn builder_adds_claude_in_service_order() {
+        let mut config = Config::default();
+        config.ai_services.openai.api_key = "sk-test".to_string();
+        config.ai_services.claude.enabled = true;
+        config.ai_services.claude.api_key = "sk-ant-test".to_string();
+
+        let pipeline = Pipeline::builder().from_config(&config).build().unwrap();
+        assert_eq!(pipeline.service_names(), vec!["OpenAI", "Claude"]);
+    }
+
+    #[test]
+    fn builder_adds_ollama_without_credentials() {
+        let mut config = Config::default();
+        config.ai_services.openai.enabled = false;
+        config.ai_services.ollama.enabled = true;
+
+        let pipeline = Pipeline::builder().from_config(&config).build().unwrap();
+        assert_eq!(pipeline.service_names(), vec!["Ollama"]);
+    }
+
+    #[test]
+    fn builder_applies_max_tokens() {
+        let mut config = Config::default();
+        config.ai_services.openai.api_key = "sk-test".to_string();
+        config.ai_services.gemini.enabled = true;
+        config.ai_services.gemini.api_key = "key".to_string();
+
+        let pipeline = Pipeline::builder().from_config(&config).build().unwrap();
+        assert!(pipeline.services.iter().all(|s| s.max_tokens() == Some(ai::DEFAULT_MAX_TOKENS)));
+
+        config.output.max_tokens = Some(4096);
+        let pipeline = Pipeline::builder().from_config(&config).build().unwrap();
+        assert_eq!(pipeline.service_count(), 2);
+        assert!(pipeline.services.iter().all(|s| s.max_tokens() == Some(4096)));
+    }
+
+    #[test]
+    fn builder_enables_openai_batch() {
+        let mut config = Config::default();
+        config.ai_services.openai.api_key = "sk-test".to_string();
+        let pipeline = Pipeline::builder().from_config(&config).build().unwrap();
+        assert!(!pipeline.batch_enabled());
+
+        config.ai_services.openai.batch = true;
+        let pipeline = Pipeline::builder().from_config(&config).build().unwrap();
+        assert!(pipeline.batch_enabled());
+        // The synchronous service stays in the chain for images the batch can't handle
+        assert_eq!(pipeline.service_names(), vec!["OpenAI"]);
+    }
+
+    #[test]
+    fn from_config_builds_each_service_once() {
+        let mut config = Config::default();
+        config.ai_services.openai.api_key = "sk-test".to_string();
+        config.service_order = ["openai", "gemini", "openai"].map(String::from).to_vec();
+        let pipeline = Pipeline::builder().from_config(&config).build().unwrap();
+        assert_eq!(pipeline.service_names(), vec!["OpenAI"]);
+    }
+
+    #[test]
+    fn builder_dry_run_override() {
+        let mut config = Config::default();
+        config.ai_services.openai.api_key = "sk-test".to_string();
+
+        let pipeline = Pipeline::builder()
+            .from_config(&config)
+            .dry_run(true)
+            .build()
+            .unwrap();
+        assert!(pipeline.dry_run);
+    }
+
+    #[test]
+    fn builder_manual_service() {
+        let pipeline = Pipeline::builder()
+            .add_service(Box::new(ai::OpenAiService::new("sk-test".into(), "gpt-4o-mini".into())))
+            .build()
+            .unwrap();
+        assert_eq!(pipeline.service_count(), 1);
+        assert_eq!(pipeline.service_names(), vec!["OpenAI"]);
+    }
+
+    // ── Generic services / analyze-only ──────────────────────────────
+
+    /// Concrete service returning a canned title, or nothing when `empty`.
+    struct StubService {
+        name: &'static str,
+        empty: bool,
+    }
+
+    #[async_trait::async_trait]
+    impl AiService for StubService {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        async fn analyze(&self, _image_base64: &str, _prompt: &str, mime_type: &str) -> Result<AiResult> {
+            Ok(AiResult {
+                title: (!self.empty).then(|| format!("{} {mime_type}", self.name)),
+                ..Default::default()
+            })
+        }
+    }
+
+    fn stub(name: &'static str, empty: bool) -> StubService {
+        StubService { name, empty }
+    }
+
+    #[tokio::test]
+    async fn analyze_with_concrete_service() {
+        let dir = TempDir::new().unwrap();
+        let png = dir.path().join("a.png");
+        fs::write(&png, b"fake").unwrap();
+
+        let ai = analyze_with(&stub("Stub", false), &png).await.unwrap();
+        assert_eq!(ai.title.as_deref(), Some("Stub image/png"));
+    }
+
+    #[tokio::test]
+    async fn generic_pipeline_fails_over_without_boxing() {
+        let dir = TempDir::new().unwrap();
+        let jpg = dir.path().join("a.jpg");
+        fs::write(&jpg, b"fake").unwrap();
+
+        let pipeline: Pipeline<StubService> = Pipeline::with_service(stub("Empty", true))
+            .add_service(stub("Second", false))
+            .build()
+            .unwrap();
+        assert_eq!(pipeline.service_names(), vec!["Empty", "Second"]);
+
+        let (ai, service) = pipeline.analyze(&jpg).await.unwrap();
+        assert_eq!(service, "Second");
+        assert_eq!(ai.title.as_deref(), Some("Second image/jpeg"));
+    }
+
+    #[tokio::test]
+    async fn analyze_all_reports_each_path_to_callback() {
+        let dir = TempDir::new().unwrap();
+        let jpg = dir.path().join("a.jpg");
+        fs::write(&jpg, b"fake").unwrap();
+        let missing = dir.path().join("missing.jpg");
+
+        let pipeline = Pipeline::builder()
+            .add_service(Box::new(stub("Stub", false)))
+            .build()
+            .unwrap();
+
+        let mut seen = Vec::new();
+        pipeline
+            .analyze_all(&[jpg.clone(), missing.clone()], |path, result| {
+                seen.push((path.to_path_buf(), result.map(|(_, s)| s.to_string()).map_err(|e| e.to_string())));
+            })
+            .await;
+
+        assert_eq!(seen.len(), 2);
+        assert_eq!(seen[0], (jpg, Ok("Stub".to_string())));
+        assert_eq!(seen[1].0, missing);
+        assert!(seen[1].1.as_ref().unwrap_err().starts_with("Failed to read file"));
+    }
+
+    #[tokio::test]
+    async fn analyze_reports_all_failures() {
+        let dir = TempDir::new().unwrap();
+        let jpg = dir.path().join("a.jpg");
+        fs::write(&jpg, b"fake").unwrap();
+
+        let pipeline = Pipeline::with_service(stub("Empty", true)).build().unwrap();
+        let err = pipeline.analyze(&jpg).await.unwrap_err();
+        assert_eq!(err.to_string(), "All AI services failed: Empty: returned empty result");
+        assert_eq!(
+            err.downcast_ref::<ExifAiError>(),
+            Some(&ExifAiError::AllServicesFailed(vec!["Empty: returned empty result".into()]))
+        );
+
+        // Processing checks the image first, and this one isn't a real JPEG
+        let error = pipeline.process_image(&jpg).await.error.unwrap();
+        assert!(matches!(error, ExifAiError::Read { .. }), "{error:?}");
+        assert!(!error.is_ai_failure());
+    }
+
+    #[tokio::test]
+    async fn timed_out_service_fails_over() {
+        let dir = TempDir::new().unwrap();
+        let jpg = dir.path().join("a.jpg");
+        fs::write(&jpg, b"fake").unwrap();
+
+        // Accepts connections but never answers
+        let silent = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", silent.local_addr().unwrap());
+        let slow = || {
+            Box::new(ai::OllamaService::new(url.clone(), "llava".into())
+                .with_timeouts(Duration::from_millis(200), Duration::from_millis(200))) as Box<dyn AiService>
+        };
+
+        let pipeline = Pipeline::builder().add_service(slow()).build().unwrap();
+        let err = pipeline.analyze(&jpg).await.unwrap_err();
+        assert_eq!(err.to_string(), "All AI services failed: Ollama: Ollama request timed out");
+
+        let pipeline = Pipeline::builder().add_service(slow()).add_service(Box::new(stub("Stub", false))).build().unwrap();
+        let (_, service) = pipeline.analyze(&jpg).await.unwrap();
+        assert_eq!(service, "Stub");
+    }
+
+    /// Service that only accepts JPEG.
+    struct JpegOnly;
+
+    #[async_trait::async_trait]
+    impl AiService for JpegOnly {
+        fn name(&self) -> &str {
+            "JpegOnly"
+        }
+
+        fn supported_mime_types(&self) -> &[&str] {
+            &["image/jpeg"]
+        }
+
+        async fn analyze(&self, _image_base64: &str, _prompt: &str, mime_type: &str) -> Result<AiResult> {
+            assert_eq!(mime_type, "image/jpeg");
+            Ok(AiResult { title: Some("JpegOnly".into()), ..Default::default() })
+        }
+    }
+
+    #[tokio::test]
+    async fn chain_skips_services_without_mime_support() {
+        let dir = TempDir::new().unwrap();
+        let png = dir.path().join("a.png");
+        let jpg = dir.path().join("a.jpg");
+        fs::write(&png, b"fake").unwrap();
+        fs::write(&jpg, b"fake").unwrap();
+
+        let pipeline = Pipeline::builder()
+            .add_service(Box::new(JpegOnly))
+            .add_service(Box::new(stub("Stub", false)))
+            .build()
+            .unwrap();
+
+        let (_, service) = pipeline.analyze(&png).await.unwrap();
+        assert_eq!(service, "Stub");
+        let (_, service) = pipeline.analyze(&jpg).await.unwrap();
+        assert_eq!(service, "JpegOnly");
+    }
+
+    #[tokio::test]
+    async fn chain_with_no_supporting_service_is_not_a_service_failure() {
+        let dir = TempDir::new().unwrap();
+        let png = dir.path().join("a.png");
+        image::RgbImage::new(2, 2).save(&png).unwrap();
+
+        let pipeline = Pipeline::with_service(JpegOnly).build().unwrap();
+        let result = pipeline.process_image(&png).await;
+        let error = result.error.unwrap();
+        assert_eq!(error, ExifAiError::Unsupported { path: png.clone(), mime_type: "image/png".into() });
+        assert_eq!(error.to_string(), "No configured AI service supports image/png");
+        assert!(result.ai_service_used.is_none());
+    }
+
+    fn decode_base64(data: &str) -> Vec<u8> {
+        base64::Engine::decode(&base64::engine::general_purpose::STANDARD, data).unwrap()
+    }
+
+    #[test]
+    fn upload_image_downscales_to_fit() {
+        let dir = TempDir::new().unwrap();
+        let png = dir.path().join("wide.png");
+        image::RgbImage::new(40, 20).save(&png).unwrap();
+
+        let (data, mime_type) = upload_image(&png, Some(10)).unwrap();
+        assert_eq!(mime_type, "image/jpeg");
+        let img = image::load_from_memory(&decode_base64(&data)).unwrap();
+        assert_eq!((img.width(), img.height()), (10, 5));
+
+        // Already small enough, or no limit: the original bytes
+        for max_dimension in [Some(40), None] {
+            let (data, mime_type) = upload_image(&png, max_dimension).unwrap();
+            assert_eq!(mime_type, "image/png");
+            assert_eq!(decode_base64(&data), fs::read(&png).unwrap());
+        }
+    }
+
+    #[test]
+    fn upload_image_sends_gif_and_bmp_as_png() {
+        let dir = TempDir::new().unwrap();
+        for name in ["scan.bmp", "anim.gif"] {
+            let path = dir.path().join(name);
+            image::RgbImage::new(12, 8).save(&path).unwrap();
+            let (data, mime_type) = upload_image(&path, None).unwrap();
+            assert_eq!(mime_type, "image/png", "{name}");
+            let img = image::load_from_memory_with_format(&decode_base64(&data), image::ImageFormat::Png).unwrap();
+            assert_eq!((img.width(), img.height()), (12, 8));
+        }
+    }
+
+    #[tokio::test]
+    async fn gif_and_bmp_get_a_sidecar() {
+        let dir = TempDir::new().unwrap();
+        let pipeline = Pipeline::with_service(PromptProbe).build().unwrap();
+        let bmp = dir.path().join("scan.bmp");
+        image::RgbImage::new(8, 8).save(&bmp).unwrap();
+        assert_eq!(collect_images(&[dir.path().to_path_buf()]), vec![bmp.clone()]);
+
+        let original = fs::read(&bmp).unwrap();
+        let result = pipeline.process_image(&bmp).await;
+        assert!(result.error.is_none(), "{:?}", result.error);
+        assert_eq!(result.write_mode, WriteMode::Sidecar);
+        assert_eq!(result.sidecar_path, Some(dir.path().join("scan.xmp")));
+        assert_eq!(fs::read(&bmp).unwrap(), original);
+    }
+
+    #[test]
+    fn upload_image_sends_undecodable_formats_as_is() {
+        let heic = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("data/test.heic");
+        let (data, mime_type) = upload_image(&heic, Some(16)).unwrap();
+        assert_eq!(mime_type, "image/heic");
+        assert_eq!(decode_base64(&data), fs::read(&heic).unwrap());
+    }
+
+    #[tokio::test]
+    async fn downscaled_upload_reaches_jpeg_only_service() {
+        let dir = TempDir::new().unwrap();
+        let png = dir.path().join("a.png");
+        image::RgbImage::new(40, 20).save(&png).unwrap();
+
+        let pipeline = Pipeline::with_service(JpegOnly).build().unwrap();
+        assert!(pipeline.analyze(&png).await.is_err());
+
+        let pipeline = Pipeline::with_service(JpegOnly).max_upload_dimension(10).build().unwrap();
+        let (_, service) = pipeline.analyze(&png).await.unwrap();
+        assert_eq!(service, "JpegOnly");
+        // The file itself is untouched
+        assert_eq!(image::image_dimensions(&png).unwrap(), (40, 20));
+    }
+
+    /// Service that counts how often it is called.
+    struct Counting(std::sync::Arc<std::sync::atomic::AtomicUsize>);
+
+    #[async_trait::async_trait]
+    impl AiService for Counting {
+        fn name(&self) -> &str {
+            "Counting"
+        }
+
+        async fn analyze(&self, _image_base64: &str, _prompt: &str, _mime_type: &str) -> Result<AiResult> {
+            self.0.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Ok(AiResult { title: Some("Counted".into()), ..Default::default() })
+        }
+    }
+
+    #[tokio::test]
+    async fn analysis_cache_skips_unchanged_images() {
+        let dir = TempDir::new().unwrap();
+        let jpg = copy_data_file(&dir, "test_canon_powershot.jpg");
+        let original = fs::read(&jpg).unwrap();
+        let cache_path = dir.path().join(cache::CACHE_FILE_NAME);
+        let calls = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let pipeline = || {
+            Pipeline::with_service(Counting(calls.clone()))
+                .analysis_cache(AnalysisCache::load(&cache_path).unwrap())
+                .build()
+                .unwrap()
+        };
+
+        let result = pipeline().process_images(std::slice::from_ref(&jpg), 1).await;
+        assert_eq!(result[0].ai_service_used.as_deref(), Some("Counting"));
+        assert!(result[0].title_written);
+        // Filed under the original bytes and the written ones
+        assert_eq!(AnalysisCache::load(&cache_path).unwrap().len(), 2);
+
+        // The written file, and a fresh copy of the original, come from the cache
+        let copy = dir.path().join("copy.jpg");
+        fs::write(&copy, &original).unwrap();
+        let pipeline = pipeline();
+        for path in [&jpg, &copy] {
+            let result = pipeline.process_image(path).await;
+            assert!(result.error.is_none(), "{:?}", result.error);
+            assert_eq!(result.ai_service_used.as_deref(), Some(cache::CACHE_SERVICE_NAME));
+            assert_eq!(result.ai_result.unwrap().title.as_deref(), Some("Counted"));
+        }
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 1);
+
+        // Different bytes miss
+        let other = copy_data_file(&dir, "test_exif.jpg");
+        assert_eq!(pipeline.process_image(&other).await.ai_service_used.as_deref(), Some("Counting"));
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn analysis_cache_is_bypassed_when_overwriting() {
+        let dir = TempDir::new().unwrap();
+        let jpg = copy_data_file(&dir, "test_canon_powershot.jpg");
+        let cache = AnalysisCache::load(&dir.path().join(cache::CACHE_FILE_NAME)).unwrap();
+        cache.insert(cache::hash_file(&jpg).unwrap(), AiResult { title: Some("Stale".into()), ..Default::default() });
+
+        let calls = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let pipeline = Pipeline::with_service(Counting(calls.clone()))
+            .fields(ExifFields { overwrite_existing: true, ..Default::default() })
+            .analysis_cache(cache)
+            .build()
+            .unwrap();
+        let result = pipeline.process_image(&jpg).await;
+        assert_eq!(result.ai_service_used.as_deref(), Some("Counting"));
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 1);
+        let cache = pipeline.analysis_cache.as_ref().unwrap();
+        assert_eq!(cache.get(&cache::hash_file(&jpg).unwrap()).unwrap().title.as_deref(), Some("Counted"));
+    }
+
+    /// Service that reports whether it was asked to refine existing metadata.
+    struct PromptProbe;
+
+    #[async_trait::async_trait]
+    impl AiService for PromptProbe {
+        fn name(&self) -> &str {
+            "Probe"
+        }
+
+        fn supported_mime_types(&self) -> &[&str] {
+            &["image/*"]
+        }
+
+        async fn analyze(&self, _image_base64: &str, prompt: &str, _mime_type: &str) -> Result<AiResult> {
+            let augmented = prompt.contains("Existing description");
+            Ok(AiResult {
+                title: Some("Probe".into()),
+                description: Some(if augmented { "refined" } else { "fresh" }.into()),
+                ..Default::default()
+            })
+        }
+    }
+
+    #[test]
+    fn effective_prompt_injects_existing_metadata() {
+        let canon = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("data/test_canon_powershot.jpg");
+        let existing = exif::read_exif(&canon).unwrap();
+        let existing_description = existing.description.unwrap();
+
+        let augment = Pipeline::with_service(PromptProbe).augment(true).tag_style(TagStyle::Keywords).build().unwrap();
+        let prompt = augment.effective_prompt(Some(&canon));
+        assert!(prompt.contains("Existing description"), "{prompt}");
+        assert!(prompt.contains(existing_description.trim()), "{prompt}");
+        assert!(prompt.ends_with(&{
+            let mut tail = String::new();
+            ai::append_tag_style(&mut tail, TagStyle::Keywords);
+            tail
+        }));
+
+        // No path (or no augment mode): the plain prompt
+        assert!(!augment.effective_prompt(None).contains("Existing description"));
+        let plain = Pipeline::with_service(PromptProbe).prompt_context_fields(Vec::new()).build().unwrap();
+        assert_eq!(plain.effective_prompt(Some(&canon)), ai::build_prompt());
+    }
+
+    #[test]
+    fn date_is_only_requested_for_undated_images() {
+        let fields = ExifFields { write_date: true, ..Default::default() };
+        let pipeline = Pipeline::with_service(PromptProbe).fields(fields).build().unwrap();
+
+        let (prompt, _) = pipeline.prompt_for(&ExifData::default());
+        assert!(prompt.contains("\"date_taken\""), "{prompt}");
+        let dated = ExifData { date_time: Some("2003-12-14 12:01:44".into()), ..Default::default() };
+        assert!(!pipeline.prompt_for(&dated).0.contains("date_taken"));
+
+        let off = Pipeline::with_service(PromptProbe).build().unwrap();
+        assert!(!off.prompt_for(&ExifData::default()).0.contains("date_taken"));
+    }
+
+    #[tokio::test]
+    async fn language_drives_the_prompt_and_the_result() {
+        let config = Config { language: Some("fr".into()), ..Default::default() };
+        let pipeline = Pipeline::builder().from_config(&config).add_service(Box::new(PromptProbe)).dry_run(true).build().unwrap();
+        assert!(pipeline.prompt_for(&ExifData::default()).0.ends_with("in French."));
+
+        let dir = TempDir::new().unwrap();
+        let jpg = copy_data_file(&dir, "test_canon_powershot.jpg");
+        let pipeline = Pipeline::with_service(PromptProbe).language("de").dry_run(true).build().unwrap();
+        assert!(pipeline.prompt_for(&ExifData::default()).0.ends_with("in German."));
+        let result = pipeline.process_image(&jpg).await;
+        assert_eq!(result.ai_result.unwrap().language.as_deref(), Some("de"));
+    }
+
+    #[test]
+    fn prompt_asks_only_for_written_or_required_fields() {
+        let fields = ExifFields { write_gps: false, write_subject: false, ..Default::default() };
+        let pipeline = Pipeline::with_service(PromptProbe).fields(fields.clone()).build().unwrap();
+        let prompt = pipeline.base_prompt();
+        assert_eq!(prompt, ai::build_prompt_for(&fields));
+        assert!(prompt.contains("\"tags\"") && !prompt.contains("\"gps\"") && !prompt.contains("\"subject\""));
+
+        let required = Pipeline::with_service(PromptProbe)
+            .fields(fields.clone())
+            .required_fields(vec![Field::Gps])
+            .build()
+            .unwrap();
+        assert!(required.base_prompt().contains("\"gps\""));
+        let sidecar_json = Pipeline::with_service(PromptProbe).fields(fields).sidecar_json(true).build().unwrap();
+        assert_eq!(sidecar_json.base_prompt(), ai::build_prompt());
+
+        let nothing = ExifFields {
+            write_title: false,
+            write_description: false,
+            write_tags: false,
+            write_gps: false,
+            write_subject: false,
+            ..Default::default()
+        };
+        let nothing = Pipeline::with_service(PromptProbe).fields(nothing).build().unwrap();
+        assert_eq!(nothing.base_prompt(), ai::build_prompt());
+    }
+
+    #[tokio::test]
+    async fn tags_only_result_is_usable_when_no_text_is_written() {
+        let dir = TempDir::new().unwrap();
+        let image = copy_data_file(&dir, "test_canon_powershot.jpg");
+        let tags_only = AiResult { tags: Some(vec!["barn".into()]), ..Default::default() };
+        let fields = ExifFields { write_title: false, write_description: false, ..Default::default() };
+
+        let pipeline = Pipeline::with_service(MockAiService::returning(tags_only.clone()).named("Tags")).fields(fields).build().unwrap();
+        let (ai, service) = pipeline.analyze(&image).await.unwrap();
+        assert_eq!((ai.tags, service), (tags_only.tags.clone(), "Tags"));
+
+        // With a title or description to write, one of them is still needed
+        let pipeline = Pipeline::with_service(MockAiService::returning(tags_only).named("Tags")).build().unwrap();
+        assert!(pipeline.analyze(&image).await.is_err());
+    }
+
+    #[test]
+    fn hierarchy_is_requested_with_its_keyword_target() {
+        let mut fields = ExifFields::default();
+        fields.keyword_targets.lr_hierarchical_subject = true;
+        let pipeline = Pipeline::with_service(PromptProbe).fields(fields.clone()).build().unwrap();
+        assert!(pipeline.prompt_for(&ExifData::default()).0.contains("\"hierarchical_tags\""));
+
+        fields.write_tags = false;
+        let no_tags = Pipeline::with_service(PromptProbe).fields(fields).build().unwrap();
+        assert!(!no_tags.prompt_for(&ExifData::default()).0.contains("hierarchical_tags"));
+        let off = Pipeline::with_service(PromptProbe).build().unwrap();
+        assert!(!off.prompt_for(&ExifData::default()).0.contains("hierarchical_tags"));
+    }
+
+    #[test]
+    fn custom_prompt_replaces_built_in_one() {
+        const ALT_TEXT: &str = "Write accessibility alt-text for this image as JSON.";
+        let canon = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("data/test_canon_powershot.jpg");
+
+        let custom = Pipeline::with_service(PromptProbe)
+            .prompt(ALT_TEXT)
+            .prompt_context_fields(Vec::new())
+            .build()
+            .unwrap();
+        assert_eq!(custom.effective_prompt(Some(&canon)), ALT_TEXT);
+
+        // Augment mode still appends the existing metadata
+        let augment = Pipeline::with_service(PromptProbe).prompt(ALT_TEXT).augment(true).build().unwrap();
+        let prompt = augment.effective_prompt(Some(&canon));
+        assert!(prompt.starts_with(ALT_TEXT) && prompt.contains("Existing description"), "{prompt}");
+        assert!(!prompt.contains("SEO"), "{prompt}");
+
+        let mut config = Config::default();
+        config.output.prompt = Some(ALT_TEXT.into());
+        let from_config = Pipeline::builder().from_config(&config).add_service(Box::new(PromptProbe)).build().unwrap();
+        assert!(from_config.effective_prompt(None).starts_with(ALT_TEXT));
+    }
+
+    #[test]
+    fn prompt_context_only_shares_listed_fields() {
+        let existing = ExifData {
+            make: Some("Canon".into()),
+            model: Some("EOS R5".into()),
+            date_time: Some("2024:06:01 18:30:00".into()),
+            gps_latitude: Some(48.858_37),
+            gps_longitude: Some(2.294_481),
+            has_gps: true,
+            body_serial_number: Some("SN-0042".into()),
+            lens_serial_number: Some("LSN-7".into()),
+            software: Some("Lightroom".into()),
+            ..Default::default()
+        };
+
+        let default = Pipeline::with_service(PromptProbe).build().unwrap();
+        let (prompt, _) = default.prompt_for(&existing);
+        assert!(prompt.contains("- Camera model: EOS R5"), "{prompt}");
+        assert!(prompt.contains("- Date taken: 2024:06:01 18:30:00"), "{prompt}");
+        for secret in ["SN-0042", "LSN-7", "48.85", "2.29", "Lightroom"] {
+            assert!(!prompt.contains(secret), "{secret} leaked: {prompt}");
+        }
+
+        let opted_in = Pipeline::with_service(PromptProbe)
+            .prompt_context_fields(vec!["gps".into(), "body_serial_number".into()])
+            .build()
+            .unwrap();
+        let (prompt, _) = opted_in.prompt_for(&existing);
+        assert!(prompt.contains("- GPS coordinates: 48.858370, 2.294481"), "{prompt}");
+        assert!(prompt.contains("SN-0042"), "{prompt}");
+        assert!(!prompt.contains("Canon") && !prompt.contains("LSN-7"), "{prompt}");
+    }
+
+    #[tokio::test]
+    async fn augment_existing_refines_and_replaces_description() {
+        let canon = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("data/test_canon_powershot.jpg");
+
+        let plain = Pipeline::with_service(PromptProbe).dry_run(true).build().unwrap();
+        let result = plain.process_image(&canon).await;
+        assert_eq!(result.ai_result.unwrap().description.as_deref(), Some("fresh"));
+        assert!(!result.description_written, "existing description is kept");
+
+        let augment = Pipeline::with_service(PromptProbe).dry_run(true).augment(true).build().unwrap();
+        let result = augment.process_image(&canon).await;
+        assert_eq!(result.ai_result.unwrap().description.as_deref(), Some("refined"));
+        assert!(result.description_written, "augmented description replaces the original");
+        assert!(result.existing_exif.description.is_some());
+    }
+
+    /// Service that returns phrase tags and echoes the prompt's last line as the title.
+    struct PhraseTagger;
+
+    #[async_trait::async_trait]
+    impl AiService for PhraseTagger {
+        fn name(&self) -> &str {
+            "Phrases"
+        }
+
+        fn supported_mime_types(&self) -> &[&str] {
+            &["image/*"]
+        }
+
+        async fn analyze(&self, _image_base64: &str, prompt: &str, _mime_type: &str) -> Result<AiResult> {
+            Ok(AiResult {
+                title: prompt.lines().last().map(String::from),
+                tags: Some(vec!["sunset over the bay".into(), "Sunset".into()]),
+                ..Default::default()
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn tag_style_keywords_shapes_prompt_and_tags() {
+        let canon = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("data/test_canon_powershot.jpg");
+
+        let plain = Pipeline::with_service(PhraseTagger).dry_run(true).build().unwrap();
+        let ai = plain.process_image(&canon).await.ai_result.unwrap();
+        assert_eq!(ai.tags.unwrap(), ["sunset over the bay", "Sunset"]);
+
+        let keywords = Pipeline::with_service(PhraseTagger).dry_run(true).tag_style(TagStyle::Keywords).build().unwrap();
+        let ai = keywords.process_image(&canon).await.ai_result.unwrap();
+        assert!(ai.title.unwrap().contains("single word"));
+        assert_eq!(ai.tags.unwrap(), ["sunset", "over", "bay"]);
+    }
+
+    #[tokio::test]
+    async fn explain_lists_every_field() {
+        let canon = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("data/test_canon_powershot.jpg");
+        let pipeline = Pipeline::with_service(PromptProbe).dry_run(true).build().unwrap();
+        let result = pipeline.process_image(&canon).await;
+
+        let lines = result.explain();
+        assert_eq!(lines.len(), 6);
+        assert!(lines.contains(&"description skipped: existing value present and overwrite=false".to_string()), "{lines:?}");
+        assert!(lines.contains(&"tags skipped: not returned by the AI".to_string()), "{lines:?}");
+        assert!(lines.contains(&"gps skipped: not returned by the AI".to_string()), "{lines:?}");
+        assert!(lines.contains(&"date skipped: not returned by the AI".to_string()), "{lines:?}");
+    }
+
+    #[tokio::test]
+    async fn explain_reports_errors() {
+        let pipeline = Pipeline::with_service(PromptProbe).build().unwrap();
+        let result = pipeline.process_image(Path::new("/nonexistent/photo.jpg")).await;
+        let lines = result.explain();
+        assert_eq!(lines.len(), 1);
+        assert!(lines[0].starts_with("nothing written: "), "{lines:?}");
+    }
+
+    #[tokio::test]
+    async fn summary_line_lists_written_and_skipped() {
+        let pipeline = Pipeline::with_service(PromptProbe).build().unwrap();
+        let mut result = pipeline.process_image(Path::new("/nonexistent/photo.jpg")).await;
+        assert!(result.summary_line().starts_with("Nothing written: "));
+
+        result.error = None;
+        assert_eq!(result.summary_line(), "Nothing written");
+
+        result.title_written = true;
+        result.gps_written = true;
+        result.skipped_fields.push(SkippedField::new(Field::Description, SkipReason::Existing));
+        assert_eq!(result.written_fields(), ["title", "gps"]);
+        assert_eq!(result.summary_line(), "Wrote: title, gps. Skipped: description (existing)");
+    }
+
+    // ── Pre-flight write check ───────────────────────────────────────
+
+    fn set_readonly(path: &Path, readonly: bool) {
+        let mut perms = fs::metadata(path).unwrap().permissions();
+        perms.set_readonly(readonly);
+        fs::set_permissions(path, perms).unwrap();
+    }
+
+    #[tokio::test]
+    async fn read_only_file_fails_before_analysis() {
+        let dir = TempDir::new().unwrap();
+        let jpg = dir.path().join("locked.jpg");
+        fs::write(&jpg, b"fake").unwrap();
+        set_readonly(&jpg, true);
+
+        let pipeline = Pipeline::with_service(PromptProbe).build().unwrap();
+        let result = pipeline.process_image(&jpg).await;
+        set_readonly(&jpg, false);
+
+        let error = result.error.unwrap();
+        assert!(
+            matches!(error, ExifAiError::Io { kind: std::io::ErrorKind::PermissionDenied, .. }),
+            "{error:?}"
+        );
+        assert!(error.to_string().contains("read-only"), "{error}");
+        assert!(result.ai_result.is_none(), "AI must not be called");
+        assert!(result.ai_service_used.is_none());
+    }
+
+    #[tokio::test]
+    async fn read_only_file_allowed_in_dry_run() {
+        let dir = TempDir::new().unwrap();
+        let jpg = dir.path().join("locked.jpg");
+        fs::copy(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("data/test_canon_powershot.jpg"), &jpg).unwrap();
+        set_readonly(&jpg, true);
+
+        let pipeline = Pipeline::with_service(PromptProbe).dry_run(true).build().unwrap();
+        let result = pipeline.process_image(&jpg).await;
+        set_readonly(&jpg, false);
+
+        assert!(result.ai_result.is_some());
+    }
+
+    #[test]
+    fn check_writable_sidecar_read_only_xmp() {
+        let dir = TempDir::new().unwrap();
+        let heic = dir.path().join("photo.heic");
+        fs::write(&heic, b"fake").unwrap();
+        assert!(check_writable(&heic, ImageKind::Sidecar).is_ok());
+
+        let xmp = dir.path().join("photo.xmp");
+        fs::write(&xmp, b"<x/>").unwrap();
+        set_readonly(&xmp, true);
+        let err = check_writable(&heic, ImageKind::Sidecar).unwrap_err();
+        set_readonly(&xmp, false);
+        assert!(err.to_string().contains("photo.xmp is read-only"));
+    }
+
+    #[test]
+    fn image_kind_from_bytes_sniffs_data_files() {
+        let sniff = |name: &str| {
+            let bytes = fs::read(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("data").join(name)).unwrap();
+            ImageKind::from_bytes(&bytes[..16])
+        };
+        assert_eq!(sniff("test_canon_powershot.jpg"), Some(ImageKind::Jpeg));
+        assert_eq!(sniff("test.tiff"), Some(ImageKind::Tiff));
+        assert_eq!(sniff("test.heic"), Some(ImageKind::Sidecar));
+        assert_eq!(ImageKind::from_bytes(b"\x89PNG\r\n\x1a\n...."), Some(ImageKind::Png));
+        assert_eq!(ImageKind::from_bytes(b"RIFF\0\0\0\0WEBPVP8 "), Some(ImageKind::WebP));
+        assert_eq!(ImageKind::from_bytes(b"\xFF\xD8"), None);
+        assert_eq!(ImageKind::from_bytes(b""), None);
+    }
+
+    #[tokio::test]
+    async fn broken_files_fail_before_analysis() {
+        let dir = TempDir::new().unwrap();
+        let canon = fs::read(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("data/test_canon_powershot.jpg")).unwrap();
+
+        let empty = dir.path().join("empty.jpg");
+        fs::write(&empty, b"").unwrap();
+        let truncated = dir.path().join("truncated.jpg");
+        fs::write(&truncated, &canon[..200]).unwrap();
+        let garbage = dir.path().join("garbage.png");
+        fs::write(&garbage, b"<html>not found</html>").unwrap();
+
+        let pipeline = Pipeline::with_service(PromptProbe).build().unwrap();
+        for (path, reason) in [
+            (&empty, "is empty (0 bytes)"),
+            (&truncated, "looks truncated or corrupt"),
+            (&garbage, "unrecognized file header"),
+        ] {
+            let result = pipeline.process_image(path).await;
+            let error = result.error.unwrap();
+            assert_eq!(error.path(), Some(path));
+            assert!(error.to_string().contains(reason), "{error}");
+            assert!(result.ai_result.is_none(), "AI must not be called for {}", path.display());
+        }
+
+        // A complete image passes
+        let whole = dir.path().join("whole.jpg");
+        fs::write(&whole, &canon).unwrap();
+        assert!(check_image_data(&whole, ImageKind::Jpeg).is_ok());
+    }
+
+    #[test]
+    fn check_writable_probes_the_directory() {
+        let dir = TempDir::new().unwrap();
+        let jpg = copy_data_file(&dir, "test_canon_powershot.jpg");
+        check_writable(&jpg, ImageKind::Jpeg).unwrap();
+        check_writable(&dir.path().join("photo.heic"), ImageKind::Sidecar).unwrap();
+        // The probe leaves nothing behind
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+
+        let err = check_writable(&dir.path().join("missing/photo.heic"), ImageKind::Sidecar).unwrap_err();
+        let io = err.downcast_ref::<std::io::Error>().unwrap();
+        assert_eq!(io.kind(), std::io::ErrorKind::NotFound);
+        assert!(err.to_string().contains("does not exist"), "{err}");
+    }
+
+    /// A 0555 directory can't be written by anyone but root.
+    #[cfg(unix)]
+    #[test]
+    fn check_writable_rejects_unwritable_directory() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = TempDir::new().unwrap();
+        let locked = dir.path().join("locked");
+        fs::create_dir(&locked).unwrap();
+        let heic = locked.join("photo.heic");
+        fs::write(&heic, b"fake").unwrap();
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o555)).unwrap();
+
+        // Root can write anywhere; only check when the directory really is unwritable
+        let writable = tempfile::NamedTempFile::new_in(&locked).is_ok();
+        let result = check_writable(&heic, ImageKind::Sidecar);
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o755)).unwrap();
+        if writable {
+            result.unwrap();
+        } else {
+            let err = result.unwrap_err();
+            assert!(err.to_string().contains("is not writable"), "{err}");
+            assert_eq!(err.downcast_ref::<std::io::Error>().unwrap().kind(), std::io::ErrorKind::PermissionDenied);
+        }
+    }
+
+    #[test]
+    fn check_writable_missing_file() {
+        let err = check_writable(Path::new("/nonexistent/photo.jpg"), ImageKind::Jpeg).unwrap_err();
+        assert!(err.to_string().starts_with("Cannot access"));
+    }
+
+    // ── WriteMode ────────────────────────────────────────────────────
+
+    fn copy_data_file(dir: &TempDir, name: &str) -> PathBuf {
+        let dst = dir.path().join(name);
+        fs::copy(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("data").join(name), &dst).unwrap();
+        dst
+    }
+
+    #[tokio::test]
+    async fn write_mode_embedded_for_jpeg() {
+        let dir = TempDir::new().unwrap();
+        let jpg = copy_data_file(&dir, "test_canon_powershot.jpg");
+
+        let pipeline = Pipeline::with_service(PromptProbe).build().unwrap();
+        let result = pipeline.process_image(&jpg).await;
+        assert!(result.error.is_none(), "{:?}", result.error);
+        assert_eq!(result.write_mode, WriteMode::Embedded);
+        assert!(result.sidecar_path.is_none());
+    }
+
+    #[tokio::test]
+    async fn process_image_with_uses_batched_result() {
+        let dir = TempDir::new().unwrap();
+        let jpg = copy_data_file(&dir, "test_canon_powershot.jpg");
+
+        let pipeline = Pipeline::with_service(PromptProbe).write_provenance(true).build().unwrap();
+        let batched = AiResult { title: Some("Batched".into()), ..Default::default() };
+        let result = pipeline.process_image_with(&jpg, Some(batched)).await;
+        assert!(result.error.is_none(), "{:?}", result.error);
+        assert_eq!(result.ai_result.unwrap().title.as_deref(), Some("Batched"));
+        assert_eq!(result.ai_service_used.as_deref(), Some("OpenAI Batch"));
+        assert!(result.title_written);
+
+        // Without a batched result the service chain runs as usual
+        let result = pipeline.process_image_with(&copy_data_file(&dir, "test_canon_powershot.jpg"), None).await;
+        assert_eq!(result.ai_service_used.as_deref(), Some("Probe"));
+    }
+
+    #[tokio::test]
+    async fn process_images_batched_without_batch_uses_the_chain() {
+        let dir = TempDir::new().unwrap();
+        let images = vec![copy_data_file(&dir, "test_canon_powershot.jpg")];
+
+        let pipeline = Pipeline::with_service(PromptProbe).build().unwrap();
+        assert!(!pipeline.batch_enabled());
+        let results = pipeline.process_images_batched(&images, 2).await;
+        assert_eq!(results.len(), 1);
+        assert!(results[0].error.is_none(), "{:?}", results[0].error);
+        assert_eq!(results[0].ai_service_used.as_deref(), Some("Probe"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn symlink_policies() {
+        let is_link = |p: &Path| fs::symlink_metadata(p).unwrap().file_type().is_symlink();
+
+        for policy in [SymlinkPolicy::Follow, SymlinkPolicy::Skip, SymlinkPolicy::Copy] {
+            let dir = TempDir::new().unwrap();
+            let target = copy_data_file(&dir, "test_canon_powershot.jpg");
+            let original = fs::read(&target).unwrap();
+            let link = dir.path().join("link.jpg");
+            std::os::unix::fs::symlink(&target, &link).unwrap();
+
+            let pipeline = Pipeline::with_service(PromptProbe).symlink_policy(policy).build().unwrap();
+            let result = pipeline.process_image(&link).await;
+            assert!(result.error.is_none(), "{policy:?}: {:?}", result.error);
+
+            match policy {
+                SymlinkPolicy::Follow => {
+                    assert!(is_link(&link));
+                    assert_ne!(fs::read(&target).unwrap(), original, "target is written");
+                }
+                SymlinkPolicy::Skip => {
+                    assert!(is_link(&link));
+                    assert!(result.ai_result.is_none());
+                    assert_eq!(result.write_mode, WriteMode::Skipped);
+                    assert_eq!(fs::read(&target).unwrap(), original);
+                }
+                SymlinkPolicy::Copy => {
+                    assert!(!is_link(&link), "link replaced by a regular file");
+                    assert_eq!(result.write_mode, WriteMode::Embedded);
+                    assert_eq!(fs::read(&target).unwrap(), original, "target untouched");
+                    assert_eq!(exif::read_exif(&link).unwrap().title.as_deref(), Some("Probe"));
+                }
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn write_mode_sidecar_for_heic() {
+        let dir = TempDir::new().unwrap();
+        let heic = copy_data_file(&dir, "test.heic");
+        let original = fs::read(&heic).unwrap();
+
+        let pipeline = Pipeline::with_service(PromptProbe).build().unwrap();
+        let result = pipeline.process_image(&heic).await;
+        assert!(result.error.is_none(), "{:?}", result.error);
+        assert_eq!(result.write_mode, WriteMode::Sidecar);
+        assert!(result.sidecar_path.unwrap().exists());
+        assert_eq!(fs::read(&heic).unwrap(), original);
+    }
+
+    #[tokio::test]
+    async fn write_mode_skipped_on_error() {
+        let dir = TempDir::new().unwrap();
+        let jpg = dir.path().join("a.jpg");
+        fs::write(&jpg, b"fake").unwrap();
+
+        let pipeline = Pipeline::with_service(stub("Empty", true)).build().unwrap();
+        let result = pipeline.process_image(&jpg).await;
+        assert!(result.error.is_some());
+        assert_eq!(result.write_mode, WriteMode::Skipped);
+    }
+
+    #[tokio::test]
+    async fn backup_metadata_only_writes_metabak_instead_of_bak() {
+        let dir = TempDir::new().unwrap();
+        let jpg = copy_data_file(&dir, "test_canon_powershot.jpg");
+        let original = fs::read(&jpg).unwrap();
+
+        let pipeline = Pipeline::with_service(PromptProbe)
+            .backup(true)
+            .backup_metadata_only(true)
+            .build()
+            .unwrap();
+        let result = pipeline.process_image(&jpg).await;
+        assert!(result.error.is_none(), "{:?}", result.error);
+        assert!(exif::metadata_backup_path(&jpg).exists());
+        assert!(!dir.path().join("test_canon_powershot.jpg.bak").exists());
+
+        exif::restore_metadata(&jpg).unwrap();
+        assert_eq!(fs::read(&jpg).unwrap(), original);
+    }
+
+    #[test]
+    fn write_mode_serializes_lowercase() {
+        assert_eq!(serde_json::to_string(&WriteMode::Sidecar).unwrap(), "\"sidecar\"");
+        assert_eq!(WriteMode::Embedded.to_string(), "embedded");
+        assert_eq!(WriteMode::for_kind(ImageKind::Png), WriteMode::Embedded);
+    }
+
+    // ── JSON sidecar ─────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn json_sidecar_matches_process_result() {
+        let dir = TempDir::new().unwrap();
+        let jpg = copy_data_file(&dir, "test_canon_powershot.jpg");
+
+        let pipeline = Pipeline::with_service(PromptProbe)
+            .write_json_sidecar(true)
+            .build()
+            .unwrap();
+        let result = pipeline.process_image(&jpg).await;
+        assert!(result.error.is_none(), "{:?}", result.error);
+
+        let json_path = result.json_sidecar_path.clone().unwrap();
+        assert_eq!(json_path, dir.path().join("test_canon_powershot.jpg.exif-ai.json"));
+        let json: serde_json::Value = serde_json::from_str(&fs::read_to_string(&json_path).unwrap()).unwrap();
+
+        assert_eq!(json["ai_service"], "Probe");
+        assert_eq!(json["ai_result"], serde_json::to_value(&result.ai_result).unwrap());
+        assert_eq!(json["title_written"], result.title_written);
+        assert_eq!(json["description_written"], result.description_written);
+        assert_eq!(json["tags_written"], result.tags_written);
+        assert_eq!(json["gps_written"], result.gps_written);
+        assert_eq!(json["subject_written"], result.subject_written);
+        assert_eq!(json["skipped_fields"], serde_json::to_value(&result.skipped_fields).unwrap());
+        assert_eq!(json["write_mode"], "embedded");
+        assert_eq!(json["sidecar_path"], serde_json::Value::Null);
+    }
+
+    #[tokio::test]
+    async fn always_sidecar_leaves_native_images_untouched() {
+        let dir = TempDir::new().unwrap();
+        let pipeline = Pipeline::with_service(PromptProbe).always_sidecar(true).build().unwrap();
+
+        for name in ["test_canon_powershot.jpg", "test.tiff"] {
+            let image = copy_data_file(&dir, name);
+            let original = fs::read(&image).unwrap();
+            let result = pipeline.process_image(&image).await;
+            assert!(result.error.is_none(), "{:?}", result.error);
+            assert_eq!(result.write_mode, WriteMode::Sidecar);
+            assert!(result.title_written);
+            assert_eq!(fs::read(&image).unwrap(), original, "{name} was modified");
+
+            let sidecar = result.sidecar_path.unwrap();
+            assert_eq!(sidecar, image.with_extension("xmp"));
+            assert!(fs::read_to_string(sidecar).unwrap().contains("Probe"));
+        }
+    }
+
+    #[tokio::test]
+    async fn sidecar_json_leaves_images_untouched() {
+        let dir = TempDir::new().unwrap();
+        let pipeline = Pipeline::with_service(PromptProbe).sidecar_json(true).build().unwrap();
+
+        for name in ["test_canon_powershot.jpg", "test.heic"] {
+            let image = copy_data_file(&dir, name);
+            let original = fs::read(&image).unwrap();
+            let result = pipeline.process_image(&image).await;
+            assert!(result.error.is_none(), "{:?}", result.error);
+            assert_eq!(result.write_mode, WriteMode::Json);
+            assert!(result.written_fields().is_empty());
+            assert!(result.sidecar_path.is_none());
+            assert_eq!(fs::read(&image).unwrap(), original, "{name} was modified");
+            assert!(!image.with_extension("xmp").exists());
+
+            let json_path = result.json_sidecar_path.clone().unwrap();
+            assert_eq!(json_path, dir.path().join(format!("{name}.json")));
+            assert_eq!(result.summary_line(), format!("Wrote JSON: {}", json_path.display()));
+            let json: serde_json::Value = serde_json::from_str(&fs::read_to_string(&json_path).unwrap()).unwrap();
+            assert_eq!(json["path"], image.display().to_string());
+            assert_eq!(json["ai_service"], "Probe");
+            assert_eq!(json["write_mode"], "json");
+            let ai: AiResult = serde_json::from_value(json["ai_result"].clone()).unwrap();
+            assert_eq!(serde_json::to_value(&ai).unwrap(), serde_json::to_value(&result.ai_result).unwrap());
+            assert_eq!(ai.title.as_deref(), Some("Probe"));
+        }
+
+        // Nothing is written on dry run
+        let jpg = dir.path().join("dry.jpg");
+        fs::copy(dir.path().join("test_canon_powershot.jpg"), &jpg).unwrap();
+        let pipeline = Pipeline::with_service(PromptProbe).sidecar_json(true).dry_run(true).build().unwrap();
+        let result = pipeline.process_image(&jpg).await;
+        assert_eq!(result.write_mode, WriteMode::Json);
+        assert!(result.json_sidecar_path.is_none());
+        assert_eq!(result.summary_line(), "Would write JSON");
+    }
+
+    #[test]
+    fn csv_cells_are_quoted_when_needed() {
+        assert_eq!(csv_cell("plain", ','), "plain");
+        assert_eq!(csv_cell("barn, red", ','), "\"barn, red\"");
+        assert_eq!(csv_cell("barn, red", '\t'), "barn, red");
+        assert_eq!(csv_cell("a\tb", '\t'), "\"a\tb\"");
+        assert_eq!(csv_cell("say \"hi\"", ','), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_cell("two\nlines", ','), "\"two\nlines\"");
+    }
+
+    #[tokio::test]
+    async fn csv_report_has_a_row_per_image() {
+        let dir = TempDir::new().unwrap();
+        let jpg = copy_data_file(&dir, "test_canon_powershot.jpg");
+        let pipeline = Pipeline::with_service(full_result()).build().unwrap();
+        let results = vec![
+            pipeline.process_image(&jpg).await,
+            pipeline.process_image(&dir.path().join("missing.jpg")).await,
+        ];
+
+        let csv = dir.path().join("report.csv");
+        write_csv_report(&results, &csv).unwrap();
+        let contents = fs::read_to_string(&csv).unwrap();
+        let lines: Vec<&str> = contents.split("\r\n").collect();
+        assert_eq!(lines.len(), 4, "{contents}");
+        assert_eq!(lines[0], CSV_COLUMNS.join(","));
+        // The fixture already has a description, so it isn't written
+        assert!(!results[0].description_written);
+        assert_eq!(
+            lines[1],
+            format!(
+                "{},Full,Red barn,A red barn in a snowy field.,barn;snow,46.5,7.9,barn,true,false,true,true,true,false,false,false,",
+                jpg.display()
+            )
+        );
+        // A failed image has empty cells, never "None"
+        let failed: Vec<&str> = lines[2].split(',').collect();
+        assert_eq!(failed.len(), CSV_COLUMNS.len());
+        assert!(failed[1..8].iter().all(|c| c.is_empty()), "{}", lines[2]);
+        assert!(!failed[16].is_empty());
+        assert!(!lines[2].contains("None"));
+        assert_eq!(lines[3], "");
+
+        let tsv = dir.path().join("report.tsv");
+        write_csv_report(&results, &tsv).unwrap();
+        assert!(fs::read_to_string(&tsv).unwrap().starts_with("path\tai_service\ttitle\t"));
+    }
+
+    /// Service that fills in every field.
+    fn full_result() -> MockAiService {
+        MockAiService::returning(AiResult {
+            title: Some("Red barn".into()),
+            description: Some("A red barn in a snowy field.".into()),
+            tags: Some(vec!["barn".into(), "snow".into()]),
+            gps: Some(ai::GpsCoords { latitude: 46.5, longitude: 7.9, altitude: None }),
+            subject: Some(vec!["barn".into()]),
+            ..Default::default()
+        })
+        .named("Full")
+    }
+
+    fn merge_chain(merge: bool) -> Pipeline {
+        let tags_and_gps = AiResult {
+            tags: Some(vec!["barn".into()]),
+            gps: Some(ai::GpsCoords { latitude: 46.5, longitude: 7.9, altitude: None }),
+            ..Default::default()
+        };
+        let text = AiResult {
+            title: Some("Barn".into()),
+            description: Some("A barn.".into()),
+            tags: Some(vec!["ignored".into()]),
+            ..Default::default()
+        };
+        Pipeline::builder()
+            .add_service(Box::new(MockAiService::returning(tags_and_gps).named("Tagger")))
+            .add_service(Box::new(MockAiService::returning(text).named("Writer")))
+            .add_service(Box::new(full_result()))
+            .merge_services(merge)
+            .dry_run(true)
+            .build()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn merge_services_fills_fields_across_the_chain() {
+        let dir = TempDir::new().unwrap();
+        let jpg = copy_data_file(&dir, "test_canon_powershot.jpg");
+
+        let result = merge_chain(true).process_image(&jpg).await;
+        assert!(result.error.is_none(), "{:?}", result.error);
+        let ai = result.ai_result.as_ref().unwrap();
+        assert_eq!(ai.title.as_deref(), Some("Barn"));
+        assert_eq!(ai.tags, Some(vec!["barn".to_string()]));
+        assert!(ai.gps.is_some());
+        // Title, description and tags were complete after "Writer"
+        assert!(ai.subject.is_none());
+        assert_eq!(result.ai_service_used.as_deref(), Some("Tagger"));
+        let sources: Vec<(Field, &str)> = result.field_sources.iter().map(|(f, s)| (*f, s.as_str())).collect();
+        assert_eq!(
+            sources,
+            vec![
+                (Field::Title, "Writer"),
+                (Field::Description, "Writer"),
+                (Field::Tags, "Tagger"),
+                (Field::Gps, "Tagger"),
+            ]
+        );
+
+        // Without merging, the tags-only answer counts as empty
+        let result = merge_chain(false).process_image(&jpg).await;
+        assert_eq!(result.ai_service_used.as_deref(), Some("Writer"));
+        assert_eq!(result.ai_result.unwrap().tags, Some(vec!["ignored".to_string()]));
+        assert!(result.field_sources.values().all(|s| s == "Writer"));
+    }
+
+    #[tokio::test]
+    async fn long_titles_and_descriptions_are_clamped() {
+        let dir = TempDir::new().unwrap();
+        let jpg = copy_data_file(&dir, "test_canon_powershot.jpg");
+
+        let pipeline = Pipeline::with_service(full_result())
+            .limits(Limits { max_title_chars: 6, max_description_chars: 14 })
+            .dry_run(true)
+            .build()
+            .unwrap();
+        let result = pipeline.process_image(&jpg).await;
+        let ai = result.ai_result.unwrap();
+        assert_eq!(ai.title.as_deref(), Some("Red"));
+        assert_eq!(ai.description.as_deref(), Some("A red barn in"));
+    }
+
+    #[tokio::test]
+    async fn disabled_fields_are_still_captured() {
+        let dir = TempDir::new().unwrap();
+        let jpg = copy_data_file(&dir, "test_canon_powershot.jpg");
+        let original = fs::read(&jpg).unwrap();
+
+        let fields = ExifFields {
+            write_title: false,
+            write_description: false,
+            write_tags: false,
+            write_gps: false,
+            write_subject: false,
+            ..Default::default()
+        };
+        let pipeline = Pipeline::with_service(full_result()).fields(fields).write_json_sidecar(true).build().unwrap();
+        let result = pipeline.process_image(&jpg).await;
+        assert!(result.error.is_none(), "{:?}", result.error);
+
+        // Nothing reaches the image...
+        assert!(!result.title_written && !result.description_written && !result.tags_written);
+        assert!(!result.gps_written && !result.subject_written);
+        assert_eq!(result.write_mode, WriteMode::Skipped);
+        assert!(result.skipped_fields.iter().all(|s| s.reason == SkipReason::Disabled));
+        assert_eq!(fs::read(&jpg).unwrap(), original);
+
+        // ...but the complete AI output is kept, in the result and the JSON catalog
+        let ai = result.ai_result.as_ref().unwrap();
+        assert_eq!(ai.title.as_deref(), Some("Red barn"));
+        assert_eq!(ai.description.as_deref(), Some("A red barn in a snowy field."));
+        assert_eq!(ai.tags.as_ref().unwrap().len(), 2);
+        assert!(ai.gps.is_some());
+        assert_eq!(ai.subject.as_ref().unwrap()[0], "barn");
+
+        let json_path = result.json_sidecar_path.clone().unwrap();
+        let json: serde_json::Value = serde_json::from_str(&fs::read_to_string(&json_path).unwrap()).unwrap();
+        assert_eq!(json["ai_result"], serde_json::to_value(&result.ai_result).unwrap());
+        assert_eq!(json["ai_result"]["tags"], serde_json::json!(["barn", "snow"]));
+    }
+
+    #[test]
+    fn provenance_hashes_prompt() {
+        let provenance = Provenance::new("OpenAI", Some("gpt-4o-mini"), "abc");
+        assert_eq!(provenance.prompt_sha256, "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+        assert_eq!(provenance.generator, format!("exif-ai {}", env!("CARGO_PKG_VERSION")));
+        assert_eq!(provenance.model.as_deref(), Some("gpt-4o-mini"));
+    }
+
+    #[tokio::test]
+    async fn write_provenance_records_prompt_hash_in_json_sidecar() {
+        let dir = TempDir::new().unwrap();
+        let jpg = copy_data_file(&dir, "test_canon_powershot.jpg");
+
+        let plain = Pipeline::with_service(PromptProbe).dry_run(true).build().unwrap();
+        assert!(plain.process_image(&jpg).await.provenance.is_none());
+
+        let pipeline = Pipeline::with_service(PromptProbe).write_provenance(true).build().unwrap();
+        let prompt = pipeline.effective_prompt(Some(&jpg));
+        let result = pipeline.process_image(&jpg).await;
+        assert!(result.error.is_none(), "{:?}", result.error);
+
+        let provenance = result.provenance.clone().unwrap();
+        assert_eq!(provenance, Provenance::new("Probe", None, &prompt));
+
+        // The catalog file is written even without write_json_sidecar
+        let json_path = result.json_sidecar_path.unwrap();
+        let json: serde_json::Value = serde_json::from_str(&fs::read_to_string(&json_path).unwrap()).unwrap();
+        assert_eq!(json["provenance"]["prompt_sha256"], provenance.prompt_sha256);
+        assert_eq!(json["provenance"]["service"], "Probe");
+        assert_eq!(json["provenance"]["model"], serde_json::Value::Null);
+    }
+
+    #[tokio::test]
+    async fn json_sidecar_skipped_on_dry_run() {
+        let dir = TempDir::new().unwrap();
+        let jpg = copy_data_file(&dir, "test_canon_powershot.jpg");
+
+        let pipeline = Pipeline::with_service(PromptProbe)
+            .write_json_sidecar(true)
+            .dry_run(true)
+            .build()
+            .unwrap();
+        let result = pipeline.process_image(&jpg).await;
+        assert!(result.error.is_none(), "{:?}", result.error);
+        assert!(result.json_sidecar_path.is_none());
+        assert!(!json_sidecar_path(&jpg).exists());
+    }
+
+    #[tokio::test]
+    async fn json_sidecar_not_written_on_error() {
+        let dir = TempDir::new().unwrap();
+        let jpg = copy_data_file(&dir, "test_canon_powershot.jpg");
+
+        let pipeline = Pipeline::with_service(stub("Empty", true))
+            .write_json_sidecar(true)
+            .build()
+            .unwrap();
+        let result = pipeline.process_image(&jpg).await;
+        assert!(result.error.is_some());
+        assert!(!json_sidecar_path(&jpg).exists());
+    }
+
+    // ── Per-image timeout ────────────────────────────────────────────
+
+    /// Service that takes far longer than any test budget.
+    struct Sleepy;
+
+    #[async_trait::async_trait]
+    impl AiService for Sleepy {
+        fn name(&self) -> &str {
+            "Sleepy"
+        }
+
+        async fn analyze(&self, _image_base64: &str, _prompt: &str, _mime_type: &str) -> Result<AiResult> {
+            tokio::time::sleep(Duration::from_secs(30)).await;
+            Ok(AiResult { title: Some("late".into()), ..Default::default() })
         }
     }
 
-    images
-}
+    #[tokio::test]
+    async fn per_image_timeout_fails_image_and_moves_on() {
+        let dir = TempDir::new().unwrap();
+        let jpg = copy_data_file(&dir, "test_canon_powershot.jpg");
+        let original = fs::read(&jpg).unwrap();
+
+        let pipeline = Pipeline::builder()
+            .add_service(Box::new(Sleepy))
+            .add_service(Box::new(stub("Fallback", false)))
+            .per_image_timeout(Duration::from_millis(100))
+            .build()
+            .unwrap();
+
+        let started = std::time::Instant::now();
+        let results = pipeline.process_all(&[jpg.clone(), jpg.clone()]).await;
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        assert_eq!(results.len(), 2);
+        for result in &results {
+            let error = result.error.as_ref().unwrap();
+            assert_eq!(*error, ExifAiError::Timeout(Duration::from_millis(100)));
+            assert_eq!(error.to_string(), "AI analysis timed out after 0.1s");
+            assert!(result.ai_result.is_none());
+        }
+        assert_eq!(fs::read(&jpg).unwrap(), original);
+    }
+
+    // ── Concurrency ──────────────────────────────────────────────────
+
+    /// Takes a moment per image and records how many calls overlapped.
+    #[derive(Default)]
+    struct InFlight {
+        current: std::sync::atomic::AtomicUsize,
+        max: std::sync::Arc<std::sync::atomic::AtomicUsize>,
+    }
+
+    #[async_trait::async_trait]
+    impl AiService for InFlight {
+        fn name(&self) -> &str {
+            "InFlight"
+        }
+
+        async fn analyze(&self, _image_base64: &str, _prompt: &str, _mime_type: &str) -> Result<AiResult> {
+            use std::sync::atomic::Ordering::SeqCst;
+            let now = self.current.fetch_add(1, SeqCst) + 1;
+            self.max.fetch_max(now, SeqCst);
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            self.current.fetch_sub(1, SeqCst);
+            Ok(AiResult { title: Some("Busy".into()), ..Default::default() })
+        }
+    }
+
+    #[tokio::test]
+    async fn process_images_runs_concurrently_in_input_order() {
+        let dir = TempDir::new().unwrap();
+        let src = copy_data_file(&dir, "test_canon_powershot.jpg");
+        let paths: Vec<PathBuf> = (0..5)
+            .map(|i| {
+                let path = dir.path().join(format!("img{i}.jpg"));
+                fs::copy(&src, &path).unwrap();
+                path
+            })
+            .collect();
+
+        let service = InFlight::default();
+        let max = service.max.clone();
+        let pipeline = Pipeline::with_service(service).build().unwrap();
+
+        let mut completed = Vec::new();
+        let results = pipeline
+            .process_images_with(&paths, HashMap::new(), 3, |i, _| completed.push(i))
+            .await;
+
+        assert_eq!(max.load(std::sync::atomic::Ordering::SeqCst), 3);
+        let order: Vec<_> = results.iter().map(|r| r.path.clone()).collect();
+        assert_eq!(order, paths);
+        assert!(results.iter().all(|r| r.error.is_none() && r.title_written));
+        completed.sort();
+        assert_eq!(completed, vec![0, 1, 2, 3, 4]);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn process_images_serializes_images_sharing_files() {
+        let dir = TempDir::new().unwrap();
+        let jpg = copy_data_file(&dir, "test_canon_powershot.jpg");
+        let link = dir.path().join("link.jpg");
+        std::os::unix::fs::symlink(&jpg, &link).unwrap();
+
+        let service = InFlight::default();
+        let max = service.max.clone();
+        let pipeline = Pipeline::with_service(service).backup(true).build().unwrap();
+
+        let results = pipeline.process_images(&[jpg.clone(), link, jpg.clone()], 3).await;
+
+        assert_eq!(max.load(std::sync::atomic::Ordering::SeqCst), 1);
+        assert!(results.iter().all(|r| r.error.is_none()));
+    }
+
+    #[tokio::test]
+    async fn process_images_reports_progress_per_image() {
+        let dir = TempDir::new().unwrap();
+        let src = copy_data_file(&dir, "test_canon_powershot.jpg");
+        let paths: Vec<PathBuf> = (0..3)
+            .map(|i| {
+                let path = dir.path().join(format!("img{i}.jpg"));
+                fs::copy(&src, &path).unwrap();
+                path
+            })
+            .collect();
+        let pipeline = Pipeline::with_service(stub("Stub", false)).build().unwrap();
+
+        let seen = std::sync::Mutex::new(Vec::new());
+        let results = pipeline
+            .process_images_with_progress(&paths, 2, |done, total, path| {
+                seen.lock().unwrap().push((done, total, path.to_path_buf()));
+            })
+            .await;
+
+        assert_eq!(results.len(), 3);
+        let mut seen = seen.into_inner().unwrap();
+        assert_eq!(seen.iter().map(|s| (s.0, s.1)).collect::<Vec<_>>(), vec![(1, 3), (2, 3), (3, 3)]);
+        seen.sort_by(|a, b| a.2.cmp(&b.2));
+        assert_eq!(seen.into_iter().map(|s| s.2).collect::<Vec<_>>(), paths);
+    }
+
+    #[tokio::test]
+    async fn process_stream_yields_every_image() {
+        use futures::StreamExt;
 
-/// Check if a file has a supported image extension.
-fn is_supported_image(path: &Path) -> bool {
-    path.extension()
-        .and_then(|ext| ext.to_str())
-        .map(|ext| IMAGE_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
-        .unwrap_or(false)
-}
+        let dir = TempDir::new().unwrap();
+        let src = copy_data_file(&dir, "test_canon_powershot.jpg");
+        let paths: Vec<PathBuf> = (0..3)
+            .map(|i| {
+                let path = dir.path().join(format!("img{i}.jpg"));
+                fs::copy(&src, &path).unwrap();
+                path
+            })
+            .collect();
+        let pipeline = Pipeline::with_service(stub("Stub", false)).build().unwrap();
 
-/// Create a backup of the original file.
-fn backup_file(path: &Path) -> Result<PathBuf> {
-    let backup_path = path.with_extension(format!(
-        "{}.bak",
-        path.extension().unwrap_or_default().to_string_lossy()
-    ));
+        let results: Vec<ProcessResult> = pipeline.process_stream(&paths, 2).collect().await;
 
-    if !backup_path.exists() {
-        std::fs::copy(path, &backup_path).context("Failed to create backup")?;
-        log::debug!("Backup created: {}", backup_path.display());
+        assert!(results.iter().all(|r| r.error.is_none() && r.title_written));
+        let mut seen: Vec<PathBuf> = results.into_iter().map(|r| r.path).collect();
+        seen.sort();
+        assert_eq!(seen, paths);
     }
 
-    Ok(backup_path)
-}
-
+    #[tokio::test]
+    async fn process_images_treats_zero_concurrency_as_one() {
+        let dir = TempDir::new().unwrap();
+        let jpg = copy_data_file(&dir, "test_canon_powershot.jpg");
+        let pipeline = Pipeline::with_service(stub("Stub", false)).build().unwrap();
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::fs;
-    use tempfile::TempDir;
+        let results = pipeline.process_images(std::slice::from_ref(&jpg), 0).await;
+        assert_eq!(results.len(), 1);
+        assert!(results[0].error.is_none());
+    }
 
-    // ── ImageKind::from_path ──────────────────────────────────────────
+    // ── Re-prompt on invalid JSON ────────────────────────────────────
 
-    #[test]
-    fn image_kind_jpeg() {
-        assert_eq!(ImageKind::from_path(Path::new("photo.jpg")), Some(ImageKind::Jpeg));
-        assert_eq!(ImageKind::from_path(Path::new("photo.jpeg")), Some(ImageKind::Jpeg));
-        assert_eq!(ImageKind::from_path(Path::new("PHOTO.JPG")), Some(ImageKind::Jpeg));
+    /// Answers with prose first, then valid JSON once told its output was invalid.
+    struct Garbled {
+        calls: std::sync::atomic::AtomicUsize,
     }
 
-    #[test]
-    fn image_kind_png() {
-        assert_eq!(ImageKind::from_path(Path::new("image.png")), Some(ImageKind::Png));
-        assert_eq!(ImageKind::from_path(Path::new("IMAGE.PNG")), Some(ImageKind::Png));
+    #[async_trait::async_trait]
+    impl AiService for Garbled {
+        fn name(&self) -> &str {
+            "Garbled"
+        }
+
+        async fn analyze(&self, _image_base64: &str, prompt: &str, _mime_type: &str) -> Result<AiResult> {
+            self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            if prompt.contains("Your previous output was not valid JSON:\n\nSure! It's a sunset.") {
+                ai::parse_ai_response(r#"{"title": "Sunset", "description": "Orange sky"}"#)
+            } else {
+                ai::parse_ai_response("Sure! It's a sunset.")
+            }
+        }
     }
 
-    #[test]
-    fn image_kind_webp() {
-        assert_eq!(ImageKind::from_path(Path::new("image.webp")), Some(ImageKind::WebP));
+    fn garbled() -> Garbled {
+        Garbled { calls: std::sync::atomic::AtomicUsize::new(0) }
     }
 
-    #[test]
-    fn image_kind_tiff() {
-        assert_eq!(ImageKind::from_path(Path::new("scan.tif")), Some(ImageKind::Tiff));
-        assert_eq!(ImageKind::from_path(Path::new("scan.tiff")), Some(ImageKind::Tiff));
+    #[tokio::test]
+    async fn reprompt_recovers_from_invalid_json() {
+        let dir = TempDir::new().unwrap();
+        let jpg = dir.path().join("a.jpg");
+        fs::write(&jpg, b"fake").unwrap();
+
+        let pipeline = Pipeline::with_service(garbled()).reprompt_on_parse_error(true).build().unwrap();
+        let (ai, service) = pipeline.analyze(&jpg).await.unwrap();
+        assert_eq!(service, "Garbled");
+        assert_eq!(ai.title.as_deref(), Some("Sunset"));
+        assert_eq!(pipeline.services[0].calls.load(std::sync::atomic::Ordering::SeqCst), 2);
     }
 
-    #[test]
-    fn image_kind_sidecar_heic() {
-        assert_eq!(ImageKind::from_path(Path::new("photo.heic")), Some(ImageKind::Sidecar));
-        assert_eq!(ImageKind::from_path(Path::new("photo.heif")), Some(ImageKind::Sidecar));
-        assert_eq!(ImageKind::from_path(Path::new("photo.HEIC")), Some(ImageKind::Sidecar));
+    #[tokio::test]
+    async fn invalid_json_fails_without_reprompt() {
+        let dir = TempDir::new().unwrap();
+        let jpg = dir.path().join("a.jpg");
+        fs::write(&jpg, b"fake").unwrap();
+
+        let pipeline = Pipeline::with_service(garbled()).build().unwrap();
+        let err = pipeline.analyze(&jpg).await.unwrap_err();
+        assert_eq!(err.to_string(), "All AI services failed: Garbled: Could not parse AI response as JSON");
+        assert_eq!(pipeline.services[0].calls.load(std::sync::atomic::Ordering::SeqCst), 1);
     }
 
-    #[test]
-    fn image_kind_sidecar_avif() {
-        assert_eq!(ImageKind::from_path(Path::new("photo.avif")), Some(ImageKind::Sidecar));
+    /// A response cut off at 1000 tokens, mid-way through the tags.
+    const TRUNCATED_RESPONSE: &str = r#"{"title": "Red barn", "description": "A red barn in a snowy field.", "tags": ["barn", "snow", "fie"#;
+
+    /// Returns [`TRUNCATED_RESPONSE`] unless given more than 1000 tokens.
+    struct Truncating {
+        limits: std::sync::Mutex<Vec<u32>>,
     }
 
-    #[test]
-    fn image_kind_sidecar_raw() {
-        for ext in &["cr3", "cr2", "dng", "nef", "arw", "raf", "orf", "rw2", "pef", "srw"] {
-            let path = format!("photo.{ext}");
-            assert_eq!(
-                ImageKind::from_path(Path::new(&path)),
-                Some(ImageKind::Sidecar),
-                "Expected Sidecar for .{ext}"
-            );
+    #[async_trait::async_trait]
+    impl AiService for Truncating {
+        fn name(&self) -> &str {
+            "Truncating"
         }
-    }
 
-    #[test]
-    fn image_kind_unsupported() {
-        assert_eq!(ImageKind::from_path(Path::new("doc.pdf")), None);
-        assert_eq!(ImageKind::from_path(Path::new("video.mp4")), None);
-        assert_eq!(ImageKind::from_path(Path::new("noext")), None);
+        fn max_tokens(&self) -> Option<u32> {
+            Some(1000)
+        }
+
+        async fn analyze(&self, image_base64: &str, prompt: &str, mime_type: &str) -> Result<AiResult> {
+            self.analyze_with_max_tokens(image_base64, prompt, mime_type, 1000).await
+        }
+
+        async fn analyze_with_max_tokens(&self, _image_base64: &str, _prompt: &str, _mime_type: &str, max_tokens: u32) -> Result<AiResult> {
+            self.limits.lock().unwrap().push(max_tokens);
+            if max_tokens > 1000 {
+                ai::parse_ai_response(r#"{"title": "Red barn", "description": "A red barn in a snowy field.", "tags": ["barn", "snow", "field"]}"#)
+            } else {
+                ai::parse_ai_response(TRUNCATED_RESPONSE)
+            }
+        }
     }
 
-    // ── ImageKind::mime_type ──────────────────────────────────────────
+    #[tokio::test]
+    async fn truncated_response_retried_with_more_tokens() {
+        let dir = TempDir::new().unwrap();
+        let jpg = dir.path().join("a.jpg");
+        fs::write(&jpg, b"fake").unwrap();
 
-    #[test]
-    fn mime_type_jpeg() {
-        let kind = ImageKind::Jpeg;
-        assert_eq!(kind.mime_type(Path::new("a.jpg")), "image/jpeg");
-        assert_eq!(kind.mime_type(Path::new("a.jpeg")), "image/jpeg");
+        let pipeline = Pipeline::with_service(Truncating { limits: Default::default() }).build().unwrap();
+        let (ai, _) = pipeline.analyze(&jpg).await.unwrap();
+        assert_eq!(ai.tags.unwrap(), vec!["barn", "snow", "field"]);
+        assert_eq!(*pipeline.services[0].limits.lock().unwrap(), vec![1000, 2000]);
     }
 
-    #[test]
-    fn mime_type_png() {
-        assert_eq!(ImageKind::Png.mime_type(Path::new("a.png")), "image/png");
+    #[tokio::test]
+    async fn non_truncated_invalid_json_not_retried_with_more_tokens() {
+        let dir = TempDir::new().unwrap();
+        let jpg = dir.path().join("a.jpg");
+        fs::write(&jpg, b"fake").unwrap();
+
+        // Garbled has no token limit and its output isn't a cut-off object
+        let pipeline = Pipeline::with_service(garbled()).build().unwrap();
+        assert!(pipeline.analyze(&jpg).await.is_err());
+        assert_eq!(pipeline.services[0].calls.load(std::sync::atomic::Ordering::SeqCst), 1);
     }
 
-    #[test]
-    fn mime_type_webp() {
-        assert_eq!(ImageKind::WebP.mime_type(Path::new("a.webp")), "image/webp");
+    // ── Filename fallback title ──────────────────────────────────────
+
+    /// Returns a description and tags but no title.
+    fn untitled() -> MockAiService {
+        MockAiService::returning(AiResult {
+            description: Some("An orange sky over the water.".into()),
+            tags: Some(vec!["sunset".into()]),
+            ..Default::default()
+        })
+        .named("Untitled")
     }
 
-    #[test]
-    fn mime_type_heic() {
-        assert_eq!(ImageKind::Sidecar.mime_type(Path::new("a.heic")), "image/heic");
-        assert_eq!(ImageKind::Sidecar.mime_type(Path::new("a.heif")), "image/heif");
+    #[tokio::test]
+    async fn filename_fallback_title_when_ai_has_none() {
+        let dir = TempDir::new().unwrap();
+        let jpg = dir.path().join("sunset-over_the-BAY.jpg");
+        fs::copy(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("data/test_canon_powershot.jpg"), &jpg).unwrap();
+
+        let pipeline = Pipeline::with_service(untitled())
+            .filename_fallback_title(true)
+            .build()
+            .unwrap();
+        let result = pipeline.process_image(&jpg).await;
+        assert!(result.error.is_none(), "{:?}", result.error);
+        assert!(result.title_from_filename);
+        assert_eq!(result.ai_result.unwrap().title.as_deref(), Some("Sunset Over The Bay"));
+        assert!(result.title_written);
     }
 
-    #[test]
-    fn mime_type_raw_formats() {
-        assert_eq!(ImageKind::Sidecar.mime_type(Path::new("a.cr3")), "image/x-canon-cr3");
-        assert_eq!(ImageKind::Sidecar.mime_type(Path::new("a.nef")), "image/x-nikon-nef");
-        assert_eq!(ImageKind::Sidecar.mime_type(Path::new("a.arw")), "image/x-sony-arw");
-        assert_eq!(ImageKind::Sidecar.mime_type(Path::new("a.dng")), "image/x-adobe-dng");
+    #[tokio::test]
+    async fn filename_fallback_title_off_or_unneeded() {
+        let dir = TempDir::new().unwrap();
+        let jpg = copy_data_file(&dir, "test_canon_powershot.jpg");
+
+        // Disabled: the title stays absent
+        let pipeline = Pipeline::with_service(untitled()).dry_run(true).build().unwrap();
+        let result = pipeline.process_image(&jpg).await;
+        assert!(!result.title_from_filename);
+        assert!(result.ai_result.unwrap().title.is_none());
+
+        // Enabled but the AI already returned a title
+        let pipeline = Pipeline::with_service(stub("Stub", false))
+            .filename_fallback_title(true)
+            .dry_run(true)
+            .build()
+            .unwrap();
+        let result = pipeline.process_image(&jpg).await;
+        assert!(!result.title_from_filename);
+        assert_eq!(result.ai_result.unwrap().title.as_deref(), Some("Stub image/jpeg"));
     }
 
     #[test]
-    fn mime_type_fallback() {
-        assert_eq!(ImageKind::Jpeg.mime_type(Path::new("noext")), "image/jpeg");
+    fn title_from_filename_slugs() {
+        assert_eq!(title_from_filename(Path::new("/x/my_holiday-2024.jpg")).as_deref(), Some("My Holiday 2024"));
+        assert_eq!(title_from_filename(Path::new("  café  du.soir .png")).as_deref(), Some("Café Du Soir"));
+        assert_eq!(title_from_filename(Path::new("---.jpg")), None);
     }
 
-    // ── is_supported_image ───────────────────────────────────────────
+    #[tokio::test]
+    async fn folder_keyword_added_to_tags() {
+        let dir = TempDir::new().unwrap();
+        let folder = dir.path().join("2024-06-paris");
+        fs::create_dir(&folder).unwrap();
+        let jpg = folder.join("photo.jpg");
+        fs::copy(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("data/test_canon_powershot.jpg"), &jpg).unwrap();
 
-    #[test]
-    fn supported_image_extensions() {
-        assert!(is_supported_image(Path::new("photo.jpg")));
-        assert!(is_supported_image(Path::new("photo.JPEG")));
-        assert!(is_supported_image(Path::new("photo.png")));
-        assert!(is_supported_image(Path::new("photo.webp")));
-        assert!(is_supported_image(Path::new("photo.tif")));
-        assert!(is_supported_image(Path::new("photo.heic")));
-        assert!(is_supported_image(Path::new("photo.cr3")));
-        assert!(is_supported_image(Path::new("photo.dng")));
+        let pipeline = Pipeline::with_service(stub("Stub", false)).folder_keyword(true).dry_run(true).build().unwrap();
+        let tags = pipeline.process_image(&jpg).await.ai_result.unwrap().tags.unwrap();
+        assert_eq!(tags.last().map(String::as_str), Some("paris 2024"));
+        assert_eq!(tags.iter().filter(|t| *t == "paris 2024").count(), 1);
+
+        let pipeline = Pipeline::with_service(stub("Stub", false)).dry_run(true).build().unwrap();
+        let tags = pipeline.process_image(&jpg).await.ai_result.unwrap().tags.unwrap_or_default();
+        assert!(!tags.iter().any(|t| t == "paris 2024"));
     }
 
     #[test]
-    fn unsupported_image_extensions() {
-        assert!(!is_supported_image(Path::new("doc.pdf")));
-        assert!(!is_supported_image(Path::new("video.mp4")));
-        assert!(!is_supported_image(Path::new("readme.txt")));
-        assert!(!is_supported_image(Path::new("noext")));
+    fn aspect_class_thresholds() {
+        assert_eq!(AspectClass::from_dimensions(1000, 1000), Some(AspectClass::Square));
+        assert_eq!(AspectClass::from_dimensions(1050, 1000), Some(AspectClass::Square));
+        assert_eq!(AspectClass::from_dimensions(1000, 1050), Some(AspectClass::Square));
+        assert_eq!(AspectClass::from_dimensions(1051, 1000), Some(AspectClass::Landscape));
+        assert_eq!(AspectClass::from_dimensions(1000, 1051), Some(AspectClass::Portrait));
+        assert_eq!(AspectClass::from_dimensions(4000, 6000), Some(AspectClass::Portrait));
+        assert_eq!(AspectClass::from_dimensions(0, 100), None);
+        assert_eq!(AspectClass::Landscape.to_string(), "landscape");
     }
 
-    // ── collect_images ───────────────────────────────────────────────
-
     #[test]
-    fn collect_images_single_file() {
+    fn image_dimensions_respect_orientation() {
+        let path = Path::new("/nonexistent.jpg");
+        let mut exif = ExifData { image_width: Some("4000".into()), image_height: Some("3000".into()), ..Default::default() };
+        assert_eq!(image_dimensions(path, &exif), Some((4000, 3000)));
+        exif.orientation = Some("6".into());
+        assert_eq!(image_dimensions(path, &exif), Some((3000, 4000)));
+        assert_eq!(image_dimensions(path, &ExifData::default()), None);
+    }
+
+    #[tokio::test]
+    async fn no_ai_writes_only_derived_fields() {
         let dir = TempDir::new().unwrap();
-        let jpg = dir.path().join("test.jpg");
-        fs::write(&jpg, b"fake").unwrap();
+        let folder = dir.path().join("2024-06-paris");
+        fs::create_dir(&folder).unwrap();
+        let jpg = folder.join("eiffel-tower.jpg");
+        fs::copy(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("data/test_canon_powershot.jpg"), &jpg).unwrap();
 
-        let images = collect_images(&[jpg.clone()]);
-        assert_eq!(images.len(), 1);
-        assert_eq!(images[0], jpg);
+        let mut fields = ExifFields { write_copyright: true, write_artist: true, write_iptc_dates: true, ..Default::default() };
+        fields.metadata_defaults.copyright = Some("© 2024 Jane Doe".into());
+        fields.metadata_defaults.artist = Some("Jane Doe".into());
+        let pipeline = Pipeline::builder()
+            .no_ai(true)
+            .fields(fields)
+            .required_fields(vec![Field::Description])
+            .filename_fallback_title(true)
+            .folder_keyword(true)
+            .write_aspect_class(true)
+            .build()
+            .unwrap();
+        assert_eq!(pipeline.service_count(), 0);
+
+        let result = pipeline.process_image(&jpg).await;
+        assert!(result.error.is_none(), "{:?}", result.error);
+        assert!(result.missing_fields.is_empty());
+        assert!(result.ai_service_used.is_none());
+        assert!(result.title_written && result.tags_written);
+        assert!(result.copyright_written && result.artist_written);
+        assert!(!result.description_written && !result.gps_written && !result.subject_written);
+
+        let written = exif::read_exif(&jpg).unwrap();
+        assert_eq!(written.title.as_deref(), Some("Eiffel Tower"));
+        assert_eq!(written.copyright.as_deref(), Some("© 2024 Jane Doe"));
+        assert_eq!(written.artist.as_deref(), Some("Jane Doe"));
+        let keywords = written.keywords.unwrap();
+        assert!(keywords.contains("paris 2024") && keywords.contains("landscape"), "{keywords}");
+        // IPTC DateCreated (2:55) stamped from DateTimeOriginal
+        let bytes = fs::read(&jpg).unwrap();
+        assert!(bytes.windows(13).any(|w| w == b"\x1c\x02\x37\x00\x0820031214"));
     }
 
     #[test]
-    fn collect_images_skips_unsupported() {
-        let dir = TempDir::new().unwrap();
-        let txt = dir.path().join("readme.txt");
-        fs::write(&txt, b"hello").unwrap();
+    fn no_ai_from_config_needs_no_services() {
+        let mut config = Config::default(); // openai enabled but key is empty
+        assert!(Pipeline::builder().from_config(&config).build().is_err());
 
-        let images = collect_images(&[txt]);
-        assert!(images.is_empty());
+        config.output.no_ai = true;
+        let pipeline = Pipeline::builder().from_config(&config).build().unwrap();
+        assert_eq!(pipeline.service_count(), 0);
     }
 
-    #[test]
-    fn collect_images_directory_recursive() {
+    #[tokio::test]
+    async fn aspect_class_added_to_tags() {
         let dir = TempDir::new().unwrap();
-        let sub = dir.path().join("sub");
-        fs::create_dir(&sub).unwrap();
+        let jpg = copy_data_file(&dir, "test_canon_powershot.jpg");
 
-        fs::write(dir.path().join("a.jpg"), b"fake").unwrap();
-        fs::write(sub.join("b.png"), b"fake").unwrap();
-        fs::write(sub.join("c.txt"), b"fake").unwrap();
+        let pipeline = Pipeline::with_service(stub("Stub", false)).write_aspect_class(true).dry_run(true).build().unwrap();
+        let tags = pipeline.process_image(&jpg).await.ai_result.unwrap().tags.unwrap();
+        assert_eq!(tags.last().map(String::as_str), Some("landscape"));
 
-        let images = collect_images(&[dir.path().to_path_buf()]);
-        assert_eq!(images.len(), 2);
+        let pipeline = Pipeline::with_service(stub("Stub", false)).dry_run(true).build().unwrap();
+        let tags = pipeline.process_image(&jpg).await.ai_result.unwrap().tags.unwrap_or_default();
+        assert!(!tags.iter().any(|t| t == "landscape"));
     }
 
     #[test]
-    fn collect_images_empty_dir() {
-        let dir = TempDir::new().unwrap();
-        let images = collect_images(&[dir.path().to_path_buf()]);
-        assert!(images.is_empty());
+    fn keyword_from_folder_cleans_name() {
+        assert_eq!(keyword_from_folder(Path::new("/x/2024-06-paris/a.jpg")).as_deref(), Some("paris 2024"));
+        assert_eq!(keyword_from_folder(Path::new("/x/Lake_Como 2023-08-14/a.jpg")).as_deref(), Some("lake como 2023"));
+        assert_eq!(keyword_from_folder(Path::new("/x/2024-06/a.jpg")).as_deref(), Some("2024"));
+        assert_eq!(keyword_from_folder(Path::new("/x/06-12/a.jpg")), None);
+        assert_eq!(keyword_from_folder(Path::new("a.jpg")), None);
     }
 
-    #[test]
-    fn collect_images_nonexistent_path() {
-        let images = collect_images(&[PathBuf::from("/nonexistent/path")]);
-        assert!(images.is_empty());
-    }
+    // ── Required AI fields ───────────────────────────────────────────
 
-    #[test]
-    fn collect_images_mixed_files_and_dirs() {
+    #[tokio::test]
+    async fn missing_required_field_is_flagged_without_writing() {
         let dir = TempDir::new().unwrap();
-        let jpg = dir.path().join("photo.jpg");
-        let sub = dir.path().join("folder");
-        fs::create_dir(&sub).unwrap();
-        fs::write(&jpg, b"fake").unwrap();
-        fs::write(sub.join("deep.heic"), b"fake").unwrap();
+        let jpg = copy_data_file(&dir, "test_canon_powershot.jpg");
+        let original = fs::read(&jpg).unwrap();
 
-        let images = collect_images(&[jpg.clone(), sub]);
-        assert_eq!(images.len(), 2);
+        // The stub returns a title only — no tags
+        let pipeline = Pipeline::with_service(stub("Stub", false))
+            .required_fields(vec![Field::Title, Field::Tags])
+            .build()
+            .unwrap();
+        let result = pipeline.process_image(&jpg).await;
+
+        assert_eq!(result.missing_fields, vec![Field::Tags]);
+        let error = result.error.unwrap();
+        assert_eq!(error, ExifAiError::MissingFields(vec![Field::Tags]));
+        assert_eq!(error.to_string(), "AI result missing required field(s): tags");
+        assert!(result.ai_result.is_some(), "AI output is kept for inspection");
+        assert_eq!(result.write_mode, WriteMode::Skipped);
+        assert_eq!(fs::read(&jpg).unwrap(), original);
     }
 
-    // ── Pipeline::builder ────────────────────────────────────────────
+    #[tokio::test]
+    async fn allow_partial_writes_despite_missing_field() {
+        let dir = TempDir::new().unwrap();
+        let jpg = copy_data_file(&dir, "test_canon_powershot.jpg");
 
-    #[test]
-    fn builder_fails_with_no_services() {
-        let mut config = Config::default();
-        config.ai_services.openai.enabled = false;
-        config.ai_services.gemini.enabled = false;
-        config.ai_services.cloudflare.enabled = false;
+        let pipeline = Pipeline::with_service(stub("Stub", false))
+            .required_fields(vec![Field::Tags])
+            .allow_partial(true)
+            .build()
+            .unwrap();
+        let result = pipeline.process_image(&jpg).await;
 
-        let result = Pipeline::builder().from_config(&config).build();
-        assert!(result.is_err());
+        assert_eq!(result.missing_fields, vec![Field::Tags]);
+        assert!(result.error.is_none());
+        assert!(result.title_written);
     }
 
     #[test]
-    fn builder_skips_empty_keys() {
-        let config = Config::default(); // openai enabled but key is empty
-        let result = Pipeline::builder().from_config(&config).build();
-        assert!(result.is_err()); // no valid services → error
+    fn missing_fields_treats_blank_as_missing() {
+        let ai = AiResult {
+            title: Some("  ".into()),
+            tags: Some(Vec::new()),
+            description: Some("ok".into()),
+            ..Default::default()
+        };
+        assert_eq!(missing_fields(&ai, &[]), Vec::<Field>::new());
+        assert_eq!(
+            missing_fields(&ai, &[Field::Title, Field::Description, Field::Tags]),
+            vec![Field::Title, Field::Tags]
+        );
     }
 
     #[test]
-    fn builder_with_key() {
-        let mut config = Config::default();
-        config.ai_services.openai.api_key = "sk-test".to_string();
+    fn required_fields_load_from_config() {
+        let config: Config = serde_json::from_value(serde_json::json!({
+            "ai_services": serde_json::to_value(Config::default().ai_services).unwrap(),
+            "service_order": [],
+            "exif_fields": serde_json::to_value(ExifFields::default()).unwrap(),
+            "output": { "dry_run": false, "backup_originals": false, "log_file": null,
+                        "required_ai_fields": ["title", "tags"] }
+        }))
+        .unwrap();
+        assert_eq!(config.output.required_ai_fields, vec![Field::Title, Field::Tags]);
+        assert!(!config.output.allow_partial);
+    }
 
-        let pipeline = Pipeline::builder().from_config(&config).build().unwrap();
-        assert_eq!(pipeline.service_count(), 1);
-        assert_eq!(pipeline.service_names(), vec!["OpenAI"]);
+    // ── Rate limiting ────────────────────────────────────────────────
+
+    /// Rate limited on the first call, then succeeds.
+    struct RateLimitedOnce {
+        calls: std::sync::atomic::AtomicUsize,
+        retry_after: Duration,
     }
 
-    #[test]
-    fn builder_dry_run_override() {
-        let mut config = Config::default();
-        config.ai_services.openai.api_key = "sk-test".to_string();
+    #[async_trait::async_trait]
+    impl AiService for RateLimitedOnce {
+        fn name(&self) -> &str {
+            "Limited"
+        }
 
-        let pipeline = Pipeline::builder()
-            .from_config(&config)
-            .dry_run(true)
-            .build()
-            .unwrap();
-        assert!(pipeline.dry_run);
+        async fn analyze(&self, _image_base64: &str, _prompt: &str, _mime_type: &str) -> Result<AiResult> {
+            if self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst) == 0 {
+                return Err(AiError::RateLimited {
+                    service: "Limited".into(),
+                    retry_after: Some(self.retry_after),
+                }
+                .into());
+            }
+            Ok(AiResult { title: Some("after retry".into()), ..Default::default() })
+        }
     }
 
-    #[test]
-    fn builder_manual_service() {
-        let pipeline = Pipeline::builder()
-            .add_service(Box::new(ai::OpenAiService::new("sk-test".into(), "gpt-4o-mini".into())))
-            .build()
-            .unwrap();
-        assert_eq!(pipeline.service_count(), 1);
-        assert_eq!(pipeline.service_names(), vec!["OpenAI"]);
+    #[tokio::test]
+    async fn rate_limited_service_is_retried_after_wait() {
+        let dir = TempDir::new().unwrap();
+        let jpg = dir.path().join("a.jpg");
+        fs::write(&jpg, b"fake").unwrap();
+
+        let service = RateLimitedOnce { calls: Default::default(), retry_after: Duration::ZERO };
+        let pipeline = Pipeline::with_service(service).build().unwrap();
+        let (ai, _) = pipeline.analyze(&jpg).await.unwrap();
+        assert_eq!(ai.title.as_deref(), Some("after retry"));
+    }
+
+    #[tokio::test]
+    async fn long_retry_after_fails_over_instead_of_waiting() {
+        let dir = TempDir::new().unwrap();
+        let jpg = dir.path().join("a.jpg");
+        fs::write(&jpg, b"fake").unwrap();
+
+        let service = RateLimitedOnce { calls: Default::default(), retry_after: Duration::from_secs(3600) };
+        let pipeline = Pipeline::with_service(service).build().unwrap();
+        let err = pipeline.analyze(&jpg).await.unwrap_err();
+        assert!(err.to_string().contains("Limited rate limited (retry after 3600s)"), "{err}");
     }
 }
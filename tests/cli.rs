@@ -0,0 +1,399 @@
+//! End-to-end checks for the `exif-ai-cli` binary.
+
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use exif_ai::config::Config;
+use tempfile::TempDir;
+
+/// Write a config with a (fake) OpenAI key so the pipeline builds.
+fn write_config(dir: &Path) -> std::path::PathBuf {
+    let mut config = Config::default();
+    config.ai_services.openai.api_key = "sk-test".into();
+    let path = dir.join("config.json");
+    config.save(Some(&path)).unwrap();
+    path
+}
+
+/// A read-only image fails the pre-flight check, so no network call is made.
+fn read_only_image(dir: &Path) -> std::path::PathBuf {
+    read_only_image_named(dir, "locked.jpg")
+}
+
+fn read_only_image_named(dir: &Path, name: &str) -> std::path::PathBuf {
+    let src = Path::new(env!("CARGO_MANIFEST_DIR")).join("data/test_canon_powershot.jpg");
+    let path = dir.join(name);
+    fs::copy(src, &path).unwrap();
+    let mut perms = fs::metadata(&path).unwrap().permissions();
+    perms.set_readonly(true);
+    fs::set_permissions(&path, perms).unwrap();
+    path
+}
+
+#[test]
+fn json_quiet_keeps_stdout_machine_readable() {
+    let dir = TempDir::new().unwrap();
+    let config = write_config(dir.path());
+    let image = read_only_image(dir.path());
+
+    let output = Command::new(env!("CARGO_BIN_EXE_exif-ai-cli"))
+        .arg("--json")
+        .arg("--quiet")
+        .arg("--config")
+        .arg(&config)
+        .arg(&image)
+        .env_remove("RUST_LOG")
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    // stdout is exactly one JSON document
+    let results: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let results = results.as_array().unwrap();
+    assert_eq!(results.len(), 1);
+    assert!(results[0]["error"].as_str().unwrap().contains("read-only"));
+
+    // Info-level progress is suppressed; the per-image error still reaches stderr
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!stderr.contains("Processing:"), "{stderr}");
+    assert!(stderr.contains("read-only"), "{stderr}");
+}
+
+#[test]
+fn progress_goes_to_stderr_not_stdout() {
+    let dir = TempDir::new().unwrap();
+    let config = write_config(dir.path());
+    let image = read_only_image(dir.path());
+
+    let output = Command::new(env!("CARGO_BIN_EXE_exif-ai-cli"))
+        .arg("--json")
+        .arg("--config")
+        .arg(&config)
+        .arg(&image)
+        .env_remove("RUST_LOG")
+        .output()
+        .unwrap();
+
+    serde_json::from_slice::<serde_json::Value>(&output.stdout).unwrap();
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Processing:"));
+}
+
+#[test]
+fn quiet_conflicts_with_verbose() {
+    let output = Command::new(env!("CARGO_BIN_EXE_exif-ai-cli"))
+        .args(["--quiet", "--verbose", "photo.jpg"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+}
+
+#[test]
+fn overwrite_conflicts_with_no_overwrite() {
+    let output = Command::new(env!("CARGO_BIN_EXE_exif-ai-cli"))
+        .args(["--overwrite", "--no-overwrite", "photo.jpg"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+}
+
+#[test]
+fn threads_must_be_positive() {
+    let output = Command::new(env!("CARGO_BIN_EXE_exif-ai-cli"))
+        .args(["--threads", "0", "photo.jpg"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--threads"));
+}
+
+#[test]
+fn print_config_masks_secrets() {
+    let dir = TempDir::new().unwrap();
+    let mut config = Config::default();
+    config.ai_services.openai.api_key = "sk-very-secret".into();
+    config.ai_services.openai.headers = Some(
+        [("X-Request-ID", "req-1"), ("Authorization", "Bearer gateway-secret")]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect(),
+    );
+    let path = dir.path().join("config.json");
+    config.save(Some(&path)).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_exif-ai-cli"))
+        .arg("--print-config")
+        .arg("--config")
+        .arg(&path)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let printed: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let openai = &printed["ai_services"]["openai"];
+    assert_eq!(openai["api_key"], "********");
+    assert_eq!(openai["headers"]["X-Request-ID"], "req-1");
+    assert_eq!(openai["headers"]["Authorization"], "********");
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("secret"));
+}
+
+/// Run the CLI with `--json --quiet --stdin` and `input` piped to stdin.
+fn run_with_stdin(config: &Path, extra: &[&str], input: &[u8]) -> serde_json::Value {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_exif-ai-cli"))
+        .args(["--json", "--quiet", "--stdin"])
+        .args(extra)
+        .arg("--config")
+        .arg(config)
+        .env_remove("RUST_LOG")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(input).unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    serde_json::from_slice(&output.stdout).unwrap()
+}
+
+fn result_paths(results: &serde_json::Value) -> Vec<String> {
+    let mut paths: Vec<String> = results
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| r["path"].as_str().unwrap().to_string())
+        .collect();
+    paths.sort();
+    paths
+}
+
+#[test]
+fn stdin_reads_newline_delimited_paths() {
+    let dir = TempDir::new().unwrap();
+    let config = write_config(dir.path());
+    let plain = read_only_image(dir.path());
+    let spaced = read_only_image_named(dir.path(), "my holiday photo.jpg");
+
+    let input = format!("{}\r\n\n{}\n", plain.display(), spaced.display());
+    let results = run_with_stdin(&config, &[], input.as_bytes());
+
+    let mut expected = vec![plain.display().to_string(), spaced.display().to_string()];
+    expected.sort();
+    assert_eq!(result_paths(&results), expected);
+}
+
+#[test]
+fn stdin_null_delimited_paths() {
+    let dir = TempDir::new().unwrap();
+    let config = write_config(dir.path());
+    let spaced = read_only_image_named(dir.path(), "a b.jpg");
+    let other = read_only_image_named(dir.path(), "c.jpg");
+
+    let input = format!("{}\0{}\0", spaced.display(), other.display());
+    let results = run_with_stdin(&config, &["--null"], input.as_bytes());
+
+    let mut expected = vec![spaced.display().to_string(), other.display().to_string()];
+    expected.sort();
+    assert_eq!(result_paths(&results), expected);
+}
+
+#[cfg(unix)]
+#[test]
+fn stdin_keeps_non_utf8_paths() {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    let dir = TempDir::new().unwrap();
+    let config = write_config(dir.path());
+    let plain = read_only_image_named(dir.path(), "plain.jpg");
+    // "café.jpg" in Latin-1, as an old camera or archive might name it
+    let odd = dir.path().join(OsStr::from_bytes(b"caf\xe9.jpg"));
+    fs::rename(&plain, &odd).unwrap();
+    let other = read_only_image_named(dir.path(), "other.jpg");
+
+    let mut input = odd.as_os_str().as_bytes().to_vec();
+    input.push(0);
+    input.extend_from_slice(other.as_os_str().as_bytes());
+    let results = run_with_stdin(&config, &["--null"], &input);
+
+    let results = results.as_array().unwrap();
+    assert_eq!(results.len(), 2, "{results:?}");
+    // Both files were found and reached the pre-flight check
+    for result in results {
+        assert!(result["error"].as_str().unwrap().contains("read-only"), "{result}");
+    }
+}
+
+#[test]
+fn null_requires_stdin() {
+    let output = Command::new(env!("CARGO_BIN_EXE_exif-ai-cli"))
+        .args(["--null", "photo.jpg"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+}
+
+#[test]
+fn no_ai_runs_without_keys() {
+    let dir = TempDir::new().unwrap();
+    let config = dir.path().join("config.json");
+    Config::default().save(Some(&config)).unwrap(); // no API keys
+    let image = dir.path().join("harbor-at-dawn.jpg");
+    fs::copy(Path::new(env!("CARGO_MANIFEST_DIR")).join("data/test_canon_powershot.jpg"), &image).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_exif-ai-cli"))
+        .args(["--no-ai", "--json", "--config"])
+        .arg(&config)
+        .arg(&image)
+        .env_remove("RUST_LOG")
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let results: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert!(results[0]["error"].is_null(), "{results}");
+    assert!(results[0]["ai_service"].is_null(), "{results}");
+}
+
+#[test]
+fn csv_report_is_written_after_processing() {
+    let dir = TempDir::new().unwrap();
+    let config = dir.path().join("config.json");
+    Config::default().save(Some(&config)).unwrap();
+    let image = dir.path().join("harbor-at-dawn.jpg");
+    fs::copy(Path::new(env!("CARGO_MANIFEST_DIR")).join("data/test_canon_powershot.jpg"), &image).unwrap();
+    let csv = dir.path().join("report.csv");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_exif-ai-cli"))
+        .args(["--no-ai", "--dry-run", "--quiet", "--config"])
+        .arg(&config)
+        .arg("--csv")
+        .arg(&csv)
+        .arg(&image)
+        .env_remove("RUST_LOG")
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let report = fs::read_to_string(&csv).unwrap();
+    let rows: Vec<&str> = report.lines().collect();
+    assert_eq!(rows.len(), 2, "{report}");
+    assert!(rows[0].starts_with("path,ai_service,title,"), "{report}");
+    assert!(rows[1].starts_with(&format!("{},,", image.display())), "{report}");
+}
+
+#[test]
+fn dump_prompt_injects_image_context() {
+    let dir = TempDir::new().unwrap();
+    let config_path = dir.path().join("config.json");
+    let mut config = Config::default(); // no API keys
+    config.output.augment_existing = true;
+    config.save(Some(&config_path)).unwrap();
+    let image = Path::new(env!("CARGO_MANIFEST_DIR")).join("data/test_canon_powershot.jpg");
+
+    let dump = |extra: &[&Path]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_exif-ai-cli"))
+            .args(["--dump-prompt", "--config"])
+            .arg(&config_path)
+            .args(extra)
+            .env_remove("RUST_LOG")
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    let with_image = dump(&[&image]);
+    assert!(with_image.contains("Existing description"), "{with_image}");
+    let generic = dump(&[]);
+    assert!(!generic.contains("Existing description"), "{generic}");
+    assert!(with_image.starts_with(generic.trim_end()));
+}
+
+#[test]
+fn dump_prompt_for_a_service_prints_its_request() {
+    let dir = TempDir::new().unwrap();
+    let config = write_config(dir.path());
+    let image = Path::new(env!("CARGO_MANIFEST_DIR")).join("data/test_canon_powershot.jpg");
+
+    let dump = |service: &str| {
+        Command::new(env!("CARGO_BIN_EXE_exif-ai-cli"))
+            .args(["--dump-prompt", "--service", service, "--config"])
+            .arg(&config)
+            .arg(&image)
+            .env_remove("RUST_LOG")
+            .output()
+            .unwrap()
+    };
+
+    let output = dump("openai");
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let request: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(request["model"], Config::default().ai_services.openai.model);
+    assert_eq!(request["messages"][0]["role"], "system");
+    let content = &request["messages"][1]["content"];
+    assert!(content[0]["text"].as_str().unwrap().contains("Camera model"), "{request}");
+    assert_eq!(content[1]["image_url"]["url"], "data:image/jpeg;base64,<base64 image>");
+
+    let output = dump("gemini");
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("\"gemini\" is not configured (configured: OpenAI)"), "{stderr}");
+}
+
+/// A folder of `count` copies of the sample image, named `00.jpg`, `01.jpg`, ….
+fn image_folder(dir: &Path, count: usize) -> std::path::PathBuf {
+    let folder = dir.join("photos");
+    fs::create_dir(&folder).unwrap();
+    let src = Path::new(env!("CARGO_MANIFEST_DIR")).join("data/test_canon_powershot.jpg");
+    for i in 0..count {
+        fs::copy(&src, folder.join(format!("{i:02}.jpg"))).unwrap();
+    }
+    folder
+}
+
+fn run_no_ai(dir: &Path, extra: &[&str], folder: &Path) -> Vec<String> {
+    let config = dir.join("config.json");
+    Config::default().save(Some(&config)).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_exif-ai-cli"))
+        .args(["--no-ai", "--dry-run", "--json", "--quiet", "--config"])
+        .arg(&config)
+        .args(extra)
+        .arg(folder)
+        .env_remove("RUST_LOG")
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let results: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    result_paths(&results)
+        .iter()
+        .map(|p| Path::new(p).file_name().unwrap().to_string_lossy().into_owned())
+        .collect()
+}
+
+#[test]
+fn limit_takes_first_images_in_sorted_order() {
+    let dir = TempDir::new().unwrap();
+    let folder = image_folder(dir.path(), 5);
+    assert_eq!(run_no_ai(dir.path(), &["--limit", "2"], &folder), ["00.jpg", "01.jpg"]);
+}
+
+#[test]
+fn sample_takes_n_images() {
+    let dir = TempDir::new().unwrap();
+    let folder = image_folder(dir.path(), 5);
+    let names = run_no_ai(dir.path(), &["--sample", "3"], &folder);
+    let mut distinct = names.clone();
+    distinct.dedup(); // already sorted
+    assert_eq!(distinct.len(), 3, "{names:?}");
+}
+
+#[test]
+fn limit_conflicts_with_sample() {
+    let output = Command::new(env!("CARGO_BIN_EXE_exif-ai-cli"))
+        .args(["--limit", "1", "--sample", "1", "photo.jpg"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+}
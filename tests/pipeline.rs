@@ -0,0 +1,80 @@
+//! End-to-end pipeline runs against canned AI services, without network access.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use exif_ai::ai::{AiResult, MockAiService};
+use exif_ai::config::ExifFields;
+use exif_ai::exif::read_exif;
+use exif_ai::pipeline::Pipeline;
+use tempfile::TempDir;
+
+fn copy_image(dir: &TempDir, name: &str) -> PathBuf {
+    let src = Path::new(env!("CARGO_MANIFEST_DIR")).join("data/test_canon_powershot.jpg");
+    let path = dir.path().join(name);
+    fs::copy(src, &path).unwrap();
+    path
+}
+
+fn barn() -> AiResult {
+    AiResult {
+        title: Some("Red barn".into()),
+        description: Some("A red barn in a snowy field.".into()),
+        tags: Some(vec!["barn".into(), "snow".into()]),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn process_image_writes_the_canned_result() {
+    let dir = TempDir::new().unwrap();
+    let jpg = copy_image(&dir, "barn.jpg");
+    let pipeline = Pipeline::builder()
+        .add_service(Box::new(MockAiService::returning(barn())))
+        .fields(ExifFields { overwrite_existing: true, ..Default::default() })
+        .build()
+        .unwrap();
+
+    let result = pipeline.process_image(&jpg).await;
+    assert!(result.error.is_none(), "{:?}", result.error);
+    assert!(result.title_written && result.description_written);
+    let exif = read_exif(&jpg).unwrap();
+    assert_eq!(exif.title.as_deref(), Some("Red barn"));
+    assert_eq!(exif.description.as_deref(), Some("A red barn in a snowy field."));
+}
+
+#[tokio::test]
+async fn failing_service_falls_over_to_the_next() {
+    let dir = TempDir::new().unwrap();
+    let jpg = copy_image(&dir, "barn.jpg");
+    let pipeline = Pipeline::builder()
+        .add_service(Box::new(MockAiService::failing("quota exceeded").named("Primary")))
+        .add_service(Box::new(MockAiService::returning(barn()).named("Backup")))
+        .build()
+        .unwrap();
+
+    let result = pipeline.process_image(&jpg).await;
+    assert!(result.error.is_none(), "{:?}", result.error);
+    assert_eq!(result.ai_service_used.as_deref(), Some("Backup"));
+    assert_eq!(read_exif(&jpg).unwrap().title.as_deref(), Some("Red barn"));
+}
+
+#[tokio::test]
+async fn process_images_reports_errors_and_leaves_files_untouched() {
+    let dir = TempDir::new().unwrap();
+    let paths = vec![copy_image(&dir, "a.jpg"), copy_image(&dir, "b.jpg")];
+    let before: Vec<Vec<u8>> = paths.iter().map(|p| fs::read(p).unwrap()).collect();
+    let pipeline = Pipeline::builder()
+        .add_service(Box::new(MockAiService::failing("service unavailable")))
+        .build()
+        .unwrap();
+
+    let results = pipeline.process_images(&paths, 2).await;
+    assert_eq!(results.len(), 2);
+    for (result, original) in results.iter().zip(&before) {
+        let error = result.error.as_ref().expect("every service failed").to_string();
+        assert!(error.contains("service unavailable"), "{error}");
+        assert!(!result.title_written);
+        assert_eq!(&fs::read(&result.path).unwrap(), original);
+    }
+}